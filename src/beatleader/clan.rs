@@ -6,7 +6,9 @@ use serde::{Deserialize, Serialize};
 use serde_with::{serde_as, DefaultOnNull, TimestampSeconds};
 
 use crate::beatleader::oauth::{ClientWithOAuth, OAuthTokenRepository};
-use crate::beatleader::player::{Difficulty, Leaderboard, LeaderboardId, Player, PlayerId, Song};
+use crate::beatleader::player::{
+    Difficulty, Leaderboard, LeaderboardId, MapType, Player, PlayerId, Song,
+};
 use crate::beatleader::{
     BlApiListResponse, BlApiResponse, BlContext, Client, List, MetaData, QueryParam, Result,
     SortOrder,
@@ -102,6 +104,60 @@ impl QueryParam for LeaderboardParam {
     }
 }
 
+#[allow(dead_code)]
+#[derive(Clone)]
+pub enum LeaderboardsSort {
+    Date,
+    Stars,
+    PlayCount,
+}
+
+#[allow(dead_code)]
+#[derive(Clone)]
+pub enum LeaderboardsParam {
+    Page(u32),
+    Count(u32),
+    Type(MapType),
+    StarsFrom(f64),
+    StarsTo(f64),
+    Sort(LeaderboardsSort),
+    Order(SortOrder),
+    Context(BlContext),
+}
+
+impl QueryParam for LeaderboardsParam {
+    fn as_query_param(&self) -> (String, String) {
+        match self {
+            LeaderboardsParam::Page(page) => ("page".to_owned(), page.to_string()),
+            LeaderboardsParam::Count(count) => ("count".to_owned(), count.to_string()),
+            LeaderboardsParam::Type(map_type) => (
+                "type".to_owned(),
+                match map_type {
+                    MapType::All => "all".to_owned(),
+                    MapType::Ranked => "ranked".to_owned(),
+                    MapType::Unranked => "unranked".to_owned(),
+                    MapType::Nominated => "nominated".to_owned(),
+                    MapType::Qualified => "qualified".to_owned(),
+                },
+            ),
+            LeaderboardsParam::StarsFrom(stars) => ("stars_from".to_owned(), stars.to_string()),
+            LeaderboardsParam::StarsTo(stars) => ("stars_to".to_owned(), stars.to_string()),
+            LeaderboardsParam::Sort(field) => (
+                "sortBy".to_owned(),
+                match field {
+                    LeaderboardsSort::Date => "timestamp".to_owned(),
+                    LeaderboardsSort::Stars => "stars".to_owned(),
+                    LeaderboardsSort::PlayCount => "playcount".to_owned(),
+                },
+            ),
+            LeaderboardsParam::Order(order) => ("order".to_owned(), order.to_string()),
+            LeaderboardsParam::Context(context) => {
+                ("leaderboardContext".to_owned(), context.to_string())
+            }
+        }
+    }
+}
+
 #[allow(dead_code)]
 #[derive(Clone)]
 pub enum ClanMapParam {
@@ -458,6 +514,16 @@ impl<'a> ClanResource<'a> {
             )
             .await
     }
+
+    pub async fn leaderboards(&self, params: &[LeaderboardsParam]) -> Result<List<Leaderboard>> {
+        self.client
+            .get_json::<BlApiListResponse<Leaderboard>, List<Leaderboard>, LeaderboardsParam>(
+                Method::GET,
+                "/leaderboards",
+                params,
+            )
+            .await
+    }
 }
 
 pub struct ClanAuthResource<'a, T: OAuthTokenRepository> {
@@ -558,6 +624,8 @@ pub struct Clan {
     pub average_accuracy: f64,
     pub players_count: u32,
     pub icon: String,
+    #[serde(default)]
+    pub color: String,
     pub capture_leaderboards_count: i32,
     pub captured_leaderboards: Option<Vec<CapturedLeaderboard>>,
     pub ranked_pool_percent_captured: f64,
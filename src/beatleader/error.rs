@@ -1,3 +1,4 @@
+use std::time::Duration;
 use std::{error, fmt};
 
 use chrono::{DateTime, Utc};
@@ -12,8 +13,10 @@ pub enum Error {
     NoContent,
     Unauthorized,
     Client(Option<String>),
+    RateLimited { retry_after: Option<Duration> },
     OAuth(Option<OAuthErrorResponse>),
     OAuthExpired(DateTime<Utc>),
+    OAuthNotSet,
     OAuthStorage,
     Server,
     JsonDecode(reqwest::Error),
@@ -30,6 +33,14 @@ impl fmt::Display for Error {
             Error::NotFound => write!(f, "not found"),
             Error::Unauthorized => write!(f, "BL unauthorized error"),
             Error::Client(_) => write!(f, "BL client error"),
+            Error::RateLimited { retry_after } => match retry_after {
+                Some(retry_after) => write!(
+                    f,
+                    "BL rate limit exceeded, try again in {}s",
+                    retry_after.as_secs()
+                ),
+                None => write!(f, "BL rate limit exceeded, try again later"),
+            },
             Error::Server => write!(f, "BL server error"),
             Error::JsonDecode(e) => write!(f, "invalid BL response: {}", e),
             Error::Db(e) => write!(f, "db error: {}", e),
@@ -43,12 +54,42 @@ impl fmt::Display for Error {
                     "unknown response"
                 }
             ),
+            Error::OAuthNotSet => write!(f, "no OAuth token stored"),
             Error::OAuthStorage => write!(f, "OAuth storage error"),
             Error::OAuthExpired(date) => write!(f, "OAuth token has expired on {}", date),
         }
     }
 }
 
+impl Error {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Error::Request(_) => "request",
+            Error::Network(_) => "network",
+            Error::NotFound => "not_found",
+            Error::NoContent => "no_content",
+            Error::Unauthorized => "unauthorized",
+            Error::Client(_) => "client",
+            Error::RateLimited { .. } => "rate_limited",
+            Error::OAuth(_) => "oauth",
+            Error::OAuthExpired(_) => "oauth_expired",
+            Error::OAuthNotSet => "oauth_not_set",
+            Error::OAuthStorage => "oauth_storage",
+            Error::Server => "server",
+            Error::JsonDecode(_) => "json_decode",
+            Error::Db(_) => "db",
+            Error::Unknown => "unknown",
+        }
+    }
+
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            Error::Network(_) | Error::Server | Error::RateLimited { .. }
+        )
+    }
+}
+
 impl error::Error for Error {
     fn source(&self) -> Option<&(dyn error::Error + 'static)> {
         match &self {
@@ -58,10 +99,12 @@ impl error::Error for Error {
             | Error::NoContent
             | Error::Unauthorized
             | Error::Client(_)
+            | Error::RateLimited { .. }
             | Error::Server
             | Error::Db(_)
             | Error::OAuth(_)
             | Error::OAuthStorage
+            | Error::OAuthNotSet
             | Error::OAuthExpired(_)
             | Error::Unknown => None,
         }
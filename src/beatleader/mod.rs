@@ -8,31 +8,40 @@ use governor::clock::DefaultClock;
 use governor::middleware::NoOpMiddleware;
 use governor::state::{InMemoryState, NotKeyed};
 use governor::{Jitter, Quota, RateLimiter};
+use reqwest::header::RETRY_AFTER;
 use reqwest::{
     Client as HttpClient, IntoUrl, Method, Request, RequestBuilder, Response as ReqwestResponse,
     Url,
 };
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
-use tracing::{debug, error, info, trace};
+use tracing::{debug, error, info, trace, warn};
 
 use player::PlayerResource;
 
 use crate::beatleader::clan::ClanResource;
 use crate::beatleader::error::Error;
 use crate::beatleader::oauth::{ClientWithOAuth, OAuthAppCredentials, OAuthTokenRepository};
+use crate::beatleader::rate_limit::{RateLimiterSnapshot, RateLimiterStats};
 use crate::beatleader::rating::AiRatingsResource;
+use crate::METRICS;
 
 pub mod clan;
 pub mod error;
 pub mod oauth;
 pub mod player;
 pub mod pp;
+pub mod rate_limit;
 pub mod rating;
 
 pub type Result<T> = std::result::Result<T, Error>;
 
-const DEFAULT_API_URL: &str = "https://api.beatleader.com";
+pub(crate) const DEFAULT_API_URL: &str = "https://api.beatleader.com";
+pub(crate) const DEFAULT_TIMEOUT: u64 = 30;
+pub(crate) const DEFAULT_RATE_LIMIT: u32 = 10;
+pub(crate) const DEFAULT_MAX_ATTEMPTS: u8 = 3;
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(500);
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(30);
 
 pub static APP_USER_AGENT: &str = concat!(
     env!("CARGO_PKG_NAME"),
@@ -45,14 +54,17 @@ pub struct Client {
     base_url: String,
     http_client: HttpClient,
     timeout: u64,
+    rate_limit: u32,
+    max_attempts: u8,
     rate_limiter: RateLimiter<NotKeyed, InMemoryState, DefaultClock, NoOpMiddleware>,
+    rate_limiter_stats: RateLimiterStats,
 }
 
 impl Client {
-    pub fn new(base_url: String, timeout: u64) -> Self {
+    pub fn new(base_url: String, timeout: u64, rate_limit: u32, max_attempts: u8) -> Self {
         info!(
-            "Initialize client with URL {} and timeout {}s. Identify myself as {}",
-            base_url, timeout, APP_USER_AGENT
+            "Initialize client with URL {}, timeout {}s, rate limit {}/s and max {} attempt(s). Identify myself as {}",
+            base_url, timeout, rate_limit, max_attempts, APP_USER_AGENT
         );
 
         Self {
@@ -65,10 +77,19 @@ impl Client {
                 .build()
                 .unwrap(),
             timeout,
-            rate_limiter: RateLimiter::direct(Quota::per_second(NonZeroU32::new(10u32).unwrap())),
+            rate_limit,
+            max_attempts: max_attempts.max(1),
+            rate_limiter: RateLimiter::direct(Quota::per_second(
+                NonZeroU32::new(rate_limit).unwrap_or(NonZeroU32::new(DEFAULT_RATE_LIMIT).unwrap()),
+            )),
+            rate_limiter_stats: RateLimiterStats::new(),
         }
     }
 
+    pub fn rate_limiter_stats(&self) -> RateLimiterSnapshot {
+        self.rate_limiter_stats.snapshot()
+    }
+
     pub fn player(&self) -> PlayerResource {
         PlayerResource::new(self)
     }
@@ -82,7 +103,12 @@ impl Client {
     }
 
     pub fn with_timeout(&self, timeout: u64) -> Client {
-        Client::new(self.base_url.clone(), timeout)
+        Client::new(
+            self.base_url.clone(),
+            timeout,
+            self.rate_limit,
+            self.max_attempts,
+        )
     }
 
     pub fn with_oauth<T: OAuthTokenRepository>(
@@ -103,6 +129,16 @@ impl Client {
         self.send_request(request.unwrap()).await
     }
 
+    pub async fn head<U: IntoUrl>(&self, endpoint: U) -> Result<ReqwestResponse> {
+        let request = self.request_builder(Method::HEAD, endpoint).build();
+
+        if let Err(err) = request {
+            return Err(Error::Request(err));
+        }
+
+        self.send_request(request.unwrap()).await
+    }
+
     async fn get_json<
         In: BlApiResponse + Sized + DeserializeOwned,
         Out: From<In> + Sized,
@@ -144,11 +180,57 @@ impl Client {
     }
 
     pub async fn send_request(&self, request: Request) -> Result<ReqwestResponse> {
+        let mut request = request;
+        let mut attempt = 1;
+
+        loop {
+            // requests with streaming body can not be cloned and therefore can not be retried;
+            // a non-idempotent request may have been processed before it failed, so it is not repeated
+            let retry_request = request
+                .method()
+                .is_idempotent()
+                .then(|| request.try_clone())
+                .flatten();
+
+            let result = self.execute_request(request).await;
+
+            let Err(ref err) = result else {
+                return result;
+            };
+
+            let Some(retry_request) = retry_request else {
+                return result;
+            };
+
+            let delay = match retry_delay(err, attempt, self.max_attempts) {
+                Some(RetryDelay::RetryAfter(delay)) | Some(RetryDelay::Backoff(delay)) => delay,
+                None => return result,
+            };
+
+            attempt += 1;
+
+            warn!(
+                "Request failed ({}), retrying in {:?} (attempt {}/{})...",
+                err, delay, attempt, self.max_attempts
+            );
+
+            tokio::time::sleep(delay).await;
+
+            request = retry_request;
+        }
+    }
+
+    async fn execute_request(&self, request: Request) -> Result<ReqwestResponse> {
         trace!("Waiting for rate limiter...");
 
-        self.rate_limiter
-            .until_ready_with_jitter(Jitter::up_to(Duration::from_millis(100)))
-            .await;
+        let blocked = self.rate_limiter.check().is_err();
+        if blocked {
+            self.rate_limiter
+                .until_ready_with_jitter(Jitter::up_to(Duration::from_millis(100)))
+                .await;
+        }
+
+        self.rate_limiter_stats.record(blocked);
 
         trace!("Got permit from rate limiter.");
 
@@ -166,7 +248,9 @@ impl Client {
 
         let response = self.http_client.execute(request).await;
 
-        match response {
+        METRICS.record_bl_api_request();
+
+        let result = match response {
             Err(err) => {
                 error!("Response error: {:#?}", err);
 
@@ -179,19 +263,15 @@ impl Client {
                     response.status().as_u16()
                 );
 
-                match response.status().as_u16() {
-                    204 => Err(Error::NoContent),
-                    200..=299 => Ok(response),
-                    401 | 403 => Err(Error::Unauthorized),
-                    404 => Err(Error::NotFound),
-                    400..=499 => Err(Error::Client(
-                        response.text_with_charset("utf-8").await.ok(),
-                    )),
-                    500..=599 => Err(Error::Server),
-                    _ => Err(Error::Unknown),
-                }
+                map_response(response).await
             }
+        };
+
+        if let Err(ref err) = result {
+            METRICS.record_bl_api_error(err.kind());
         }
+
+        result
     }
 
     pub(crate) fn request_builder<U: IntoUrl>(
@@ -215,9 +295,61 @@ impl Client {
     }
 }
 
+async fn map_response(response: ReqwestResponse) -> Result<ReqwestResponse> {
+    match response.status().as_u16() {
+        204 => Err(Error::NoContent),
+        200..=299 => Ok(response),
+        401 | 403 => Err(Error::Unauthorized),
+        404 => Err(Error::NotFound),
+        429 => Err(Error::RateLimited {
+            retry_after: response
+                .headers()
+                .get(RETRY_AFTER)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.trim().parse::<u64>().ok())
+                .map(Duration::from_secs),
+        }),
+        400..=499 => Err(Error::Client(
+            response.text_with_charset("utf-8").await.ok(),
+        )),
+        500..=599 => Err(Error::Server),
+        _ => Err(Error::Unknown),
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum RetryDelay {
+    RetryAfter(Duration),
+    Backoff(Duration),
+}
+
+// Retry-After sent with 429 is honored (capped), other retryable errors use exponential backoff;
+// both count toward the max attempts
+fn retry_delay(err: &Error, attempt: u8, max_attempts: u8) -> Option<RetryDelay> {
+    if attempt >= max_attempts {
+        return None;
+    }
+
+    match err {
+        Error::RateLimited {
+            retry_after: Some(retry_after),
+        } => Some(RetryDelay::RetryAfter((*retry_after).min(MAX_RETRY_AFTER))),
+        err if err.is_retryable() => Some(RetryDelay::Backoff(
+            Jitter::up_to(Duration::from_millis(250))
+                + RETRY_BASE_DELAY * 2u32.pow(attempt as u32 - 1),
+        )),
+        _ => None,
+    }
+}
+
 impl Default for Client {
     fn default() -> Self {
-        Self::new(DEFAULT_API_URL.to_string(), 30)
+        Self::new(
+            DEFAULT_API_URL.to_string(),
+            DEFAULT_TIMEOUT,
+            DEFAULT_RATE_LIMIT,
+            DEFAULT_MAX_ATTEMPTS,
+        )
     }
 }
 
@@ -244,7 +376,7 @@ impl Display for SortOrder {
 }
 
 #[allow(dead_code)]
-#[derive(Serialize, Deserialize, Default, Debug, Clone)]
+#[derive(Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq, Hash)]
 pub enum BlContext {
     #[default]
     #[serde(rename = "general")]
@@ -420,3 +552,140 @@ where
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use axum::http::StatusCode;
+    use reqwest::header::RETRY_AFTER;
+    use reqwest::Method;
+
+    use crate::beatleader::error::Error;
+    use crate::beatleader::{map_response, retry_delay, Client, RetryDelay, MAX_RETRY_AFTER};
+
+    fn mocked_response(status: u16, retry_after: Option<&str>) -> reqwest::Response {
+        let mut builder = axum::http::Response::builder().status(status);
+        if let Some(retry_after) = retry_after {
+            builder = builder.header("Retry-After", retry_after);
+        }
+
+        builder.body("").unwrap().into()
+    }
+
+    #[tokio::test]
+    async fn it_reads_retry_after_from_429_response() {
+        let result = map_response(mocked_response(429, Some("5"))).await;
+        assert!(matches!(
+            result,
+            Err(Error::RateLimited {
+                retry_after: Some(retry_after)
+            }) if retry_after == Duration::from_secs(5)
+        ));
+
+        let result = map_response(mocked_response(429, None)).await;
+        assert!(matches!(
+            result,
+            Err(Error::RateLimited { retry_after: None })
+        ));
+
+        let result = map_response(mocked_response(400, Some("5"))).await;
+        assert!(matches!(result, Err(Error::Client(_))));
+    }
+
+    #[tokio::test]
+    async fn it_counts_retry_after_toward_max_attempts() {
+        let err = map_response(mocked_response(429, Some("2")))
+            .await
+            .unwrap_err();
+
+        assert_eq!(
+            retry_delay(&err, 1, 2),
+            Some(RetryDelay::RetryAfter(Duration::from_secs(2)))
+        );
+        assert_eq!(retry_delay(&err, 2, 2), None);
+
+        let err = map_response(mocked_response(429, Some("3600")))
+            .await
+            .unwrap_err();
+        assert_eq!(
+            retry_delay(&err, 1, 2),
+            Some(RetryDelay::RetryAfter(MAX_RETRY_AFTER))
+        );
+    }
+
+    #[tokio::test]
+    async fn it_stops_retrying_429_after_max_attempts() {
+        let hits = Arc::new(AtomicUsize::new(0));
+        let server_hits = Arc::clone(&hits);
+        let router = axum::Router::new().route(
+            "/player/1",
+            axum::routing::get(move || {
+                server_hits.fetch_add(1, Ordering::SeqCst);
+
+                async { (StatusCode::TOO_MANY_REQUESTS, [(RETRY_AFTER, "0")]) }
+            }),
+        );
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, router).await });
+
+        let mut client = Client::new(format!("http://{}", addr), 5, 100, 3);
+        // mocked server does not speak https
+        client.http_client = reqwest::Client::new();
+
+        let result = client.get("/player/1").await;
+
+        assert!(matches!(result, Err(Error::RateLimited { .. })));
+        assert_eq!(hits.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn it_does_not_retry_non_idempotent_requests() {
+        let hits = Arc::new(AtomicUsize::new(0));
+        let server_hits = Arc::clone(&hits);
+        let router = axum::Router::new().route(
+            "/clan/invite",
+            axum::routing::post(move || {
+                server_hits.fetch_add(1, Ordering::SeqCst);
+
+                async { StatusCode::INTERNAL_SERVER_ERROR }
+            }),
+        );
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, router).await });
+
+        let mut client = Client::new(format!("http://{}", addr), 5, 100, 3);
+        // mocked server does not speak https
+        client.http_client = reqwest::Client::new();
+
+        let request = client
+            .request_builder(Method::POST, "/clan/invite")
+            .build()
+            .unwrap();
+        let result = client.send_request(request).await;
+
+        assert!(matches!(result, Err(Error::Server)));
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn it_backs_off_on_server_errors_only() {
+        assert!(matches!(
+            retry_delay(&Error::Server, 1, 3),
+            Some(RetryDelay::Backoff(delay)) if delay >= Duration::from_millis(500)
+        ));
+        assert!(matches!(
+            retry_delay(&Error::Server, 2, 3),
+            Some(RetryDelay::Backoff(delay)) if delay >= Duration::from_millis(1000)
+        ));
+        assert_eq!(retry_delay(&Error::Server, 3, 3), None);
+        assert_eq!(retry_delay(&Error::NotFound, 1, 3), None);
+        assert_eq!(retry_delay(&Error::Client(None), 1, 3), None);
+    }
+}
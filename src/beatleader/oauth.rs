@@ -189,6 +189,14 @@ impl OAuthToken {
         self.expiration_date
     }
 
+    pub fn get_scopes(&self) -> &Vec<OAuthScope> {
+        &self.scopes
+    }
+
+    pub fn has_refresh_token(&self) -> bool {
+        self.refresh_token.is_some()
+    }
+
     pub fn is_valid_for(&self, duration: Duration) -> bool {
         self.expiration_date.ge(&(Utc::now() + duration))
     }
@@ -393,7 +401,7 @@ where
 
     pub async fn refresh_token_if_needed(&self) -> super::Result<OAuthToken> {
         let Some(oauth_token) = self.get_token().await? else {
-            return Err(Error::OAuthStorage);
+            return Err(Error::OAuthNotSet);
         };
 
         trace!("OAuth token retrieved from repository");
@@ -407,11 +415,13 @@ where
         if oauth_token.refresh_token.is_none() {
             trace!("No refresh token, skip refreshing.");
 
-            return Err(Error::OAuthStorage);
+            return Err(Error::OAuthExpired(oauth_token.get_expiration()));
         }
 
         let oauth_credentials = self.oauth_credentials.clone();
         let oauth_token_repository = self.oauth_token_repository.clone();
+        // keep the configured API URL, the default client would always refresh against production
+        let client = self.client.with_timeout(self.client.get_timeout());
 
         trace!("Trying to refresh expired OAuth token...");
 
@@ -421,7 +431,7 @@ where
                 Box::pin(async move {
                     // refresh only if the token has not changed in the meantime
                     if token.access_token == oauth_token.access_token {
-                        let new_token_result = Client::default()
+                        let new_token_result = client
                             .with_oauth(oauth_credentials, oauth_token_repository)
                             .oauth()
                             .refresh_token(oauth_token.refresh_token.as_ref().unwrap())
@@ -435,6 +445,11 @@ where
             })
             .await?;
 
+        // refreshing failed, the stored token is left untouched
+        if !oauth_token.is_valid_for(Duration::zero()) {
+            return Err(Error::OAuthExpired(oauth_token.get_expiration()));
+        }
+
         debug!("OAuth token refreshed.");
 
         Ok(oauth_token)
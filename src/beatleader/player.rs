@@ -3,7 +3,7 @@ use serde::{Deserialize, Serialize};
 use serde_repr::{Deserialize_repr, Serialize_repr};
 use serde_with::{serde_as, DefaultOnError, DefaultOnNull, TimestampSeconds};
 
-use chrono::serde::ts_seconds;
+use chrono::serde::{ts_seconds, ts_seconds_option};
 use chrono::{DateTime, Utc};
 use poise::serenity_prelude::UserId;
 
@@ -55,6 +55,17 @@ impl<'a> PlayerResource<'a> {
             )
             .await
     }
+
+    // statistics are calculated from the replay, they may not exist for older scores
+    pub async fn score_statistic(&self, score_id: u32) -> beatleader::Result<ScoreStatistic> {
+        self.client
+            .get_json::<ScoreStatistic, ScoreStatistic, PlayerScoreParam>(
+                Method::GET,
+                &format!("/score/statistic/{}", score_id),
+                &[],
+            )
+            .await
+    }
 }
 
 #[allow(dead_code)]
@@ -77,6 +88,8 @@ pub enum MapType {
     All,
     Ranked,
     Unranked,
+    Nominated,
+    Qualified,
 }
 
 #[allow(dead_code)]
@@ -121,6 +134,8 @@ impl QueryParam for PlayerScoreParam {
                     MapType::All => "all".to_owned(),
                     MapType::Ranked => "ranked".to_owned(),
                     MapType::Unranked => "unranked".to_owned(),
+                    MapType::Nominated => "nominated".to_owned(),
+                    MapType::Qualified => "qualified".to_owned(),
                 },
             ),
             PlayerScoreParam::Context(context) => {
@@ -153,6 +168,10 @@ pub struct Player {
     pub clans: Vec<PlayerClan>,
     pub socials: Vec<Social>,
     pub profile_settings: PlayerProfileSettings,
+    #[serde(default, with = "ts_seconds_option")]
+    pub created_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub platform: String,
 }
 
 impl BlApiResponse for Player {}
@@ -216,6 +235,46 @@ pub struct PlayerScoreStats {
     pub watched_replays: u32,
     pub peak_rank: u32,
     pub top1_count: i32,
+    // headset used for most of the top scores
+    #[serde(default, rename = "topHMD")]
+    pub top_hmd: u32,
+}
+
+/// Returns the name of the headset with the given BL id
+pub fn hmd_name(hmd: u32) -> &'static str {
+    match hmd {
+        1 => "Rift",
+        2 => "Vive",
+        4 => "Vive Pro",
+        8 => "Windows Mixed Reality",
+        16 => "Rift S",
+        32 => "Quest",
+        33 => "Pico Neo 3",
+        34 => "Pico Neo 2",
+        35 => "Vive Pro 2",
+        36 => "Vive Elite",
+        38 => "Pimax 8K",
+        39 => "Pimax 5K",
+        40 => "Pimax Artisan",
+        41 => "HP Reverb",
+        42 => "Samsung WMR",
+        45 => "Lenovo Explorer",
+        46 => "Acer WMR",
+        47 => "Vive Focus",
+        49 => "Dell Visor",
+        56 => "Asus WMR",
+        60 => "Pico Neo 4",
+        61 => "Quest Pro",
+        62 => "Pimax Crystal",
+        64 => "Valve Index",
+        66 => "Bigscreen Beyond",
+        70 => "PlayStation VR2",
+        128 => "Vive Cosmos",
+        256 => "Quest 2",
+        512 => "Quest 3",
+        513 => "Quest 3S",
+        _ => "Unknown",
+    }
 }
 
 #[derive(Deserialize, Debug, Clone, Default)]
@@ -272,10 +331,47 @@ pub struct Score {
     pub timeset: DateTime<Utc>,
     #[serde(with = "ts_seconds")]
     pub timepost: DateTime<Utc>,
+    #[serde_as(deserialize_as = "DefaultOnNull")]
+    #[serde(default)]
+    pub replay: String,
 }
 
 impl BlApiResponse for Score {}
 
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ScoreStatistic {
+    #[serde(default)]
+    pub accuracy_tracker: Option<AccuracyTracker>,
+}
+
+impl BlApiResponse for ScoreStatistic {}
+
+#[serde_as]
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct AccuracyTracker {
+    #[serde_as(deserialize_as = "DefaultOnNull")]
+    #[serde(default)]
+    pub acc_left: f64,
+    #[serde_as(deserialize_as = "DefaultOnNull")]
+    #[serde(default)]
+    pub acc_right: f64,
+    // 1.0 is a full swing, lower values mean underswing
+    #[serde_as(deserialize_as = "DefaultOnNull")]
+    #[serde(default)]
+    pub left_preswing: f64,
+    #[serde_as(deserialize_as = "DefaultOnNull")]
+    #[serde(default)]
+    pub right_preswing: f64,
+    #[serde_as(deserialize_as = "DefaultOnNull")]
+    #[serde(default)]
+    pub left_postswing: f64,
+    #[serde_as(deserialize_as = "DefaultOnNull")]
+    #[serde(default)]
+    pub right_postswing: f64,
+}
+
 pub type LeaderboardId = String;
 
 #[serde_as]
@@ -92,6 +92,21 @@ pub(crate) fn calculate_pp_boundary(coefficient: f64, pps: &mut [f64], expected_
     calculate_raw_pp_at_idx(coefficient, pps, 0, expected_pp)
 }
 
+pub(crate) fn calculate_std_deviation(values: &[f64]) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
+
+    let mean = values.iter().sum::<f64>() / values.len() as f64;
+    let variance = values
+        .iter()
+        .map(|value| (value - mean).powi(2))
+        .sum::<f64>()
+        / values.len() as f64;
+
+    variance.sqrt()
+}
+
 pub fn curve_at_value(value: f64) -> f64 {
     let mut idx: usize = 0;
     for (i, val) in CURVE.iter().enumerate() {
@@ -223,9 +238,29 @@ mod tests {
 
     use crate::beatleader::pp::{
         calculate_acc_from_pp, calculate_pp_boundary, calculate_pp_from_acc,
-        calculate_total_pp_from_sorted, StarRating, WEIGHT_COEFFICIENT,
+        calculate_std_deviation, calculate_total_pp_from_sorted, StarRating, WEIGHT_COEFFICIENT,
     };
 
+    #[test]
+    fn it_properly_calculates_std_deviation() {
+        assert_eq!(calculate_std_deviation(&[]), 0.0);
+        assert_eq!(calculate_std_deviation(&[95.5]), 0.0);
+        assert_eq!(calculate_std_deviation(&[95.0, 95.0, 95.0]), 0.0);
+
+        assert_eq!(
+            format!("{:.5}", 2.0),
+            format!(
+                "{:.5}",
+                calculate_std_deviation(&[92.0, 94.0, 94.0, 94.0, 95.0, 95.0, 97.0, 99.0])
+            )
+        );
+
+        assert_eq!(
+            format!("{:.5}", 1.24722),
+            format!("{:.5}", calculate_std_deviation(&[96.0, 97.0, 99.0]))
+        );
+    }
+
     #[test]
     fn it_properly_calculates_total_pp() {
         let mut pps = vec![100.0, 200.0, 300.0, 400.0];
@@ -0,0 +1,94 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use chrono::Utc;
+use tracing::{debug, warn};
+
+// how often (in seconds) a summary of rate limiter usage is reported
+const REPORT_INTERVAL: u64 = 60;
+
+// report at warn level if the limiter blocked more requests than this in the last interval
+const BLOCKED_WARN_THRESHOLD: u64 = 100;
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct RateLimiterSnapshot {
+    pub requests: u64,
+    pub blocked: u64,
+}
+
+#[derive(Debug, Default)]
+pub struct RateLimiterStats {
+    requests: AtomicU64,
+    blocked: AtomicU64,
+    interval_requests: AtomicU64,
+    interval_blocked: AtomicU64,
+    interval_started_at: AtomicU64,
+}
+
+impl RateLimiterStats {
+    pub fn new() -> Self {
+        Self {
+            interval_started_at: AtomicU64::new(Self::now()),
+            ..Default::default()
+        }
+    }
+
+    pub fn record(&self, blocked: bool) {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        self.interval_requests.fetch_add(1, Ordering::Relaxed);
+
+        if blocked {
+            self.blocked.fetch_add(1, Ordering::Relaxed);
+            self.interval_blocked.fetch_add(1, Ordering::Relaxed);
+        }
+
+        self.report_if_needed();
+    }
+
+    pub fn snapshot(&self) -> RateLimiterSnapshot {
+        RateLimiterSnapshot {
+            requests: self.requests.load(Ordering::Relaxed),
+            blocked: self.blocked.load(Ordering::Relaxed),
+        }
+    }
+
+    fn report_if_needed(&self) {
+        let now = Self::now();
+        let started_at = self.interval_started_at.load(Ordering::Relaxed);
+
+        if now < started_at + REPORT_INTERVAL {
+            return;
+        }
+
+        // only one caller wins the race and reports the interval
+        if self
+            .interval_started_at
+            .compare_exchange(started_at, now, Ordering::Relaxed, Ordering::Relaxed)
+            .is_err()
+        {
+            return;
+        }
+
+        let requests = self.interval_requests.swap(0, Ordering::Relaxed);
+        let blocked = self.interval_blocked.swap(0, Ordering::Relaxed);
+
+        if blocked > BLOCKED_WARN_THRESHOLD {
+            warn!(
+                "BL API rate limiter blocked {} of {} requests in the last {}s",
+                blocked,
+                requests,
+                now - started_at
+            );
+        } else {
+            debug!(
+                "BL API rate limiter blocked {} of {} requests in the last {}s",
+                blocked,
+                requests,
+                now - started_at
+            );
+        }
+    }
+
+    fn now() -> u64 {
+        Utc::now().timestamp().max(0) as u64
+    }
+}
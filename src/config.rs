@@ -3,6 +3,14 @@ use serde::{Deserialize, Serialize};
 use std::net::Ipv4Addr;
 use tracing::info;
 
+use crate::beatleader::{BlContext, DEFAULT_API_URL};
+
+/// Hard limit of the number of songs in a generated clan wars playlist, regardless of settings
+pub(crate) const PLAYLIST_MAX_SONGS_CEILING: u32 = 1000;
+
+/// Max number of maps in a single clan wars maps announcement
+pub(crate) const CLAN_WARS_MAPS_MAX_COUNT: u16 = 100;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TracingLevel(String);
 
@@ -27,7 +35,7 @@ impl From<TracingLevel> for tracing::Level {
 #[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub enum TracingFormat {
     #[default]
-    #[serde(rename = "compact")]
+    #[serde(rename = "compact", alias = "text")]
     Compact,
     #[serde(rename = "pretty")]
     Pretty,
@@ -69,22 +77,52 @@ pub(crate) struct ServerSettings {
     pub url: String,
 }
 
+impl ServerSettings {
+    // address of the bot's own OAuth redirect route
+    pub fn oauth_redirect_uri(&self) -> String {
+        format!("{}/bl-oauth", self.url.trim_end_matches('/'))
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[allow(unused)]
 pub(crate) struct Settings {
     pub discord_token: String,
     pub refresh_interval: u64,
+    // cheaper tasks may run more often than the player stats and roles update,
+    // refresh_interval is used if not set
+    pub oauth_refresh_interval: Option<u64>,
+    pub clan_peak_interval: Option<u64>,
     pub storage_path: String,
     pub clan_wars_interval: u64,
     pub clan_wars_maps_count: u16,
     pub clan_wars_contribution_interval: u64,
     pub commander_orders_retention: u64,
+    pub playlist_max_songs: u32,
+    // days since the last sync after which clan wars playlists are removed, 0 keeps them forever
+    pub playlist_ttl: u64,
+    // base URL of the BL API, overridden only for testing against staging
+    pub bl_api_url: String,
+    pub bl_api_rate_limit: u32,
+    pub bl_api_max_attempts: u8,
+    pub score_contexts: Vec<BlContext>,
+    pub metrics_enabled: bool,
+    // privileged Server Members intent, it must be enabled in the Discord developer portal first
+    pub guild_members_intent: bool,
     pub oauth: Option<OAuthSettings>,
     pub server: ServerSettings,
     pub tracing: TracingSettings,
 }
 
 impl Settings {
+    pub fn get_oauth_refresh_interval(&self) -> u64 {
+        self.oauth_refresh_interval.unwrap_or(self.refresh_interval)
+    }
+
+    pub fn get_clan_peak_interval(&self) -> u64 {
+        self.clan_peak_interval.unwrap_or(self.refresh_interval)
+    }
+
     pub fn new() -> Result<Self, ConfigError> {
         info!("Creating settings from configuration file...");
 
@@ -95,6 +133,14 @@ impl Settings {
             .set_default("clan_wars_maps_count", 30)?
             .set_default("clan_wars_contribution_interval", 180)?
             .set_default("commander_orders_retention", 30)?
+            .set_default("playlist_max_songs", 300)?
+            .set_default("playlist_ttl", 90)?
+            .set_default("bl_api_url", DEFAULT_API_URL)?
+            .set_default("bl_api_rate_limit", 10)?
+            .set_default("bl_api_max_attempts", 3)?
+            .set_default("score_contexts", vec!["general"])?
+            .set_default("metrics_enabled", false)?
+            .set_default("guild_members_intent", false)?
             .set_default(
                 "server",
                 ValueKind::Array(vec![
@@ -116,24 +162,79 @@ impl Settings {
                     ));
                 }
 
-                if config.clan_wars_interval < 30 {
+                if config
+                    .oauth_refresh_interval
+                    .is_some_and(|interval| interval < 30)
+                {
                     return Err(ConfigError::Message(
-                        "CLAN_WARS_INTERVAL should be at least 30 minutes".to_owned(),
+                        "OAUTH_REFRESH_INTERVAL should be at least 30 seconds".to_owned(),
                     ));
                 }
 
-                if config.clan_wars_maps_count > 100 {
+                if config
+                    .clan_peak_interval
+                    .is_some_and(|interval| interval < 30)
+                {
                     return Err(ConfigError::Message(
-                        "CLAN_WARS_MAPS_COUNT should not be greater than 100".to_owned(),
+                        "CLAN_PEAK_INTERVAL should be at least 30 seconds".to_owned(),
                     ));
                 }
 
+                if config.clan_wars_interval < 30 {
+                    return Err(ConfigError::Message(
+                        "CLAN_WARS_INTERVAL should be at least 30 minutes".to_owned(),
+                    ));
+                }
+
+                if config.clan_wars_maps_count > CLAN_WARS_MAPS_MAX_COUNT {
+                    return Err(ConfigError::Message(format!(
+                        "CLAN_WARS_MAPS_COUNT should not be greater than {}",
+                        CLAN_WARS_MAPS_MAX_COUNT
+                    )));
+                }
+
                 if config.clan_wars_contribution_interval < 30 {
                     return Err(ConfigError::Message(
                         "CLAN_WARS_CONTRIBUTION_INTERVAL should be at least 30 minutes".to_owned(),
                     ));
                 }
 
+                if config.playlist_max_songs < 1
+                    || config.playlist_max_songs > PLAYLIST_MAX_SONGS_CEILING
+                {
+                    return Err(ConfigError::Message(format!(
+                        "PLAYLIST_MAX_SONGS should be between 1 and {}",
+                        PLAYLIST_MAX_SONGS_CEILING
+                    )));
+                }
+
+                if !reqwest::Url::parse(config.bl_api_url.as_str())
+                    .is_ok_and(|url| url.scheme() == "https" && url.host().is_some())
+                {
+                    return Err(ConfigError::Message(
+                        "BL_API_URL should be a valid https URL".to_owned(),
+                    ));
+                }
+
+                if config.bl_api_rate_limit < 1 || config.bl_api_rate_limit > 50 {
+                    return Err(ConfigError::Message(
+                        "BL_API_RATE_LIMIT should be between 1 and 50 requests per second"
+                            .to_owned(),
+                    ));
+                }
+
+                if config.bl_api_max_attempts < 1 || config.bl_api_max_attempts > 10 {
+                    return Err(ConfigError::Message(
+                        "BL_API_MAX_ATTEMPTS should be between 1 and 10".to_owned(),
+                    ));
+                }
+
+                if !config.score_contexts.contains(&BlContext::General) {
+                    return Err(ConfigError::Message(
+                        "SCORE_CONTEXTS should include general context".to_owned(),
+                    ));
+                }
+
                 info!("Settings created.");
 
                 Ok(config)
@@ -7,7 +7,7 @@ use tracing::{debug, info, trace};
 use crate::beatleader::clan::ClanTag;
 use crate::beatleader::error::Error as BlError;
 use crate::beatleader::player::{
-    MapType, Player as BlPlayer, PlayerId, PlayerScoreParam, PlayerScoreSort,
+    hmd_name, MapType, Player as BlPlayer, PlayerId, PlayerScoreParam, PlayerScoreSort,
 };
 use crate::beatleader::{BlContext, SortOrder};
 use crate::discord::bot::beatleader::score::{fetch_scores, Score};
@@ -15,6 +15,9 @@ use crate::discord::bot::{Metric, PlayerMetricValue};
 use crate::storage::{StorageKey, StorageValue};
 use crate::BL_CLIENT;
 
+pub(crate) const NO_SCORES_DAYS_SINCE_LAST_SCORE: u32 = u32::MAX;
+pub(crate) const PROFILE_VERIFICATION_STEPS: &str = "Log in to the Beat Leader website and link your Discord account under Settings/Account (<https://www.beatleader.com/settings#account>).";
+
 #[derive(Serialize, Deserialize, Default, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 #[serde(default)]
@@ -49,6 +52,8 @@ pub struct Player {
     pub top_pp: f64,
     pub top_stars: f64,
     pub plus_1pp: f64,
+    // none until the player has enough ranked scores to measure it
+    pub acc_consistency: Option<f64>,
     pub total_play_count: u32,
     pub ranked_play_count: u32,
     pub unranked_play_count: u32,
@@ -59,6 +64,9 @@ pub struct Player {
     pub total_replay_watched: u32,
     pub watched_replays: u32,
     pub clans: Vec<String>,
+    pub hmd: String,
+    // as reported by BL, e.g. "steam", "oculuspc" or "oculus" (Quest)
+    pub platform: String,
     pub is_verified: bool,
     #[serde(with = "ts_seconds")]
     pub last_ranked_score_time: DateTime<Utc>,
@@ -72,6 +80,13 @@ pub struct Player {
     pub last_scores_fetch: Option<DateTime<Utc>>,
     #[serde(with = "ts_seconds_option")]
     pub last_ranked_paused_at: Option<DateTime<Utc>>,
+    // sum of pauses in the cached ranked scores
+    pub total_ranked_pauses: u32,
+    #[serde(with = "ts_seconds_option")]
+    pub account_created_at: Option<DateTime<Utc>>,
+    pub replay_watch_subscribed: bool,
+    // total replays watched count the player was last notified about
+    pub replay_watch_notified_count: Option<u32>,
 }
 
 impl StorageKey for UserId {}
@@ -136,11 +151,18 @@ impl Player {
             } else {
                 0.0
             },
+            acc_consistency: if let Some(old_player) = previous {
+                old_player.acc_consistency
+            } else {
+                None
+            },
             total_play_count: bl_player.score_stats.total_play_count,
             ranked_play_count: bl_player.score_stats.ranked_play_count,
             unranked_play_count: bl_player.score_stats.unranked_play_count,
             peak_rank: bl_player.score_stats.peak_rank,
             top1_count: bl_player.score_stats.top1_count,
+            hmd: hmd_name(bl_player.score_stats.top_hmd).to_owned(),
+            platform: bl_player.platform,
             anonymous_replay_watched: bl_player.score_stats.anonymous_replay_watched,
             authorized_replay_watched: bl_player.score_stats.authorized_replay_watched,
             total_replay_watched: bl_player.score_stats.anonymous_replay_watched
@@ -168,6 +190,22 @@ impl Player {
             } else {
                 None
             },
+            total_ranked_pauses: if let Some(player) = previous {
+                player.total_ranked_pauses
+            } else {
+                0
+            },
+            account_created_at: bl_player.created_at,
+            replay_watch_subscribed: if let Some(player) = previous {
+                player.replay_watch_subscribed
+            } else {
+                false
+            },
+            replay_watch_notified_count: if let Some(player) = previous {
+                player.replay_watch_notified_count
+            } else {
+                None
+            },
         }
     }
 
@@ -187,6 +225,39 @@ impl Player {
         self.linked_guilds.contains(guild_id)
     }
 
+    // profiles linked by BL id or URL stay unverified until the Discord account is linked
+    // on the BL website, guilds requiring verified profiles do not accept them
+    pub(crate) fn can_be_linked_to_guild(&self, requires_verified_profile: bool) -> bool {
+        self.is_verified || !requires_verified_profile
+    }
+
+    // only increases since the last notification count, there is nothing to notify about
+    // until the first count is stored
+    pub(crate) fn get_new_replay_watches(&self) -> Option<u32> {
+        match self.replay_watch_notified_count {
+            Some(notified_count) if self.total_replay_watched > notified_count => {
+                Some(self.total_replay_watched - notified_count)
+            }
+            _ => None,
+        }
+    }
+
+    // None if BL does not know when the account was created
+    pub(crate) fn account_age_days(&self) -> Option<u32> {
+        self.account_created_at
+            .filter(|created_at| created_at.timestamp() > 0)
+            .map(|created_at| (Utc::now() - created_at).num_days().max(0) as u32)
+    }
+
+    // players without any score get the sentinel value so they never pass activity requirements
+    pub(crate) fn days_since_last_score(&self) -> u32 {
+        if self.total_play_count == 0 || self.last_score_time.timestamp() <= 0 {
+            return NO_SCORES_DAYS_SINCE_LAST_SCORE;
+        }
+
+        (Utc::now() - self.last_score_time).num_days().max(0) as u32
+    }
+
     pub(crate) fn get_metric_with_value(&self, metric: Metric) -> PlayerMetricValue {
         match metric {
             Metric::TopPp => PlayerMetricValue::TopPp(self.top_pp),
@@ -205,8 +276,47 @@ impl Player {
             Metric::MainClan => PlayerMetricValue::MainClan(self.clans.clone()),
             Metric::TopStars => PlayerMetricValue::TopStars(self.top_stars),
             Metric::LastPause => PlayerMetricValue::LastPause(self.last_ranked_paused_at),
+            Metric::TotalPauses => PlayerMetricValue::TotalPauses(self.total_ranked_pauses),
+            Metric::AccConsistency => PlayerMetricValue::AccConsistency(self.acc_consistency),
+            Metric::DaysSinceLastScore => {
+                PlayerMetricValue::DaysSinceLastScore(self.days_since_last_score())
+            }
+            Metric::Country => PlayerMetricValue::Country(self.country.clone()),
+            Metric::Hmd => PlayerMetricValue::Hmd(self.hmd.clone()),
+            Metric::Platform => PlayerMetricValue::Platform(self.platform.clone()),
+        }
+    }
+}
+
+// players who have not watched any replay are left out, tied players share the same place
+// and all players tied on the last place are included
+pub(crate) fn rank_by_watched_replays(mut players: Vec<Player>, top: u32) -> Vec<(u32, Player)> {
+    players.retain(|player| player.watched_replays > 0);
+    players.sort_unstable_by(|a, b| {
+        b.watched_replays
+            .cmp(&a.watched_replays)
+            .then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase()))
+    });
+
+    let mut ranking: Vec<(u32, Player)> = Vec::with_capacity(players.len());
+    for (idx, player) in players.into_iter().enumerate() {
+        let place = match ranking.last() {
+            Some((last_place, last_player))
+                if last_player.watched_replays == player.watched_replays =>
+            {
+                *last_place
+            }
+            _ => idx as u32 + 1,
+        };
+
+        if place > top {
+            break;
         }
+
+        ranking.push((place, player));
     }
+
+    ranking
 }
 
 pub(crate) async fn fetch_player_from_bl(player_id: &PlayerId) -> Result<BlPlayer, BlError> {
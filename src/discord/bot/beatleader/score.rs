@@ -1,4 +1,5 @@
 use std::borrow::Cow;
+use std::cmp::Ordering;
 use std::fmt;
 use std::fmt::{Display, Formatter};
 use std::sync::Arc;
@@ -15,19 +16,22 @@ use tracing::info;
 
 use crate::beatleader::error::Error as BlError;
 use crate::beatleader::player::{
-    Difficulty, DifficultyStatus, Duration, LeaderboardId, ModifiersRatings, PlayerId,
-    PlayerScoreParam, Score as BlScore,
+    AccuracyTracker, Difficulty, DifficultyStatus, Duration, LeaderboardId, ModifiersRatings,
+    PlayerId, PlayerScoreParam, PlayerScoreSort, Score as BlScore,
 };
-use crate::beatleader::pp::{calculate_pp_boundary, WEIGHT_COEFFICIENT};
+use crate::beatleader::pp::{calculate_pp_boundary, calculate_std_deviation, WEIGHT_COEFFICIENT};
 use crate::beatleader::rating::{AiModifierRating, AiRatingMapCalculation, AiRatings};
-use crate::beatleader::{BlContext, List as BlList};
+use crate::beatleader::{BlContext, List as BlList, SortOrder};
 use crate::discord::bot::beatleader::player::Player;
 use crate::other::string_utils;
-use crate::other::string_utils::capitalize;
+use crate::other::string_utils::{capitalize, format_decimal};
 use crate::storage::player_scores::PlayerScoresRepository;
 use crate::BL_CLIENT;
 
 const DEFAULT_MAX_RATING: f64 = 15.0;
+const ACC_CONSISTENCY_TOP_SCORES: usize = 50;
+// the deviation of a few scores says nothing about consistency
+const ACC_CONSISTENCY_MIN_SCORES: usize = 10;
 
 #[serde_as]
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -73,6 +77,9 @@ pub struct Score {
     pub timeset: DateTime<Utc>,
     #[serde(with = "ts_seconds")]
     pub timepost: DateTime<Utc>,
+    // url of the .bsor file
+    #[serde(default)]
+    pub replay: String,
 }
 
 impl From<BlScore> for Score {
@@ -120,6 +127,7 @@ impl From<BlScore> for Score {
             difficulty_value: bl_score.leaderboard.difficulty.value,
             timeset: bl_score.timeset,
             timepost: bl_score.timepost,
+            replay: bl_score.replay,
         }
     }
 }
@@ -131,6 +139,7 @@ impl Score {
         player: &Player,
         bl_context: &BlContext,
         embed_image: Option<&Vec<u8>>,
+        decimal_places: u8,
     ) -> CreateMessage {
         let mut message = message;
 
@@ -143,7 +152,13 @@ impl Score {
             ));
         }
 
-        message.embed(self.add_embed(CreateEmbed::new(), player, bl_context, with_embed_image))
+        message.embed(self.add_embed(
+            CreateEmbed::new(),
+            player,
+            bl_context,
+            with_embed_image,
+            decimal_places,
+        ))
     }
 
     pub(crate) fn add_embed_to_reply(
@@ -152,6 +167,7 @@ impl Score {
         player: &Player,
         bl_context: &BlContext,
         embed_image: Option<&Vec<u8>>,
+        decimal_places: u8,
     ) -> CreateReply {
         let mut reply = reply;
 
@@ -164,7 +180,35 @@ impl Score {
             ));
         }
 
-        reply.embed(self.add_embed(CreateEmbed::new(), player, bl_context, with_embed_image))
+        reply.embed(self.add_embed(
+            CreateEmbed::new(),
+            player,
+            bl_context,
+            with_embed_image,
+            decimal_places,
+        ))
+    }
+
+    pub(crate) fn compact_stats(&self, decimal_places: u8) -> String {
+        format!(
+            "{}%{} / {}pp / {}",
+            format_decimal(self.accuracy, decimal_places),
+            if self.modifiers.is_empty() {
+                "".to_owned()
+            } else {
+                format!(" {}", self.modifiers)
+            },
+            format_decimal(self.pp, decimal_places),
+            if self.mistakes == 0 {
+                "FC".to_owned()
+            } else {
+                format!(
+                    "{} mistake{}",
+                    self.mistakes,
+                    if self.mistakes > 1 { "s" } else { "" }
+                )
+            }
+        )
     }
 
     pub(crate) fn add_embed(
@@ -173,6 +217,7 @@ impl Score {
         player: &Player,
         bl_context: &BlContext,
         with_embed_image: bool,
+        decimal_places: u8,
     ) -> CreateEmbed {
         let mut desc = "".to_owned();
 
@@ -185,7 +230,10 @@ impl Score {
 
         if let Some(difficulty_rating) = self.difficulty_score_rating.as_ref() {
             if difficulty_rating.stars > 0.0 {
-                desc.push_str(&format!(" / {:.2}⭐", difficulty_rating.stars));
+                desc.push_str(&format!(
+                    " / {}⭐",
+                    format_decimal(difficulty_rating.stars, decimal_places)
+                ));
             }
         }
 
@@ -216,19 +264,34 @@ impl Score {
 
             if self.pp > 0.00 {
                 if self.full_combo {
-                    embed = embed.field("PP", format!("{:.2}", self.pp), true);
+                    embed = embed.field("PP", format_decimal(self.pp, decimal_places), true);
                 } else {
-                    embed =
-                        embed.field("PP", format!("{:.2} ({:.2} FC)", self.pp, self.fc_pp), true);
+                    embed = embed.field(
+                        "PP",
+                        format!(
+                            "{} ({} FC)",
+                            format_decimal(self.pp, decimal_places),
+                            format_decimal(self.fc_pp, decimal_places)
+                        ),
+                        true,
+                    );
                 }
             }
 
             if self.full_combo {
-                embed = embed.field("Acc", format!("{:.2}%", self.accuracy), true);
+                embed = embed.field(
+                    "Acc",
+                    format!("{}%", format_decimal(self.accuracy, decimal_places)),
+                    true,
+                );
             } else {
                 embed = embed.field(
                     "Acc",
-                    format!("{:.2}% ({:.2}% FC)", self.accuracy, self.fc_accuracy),
+                    format!(
+                        "{}% ({}% FC)",
+                        format_decimal(self.accuracy, decimal_places),
+                        format_decimal(self.fc_accuracy, decimal_places)
+                    ),
                     true,
                 );
             }
@@ -244,8 +307,16 @@ impl Score {
                     },
                     true,
                 )
-                .field("Acc Left", format!("{:.2}", self.acc_left), true)
-                .field("Acc Right", format!("{:.2}", self.acc_right), true)
+                .field(
+                    "Acc Left",
+                    format_decimal(self.acc_left, decimal_places),
+                    true,
+                )
+                .field(
+                    "Acc Right",
+                    format_decimal(self.acc_right, decimal_places),
+                    true,
+                )
                 .field("Pauses", self.pauses.to_string(), true)
                 .field("Max combo", self.max_combo.to_string(), true)
                 .field("Max streak", self.max_streak.to_string(), true);
@@ -273,6 +344,104 @@ pub(crate) async fn fetch_scores(
     Ok(BL_CLIENT.player().scores(player_id, params).await?.into())
 }
 
+// scores sorted by rank, the fetching stops at the first score that is not #1
+pub(crate) async fn fetch_top1_scores(
+    player_id: &PlayerId,
+    max_count: u32,
+) -> Result<Vec<Score>, BlError> {
+    const ITEMS_PER_PAGE: u32 = 100;
+
+    let mut top1_scores = Vec::new();
+    let mut page = 1;
+
+    loop {
+        let scores_page = fetch_scores(
+            player_id,
+            &[
+                PlayerScoreParam::Page(page),
+                PlayerScoreParam::Count(ITEMS_PER_PAGE),
+                PlayerScoreParam::Sort(PlayerScoreSort::Rank),
+                PlayerScoreParam::Order(SortOrder::Ascending),
+            ],
+        )
+        .await?;
+
+        let page_is_complete = scores_page.data.len() as u32 >= ITEMS_PER_PAGE;
+        let mut rank_above_1_found = false;
+
+        for score in scores_page.data {
+            if score.rank != 1 {
+                rank_above_1_found = true;
+                break;
+            }
+
+            top1_scores.push(score);
+        }
+
+        if rank_above_1_found
+            || !page_is_complete
+            || top1_scores.len() as u32 >= max_count
+            || page >= scores_page.total.div_ceil(ITEMS_PER_PAGE)
+        {
+            top1_scores.truncate(max_count as usize);
+
+            return Ok(top1_scores);
+        }
+
+        page += 1;
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Top1Group {
+    pub mode_name: String,
+    pub difficulty_name: String,
+    pub count: u32,
+    pub sample_songs: Vec<String>,
+}
+
+// groups are sorted by count, the hardest difficulty first if counts are equal
+pub(crate) fn group_top1_scores(scores: &[Score], samples_count: usize) -> Vec<Top1Group> {
+    let mut groups = Vec::<(u32, Top1Group)>::new();
+
+    for score in scores.iter().filter(|score| score.rank == 1) {
+        let idx = match groups.iter().position(|(_, group)| {
+            group.mode_name == score.difficulty_mode_name
+                && group.difficulty_name == score.difficulty_name
+        }) {
+            Some(idx) => idx,
+            None => {
+                groups.push((
+                    score.difficulty_value,
+                    Top1Group {
+                        mode_name: score.difficulty_mode_name.clone(),
+                        difficulty_name: score.difficulty_name.clone(),
+                        count: 0,
+                        sample_songs: vec![],
+                    },
+                ));
+
+                groups.len() - 1
+            }
+        };
+
+        let group = &mut groups[idx].1;
+        group.count += 1;
+        if group.sample_songs.len() < samples_count {
+            group.sample_songs.push(score.song_name.clone());
+        }
+    }
+
+    groups.sort_by(|(a_value, a), (b_value, b)| {
+        b.count
+            .cmp(&a.count)
+            .then(b_value.cmp(a_value))
+            .then(a.mode_name.cmp(&b.mode_name))
+    });
+
+    groups.into_iter().map(|(_, group)| group).collect()
+}
+
 pub(crate) async fn fetch_ai_ratings(
     hash: &str,
     mode_name: &str,
@@ -281,12 +450,69 @@ pub(crate) async fn fetch_ai_ratings(
     BL_CLIENT.ai_ratings().get(hash, mode_name, value).await
 }
 
+// per-hand swing data of a score, only available if BL has processed its replay
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct HandStats {
+    pub left_preswing: f64,
+    pub right_preswing: f64,
+    pub left_postswing: f64,
+    pub right_postswing: f64,
+}
+
+impl HandStats {
+    // swing completeness of a hand, 1.0 for full swings, lower values show the underswing
+    pub fn left_swing(&self) -> f64 {
+        ((self.left_preswing + self.left_postswing) / 2.0).clamp(0.0, 1.0)
+    }
+
+    pub fn right_swing(&self) -> f64 {
+        ((self.right_preswing + self.right_postswing) / 2.0).clamp(0.0, 1.0)
+    }
+
+    pub fn from_tracker(tracker: &AccuracyTracker) -> Option<Self> {
+        let hand_stats = HandStats {
+            left_preswing: tracker.left_preswing,
+            right_preswing: tracker.right_preswing,
+            left_postswing: tracker.left_postswing,
+            right_postswing: tracker.right_postswing,
+        };
+
+        // a score without swing data for both hands (e.g. one saber) is not worth showing
+        let has_data = [
+            hand_stats.left_preswing,
+            hand_stats.right_preswing,
+            hand_stats.left_postswing,
+            hand_stats.right_postswing,
+        ]
+        .iter()
+        .all(|v| v.is_finite() && *v > 0.0);
+
+        has_data.then_some(hand_stats)
+    }
+}
+
+pub(crate) async fn fetch_hand_stats(score_id: u32) -> Option<HandStats> {
+    match BL_CLIENT.player().score_statistic(score_id).await {
+        Ok(statistic) => statistic
+            .accuracy_tracker
+            .as_ref()
+            .and_then(HandStats::from_tracker),
+        Err(err) => {
+            info!("Score {} statistic is not available: {}", score_id, err);
+
+            None
+        }
+    }
+}
+
 #[derive(Debug, Default)]
 pub(crate) struct ScoreStats {
     pub last_scores_fetch: DateTime<Utc>,
     pub last_ranked_paused_at: Option<DateTime<Utc>>,
+    pub total_ranked_pauses: u32,
     pub top_stars: f64,
     pub plus_1pp: f64,
+    pub acc_consistency: Option<f64>,
 }
 
 pub(crate) async fn fetch_ranked_scores_stats(
@@ -336,6 +562,16 @@ pub(crate) async fn fetch_ranked_scores_stats(
         }
     });
 
+    let total_ranked_pauses = player_scores
+        .scores
+        .iter()
+        .map(|score| score.pauses)
+        .sum::<u32>();
+
+    let mut top_scores = player_scores.scores.iter().collect::<Vec<_>>();
+    top_scores.sort_unstable_by(|a, b| b.pp.partial_cmp(&a.pp).unwrap_or(Ordering::Equal));
+    let acc_consistency = get_acc_consistency(&top_scores);
+
     let plus_1pp = calculate_pp_boundary(WEIGHT_COEFFICIENT, &mut pps, 1.0);
 
     info!("Ranked scores stats of {} updated.", player.name);
@@ -344,10 +580,76 @@ pub(crate) async fn fetch_ranked_scores_stats(
         last_scores_fetch: Utc::now(),
         top_stars,
         last_ranked_paused_at,
+        total_ranked_pauses,
         plus_1pp,
+        acc_consistency,
     }))
 }
 
+// standard deviation of the accuracy of the top pp scores
+pub(crate) fn get_acc_consistency(top_scores: &[&Score]) -> Option<f64> {
+    if top_scores.len() < ACC_CONSISTENCY_MIN_SCORES {
+        return None;
+    }
+
+    Some(calculate_std_deviation(
+        &top_scores
+            .iter()
+            .take(ACC_CONSISTENCY_TOP_SCORES)
+            .map(|score| score.accuracy)
+            .collect::<Vec<f64>>(),
+    ))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct WeeklyPauses {
+    pub since: DateTime<Utc>,
+    pub plays: u32,
+    pub paused_plays: u32,
+    pub pauses: u32,
+}
+
+impl WeeklyPauses {
+    pub fn pauses_per_play(&self) -> f64 {
+        if self.plays == 0 {
+            0.0
+        } else {
+            self.pauses as f64 / self.plays as f64
+        }
+    }
+}
+
+// rolling 7-day windows ending now, the most recent week first; weeks without plays are kept,
+// so the trend is not skewed by the weeks the player did not play
+pub(crate) fn weekly_pause_report(
+    scores: &[Score],
+    now: DateTime<Utc>,
+    weeks: u32,
+) -> Vec<WeeklyPauses> {
+    let mut report = (1..=weeks as i32)
+        .map(|week| WeeklyPauses {
+            since: now - chrono::Duration::weeks(week as i64),
+            plays: 0,
+            paused_plays: 0,
+            pauses: 0,
+        })
+        .collect::<Vec<_>>();
+
+    for score in scores.iter().filter(|score| score.timepost <= now) {
+        let week = (now - score.timepost).num_weeks() as usize;
+
+        if let Some(weekly) = report.get_mut(week) {
+            weekly.plays += 1;
+            weekly.pauses += score.pauses;
+            if score.pauses > 0 {
+                weekly.paused_plays += 1;
+            }
+        }
+    }
+
+    report
+}
+
 #[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
 pub enum MapRatingModifier {
     #[default]
@@ -402,23 +704,27 @@ pub struct MapRatings {
 }
 
 impl MapRatings {
-    pub fn to_stars_string(&self, bold_modifier: Option<MapRatingModifier>) -> String {
+    pub fn to_stars_string(
+        &self,
+        bold_modifier: Option<MapRatingModifier>,
+        decimal_places: u8,
+    ) -> String {
         let ss = self
             .ss
             .as_ref()
-            .map_or_else(|| "-".to_owned(), |s| s.to_stars_string());
+            .map_or_else(|| "-".to_owned(), |s| s.to_stars_string(decimal_places));
         let none = self
             .none
             .as_ref()
-            .map_or_else(|| "-".to_owned(), |s| s.to_stars_string());
+            .map_or_else(|| "-".to_owned(), |s| s.to_stars_string(decimal_places));
         let fs = self
             .fs
             .as_ref()
-            .map_or_else(|| "-".to_owned(), |s| s.to_stars_string());
+            .map_or_else(|| "-".to_owned(), |s| s.to_stars_string(decimal_places));
         let sf = self
             .sf
             .as_ref()
-            .map_or_else(|| "-".to_owned(), |s| s.to_stars_string());
+            .map_or_else(|| "-".to_owned(), |s| s.to_stars_string(decimal_places));
 
         format!(
             "{} SS / {} / {} FS / {} SF",
@@ -540,8 +846,8 @@ impl MapRating {
         }
     }
 
-    pub fn to_stars_string(&self) -> String {
-        format!("{:.2}⭐", self.stars)
+    pub fn to_stars_string(&self, decimal_places: u8) -> String {
+        format!("{}⭐", format_decimal(self.stars, decimal_places))
     }
 
     pub fn from_ai_ratings_and_modifier(ratings: &AiRatings, modifier: MapRatingModifier) -> Self {
@@ -1,14 +1,21 @@
-use poise::serenity_prelude::{Attachment, CreateAttachment};
+use poise::serenity_prelude::{
+    Attachment, CreateAttachment, CreateEmbed, GuildId, Role, RoleId, UserId,
+};
 use poise::CreateReply;
 use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use tracing::info;
 
 use crate::discord::bot::beatleader::player::Player as BotPlayer;
-use crate::discord::bot::GuildSettings;
+use crate::discord::bot::commands::guild::{get_guild_id, get_guild_settings, say_guild_settings};
+use crate::discord::bot::{GuildSettings, RoleGroup, RoleSettings, DISCORD_ATTACHMENT_MAX_SIZE};
 use crate::discord::Context;
+use crate::other::ram_reporter::PEAK_ALLOC;
 use crate::storage::bsmaps::BsMap;
 use crate::storage::player_oauth_token::PlayerOAuthToken;
-use crate::Error;
+use crate::storage::StorageError;
+use crate::{Error, BINARY_FILE_CACHE};
 
 #[derive(Serialize, Deserialize, Default, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
@@ -18,6 +25,32 @@ struct BotData {
     players: Vec<BotPlayer>,
     player_oauth_tokens: Vec<PlayerOAuthToken>,
     maps: Vec<BsMap>,
+    // role names of every guild, used to map roles when the backup is imported to another guild
+    #[serde(default)]
+    role_names: HashMap<GuildId, HashMap<RoleId, String>>,
+}
+
+const BACKUP_SEND_MAX_ATTEMPTS: u32 = 3;
+// bl-import accepts this many part attachments
+const BACKUP_MAX_PARTS: usize = 8;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct BackupPart {
+    file_name: String,
+    size: usize,
+    crc32: u32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct BackupManifest {
+    version: String,
+    backup_id: String,
+    // total size of the backup in bytes
+    size: usize,
+    // in the order the parts are joined in
+    parts: Vec<BackupPart>,
 }
 
 /// Export bot data
@@ -41,36 +74,263 @@ pub(crate) async fn cmd_export(ctx: Context<'_>) -> Result<(), Error> {
 
     ctx.defer_ephemeral().await?;
 
+    let guilds = ctx.data().guild_settings_repository.all().await;
+
+    let mut role_names = HashMap::with_capacity(guilds.len());
+    for guild_settings in guilds.iter() {
+        // the bot may no longer be a member of the guild
+        if let Ok(roles) = guild_settings.get_key().roles(ctx).await {
+            role_names.insert(
+                guild_settings.get_key(),
+                roles
+                    .into_iter()
+                    .map(|(role_id, role)| (role_id, role.name))
+                    .collect(),
+            );
+        }
+    }
+
     let data = BotData {
         version: env!("CARGO_PKG_VERSION").to_owned(),
-        guilds: ctx.data().guild_settings_repository.all().await,
+        guilds,
         players: ctx.data().players_repository.all().await,
         player_oauth_tokens: ctx.data().player_oauth_token_repository.all().await,
         maps: ctx.data().maps_repository.all().await,
+        role_names,
     };
 
-    match serde_json::to_string::<BotData>(&data) {
-        Ok(data_json) => {
-            ctx.send(
+    let data_json = match serde_json::to_string::<BotData>(&data) {
+        Ok(data_json) => data_json.into_bytes(),
+        Err(err) => {
+            ctx.say(format!("An error occurred: {}", err)).await?;
+
+            return Ok(());
+        }
+    };
+
+    // attachments above Discord's upload limit are rejected, so larger backups are split into parts
+    if data_json.len() <= DISCORD_ATTACHMENT_MAX_SIZE {
+        if let Err(err) = send_backup_file(
+            ctx,
+            "Requested backup:".to_owned(),
+            "bl-bot-backup.json".to_owned(),
+            data_json,
+        )
+        .await
+        {
+            ctx.say(format!("Backup could not be sent: {}", err))
+                .await?;
+        }
+
+        return Ok(());
+    }
+
+    let (manifest, parts) = split_backup(&data_json, DISCORD_ATTACHMENT_MAX_SIZE);
+
+    if parts.len() > BACKUP_MAX_PARTS {
+        ctx.say(format!(
+            "Backup is too large: it would need {} parts, but only {} parts can be imported.",
+            parts.len(),
+            BACKUP_MAX_PARTS
+        ))
+        .await?;
+
+        return Ok(());
+    }
+
+    info!(
+        "Backup {} is too large for a single attachment, sending it in {} parts...",
+        manifest.backup_id,
+        parts.len()
+    );
+
+    let manifest_json = match serde_json::to_string::<BackupManifest>(&manifest) {
+        Ok(manifest_json) => manifest_json.into_bytes(),
+        Err(err) => {
+            ctx.say(format!("An error occurred: {}", err)).await?;
+
+            return Ok(());
+        }
+    };
+
+    if let Err(err) = send_backup_file(
+        ctx,
+        format!(
+            "Requested backup is split into {} parts, import the manifest together with all the parts:",
+            parts.len()
+        ),
+        format!("bl-bot-backup-{}-manifest.json", manifest.backup_id),
+        manifest_json,
+    )
+    .await
+    {
+        ctx.say(format!("Backup could not be sent: {}", err))
+            .await?;
+
+        return Ok(());
+    }
+
+    for (idx, (part, bytes)) in manifest.parts.iter().zip(parts).enumerate() {
+        // a backup missing any part can not be imported, so do not send the remaining ones
+        if let Err(err) = send_backup_file(
+            ctx,
+            format!("Part {} of {}:", idx + 1, manifest.parts.len()),
+            part.file_name.clone(),
+            bytes,
+        )
+        .await
+        {
+            ctx.say(format!(
+                "Export aborted, part {} of {} could not be sent: {}. The backup is incomplete and can not be imported, export it again.",
+                idx + 1,
+                manifest.parts.len(),
+                err
+            ))
+            .await?;
+
+            return Ok(());
+        }
+    }
+
+    Ok(())
+}
+
+// large attachments fail to upload from time to time, so every file is retried a few times
+async fn send_backup_file(
+    ctx: Context<'_>,
+    content: String,
+    file_name: String,
+    bytes: Vec<u8>,
+) -> Result<(), Error> {
+    let mut attempt = 1;
+
+    loop {
+        match ctx
+            .send(
                 CreateReply::default()
-                    .content("Requested backup:")
+                    .content(content.clone())
                     .attachment(CreateAttachment::bytes(
-                        Cow::from(data_json.into_bytes()),
-                        "bl-bot-backup.json".to_owned(),
+                        Cow::from(bytes.clone()),
+                        file_name.clone(),
                     ))
                     .ephemeral(true),
             )
-            .await?;
+            .await
+        {
+            Ok(_) => return Ok(()),
+            Err(err) if attempt < BACKUP_SEND_MAX_ATTEMPTS => {
+                tracing::warn!(
+                    "Can not send backup file {} (attempt {}): {}, retrying...",
+                    &file_name,
+                    attempt,
+                    err
+                );
+
+                attempt += 1;
+            }
+            Err(err) => {
+                tracing::error!(
+                    "Can not send backup file {} (attempt {}): {}",
+                    &file_name,
+                    attempt,
+                    err
+                );
+
+                return Err(err.into());
+            }
         }
-        Err(err) => {
-            ctx.say(format!("An error occurred: {}", err)).await?;
+    }
+}
+
+// backups larger than a single attachment are split into raw byte parts described by the manifest
+fn split_backup(data: &[u8], part_max_bytes: usize) -> (BackupManifest, Vec<Vec<u8>>) {
+    let backup_id = uuid::Uuid::new_v4().simple().to_string()[..8].to_owned();
+    let parts = data
+        .chunks(part_max_bytes)
+        .map(|chunk| chunk.to_vec())
+        .collect::<Vec<_>>();
+    let parts_count = parts.len();
+
+    let manifest = BackupManifest {
+        version: env!("CARGO_PKG_VERSION").to_owned(),
+        backup_id: backup_id.clone(),
+        size: data.len(),
+        parts: parts
+            .iter()
+            .enumerate()
+            .map(|(idx, bytes)| BackupPart {
+                file_name: format!(
+                    "bl-bot-backup-{}-{}-of-{}.part",
+                    backup_id,
+                    idx + 1,
+                    parts_count
+                ),
+                size: bytes.len(),
+                crc32: crc32fast::hash(bytes),
+            })
+            .collect(),
+    };
+
+    (manifest, parts)
+}
+
+// parts are keyed by file name, so they can be attached in any order
+fn assemble_backup(
+    manifest: &BackupManifest,
+    mut parts: HashMap<String, Vec<u8>>,
+) -> Result<Vec<u8>, String> {
+    let mut data = Vec::with_capacity(manifest.size);
+
+    for part in manifest.parts.iter() {
+        let Some(bytes) = parts.remove(&part.file_name) else {
+            return Err(format!("Backup part {} is missing.", part.file_name));
+        };
+
+        if bytes.len() != part.size || crc32fast::hash(&bytes) != part.crc32 {
+            return Err(format!("Backup part {} is corrupted.", part.file_name));
         }
+
+        data.extend(bytes);
+    }
+
+    if data.len() != manifest.size {
+        return Err("Backup size does not match the manifest.".to_owned());
+    }
+
+    Ok(data)
+}
+
+async fn download_backup(
+    backup_json: &Attachment,
+    parts: &[Attachment],
+) -> Result<Vec<u8>, String> {
+    let bytes = backup_json
+        .download()
+        .await
+        .map_err(|err| format!("An error occurred during downloading attachment: {}", err))?;
+
+    // anything that is not a manifest is treated as a single file backup
+    let Ok(manifest) = serde_json::from_slice::<BackupManifest>(&bytes) else {
+        return Ok(bytes);
     };
 
-    Ok(())
+    let mut downloaded = HashMap::with_capacity(parts.len());
+    for part in parts {
+        let bytes = part.download().await.map_err(|err| {
+            format!(
+                "An error occurred during downloading attachment {}: {}",
+                part.filename, err
+            )
+        })?;
+
+        downloaded.insert(part.filename.clone(), bytes);
+    }
+
+    assemble_backup(&manifest, downloaded)
 }
 
 /// Import bot data
+#[allow(clippy::too_many_arguments)]
 #[tracing::instrument(skip(ctx), level=tracing::Level::INFO, name="bot_command:bl-import")]
 #[poise::command(
     slash_command,
@@ -84,7 +344,18 @@ pub(crate) async fn cmd_export(ctx: Context<'_>) -> Result<(), Error> {
 )]
 pub(crate) async fn cmd_import(
     ctx: Context<'_>,
-    #[description = "bl-bot-backup.json"] backup_json: Attachment,
+    #[description = "bl-bot-backup.json or the manifest of a backup split into parts"]
+    backup_json: Attachment,
+    #[description = "Id of the backed up server whose settings should be copied to this server"]
+    from_guild_id: Option<String>,
+    #[description = "Backup part 1"] part_1: Option<Attachment>,
+    #[description = "Backup part 2"] part_2: Option<Attachment>,
+    #[description = "Backup part 3"] part_3: Option<Attachment>,
+    #[description = "Backup part 4"] part_4: Option<Attachment>,
+    #[description = "Backup part 5"] part_5: Option<Attachment>,
+    #[description = "Backup part 6"] part_6: Option<Attachment>,
+    #[description = "Backup part 7"] part_7: Option<Attachment>,
+    #[description = "Backup part 8"] part_8: Option<Attachment>,
 ) -> Result<(), Error> {
     let is_bot_owner = ctx.framework().options().owners.contains(&ctx.author().id);
     if !is_bot_owner {
@@ -94,57 +365,344 @@ pub(crate) async fn cmd_import(
 
     ctx.defer_ephemeral().await?;
 
-    match backup_json.download().await {
+    let parts: [_; BACKUP_MAX_PARTS] = [
+        part_1, part_2, part_3, part_4, part_5, part_6, part_7, part_8,
+    ];
+    let parts = parts.into_iter().flatten().collect::<Vec<_>>();
+
+    let bytes = match download_backup(&backup_json, &parts).await {
+        Ok(bytes) => bytes,
+        Err(err) => {
+            ctx.say(err).await?;
+
+            return Ok(());
+        }
+    };
+
+    let mut data = match String::from_utf8(bytes) {
+        Ok(json) => match serde_json::from_str::<BotData>(json.as_str()) {
+            Ok(data) => data,
+            Err(err) => {
+                ctx.say(format!(
+                    "An error occurred during deserializing attachment: {}",
+                    err
+                ))
+                .await?;
+
+                return Ok(());
+            }
+        },
+        Err(err) => {
+            ctx.say(format!(
+                "An error occurred during converting attachment to utf8: {}",
+                err
+            ))
+            .await?;
+
+            return Ok(());
+        }
+    };
+
+    let mut unmapped = None;
+
+    if let Some(from_guild_id) = from_guild_id {
+        let Some(from_guild_id) = from_guild_id
+            .trim()
+            .parse::<u64>()
+            .ok()
+            .filter(|id| *id != 0)
+            .map(GuildId::new)
+        else {
+            ctx.say("Invalid server id.").await?;
+
+            return Ok(());
+        };
+
+        let guild_id = get_guild_id(ctx, true).await?;
+
+        let guild_roles = match guild_id.roles(ctx).await {
+            Ok(roles) => roles,
+            Err(err) => {
+                ctx.say(format!("An error occurred: {}", err)).await?;
+
+                return Ok(());
+            }
+        };
+
+        // older backups do not contain role names, the bot may still be a member of the backed up server
+        if data
+            .role_names
+            .get(&from_guild_id)
+            .is_none_or(|role_names| role_names.is_empty())
+        {
+            if let Ok(roles) = from_guild_id.roles(ctx).await {
+                data.role_names.insert(
+                    from_guild_id,
+                    roles
+                        .into_iter()
+                        .map(|(role_id, role)| (role_id, role.name))
+                        .collect(),
+                );
+            }
+        }
+
+        let mut member_ids = HashSet::new();
+        for player in data
+            .players
+            .iter()
+            .filter(|player| player.is_linked_to_guild(&from_guild_id))
+        {
+            if guild_id.member(ctx, player.user_id).await.is_ok() {
+                member_ids.insert(player.user_id);
+            }
+        }
+
+        match rehome(
+            &mut data,
+            from_guild_id,
+            guild_id,
+            &guild_roles,
+            &member_ids,
+        ) {
+            Some(roles) => unmapped = Some(roles),
+            None => {
+                ctx.say(format!("Server {} not found in the backup.", from_guild_id))
+                    .await?;
+
+                return Ok(());
+            }
+        }
+    }
+
+    if let Err(err) = ctx
+        .data()
+        .guild_settings_repository
+        .restore(data.guilds)
+        .await
+    {
+        ctx.say(format!(
+            "An error occurred during restoring guild settings: {}",
+            err
+        ))
+        .await?;
+
+        return Ok(());
+    }
+
+    if let Err(err) = ctx.data().players_repository.restore(data.players).await {
+        ctx.say(format!(
+            "An error occurred during restoring linked players: {}",
+            err
+        ))
+        .await?;
+
+        return Ok(());
+    }
+
+    if let Err(err) = ctx
+        .data()
+        .player_oauth_token_repository
+        .restore(data.player_oauth_tokens)
+        .await
+    {
+        ctx.say(format!(
+            "An error occurred during restoring oauth tokens: {}",
+            err
+        ))
+        .await?;
+
+        return Ok(());
+    }
+
+    if let Err(err) = ctx.data().maps_repository.restore(data.maps).await {
+        ctx.say(format!("An error occurred during restoring maps: {}", err))
+            .await?;
+
+        return Ok(());
+    }
+
+    match unmapped {
+        None => {
+            ctx.say("Data successfully restored.").await?;
+        }
+        Some(unmapped) if unmapped.is_empty() => {
+            ctx.say("Data successfully restored. Channels of this server need to be set again.")
+                .await?;
+        }
+        Some(unmapped) => {
+            ctx.say(format!(
+                "Data successfully restored. Channels of this server need to be set again. Could not map roles (they were removed from the settings): {}",
+                unmapped.join(", ")
+            ))
+            .await?;
+        }
+    }
+
+    Ok(())
+}
+
+// copies settings of the backed up guild to the target guild, mapping roles by name, and links
+// players that are members of the target guild to it;
+// returns names of the roles that could not be mapped or None if the guild is not in the backup
+fn rehome(
+    data: &mut BotData,
+    from_guild_id: GuildId,
+    guild_id: GuildId,
+    guild_roles: &HashMap<RoleId, Role>,
+    member_ids: &HashSet<UserId>,
+) -> Option<Vec<String>> {
+    let mut guild_settings = data
+        .guilds
+        .iter()
+        .find(|guild_settings| guild_settings.get_key() == from_guild_id)?
+        .clone();
+
+    let role_names = data.role_names.get(&from_guild_id);
+    let role_name = |role_id: RoleId| role_names.and_then(|names| names.get(&role_id));
+
+    let unmapped = guild_settings.rehome(guild_id, |role_id| {
+        role_name(role_id).and_then(|name| {
+            guild_roles
+                .values()
+                .find(|role| &role.name == name)
+                .map(|role| role.id)
+        })
+    });
+
+    data.guilds
+        .retain(|guild_settings| guild_settings.get_key() != guild_id);
+    data.guilds.push(guild_settings);
+
+    for player in data.players.iter_mut() {
+        if player.is_linked_to_guild(&from_guild_id)
+            && !player.is_linked_to_guild(&guild_id)
+            && member_ids.contains(&player.user_id)
+        {
+            player.linked_guilds.push(guild_id);
+        }
+    }
+
+    Some(
+        unmapped
+            .into_iter()
+            .map(|role_id| match role_name(role_id) {
+                Some(name) => format!("{} ({})", name, role_id),
+                None => role_id.to_string(),
+            })
+            .collect(),
+    )
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+struct AutoRole {
+    group: RoleGroup,
+    role_name: String,
+    // role ids are not shared between guilds, so the prerequisite is mapped by name too
+    #[serde(default)]
+    required_role_name: String,
+    settings: RoleSettings,
+}
+
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+struct AutoRolesData {
+    version: String,
+    auto_roles: Vec<AutoRole>,
+}
+
+/// Export auto roles settings
+#[tracing::instrument(skip(ctx), level=tracing::Level::INFO, name="bot_command:bl-export-roles")]
+#[poise::command(
+    slash_command,
+    rename = "bl-export-roles",
+    ephemeral,
+    required_permissions = "MANAGE_ROLES",
+    default_member_permissions = "MANAGE_ROLES",
+    required_bot_permissions = "MANAGE_ROLES",
+    guild_only,
+    hide_in_help
+)]
+pub(crate) async fn cmd_export_roles(ctx: Context<'_>) -> Result<(), Error> {
+    ctx.defer_ephemeral().await?;
+
+    let guild_settings = get_guild_settings(ctx, true).await?;
+
+    let guild_roles = match guild_settings.get_key().roles(ctx).await {
+        Ok(roles) => roles,
+        Err(err) => {
+            ctx.say(format!("An error occurred: {}", err)).await?;
+
+            return Ok(());
+        }
+    };
+
+    let data = AutoRolesData {
+        version: env!("CARGO_PKG_VERSION").to_owned(),
+        auto_roles: guild_settings
+            .get_auto_roles()
+            .into_iter()
+            .map(|(group, settings)| AutoRole {
+                group,
+                role_name: guild_roles
+                    .get(&settings.get_role_id())
+                    .map(|role| role.name.clone())
+                    .unwrap_or_default(),
+                required_role_name: settings
+                    .get_required_role()
+                    .and_then(|required_role| guild_roles.get(&required_role))
+                    .map(|role| role.name.clone())
+                    .unwrap_or_default(),
+                settings,
+            })
+            .collect(),
+    };
+
+    match serde_json::to_string::<AutoRolesData>(&data) {
+        Ok(data_json) => {
+            ctx.send(
+                CreateReply::default()
+                    .content("Auto roles settings:")
+                    .attachment(CreateAttachment::bytes(
+                        Cow::from(data_json.into_bytes()),
+                        "bl-bot-auto-roles.json".to_owned(),
+                    ))
+                    .ephemeral(true),
+            )
+            .await?;
+        }
+        Err(err) => {
+            ctx.say(format!("An error occurred: {}", err)).await?;
+        }
+    };
+
+    Ok(())
+}
+
+/// Import auto roles settings
+#[tracing::instrument(skip(ctx), level=tracing::Level::INFO, name="bot_command:bl-import-roles")]
+#[poise::command(
+    slash_command,
+    rename = "bl-import-roles",
+    ephemeral,
+    required_permissions = "MANAGE_ROLES",
+    default_member_permissions = "MANAGE_ROLES",
+    required_bot_permissions = "MANAGE_ROLES",
+    guild_only,
+    hide_in_help
+)]
+pub(crate) async fn cmd_import_roles(
+    ctx: Context<'_>,
+    #[description = "bl-bot-auto-roles.json"] roles_json: Attachment,
+) -> Result<(), Error> {
+    ctx.defer_ephemeral().await?;
+
+    let guild_id = get_guild_id(ctx, true).await?;
+
+    let data = match roles_json.download().await {
         Ok(bytes) => match String::from_utf8(bytes) {
-            Ok(json) => match serde_json::from_str::<BotData>(json.as_str()) {
-                Ok(data) => {
-                    if let Err(err) = ctx
-                        .data()
-                        .guild_settings_repository
-                        .restore(data.guilds)
-                        .await
-                    {
-                        ctx.say(format!(
-                            "An error occurred during restoring guild settings: {}",
-                            err
-                        ))
-                        .await?;
-
-                        return Ok(());
-                    }
-
-                    if let Err(err) = ctx.data().players_repository.restore(data.players).await {
-                        ctx.say(format!(
-                            "An error occurred during restoring linked players: {}",
-                            err
-                        ))
-                        .await?;
-
-                        return Ok(());
-                    }
-
-                    if let Err(err) = ctx
-                        .data()
-                        .player_oauth_token_repository
-                        .restore(data.player_oauth_tokens)
-                        .await
-                    {
-                        ctx.say(format!(
-                            "An error occurred during restoring oauth tokens: {}",
-                            err
-                        ))
-                        .await?;
-
-                        return Ok(());
-                    }
-
-                    if let Err(err) = ctx.data().maps_repository.restore(data.maps).await {
-                        ctx.say(format!("An error occurred during restoring maps: {}", err))
-                            .await?;
-
-                        return Ok(());
-                    }
-                }
+            Ok(json) => match serde_json::from_str::<AutoRolesData>(json.as_str()) {
+                Ok(data) => data,
                 Err(err) => {
                     ctx.say(format!(
                         "An error occurred during deserializing attachment: {}",
@@ -176,7 +734,309 @@ pub(crate) async fn cmd_import(
         }
     };
 
-    ctx.say("Data successfully restored.").await?;
+    let guild_roles = match guild_id.roles(ctx).await {
+        Ok(roles) => roles,
+        Err(err) => {
+            ctx.say(format!("An error occurred: {}", err)).await?;
+
+            return Ok(());
+        }
+    };
+
+    let mut unmapped = Vec::new();
+    let mut auto_roles = Vec::with_capacity(data.auto_roles.len());
+
+    // role ids are not shared between guilds, fall back to the role name
+    let map_role = |role_id: RoleId, role_name: &str| {
+        if guild_roles.contains_key(&role_id) {
+            return Some(role_id);
+        }
+
+        guild_roles
+            .values()
+            .find(|role| !role_name.is_empty() && role.name == role_name)
+            .map(|role| role.id)
+    };
+
+    for mut auto_role in data.auto_roles {
+        let Some(role_id) = map_role(auto_role.settings.get_role_id(), &auto_role.role_name) else {
+            unmapped.push(format!("{} / {}", auto_role.group, auto_role.role_name));
+            continue;
+        };
+        auto_role.settings.set_role_id(role_id);
+
+        if let Some(required_role) = auto_role.settings.get_required_role() {
+            let mapped_required_role = map_role(required_role, &auto_role.required_role_name);
+            if mapped_required_role.is_none() {
+                unmapped.push(format!(
+                    "{} / {} prerequisite {}",
+                    auto_role.group, auto_role.role_name, auto_role.required_role_name
+                ));
+            }
+            auto_role.settings.set_required_role(mapped_required_role);
+        }
+
+        auto_roles.push((auto_role.group, auto_role.settings));
+    }
+
+    if auto_roles.is_empty() {
+        ctx.say("No auto role could be mapped to roles of this server.")
+            .await?;
+
+        return Ok(());
+    }
+
+    match ctx
+        .data()
+        .guild_settings_repository
+        .merge_auto_roles(guild_id, auto_roles)
+        .await
+    {
+        Ok(guild_settings) => {
+            say_guild_settings(ctx, &guild_settings).await?;
+
+            if !unmapped.is_empty() {
+                ctx.say(format!(
+                    "Could not map roles (create them and import again): {}",
+                    unmapped.join(", ")
+                ))
+                .await?;
+            }
+        }
+        Err(e) => {
+            ctx.say(format!("An error occurred: {}", e)).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Remove orphaned storage files and rebuild storage indexes
+#[tracing::instrument(skip(ctx), level=tracing::Level::INFO, name="bot_command:bl-compact-storage")]
+#[poise::command(
+    slash_command,
+    rename = "bl-compact-storage",
+    ephemeral,
+    required_permissions = "MANAGE_ROLES",
+    default_member_permissions = "MANAGE_ROLES",
+    required_bot_permissions = "MANAGE_ROLES",
+    guild_only,
+    hide_in_help
+)]
+pub(crate) async fn cmd_compact_storage(ctx: Context<'_>) -> Result<(), Error> {
+    let is_bot_owner = ctx.framework().options().owners.contains(&ctx.author().id);
+    if !is_bot_owner {
+        ctx.say("Can only be used by bot owner").await?;
+        return Ok(());
+    }
+
+    ctx.defer_ephemeral().await?;
+
+    let data = ctx.data();
+
+    let result = async {
+        let mut stats = vec![
+            data.guild_settings_repository.compact().await?,
+            data.player_oauth_token_repository.compact().await?,
+            data.playlists_repository.compact().await?,
+            data.maps_repository.compact().await?,
+            data.clan_peak_repository.compact().await?,
+            data.role_change_repository.compact().await?,
+            data.clan_wars_post_repository.compact().await?,
+            data.clan_recap_repository.compact().await?,
+        ];
+        stats.extend(data.players_repository.compact().await?);
+        stats.extend(data.clan_map_capture_repository.compact().await?);
+
+        Ok::<_, StorageError>(stats)
+    }
+    .await;
+
+    match result {
+        Ok(stats) => {
+            let removed = stats.iter().map(|s| s.removed).sum::<usize>();
+            let restored = stats.iter().map(|s| s.restored).sum::<usize>();
+
+            info!(
+                "Storage compacted, orphaned files removed: {}, missing files restored: {}",
+                removed, restored
+            );
+
+            ctx.say(format!(
+                "Storage compacted:\n{}",
+                stats
+                    .iter()
+                    .map(|s| format!("- {}", s))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            ))
+            .await?;
+        }
+        Err(err) => {
+            ctx.say(format!("An error occurred: {}", err)).await?;
+        }
+    }
 
     Ok(())
 }
+
+/// Show storage and cache sizes
+#[tracing::instrument(skip(ctx), level=tracing::Level::INFO, name="bot_command:bl-stats")]
+#[poise::command(
+    slash_command,
+    rename = "bl-stats",
+    ephemeral,
+    required_permissions = "MANAGE_ROLES",
+    default_member_permissions = "MANAGE_ROLES",
+    required_bot_permissions = "MANAGE_ROLES",
+    guild_only,
+    hide_in_help
+)]
+pub(crate) async fn cmd_stats(ctx: Context<'_>) -> Result<(), Error> {
+    let is_bot_owner = ctx.framework().options().owners.contains(&ctx.author().id);
+    if !is_bot_owner {
+        ctx.say("Can only be used by bot owner").await?;
+        return Ok(());
+    }
+
+    let data = ctx.data();
+
+    let mut scores = Vec::new();
+    for player_scores_repository in data.player_scores_repositories.all() {
+        scores.push(format!(
+            "{}: {}",
+            player_scores_repository.bl_context,
+            match player_scores_repository.len().await {
+                Ok(len) => len.to_string(),
+                Err(err) => format!("error ({})", err),
+            }
+        ));
+    }
+    scores.sort_unstable();
+
+    let repositories = [
+        ("Guild settings", data.guild_settings_repository.len().await),
+        ("Players", data.players_repository.len().await),
+        ("Playlists", data.playlists_repository.len().await),
+        ("Maps", data.maps_repository.len().await),
+        (
+            "OAuth tokens",
+            data.player_oauth_token_repository.len().await,
+        ),
+        ("Clan peaks", data.clan_peak_repository.len().await),
+        (
+            "Clan map captures",
+            data.clan_map_capture_repository.len().await,
+        ),
+        ("Role changes", data.role_change_repository.len().await),
+        (
+            "Clan wars posts",
+            data.clan_wars_post_repository.len().await,
+        ),
+        ("Clan recaps", data.clan_recap_repository.len().await),
+    ];
+
+    let mut embed = CreateEmbed::new().title("Bot stats");
+    for (name, len) in repositories {
+        embed = embed.field(name, len.to_string(), true);
+    }
+
+    embed = embed
+        .field("Player scores", scores.join("\n"), false)
+        .field(
+            "RAM",
+            format!(
+                "{:.1} MB (peak: {:.1} MB)",
+                PEAK_ALLOC.current_usage_as_mb(),
+                PEAK_ALLOC.peak_usage_as_mb()
+            ),
+            true,
+        )
+        .field(
+            "File cache",
+            format!(
+                "{:.1} MB",
+                BINARY_FILE_CACHE.size_in_bytes() as f64 / (1024.0 * 1024.0)
+            ),
+            true,
+        );
+
+    ctx.send(CreateReply::default().embed(embed).ephemeral(true))
+        .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{HashMap, HashSet};
+
+    use poise::serenity_prelude::{GuildId, UserId};
+
+    use crate::discord::bot::beatleader::player::Player as BotPlayer;
+    use crate::discord::bot::commands::backup::{assemble_backup, rehome, split_backup, BotData};
+    use crate::discord::bot::GuildSettings;
+
+    #[test]
+    fn it_splits_and_assembles_backup() {
+        let data = b"0123456789".to_vec();
+
+        let (manifest, parts) = split_backup(&data, 4);
+        assert_eq!(manifest.size, 10);
+        assert_eq!(
+            parts.iter().map(|part| part.len()).collect::<Vec<_>>(),
+            vec![4, 4, 2]
+        );
+
+        let files = manifest
+            .parts
+            .iter()
+            .map(|part| part.file_name.clone())
+            .zip(parts)
+            .collect::<HashMap<_, _>>();
+
+        assert_eq!(assemble_backup(&manifest, files.clone()), Ok(data));
+
+        let mut missing = files.clone();
+        missing.remove(&manifest.parts[1].file_name);
+        assert!(assemble_backup(&manifest, missing).is_err());
+
+        let mut corrupted = files;
+        corrupted.insert(manifest.parts[2].file_name.clone(), b"xx".to_vec());
+        assert!(assemble_backup(&manifest, corrupted).is_err());
+    }
+
+    #[test]
+    fn it_links_only_target_guild_members_on_rehome() {
+        let from_guild_id = GuildId::new(1);
+        let guild_id = GuildId::new(2);
+
+        let player = |user_id: u64| BotPlayer {
+            user_id: UserId::new(user_id),
+            linked_guilds: vec![from_guild_id],
+            ..Default::default()
+        };
+
+        let mut data = BotData {
+            guilds: vec![GuildSettings::new(from_guild_id)],
+            players: vec![player(10), player(11)],
+            ..Default::default()
+        };
+
+        let unmapped = rehome(
+            &mut data,
+            from_guild_id,
+            guild_id,
+            &HashMap::new(),
+            &HashSet::from([UserId::new(10)]),
+        );
+        assert_eq!(unmapped, Some(vec![]));
+
+        assert!(data.players[0].is_linked_to_guild(&guild_id));
+        assert!(!data.players[1].is_linked_to_guild(&guild_id));
+        assert!(data
+            .guilds
+            .iter()
+            .any(|guild_settings| guild_settings.get_key() == guild_id));
+    }
+}
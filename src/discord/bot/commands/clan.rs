@@ -2,36 +2,55 @@
 use std::borrow::Cow;
 use std::cmp::Ordering;
 use std::sync::Arc;
+use std::time::Duration;
 
-use magic_crypt::{new_magic_crypt, MagicCryptTrait};
+use bytes::Bytes;
+use chrono::Weekday;
+use futures::FutureExt;
 use poise::serenity_prelude::{
-    ChannelId, CreateAttachment, Message, Permissions, Role, User, UserId,
+    Attachment, ButtonStyle, ChannelId, ComponentInteractionCollector,
+    ComponentInteractionDataKind, CreateActionRow, CreateAllowedMentions, CreateAttachment,
+    CreateButton, CreateEmbed, CreateEmbedAuthor, CreateInteractionResponse, CreateSelectMenu,
+    CreateSelectMenuKind, CreateSelectMenuOption, Message, Permissions, Role, User, UserId,
 };
 use poise::CreateReply;
 
 use crate::beatleader::clan::ClanMapParam;
 use crate::beatleader::clan::ClanRankingParam;
-use crate::beatleader::clan::{Clan, ClanTag};
+use crate::beatleader::clan::{Clan, ClanParam, ClanSort, ClanTag};
+use crate::beatleader::error::Error as BlError;
 use crate::beatleader::oauth::{OAuthScope, OAuthTokenRepository};
 use crate::beatleader::player::DifficultyStatus;
 use crate::beatleader::pp::calculate_total_pp_from_sorted;
 use crate::beatleader::pp::CLAN_WEIGHT_COEFFICIENT;
-use crate::beatleader::DataWithMeta;
+use crate::beatleader::{BlContext, DataWithMeta, SortOrder};
+use crate::config::{CLAN_WARS_MAPS_MAX_COUNT, PLAYLIST_MAX_SONGS_CEILING};
 use crate::discord::bot::beatleader::clan::{
-    fetch_clan, AccBoundary, ClanMapWithScores, ClanWarsFc, ClanWarsPlayDate, ClanWarsSort,
-    Playlist,
+    clan_not_found_message, fetch_clan, AccBoundary, ClanMapWithScores, ClanWarsContext,
+    ClanWarsFc, ClanWarsPlayDate, ClanWarsSort, Playlist, PlaylistMapStatus,
+    PLAYLIST_COVER_MAX_BYTES,
 };
-use crate::discord::bot::beatleader::player::fetch_player_from_bl;
-use crate::discord::bot::commands::guild::{get_guild_id, get_guild_settings};
+use crate::discord::bot::beatleader::player::{
+    fetch_player_from_bl, Player, PROFILE_VERIFICATION_STEPS,
+};
+use crate::discord::bot::commands::guild::{get_guild_id, get_guild_settings, say_guild_settings};
 use crate::discord::bot::commands::player::{
     link_user_if_needed, say_profile_not_linked, say_without_ping,
 };
 use crate::discord::bot::commands::{
-    get_leaderboard_ids_from_message, get_user_id_with_required_permission,
+    get_leaderboard_ids_from_list, get_leaderboard_ids_from_message,
+    get_user_id_with_required_permission,
+};
+use crate::discord::bot::{
+    get_binary_file, ClanRecapSchedule, ClanSettings, GuildOAuthTokenRepository, GuildSettings,
+    MAX_DISCORD_MESSAGE_LENGTH,
 };
-use crate::discord::bot::{ClanSettings, GuildOAuthTokenRepository};
+use crate::discord::worker::clan_contribution::{calculate_clan_contribution, ClanContribution};
 use crate::discord::Context;
+use crate::embed::embed_clan_summary;
+use crate::other::string_utils::{capitalize, format_decimal, DEFAULT_DECIMAL_PLACES};
 use crate::storage::bsmaps::{BsMap, BsMapType, BsMapsRepository};
+use crate::webserver::routes::{decode_oauth_state, encode_oauth_state};
 use crate::{Error, BL_CLIENT};
 
 /// Set up sending of clan invitations
@@ -76,7 +95,16 @@ pub(crate) async fn cmd_set_clan_invitation(
 
     let player = player.unwrap();
     if !player.is_verified {
-        say_without_ping(ctx, "The profile must be verified. Go to <https://www.beatleader.com/settings#account> and link your discord account with your BL profile.", true).await?;
+        say_without_ping(
+            ctx,
+            format!(
+                "The profile must be verified. {}",
+                PROFILE_VERIFICATION_STEPS
+            )
+            .as_str(),
+            true,
+        )
+        .await?;
         return Ok(());
     }
 
@@ -170,11 +198,14 @@ pub(crate) async fn cmd_set_clan_invitation(
         Arc::clone(&ctx.data().player_oauth_token_repository),
     );
 
-    let mc = new_magic_crypt!(oauth_credentials.client_secret.clone(), 256);
+    let oauth_state = encode_oauth_state(
+        oauth_credentials.client_secret.as_str(),
+        guild_settings.guild_id,
+    );
 
     let oauth_client = BL_CLIENT.with_oauth(oauth_credentials, guild_oauth_token_repository);
 
-    msg_contents.push_str(format!("\nGreat, you are the owner of the {} clan. Now click this link and authorize the bot to send invitations to the clan on your behalf. {}", &player_clan.tag, oauth_client.oauth().authorize_url(vec![OAuthScope::Profile, OAuthScope::OfflineAccess, OAuthScope::Clan], mc.encrypt_str_to_base64(guild_settings.guild_id.to_string())).unwrap_or("Error when generating authorization link".to_owned())).as_str());
+    msg_contents.push_str(format!("\nGreat, you are the owner of the {} clan. Now click this link and authorize the bot to send invitations to the clan on your behalf. {}", &player_clan.tag, oauth_client.oauth().authorize_url(vec![OAuthScope::Profile, OAuthScope::OfflineAccess, OAuthScope::Clan], oauth_state).unwrap_or("Error when generating authorization link".to_owned())).as_str());
 
     let msg_contents_clone = msg_contents.clone();
     msg.edit(ctx, CreateReply::default().content(&msg_contents_clone))
@@ -183,6 +214,279 @@ pub(crate) async fn cmd_set_clan_invitation(
     Ok(())
 }
 
+// clan invitations OAuth token can be managed by the user who set it up, the clan owner or the bot owner
+async fn can_manage_clan_oauth(ctx: Context<'_>, clan_settings: &ClanSettings) -> bool {
+    let author_id = ctx.author().id;
+
+    if ctx.framework().options().owners.contains(&author_id)
+        || clan_settings.get_user_id() == author_id
+    {
+        return true;
+    }
+
+    ctx.data()
+        .players_repository
+        .get(&author_id)
+        .await
+        .is_some_and(|player| player.id == clan_settings.get_owner())
+}
+
+/// Show the status of the stored clan invitations authorization
+#[tracing::instrument(skip(ctx), level=tracing::Level::INFO, name="bot_command:bl-clan-oauth-status")]
+#[poise::command(
+    slash_command,
+    rename = "bl-clan-oauth-status",
+    ephemeral,
+    required_permissions = "MANAGE_ROLES",
+    default_member_permissions = "MANAGE_ROLES",
+    required_bot_permissions = "MANAGE_ROLES",
+    guild_only,
+    hide_in_help
+)]
+pub(crate) async fn cmd_clan_oauth_status(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_settings = get_guild_settings(ctx, true).await?;
+
+    let Some(clan_settings) = guild_settings.get_clan_settings() else {
+        say_without_ping(
+            ctx,
+            "Clan invitations are not set up, use the ``/bl-set-clan-invitation`` command first.",
+            true,
+        )
+        .await?;
+        return Ok(());
+    };
+
+    if !can_manage_clan_oauth(ctx, &clan_settings).await {
+        say_without_ping(ctx, "Can only be used by the clan owner", true).await?;
+        return Ok(());
+    }
+
+    let token_status = match ctx
+        .data()
+        .player_oauth_token_repository
+        .get(&clan_settings.get_owner())
+        .await
+    {
+        Some(player_oauth_token) => format!(
+            "stored\nScopes: {}\nExpires: <t:{}:R>\nRefresh token: {}",
+            player_oauth_token
+                .oauth_token
+                .get_scopes()
+                .iter()
+                .map(String::from)
+                .collect::<Vec<_>>()
+                .join(", "),
+            player_oauth_token.oauth_token.get_expiration().timestamp(),
+            if player_oauth_token.oauth_token.has_refresh_token() {
+                "yes"
+            } else {
+                "no"
+            }
+        ),
+        None => "not stored".to_owned(),
+    };
+
+    say_without_ping(
+        ctx,
+        format!(
+            "Clan: {}\nInvitations enabled: {}\nOAuth token: {}",
+            clan_settings.get_clan(),
+            if clan_settings.is_oauth_token_set() {
+                "yes"
+            } else {
+                "no"
+            },
+            token_status
+        )
+        .as_str(),
+        true,
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Check the bot's OAuth configuration used by clan invitations
+#[tracing::instrument(skip(ctx), level=tracing::Level::INFO, name="bot_command:bl-oauth-diagnostics")]
+#[poise::command(
+    slash_command,
+    rename = "bl-oauth-diagnostics",
+    ephemeral,
+    required_permissions = "MANAGE_ROLES",
+    default_member_permissions = "MANAGE_ROLES",
+    required_bot_permissions = "MANAGE_ROLES",
+    guild_only,
+    hide_in_help
+)]
+pub(crate) async fn cmd_oauth_diagnostics(ctx: Context<'_>) -> Result<(), Error> {
+    const REDIRECT_URI_TIMEOUT: Duration = Duration::from_secs(10);
+
+    let is_bot_owner = ctx.framework().options().owners.contains(&ctx.author().id);
+    if !is_bot_owner {
+        ctx.say("Can only be used by bot owner").await?;
+        return Ok(());
+    }
+
+    ctx.defer_ephemeral().await?;
+
+    let settings = &ctx.data().settings;
+
+    let Some(oauth_settings) = settings.oauth.as_ref() else {
+        say_without_ping(
+            ctx,
+            "OAuth settings: FAILED, the ``oauth`` section is missing in the config file",
+            true,
+        )
+        .await?;
+
+        return Ok(());
+    };
+
+    let mut checks = vec![];
+
+    let missing = [
+        ("client_id", &oauth_settings.client_id),
+        ("client_secret", &oauth_settings.client_secret),
+        ("redirect_uri", &oauth_settings.redirect_uri),
+    ]
+    .into_iter()
+    .filter(|(_, value)| value.trim().is_empty())
+    .map(|(name, _)| name)
+    .collect::<Vec<_>>();
+    checks.push(if missing.is_empty() {
+        "OAuth settings: OK".to_owned()
+    } else {
+        format!("OAuth settings: FAILED, empty {}", missing.join(", "))
+    });
+
+    // the authorization code may also be handled by an external frontend, so a different
+    // redirect URI is not an error
+    let bot_redirect_uri = settings.server.oauth_redirect_uri();
+    checks.push(
+        if oauth_settings.redirect_uri.trim_end_matches('/') == bot_redirect_uri {
+            "Redirect URI: OK, handled by the bot".to_owned()
+        } else {
+            format!(
+                "Redirect URI: WARNING, <{}> does not match the bot's <{}>, make sure an external frontend handles it",
+                oauth_settings.redirect_uri, bot_redirect_uri
+            )
+        },
+    );
+
+    // any HTTP response means the redirect URI is reachable, the route answers with an error
+    // status without an authorization code
+    let reachability = match reqwest::Client::builder()
+        .timeout(REDIRECT_URI_TIMEOUT)
+        .build()
+    {
+        Ok(client) => match client
+            .get(oauth_settings.redirect_uri.as_str())
+            .send()
+            .await
+        {
+            Ok(response) => format!("Redirect URI reachable: OK ({})", response.status()),
+            Err(err) => format!("Redirect URI reachable: FAILED, {}", err),
+        },
+        Err(err) => format!("Redirect URI reachable: FAILED, {}", err),
+    };
+    checks.push(reachability);
+
+    let guild_id = get_guild_id(ctx, true).await?;
+    let state = encode_oauth_state(oauth_settings.client_secret.as_str(), guild_id);
+    checks.push(
+        match decode_oauth_state(oauth_settings.client_secret.as_str(), state.as_str()) {
+            Ok(decoded_guild_id) if decoded_guild_id == guild_id => {
+                "OAuth state encryption: OK".to_owned()
+            }
+            Ok(decoded_guild_id) => format!(
+                "OAuth state encryption: FAILED, decoded {} instead of {}",
+                decoded_guild_id, guild_id
+            ),
+            Err(err) => format!("OAuth state encryption: FAILED, {:?}", err),
+        },
+    );
+
+    say_without_ping(ctx, checks.join("\n").as_str(), true).await?;
+
+    Ok(())
+}
+
+/// Revoke the stored clan invitations authorization
+#[tracing::instrument(skip(ctx), level=tracing::Level::INFO, name="bot_command:bl-clan-oauth-revoke")]
+#[poise::command(
+    slash_command,
+    rename = "bl-clan-oauth-revoke",
+    ephemeral,
+    required_permissions = "MANAGE_ROLES",
+    default_member_permissions = "MANAGE_ROLES",
+    required_bot_permissions = "MANAGE_ROLES",
+    guild_only,
+    hide_in_help
+)]
+pub(crate) async fn cmd_clan_oauth_revoke(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_settings = get_guild_settings(ctx, true).await?;
+
+    let Some(clan_settings) = guild_settings.get_clan_settings() else {
+        say_without_ping(
+            ctx,
+            "Clan invitations are not set up, use the ``/bl-set-clan-invitation`` command first.",
+            true,
+        )
+        .await?;
+        return Ok(());
+    };
+
+    if !can_manage_clan_oauth(ctx, &clan_settings).await {
+        say_without_ping(ctx, "Can only be used by the clan owner", true).await?;
+        return Ok(());
+    }
+
+    let owner_id = clan_settings.get_owner();
+
+    if let Err(err) = ctx
+        .data()
+        .player_oauth_token_repository
+        .remove(&owner_id)
+        .await
+    {
+        say_without_ping(ctx, format!("An error occurred: {}", err).as_str(), true).await?;
+        return Ok(());
+    }
+
+    // the token is shared by all guilds set up for the same clan owner
+    for guild in ctx.data().guild_settings_repository.all().await {
+        let Some(mut other_clan_settings) = guild.get_clan_settings() else {
+            continue;
+        };
+
+        if other_clan_settings.get_owner() != owner_id || !other_clan_settings.is_oauth_token_set()
+        {
+            continue;
+        }
+
+        other_clan_settings.set_oauth_token(false);
+
+        if let Err(err) = ctx
+            .data()
+            .guild_settings_repository
+            .set_clan_settings(&guild.get_key(), Some(other_clan_settings))
+            .await
+        {
+            say_without_ping(ctx, format!("An error occurred: {}", err).as_str(), true).await?;
+            return Ok(());
+        }
+    }
+
+    say_without_ping(
+        ctx,
+        "Clan invitations authorization has been revoked. Use the ``/bl-set-clan-invitation`` command to set it up again.",
+        true,
+    )
+    .await?;
+
+    Ok(())
+}
+
 /// Send yourself an invitation to join the clan
 #[tracing::instrument(skip(ctx), level=tracing::Level::INFO, name="bot_command:bl-clan-invitation")]
 #[poise::command(slash_command, rename = "bl-clan-invitation", guild_only)]
@@ -253,6 +557,18 @@ pub(crate) async fn cmd_clan_invitation(ctx: Context<'_>) -> Result<(), Error> {
                 return Ok(());
             }
 
+            let clan_tag = clan_settings.get_clan();
+
+            let clan_membership_cache = &ctx.data().clan_membership_cache;
+            if clan_membership_cache
+                .get(&player.id)
+                .is_some_and(|clans| clans.contains(&clan_tag))
+            {
+                say_without_ping(ctx, "You are already a clan member.", true).await?;
+
+                return Ok(());
+            }
+
             let bl_player = fetch_player_from_bl(&player.id).await;
             if bl_player.is_err() {
                 say_without_ping(
@@ -271,7 +587,14 @@ pub(crate) async fn cmd_clan_invitation(ctx: Context<'_>) -> Result<(), Error> {
 
             let bl_player = bl_player.unwrap();
 
-            let clan_tag = clan_settings.get_clan();
+            clan_membership_cache.insert(
+                &player.id,
+                bl_player
+                    .clans
+                    .iter()
+                    .map(|clan| clan.tag.clone())
+                    .collect(),
+            );
 
             if bl_player.clans.iter().any(|clan| clan.tag == clan_tag) {
                 say_without_ping(ctx, "You are already a clan member.", true).await?;
@@ -290,10 +613,14 @@ pub(crate) async fn cmd_clan_invitation(ctx: Context<'_>) -> Result<(), Error> {
             }) {
                 say_without_ping(
                     ctx,
-                    "The profile must be verified. Go to <https://www.beatleader.com/settings#account> and link your discord account with your BL profile.",
+                    format!(
+                        "The profile must be verified. {}",
+                        PROFILE_VERIFICATION_STEPS
+                    )
+                    .as_str(),
                     true,
                 )
-                    .await?;
+                .await?;
 
                 return Ok(());
             }
@@ -307,18 +634,14 @@ pub(crate) async fn cmd_clan_invitation(ctx: Context<'_>) -> Result<(), Error> {
                 guild_oauth_token_repository,
             );
 
-            let invitation_result = oauth_client.clan_auth().invite(bl_player.id).await;
-            if invitation_result.is_err() {
-                say_without_ping(
-                    ctx,
-                    format!(
-                        "Error: sending clan invitation failed: {}",
-                        invitation_result.err().unwrap()
-                    )
-                    .as_str(),
-                    true,
-                )
-                .await?;
+            if let Err(err) = oauth_client.clan_auth().invite(bl_player.id).await {
+                let message = match err {
+                    BlError::OAuthNotSet | BlError::OAuthExpired(_) => "Error: clan invitations authorization is missing or has expired. The clan owner needs to use the ``/bl-set-clan-invitation`` command again.".to_owned(),
+                    BlError::OAuthStorage => "Error: can not access the stored clan invitations authorization, please contact the bot owner.".to_owned(),
+                    err => format!("Error: sending clan invitation failed: {}", err),
+                };
+
+                say_without_ping(ctx, message.as_str(), true).await?;
 
                 return Ok(());
             }
@@ -345,6 +668,117 @@ pub(crate) async fn cmd_clan_invitation(ctx: Context<'_>) -> Result<(), Error> {
     }
 }
 
+/// Displays clan stats
+#[tracing::instrument(skip(ctx), level=tracing::Level::INFO, name="bot_command:bl-clan-stats")]
+#[poise::command(slash_command, rename = "bl-clan-stats", guild_only)]
+pub(crate) async fn cmd_clan_stats(
+    ctx: Context<'_>,
+    #[description = "Clan tag (default: clan set up in this guild)"] tag: Option<String>,
+) -> Result<(), Error> {
+    ctx.defer().await?;
+
+    let clan_tag = match tag {
+        Some(tag) => tag.trim().to_uppercase(),
+        None => {
+            let guild_settings = get_guild_settings(ctx, true).await?;
+
+            match guild_settings.clan_settings {
+                Some(clan_settings) => clan_settings.get_clan(),
+                None => {
+                    say_without_ping(ctx, "Clan is not set up in this guild.", true).await?;
+
+                    return Ok(());
+                }
+            }
+        }
+    };
+
+    // stats of any clan can be shown also on servers not set up yet
+    let decimal_places = match ctx.guild_id() {
+        Some(guild_id) => ctx
+            .data()
+            .guild_settings_repository
+            .get(&guild_id)
+            .await
+            .map(|guild_settings| guild_settings.get_decimal_places())
+            .unwrap_or(DEFAULT_DECIMAL_PLACES),
+        None => DEFAULT_DECIMAL_PLACES,
+    };
+
+    let clan = match fetch_clan(&clan_tag).await {
+        Ok(clan) => clan,
+        Err(BlError::NotFound) => {
+            say_without_ping(
+                ctx,
+                capitalize(&clan_not_found_message(&clan_tag).await).as_str(),
+                true,
+            )
+            .await?;
+
+            return Ok(());
+        }
+        Err(err) => {
+            say_without_ping(
+                ctx,
+                format!("Can not fetch {} clan data: {}", &clan_tag, err).as_str(),
+                true,
+            )
+            .await?;
+
+            return Ok(());
+        }
+    };
+
+    // BL does not return the clan rank directly, look for the clan in the top 100 by captured maps
+    let rank = BL_CLIENT
+        .clan()
+        .clans(&[
+            ClanParam::Sort(ClanSort::MapsCaptured),
+            ClanParam::Order(SortOrder::Descending),
+            ClanParam::Count(100),
+        ])
+        .await
+        .ok()
+        .and_then(|clans| clans.data.iter().position(|c| c.id == clan.id))
+        .map(|idx| idx as u32 + 1);
+
+    let clan_icon = if !clan.icon.is_empty() {
+        get_binary_file(&clan.icon).await.unwrap_or_default()
+    } else {
+        Bytes::new()
+    };
+
+    let embed_image = embed_clan_summary(&clan, rank, clan_icon.as_ref(), decimal_places)
+        .catch_unwind()
+        .await
+        .ok()
+        .flatten();
+
+    let mut reply = CreateReply::default()
+        .allowed_mentions(CreateAllowedMentions::new())
+        .ephemeral(false);
+
+    reply = match embed_image {
+        Some(embed_buffer) => reply.attachment(CreateAttachment::bytes(
+            Cow::<[u8]>::from(embed_buffer),
+            "embed.png".to_string(),
+        )),
+        None => reply.content(format!(
+            "# {}{} clan\n**{}pp** • **{}** members • **{}** maps captured ({}%)",
+            rank.map(|rank| format!("#{} ", rank)).unwrap_or_default(),
+            &clan.tag,
+            format_decimal(clan.pp, decimal_places),
+            clan.players_count,
+            clan.capture_leaderboards_count,
+            format_decimal(clan.ranked_pool_percent_captured * 100.0, decimal_places)
+        )),
+    };
+
+    ctx.send(reply).await?;
+
+    Ok(())
+}
+
 /// Generate clan wars playlist (clan members only)
 #[tracing::instrument(skip(ctx), level=tracing::Level::INFO, name="bot_command:bl-clan-wars-playlist")]
 #[poise::command(slash_command, rename = "bl-clan-wars-playlist", guild_only)]
@@ -352,29 +786,75 @@ pub(crate) async fn cmd_clan_wars_playlist(
     ctx: Context<'_>,
     #[description = "Playlist type (default: To Conquer)"] playlist_type: Option<ClanWarsSort>,
     #[description = "Last played (default: Never)"] played: Option<ClanWarsPlayDate>,
-    #[description = "Maps count (max: 300, default: 100)"] count: Option<u32>,
+    #[description = "Maps count (default: 100)"] count: Option<u32>,
     #[description = "Maps map stars (default: player's top stars)"] max_stars: Option<f64>,
     #[description = "Maps clan pp difference (default: player's top pp)"] max_clan_pp_diff: Option<
         f64,
     >,
-    #[description = "FC status"] fc: Option<ClanWarsFc>,
+    #[description = "Min. maps clan pp difference, skips nearly captured maps (default: 0)"]
+    min_clan_pp_diff: Option<f64>,
+    #[description = "Maps status, clan wars maps are ranked only (default: any)"]
+    map_status: Option<PlaylistMapStatus>,
+    #[description = "FC status (default: guild setting, No matter if not set)"] fc: Option<
+        ClanWarsFc,
+    >,
     #[description = "Skip the commander's orders (default: false)"] skip_commander_order: Option<
         bool,
     >,
     #[description = "Your custom playlist name (default: autogenerated"] playlist_name: Option<
         String,
     >,
+    #[description = "Scores context (default: General)"] context: Option<ClanWarsContext>,
+    #[description = "Only list the maps, without saving the playlist (default: false)"]
+    preview: Option<bool>,
 ) -> Result<(), Error> {
-    ctx.defer().await?;
+    // the deferred response decides whether the reply can be ephemeral
+    if preview.unwrap_or(false) {
+        ctx.defer_ephemeral().await?;
+    } else {
+        ctx.defer().await?;
+    }
+
+    let bl_context: BlContext = context.unwrap_or_default().into();
+    if !ctx.data().player_scores_repositories.contains(&bl_context) {
+        say_without_ping(
+            ctx,
+            format!("Playlists in {} context are not available.", bl_context).as_str(),
+            true,
+        )
+        .await?;
+
+        return Ok(());
+    }
+
+    // only ranked maps take part in clan wars, any other status would always give an empty playlist
+    if map_status
+        .as_ref()
+        .is_some_and(|map_status| map_status != &PlaylistMapStatus::Ranked)
+    {
+        say_without_ping(
+            ctx,
+            "Clan wars are played on ranked maps only, use the Ranked map status or leave it empty. Try /bl-practice-playlist for qualified or nominated maps.",
+            true,
+        )
+        .await?;
+
+        return Ok(());
+    }
 
     let playlist_type_filter = playlist_type.unwrap_or(ClanWarsSort::ToConquer);
     let played_filter = played.unwrap_or(ClanWarsPlayDate::Never);
-    let count = match count {
-        None => 100,
-        Some(v) if v > 0 && v <= 300 => v,
-        Some(_others) => 100,
+    let max_count = ctx
+        .data()
+        .settings
+        .playlist_max_songs
+        .min(PLAYLIST_MAX_SONGS_CEILING);
+    let (count, clamped) = match count {
+        None => (100.min(max_count), false),
+        Some(0) => (100.min(max_count), false),
+        Some(v) if v > max_count => (max_count, true),
+        Some(v) => (v, false),
     };
-    let fc_status: Option<bool> = fc.unwrap_or(ClanWarsFc::NoMatter).into();
 
     let guild_settings = get_guild_settings(ctx, true).await?;
     if guild_settings.clan_settings.is_none() {
@@ -385,130 +865,161 @@ pub(crate) async fn cmd_clan_wars_playlist(
 
     let clan_settings = guild_settings.clan_settings.clone().unwrap();
 
-    let current_user = ctx.author();
+    let clan_tag = clan_settings.get_clan();
 
-    match link_user_if_needed(
-        ctx,
-        &guild_settings.guild_id,
-        current_user,
-        guild_settings.requires_verified_profile,
+    let Some(player) = get_clan_member(ctx, &guild_settings, &clan_tag).await? else {
+        return Ok(());
+    };
+
+    let cover = guild_settings.get_playlist_cover(&playlist_type_filter);
+
+    match Playlist::for_clan_player(
+        &ctx.data().player_scores_repositories.clone(),
+        &ctx.data().maps_repository.clone(),
+        &ctx.data().settings.server.url.clone(),
+        clan_tag,
+        player,
+        bl_context,
+        playlist_type_filter,
+        played_filter,
+        count,
+        max_stars,
+        max_clan_pp_diff,
+        min_clan_pp_diff,
+        map_status,
+        fc,
+        guild_settings.is_playlist_skip_fcs(),
+        skip_commander_order,
+        guild_settings.get_commander_order_min_stars(),
+        playlist_name,
+        false,
+        cover,
     )
     .await
     {
-        Some(player) => {
-            if !player.is_linked_to_guild(&guild_settings.guild_id) {
-                say_profile_not_linked(
-                    ctx,
-                    &current_user.id,
-                    guild_settings.requires_verified_profile,
-                )
-                .await?;
+        Ok(playlist) if preview.unwrap_or(false) => {
+            ctx.send(
+                CreateReply::default()
+                    .content(format_playlist_preview(&playlist, count, clamped))
+                    .allowed_mentions(CreateAllowedMentions::new())
+                    .ephemeral(true),
+            )
+            .await?;
 
-                return Ok(());
+            Ok(())
+        }
+        Ok(playlist) => match &ctx.data().playlists_repository.save(playlist.clone()).await {
+            Ok(_) => {
+                match serde_json::to_string::<Playlist>(&playlist) {
+                    Ok(data_json) => {
+                        ctx.send(
+                            CreateReply::default()
+                                .content(if clamped {
+                                    format!("Here's your personalized playlist (maps count limited to {}):", count)
+                                } else {
+                                    "Here's your personalized playlist:".to_owned()
+                                })
+                                .attachment(CreateAttachment::bytes(
+                                    Cow::from(data_json.into_bytes()),
+                                    format!(
+                                        "{}.json",
+                                        playlist
+                                            .get_title()
+                                            .replace([' ', '-', '/', '\\', ':'], "_")
+                                    ),
+                                ))
+                                .ephemeral(true),
+                        )
+                        .await?;
+                    }
+                    Err(err) => {
+                        ctx.say(format!("An error occurred: {}", err)).await?;
+                    }
+                };
+
+                Ok(())
             }
+            Err(err) => {
+                ctx.say(format!("An error occurred: {}", err)).await?;
 
-            let bl_player = fetch_player_from_bl(&player.id).await;
-            if bl_player.is_err() {
-                say_without_ping(
-                    ctx,
-                    format!(
-                        "Error: can not fetch player data from BL: {}",
-                        bl_player.err().unwrap()
-                    )
-                    .as_str(),
-                    true,
-                )
-                .await?;
-
-                return Ok(());
+                Ok(())
             }
+        },
+        Err(err) => {
+            say_without_ping(ctx, err.as_str(), false).await?;
 
-            let bl_player = bl_player.unwrap();
+            Ok(())
+        }
+    }
+}
 
-            let clan_tag = clan_settings.get_clan();
+// songs are listed partially, so the preview fits in a single message
+const PLAYLIST_PREVIEW_MAX_SONGS: usize = 15;
 
-            if !bl_player.clans.iter().any(|clan| clan.tag == clan_tag) {
-                say_without_ping(
-                    ctx,
-                    format!("You are not a member of the {} clan.", &clan_tag).as_str(),
-                    false,
-                )
-                .await?;
+fn format_playlist_preview(playlist: &Playlist, count: u32, clamped: bool) -> String {
+    let songs = playlist.get_songs();
 
-                return Ok(());
-            }
+    let mut lines = vec![format!(
+        "Preview of **{}** (not saved): {} map(s){}",
+        playlist.get_title(),
+        songs.len(),
+        if clamped {
+            format!(", maps count limited to {}", count)
+        } else {
+            "".to_owned()
+        }
+    )];
+
+    lines.extend(
+        songs
+            .iter()
+            .take(PLAYLIST_PREVIEW_MAX_SONGS)
+            .enumerate()
+            .map(|(idx, song)| format!("{}. {}", idx + 1, song)),
+    );
 
-            if bl_player.clans.first().unwrap().tag != clan_tag {
-                say_without_ping(
-                    ctx,
-                    format!("You did not set clan {} as primary. Go to your profile and move the clan to the first position on the list.", &clan_tag).as_str(),
-                    true,
-                )
-                    .await?;
+    if songs.len() > PLAYLIST_PREVIEW_MAX_SONGS {
+        lines.push(format!(
+            "...and {} more",
+            songs.len() - PLAYLIST_PREVIEW_MAX_SONGS
+        ));
+    }
 
-                return Ok(());
-            }
+    let mut preview = lines.join("\n");
+    if preview.chars().count() > MAX_DISCORD_MESSAGE_LENGTH {
+        preview = preview
+            .chars()
+            .take(MAX_DISCORD_MESSAGE_LENGTH - 3)
+            .collect::<String>()
+            + "...";
+    }
 
-            match Playlist::for_clan_player(
-                &ctx.data().player_scores_repository.clone(),
-                &ctx.data().maps_repository.clone(),
-                &ctx.data().settings.server.url.clone(),
-                clan_tag,
-                player,
-                playlist_type_filter,
-                played_filter,
-                count,
-                max_stars,
-                max_clan_pp_diff,
-                fc_status,
-                skip_commander_order,
-                playlist_name,
-                false,
-            )
-            .await
-            {
-                Ok(playlist) => match &ctx.data().playlists_repository.save(playlist.clone()).await
-                {
-                    Ok(_) => {
-                        match serde_json::to_string::<Playlist>(&playlist) {
-                            Ok(data_json) => {
-                                ctx.send(
-                                    CreateReply::default()
-                                        .content("Here's your personalized playlist:")
-                                        .attachment(CreateAttachment::bytes(
-                                            Cow::from(data_json.into_bytes()),
-                                            format!(
-                                                "{}.json",
-                                                playlist
-                                                    .get_title()
-                                                    .replace([' ', '-', '/', '\\', ':'], "_")
-                                            ),
-                                        ))
-                                        .ephemeral(true),
-                                )
-                                .await?;
-                            }
-                            Err(err) => {
-                                ctx.say(format!("An error occurred: {}", err)).await?;
-                            }
-                        };
-
-                        Ok(())
-                    }
-                    Err(err) => {
-                        ctx.say(format!("An error occurred: {}", err)).await?;
+    preview
+}
 
-                        Ok(())
-                    }
-                },
-                Err(err) => {
-                    say_without_ping(ctx, err.as_str(), false).await?;
+// select menu options are limited to 25
+const MY_PLAYLISTS_MAX_LISTED: usize = 25;
 
-                    Ok(())
-                }
-            }
-        }
-        None => {
+/// List your stored clan wars playlists and remove the ones you no longer use
+#[tracing::instrument(skip(ctx), level=tracing::Level::INFO, name="bot_command:bl-my-playlists")]
+#[poise::command(slash_command, rename = "bl-my-playlists", ephemeral, guild_only)]
+pub(crate) async fn cmd_my_playlists(ctx: Context<'_>) -> Result<(), Error> {
+    ctx.defer_ephemeral().await?;
+
+    let guild_settings = get_guild_settings(ctx, true).await?;
+
+    let current_user = ctx.author();
+
+    let player = match link_user_if_needed(
+        ctx,
+        &guild_settings.guild_id,
+        current_user,
+        guild_settings.requires_verified_profile,
+    )
+    .await
+    {
+        Some(player) if player.is_linked_to_guild(&guild_settings.guild_id) => player,
+        _ => {
             say_profile_not_linked(
                 ctx,
                 &current_user.id,
@@ -516,9 +1027,320 @@ pub(crate) async fn cmd_clan_wars_playlist(
             )
             .await?;
 
-            Ok(())
+            return Ok(());
         }
+    };
+
+    let mut playlists = ctx.data().playlists_repository.by_player(&player.id).await;
+    if playlists.is_empty() {
+        say_without_ping(ctx, "You have no stored playlists.", true).await?;
+
+        return Ok(());
     }
+
+    let mut selected_id: Option<String> = None;
+
+    let msg = ctx
+        .send(
+            CreateReply::default()
+                .content(format_player_playlists(&playlists))
+                .components(add_my_playlists_components(&playlists, &selected_id))
+                .allowed_mentions(CreateAllowedMentions::new())
+                .ephemeral(true),
+        )
+        .await?;
+
+    while let Some(mci) = ComponentInteractionCollector::new(ctx)
+        .author_id(current_user.id)
+        .message_id(msg.message().await?.id)
+        .timeout(Duration::from_secs(120))
+        .await
+    {
+        mci.create_response(ctx, CreateInteractionResponse::Acknowledge)
+            .await?;
+
+        match mci.data.custom_id.as_str() {
+            "playlist_id" => {
+                selected_id = match &mci.data.kind {
+                    ComponentInteractionDataKind::StringSelect { values } => {
+                        values.first().cloned()
+                    }
+                    _ => None,
+                };
+            }
+            "playlist_delete_btn" => {
+                let Some(playlist_id) = selected_id.take() else {
+                    continue;
+                };
+
+                // make sure the playlist still belongs to the player before removing it
+                if !playlists
+                    .iter()
+                    .any(|playlist| playlist.get_id() == &playlist_id)
+                {
+                    continue;
+                }
+
+                if let Err(err) = ctx.data().playlists_repository.remove(&playlist_id).await {
+                    msg.edit(
+                        ctx,
+                        CreateReply::default()
+                            .content(format!("An error occurred: {}", err))
+                            .components(vec![]),
+                    )
+                    .await?;
+
+                    return Ok(());
+                }
+
+                tracing::info!(
+                    "Playlist {} removed by BL player {}.",
+                    &playlist_id,
+                    &player.name
+                );
+
+                playlists.retain(|playlist| playlist.get_id() != &playlist_id);
+
+                if playlists.is_empty() {
+                    msg.edit(
+                        ctx,
+                        CreateReply::default()
+                            .content("Playlist removed, you have no stored playlists left.")
+                            .components(vec![]),
+                    )
+                    .await?;
+
+                    return Ok(());
+                }
+            }
+            _ => continue,
+        }
+
+        msg.edit(
+            ctx,
+            CreateReply::default()
+                .content(format_player_playlists(&playlists))
+                .components(add_my_playlists_components(&playlists, &selected_id)),
+        )
+        .await?;
+    }
+
+    msg.edit(
+        ctx,
+        CreateReply::default()
+            .content(format_player_playlists(&playlists))
+            .components(vec![]),
+    )
+    .await?;
+
+    Ok(())
+}
+
+fn format_player_playlists(playlists: &[Playlist]) -> String {
+    let mut lines = vec![format!("Your stored playlists: {}", playlists.len())];
+
+    lines.extend(
+        playlists
+            .iter()
+            .take(MY_PLAYLISTS_MAX_LISTED)
+            .enumerate()
+            .map(|(idx, playlist)| {
+                format!(
+                    "{}. **{}** ({}) <{}>",
+                    idx + 1,
+                    playlist.get_title(),
+                    playlist
+                        .custom_data
+                        .as_ref()
+                        .map(|custom_data| custom_data.playlist_type.to_string())
+                        .unwrap_or_default(),
+                    playlist.get_sync_url().cloned().unwrap_or_default()
+                )
+            }),
+    );
+
+    if playlists.len() > MY_PLAYLISTS_MAX_LISTED {
+        lines.push(format!(
+            "...and {} more",
+            playlists.len() - MY_PLAYLISTS_MAX_LISTED
+        ));
+    }
+
+    let mut list = lines.join("\n");
+    if list.chars().count() > MAX_DISCORD_MESSAGE_LENGTH {
+        list = list
+            .chars()
+            .take(MAX_DISCORD_MESSAGE_LENGTH - 3)
+            .collect::<String>()
+            + "...";
+    }
+
+    list
+}
+
+fn add_my_playlists_components(
+    playlists: &[Playlist],
+    selected_id: &Option<String>,
+) -> Vec<CreateActionRow> {
+    vec![
+        CreateActionRow::SelectMenu(
+            CreateSelectMenu::new(
+                "playlist_id",
+                CreateSelectMenuKind::String {
+                    options: playlists
+                        .iter()
+                        .take(MY_PLAYLISTS_MAX_LISTED)
+                        .enumerate()
+                        .map(|(idx, playlist)| {
+                            let label = format!("{}. {}", idx + 1, playlist.get_title());
+
+                            CreateSelectMenuOption::new(
+                                label.chars().take(100).collect::<String>(),
+                                playlist.get_id().clone(),
+                            )
+                            .default_selection(selected_id.as_ref() == Some(playlist.get_id()))
+                        })
+                        .collect::<Vec<_>>(),
+                },
+            )
+            .placeholder("Select a playlist to remove"),
+        ),
+        CreateActionRow::Buttons(vec![CreateButton::new("playlist_delete_btn")
+            .label("Remove playlist")
+            .style(ButtonStyle::Danger)
+            .disabled(selected_id.is_none())]),
+    ]
+}
+
+/// Show your standing in the clan wars contribution (clan members only)
+#[tracing::instrument(skip(ctx), level=tracing::Level::INFO, name="bot_command:bl-clan-contribution")]
+#[poise::command(slash_command, rename = "bl-clan-contribution", ephemeral, guild_only)]
+pub(crate) async fn cmd_clan_contribution(ctx: Context<'_>) -> Result<(), Error> {
+    ctx.defer_ephemeral().await?;
+
+    let guild_settings = get_guild_settings(ctx, true).await?;
+    if guild_settings.clan_settings.is_none() {
+        say_without_ping(ctx, "Clan is not set up in this guild.", true).await?;
+
+        return Ok(());
+    }
+
+    let clan_settings = guild_settings.clan_settings.clone().unwrap();
+
+    let clan_tag = clan_settings.get_clan();
+    let decimal_places = guild_settings.get_decimal_places();
+
+    let Some(player) = get_clan_member(ctx, &guild_settings, &clan_tag).await? else {
+        return Ok(());
+    };
+
+    if !clan_settings
+        .get_clan_wars_soldiers()
+        .contains(&ctx.author().id)
+    {
+        say_without_ping(
+            ctx,
+            "You are not enlisted for clan wars. Use ``/bl-clan-wars-enlist`` command first.",
+            true,
+        )
+        .await?;
+
+        return Ok(());
+    }
+
+    let Some(ClanContribution {
+        captured_clan_stats,
+        bonus_maps_count,
+    }) = calculate_clan_contribution(
+        clan_tag.clone(),
+        clan_settings.get_clan_wars_soldiers(),
+        &ctx.data().players_repository,
+        ctx.data().player_scores_repositories.general(),
+    )
+    .await
+    else {
+        say_without_ping(
+            ctx,
+            "Error: can not calculate clan wars contribution, try again later.",
+            true,
+        )
+        .await?;
+
+        return Ok(());
+    };
+
+    let Some((idx, stats)) = captured_clan_stats
+        .soldiers
+        .iter()
+        .enumerate()
+        .find(|(_, s)| s.player.id == player.id)
+    else {
+        say_without_ping(
+            ctx,
+            format!(
+                "You have no contribution to the {} clan wars yet.",
+                &clan_tag
+            )
+            .as_str(),
+            true,
+        )
+        .await?;
+
+        return Ok(());
+    };
+
+    ctx.send(
+        CreateReply::default()
+            .embed(
+                CreateEmbed::new()
+                    .author(
+                        CreateEmbedAuthor::new(player.name.clone())
+                            .icon_url(player.avatar.clone())
+                            .url(format!("https://www.beatleader.com/u/{}", player.id)),
+                    )
+                    .title(format!("{} clan wars contribution", &clan_tag))
+                    .field(
+                        "Rank",
+                        format!("#{}/{}", idx + 1, captured_clan_stats.soldiers.len()),
+                        true,
+                    )
+                    .field(
+                        "Total points",
+                        format_decimal(stats.total_points, decimal_places),
+                        true,
+                    )
+                    .field("\u{200b}", "\u{200b}", true)
+                    .field(
+                        "Cap. maps",
+                        format!("{}/{}", stats.maps_count, captured_clan_stats.maps_count),
+                        true,
+                    )
+                    .field(
+                        "Cap. points",
+                        format_decimal(stats.points, decimal_places),
+                        true,
+                    )
+                    .field("\u{200b}", "\u{200b}", true)
+                    .field(
+                        "Bonus maps",
+                        format!("{}/{}", stats.bonus_maps_count, bonus_maps_count),
+                        true,
+                    )
+                    .field(
+                        "Bonus points",
+                        format_decimal(
+                            stats.bonus_points * CLAN_WEIGHT_COEFFICIENT,
+                            decimal_places,
+                        ),
+                        true,
+                    )
+                    .field("\u{200b}", "\u{200b}", true),
+            )
+            .ephemeral(true),
+    )
+    .await?;
+
+    Ok(())
 }
 
 /// Send the player an invitation to join the clan
@@ -538,11 +1360,7 @@ pub(crate) async fn cmd_invite_player(
 }
 
 #[tracing::instrument(skip(ctx, message), level=tracing::Level::INFO, name="bot_command:capture-map")]
-#[poise::command(
-    context_menu_command = "Capture the map",
-    guild_only,
-    member_cooldown = 5
-)]
+#[poise::command(context_menu_command = "Capture the map", guild_only)]
 pub(crate) async fn cmd_capture(
     ctx: Context<'_>,
     #[description = "Message to analyze"] message: Message,
@@ -698,66 +1516,408 @@ pub(crate) async fn cmd_capture(
                     pps_without_player
                         .sort_unstable_by(|a, b| b.partial_cmp(a).unwrap_or(Ordering::Equal));
 
-                    let mut clan_map_with_scores = ClanMapWithScores {
-                        map,
-                        scores: data.data,
-                        pp_boundary: 0.0,
-                        acc_boundary: AccBoundary::default(),
-                    };
+                    let mut clan_map_with_scores = ClanMapWithScores {
+                        map,
+                        scores: data.data,
+                        pp_boundary: 0.0,
+                        acc_boundary: AccBoundary::default(),
+                    };
+
+                    // calculate clan pp without player and pp_boundary
+                    let clan_pp_without_player = calculate_total_pp_from_sorted(
+                        CLAN_WEIGHT_COEFFICIENT,
+                        &pps_without_player,
+                        0,
+                    );
+                    clan_map_with_scores.map.pp = clan_pp_without_player - leading_clan_pp;
+                    clan_map_with_scores.calc_pp_boundary(Some(player.id.clone()));
+
+                    // set real pp loss
+                    clan_map_with_scores.map.pp = real_pp_loss;
+
+                    msg.edit(
+                        ctx,
+                        CreateReply::default().content(clan_map_with_scores.to_player_string(
+                            clan_tag,
+                            leading_clan_tag,
+                            player.id,
+                            is_captured,
+                            guild_settings.get_decimal_places(),
+                        )),
+                    )
+                    .await?;
+                }
+                Err(err) => {
+                    msg.edit(
+                        ctx,
+                        CreateReply::default()
+                            .content(format!("Oh snap! An error occurred: {}", err)),
+                    )
+                    .await?;
+                }
+            }
+
+            Ok(())
+        }
+        None => {
+            say_profile_not_linked(
+                ctx,
+                &current_user.id,
+                guild_settings.requires_verified_profile,
+            )
+            .await?;
+
+            Ok(())
+        }
+    }
+}
+
+/// Set or unset clan wars maps channel
+#[tracing::instrument(skip(ctx), level=tracing::Level::INFO, name="bot_command:bl-set-clan-wars-maps-channel")]
+#[poise::command(
+    slash_command,
+    rename = "bl-set-clan-wars-maps-channel",
+    ephemeral,
+    required_permissions = "MANAGE_ROLES",
+    default_member_permissions = "MANAGE_ROLES",
+    required_bot_permissions = "MANAGE_ROLES",
+    guild_only,
+    hide_in_help
+)]
+pub(crate) async fn cmd_set_clan_wars_maps_channel(
+    ctx: Context<'_>,
+    #[description = "The channel where the bot will post maps to play within clan wars. Leave empty to disable."]
+    #[channel_types("Text")]
+    channel_id: Option<ChannelId>,
+) -> Result<(), Error> {
+    let guild_id = get_guild_id(ctx, true).await?;
+
+    match ctx
+        .data()
+        .guild_settings_repository
+        .set_clan_wars_maps_channel(&guild_id, channel_id)
+        .await
+    {
+        Ok(guild_settings) => {
+            say_guild_settings(ctx, &guild_settings).await?;
+
+            Ok(())
+        }
+        Err(e) => {
+            ctx.say(format!("An error occurred: {}", e)).await?;
+
+            Ok(())
+        }
+    }
+}
+
+/// Set or unset the number of posted clan wars maps
+#[tracing::instrument(skip(ctx), level=tracing::Level::INFO, name="bot_command:bl-set-clan-wars-maps-count")]
+#[poise::command(
+    slash_command,
+    rename = "bl-set-clan-wars-maps-count",
+    ephemeral,
+    required_permissions = "MANAGE_ROLES",
+    default_member_permissions = "MANAGE_ROLES",
+    required_bot_permissions = "MANAGE_ROLES",
+    guild_only,
+    hide_in_help
+)]
+pub(crate) async fn cmd_set_clan_wars_maps_count(
+    ctx: Context<'_>,
+    #[description = "Number of clan wars maps posted to the channel. Leave empty to use the bot default."]
+    #[min = 1]
+    #[max = 100]
+    count: Option<u16>,
+) -> Result<(), Error> {
+    if count.is_some_and(|count| count == 0 || count > CLAN_WARS_MAPS_MAX_COUNT) {
+        say_without_ping(
+            ctx,
+            format!(
+                "Maps count must be between 1 and {}.",
+                CLAN_WARS_MAPS_MAX_COUNT
+            )
+            .as_str(),
+            true,
+        )
+        .await?;
+
+        return Ok(());
+    }
+
+    let guild_id = get_guild_id(ctx, true).await?;
+
+    match ctx
+        .data()
+        .guild_settings_repository
+        .set_clan_wars_maps_count(&guild_id, count)
+        .await
+    {
+        Ok(guild_settings) => {
+            say_guild_settings(ctx, &guild_settings).await?;
+
+            Ok(())
+        }
+        Err(e) => {
+            ctx.say(format!("An error occurred: {}", e)).await?;
+
+            Ok(())
+        }
+    }
+}
+
+/// Set or unset star range of the posted clan wars maps
+#[tracing::instrument(skip(ctx), level=tracing::Level::INFO, name="bot_command:bl-set-clan-wars-star-range")]
+#[poise::command(
+    slash_command,
+    rename = "bl-set-clan-wars-star-range",
+    ephemeral,
+    required_permissions = "MANAGE_ROLES",
+    default_member_permissions = "MANAGE_ROLES",
+    required_bot_permissions = "MANAGE_ROLES",
+    guild_only,
+    hide_in_help
+)]
+pub(crate) async fn cmd_set_clan_wars_star_range(
+    ctx: Context<'_>,
+    #[description = "Min. stars of the posted clan wars maps. Leave empty for no lower bound."]
+    #[min = 0]
+    min_stars: Option<f64>,
+    #[description = "Max. stars of the posted clan wars maps. Leave empty for no upper bound."]
+    #[min = 0]
+    max_stars: Option<f64>,
+) -> Result<(), Error> {
+    if min_stars.is_some_and(|stars| !stars.is_finite() || stars < 0.0)
+        || max_stars.is_some_and(|stars| !stars.is_finite() || stars < 0.0)
+    {
+        say_without_ping(ctx, "Stars must be a non-negative number.", true).await?;
+
+        return Ok(());
+    }
+
+    if let (Some(min), Some(max)) = (min_stars, max_stars) {
+        if min > max {
+            say_without_ping(ctx, "Min. stars can not be greater than max. stars.", true).await?;
+
+            return Ok(());
+        }
+    }
+
+    let guild_id = get_guild_id(ctx, true).await?;
+
+    match ctx
+        .data()
+        .guild_settings_repository
+        .set_clan_wars_star_range(&guild_id, min_stars, max_stars)
+        .await
+    {
+        Ok(guild_settings) => {
+            say_guild_settings(ctx, &guild_settings).await?;
+
+            Ok(())
+        }
+        Err(e) => {
+            ctx.say(format!("An error occurred: {}", e)).await?;
+
+            Ok(())
+        }
+    }
+}
+
+/// Set or unset clan wars contribution channel
+#[tracing::instrument(skip(ctx), level=tracing::Level::INFO, name="bot_command:bl-set-clan-wars-contribution-channel")]
+#[poise::command(
+    slash_command,
+    rename = "bl-set-clan-wars-contrib-channel",
+    ephemeral,
+    required_permissions = "MANAGE_ROLES",
+    default_member_permissions = "MANAGE_ROLES",
+    required_bot_permissions = "MANAGE_ROLES",
+    guild_only,
+    hide_in_help
+)]
+pub(crate) async fn cmd_set_clan_wars_contribution_channel(
+    ctx: Context<'_>,
+    #[description = "The channel where the bot will post clan wars contributions. Leave empty to disable."]
+    #[channel_types("Text")]
+    channel_id: Option<ChannelId>,
+) -> Result<(), Error> {
+    let guild_id = get_guild_id(ctx, true).await?;
+
+    match ctx
+        .data()
+        .guild_settings_repository
+        .set_clan_wars_maps_contribution_channel(&guild_id, channel_id)
+        .await
+    {
+        Ok(guild_settings) => {
+            say_guild_settings(ctx, &guild_settings).await?;
+
+            Ok(())
+        }
+        Err(e) => {
+            ctx.say(format!("An error occurred: {}", e)).await?;
+
+            Ok(())
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, poise::ChoiceParameter)]
+pub(crate) enum ClanRecapDay {
+    Monday,
+    Tuesday,
+    Wednesday,
+    Thursday,
+    Friday,
+    Saturday,
+    Sunday,
+}
+
+impl From<ClanRecapDay> for Weekday {
+    fn from(value: ClanRecapDay) -> Self {
+        match value {
+            ClanRecapDay::Monday => Weekday::Mon,
+            ClanRecapDay::Tuesday => Weekday::Tue,
+            ClanRecapDay::Wednesday => Weekday::Wed,
+            ClanRecapDay::Thursday => Weekday::Thu,
+            ClanRecapDay::Friday => Weekday::Fri,
+            ClanRecapDay::Saturday => Weekday::Sat,
+            ClanRecapDay::Sunday => Weekday::Sun,
+        }
+    }
+}
+
+/// Set or unset the schedule of the weekly clan recap posted to the clan wars maps channel
+#[tracing::instrument(skip(ctx), level=tracing::Level::INFO, name="bot_command:bl-set-clan-recap")]
+#[poise::command(
+    slash_command,
+    rename = "bl-set-clan-recap",
+    ephemeral,
+    required_permissions = "MANAGE_ROLES",
+    default_member_permissions = "MANAGE_ROLES",
+    required_bot_permissions = "MANAGE_ROLES",
+    guild_only,
+    hide_in_help
+)]
+pub(crate) async fn cmd_set_clan_recap(
+    ctx: Context<'_>,
+    #[description = "Day of the week to post the recap on. Leave empty to disable."] day: Option<
+        ClanRecapDay,
+    >,
+    #[description = "Hour to post the recap at, in the server timezone (default: 18)"]
+    #[min = 0]
+    #[max = 23]
+    hour: Option<u32>,
+) -> Result<(), Error> {
+    let guild_id = get_guild_id(ctx, true).await?;
+
+    match ctx
+        .data()
+        .guild_settings_repository
+        .set_clan_recap_schedule(
+            &guild_id,
+            day.map(|day| ClanRecapSchedule::new(day.into(), hour.unwrap_or(18))),
+        )
+        .await
+    {
+        Ok(guild_settings) => {
+            say_guild_settings(ctx, &guild_settings).await?;
+
+            Ok(())
+        }
+        Err(e) => {
+            ctx.say(format!("An error occurred: {}", e)).await?;
+
+            Ok(())
+        }
+    }
+}
+
+/// Set or unset the cover image of clan wars playlists
+#[tracing::instrument(skip(ctx), level=tracing::Level::INFO, name="bot_command:bl-set-playlist-cover")]
+#[poise::command(
+    slash_command,
+    rename = "bl-set-playlist-cover",
+    ephemeral,
+    required_permissions = "MANAGE_ROLES",
+    default_member_permissions = "MANAGE_ROLES",
+    required_bot_permissions = "MANAGE_ROLES",
+    guild_only,
+    hide_in_help
+)]
+pub(crate) async fn cmd_set_playlist_cover(
+    ctx: Context<'_>,
+    #[description = "Cover image (PNG or JPEG). Leave empty to restore the default cover."]
+    cover: Option<Attachment>,
+    #[description = "Playlist type (default: all types without their own cover)"]
+    playlist_type: Option<ClanWarsSort>,
+) -> Result<(), Error> {
+    ctx.defer_ephemeral().await?;
+
+    let guild_id = get_guild_id(ctx, true).await?;
 
-                    // calculate clan pp without player and pp_boundary
-                    let clan_pp_without_player = calculate_total_pp_from_sorted(
-                        CLAN_WEIGHT_COEFFICIENT,
-                        &pps_without_player,
-                        0,
-                    );
-                    clan_map_with_scores.map.pp = clan_pp_without_player - leading_clan_pp;
-                    clan_map_with_scores.calc_pp_boundary(Some(player.id.clone()));
+    let cover = match cover {
+        Some(attachment) => {
+            if attachment.size as usize > PLAYLIST_COVER_MAX_BYTES {
+                ctx.say(format!(
+                    "Playlist cover can not be larger than {}KB",
+                    PLAYLIST_COVER_MAX_BYTES / 1024
+                ))
+                .await?;
 
-                    // set real pp loss
-                    clan_map_with_scores.map.pp = real_pp_loss;
+                return Ok(());
+            }
 
-                    msg.edit(
-                        ctx,
-                        CreateReply::default().content(clan_map_with_scores.to_player_string(
-                            clan_tag,
-                            leading_clan_tag,
-                            player.id,
-                            is_captured,
-                        )),
-                    )
+            let bytes = match attachment.download().await {
+                Ok(bytes) => bytes,
+                Err(err) => {
+                    ctx.say(format!(
+                        "An error occurred during downloading attachment: {}",
+                        err
+                    ))
                     .await?;
+
+                    return Ok(());
                 }
+            };
+
+            match Playlist::encode_cover(&bytes) {
+                Ok(cover) => Some(cover),
                 Err(err) => {
-                    msg.edit(
-                        ctx,
-                        CreateReply::default()
-                            .content(format!("Oh snap! An error occurred: {}", err)),
-                    )
-                    .await?;
+                    ctx.say(err).await?;
+
+                    return Ok(());
                 }
             }
+        }
+        None => None,
+    };
+
+    match ctx
+        .data()
+        .guild_settings_repository
+        .set_playlist_cover(&guild_id, playlist_type, cover)
+        .await
+    {
+        Ok(guild_settings) => {
+            say_guild_settings(ctx, &guild_settings).await?;
 
             Ok(())
         }
-        None => {
-            say_profile_not_linked(
-                ctx,
-                &current_user.id,
-                guild_settings.requires_verified_profile,
-            )
-            .await?;
+        Err(e) => {
+            ctx.say(format!("An error occurred: {}", e)).await?;
 
             Ok(())
         }
     }
 }
 
-/// Set or unset clan wars maps channel
-#[tracing::instrument(skip(ctx), level=tracing::Level::INFO, name="bot_command:bl-set-clan-wars-maps-channel")]
+/// Set or unset the min. stars of commander's orders injected into clan wars playlists
+#[tracing::instrument(skip(ctx), level=tracing::Level::INFO, name="bot_command:bl-set-commander-order-min-stars")]
 #[poise::command(
     slash_command,
-    rename = "bl-set-clan-wars-maps-channel",
+    rename = "bl-set-commander-order-min-stars",
     ephemeral,
     required_permissions = "MANAGE_ROLES",
     default_member_permissions = "MANAGE_ROLES",
@@ -765,22 +1925,28 @@ pub(crate) async fn cmd_capture(
     guild_only,
     hide_in_help
 )]
-pub(crate) async fn cmd_set_clan_wars_maps_channel(
+pub(crate) async fn cmd_set_commander_order_min_stars(
     ctx: Context<'_>,
-    #[description = "The channel where the bot will post maps to play within clan wars. Leave empty to disable."]
-    #[channel_types("Text")]
-    channel_id: Option<ChannelId>,
+    #[description = "Min. stars of commander's orders added to playlists. Leave empty for no lower bound."]
+    #[min = 0]
+    min_stars: Option<f64>,
 ) -> Result<(), Error> {
+    if min_stars.is_some_and(|stars| !stars.is_finite() || stars < 0.0) {
+        say_without_ping(ctx, "Stars must be a non-negative number.", true).await?;
+
+        return Ok(());
+    }
+
     let guild_id = get_guild_id(ctx, true).await?;
 
     match ctx
         .data()
         .guild_settings_repository
-        .set_clan_wars_maps_channel(&guild_id, channel_id)
+        .set_commander_order_min_stars(&guild_id, min_stars)
         .await
     {
         Ok(guild_settings) => {
-            ctx.say(format!("{}", guild_settings)).await?;
+            say_guild_settings(ctx, &guild_settings).await?;
 
             Ok(())
         }
@@ -792,11 +1958,11 @@ pub(crate) async fn cmd_set_clan_wars_maps_channel(
     }
 }
 
-/// Set or unset clan wars contribution channel
-#[tracing::instrument(skip(ctx), level=tracing::Level::INFO, name="bot_command:bl-set-clan-wars-contribution-channel")]
+/// Set whether FC'd maps are skipped in clan wars playlists by default
+#[tracing::instrument(skip(ctx), level=tracing::Level::INFO, name="bot_command:bl-set-playlist-skip-fcs")]
 #[poise::command(
     slash_command,
-    rename = "bl-set-clan-wars-contrib-channel",
+    rename = "bl-set-playlist-skip-fcs",
     ephemeral,
     required_permissions = "MANAGE_ROLES",
     default_member_permissions = "MANAGE_ROLES",
@@ -804,22 +1970,22 @@ pub(crate) async fn cmd_set_clan_wars_maps_channel(
     guild_only,
     hide_in_help
 )]
-pub(crate) async fn cmd_set_clan_wars_contribution_channel(
+pub(crate) async fn cmd_set_playlist_skip_fcs(
     ctx: Context<'_>,
-    #[description = "The channel where the bot will post clan wars contributions. Leave empty to disable."]
-    #[channel_types("Text")]
-    channel_id: Option<ChannelId>,
+    #[description = "Skip FC'd maps if the playlist FC status is not given"] skip_fcs: bool,
 ) -> Result<(), Error> {
+    ctx.defer_ephemeral().await?;
+
     let guild_id = get_guild_id(ctx, true).await?;
 
     match ctx
         .data()
         .guild_settings_repository
-        .set_clan_wars_maps_contribution_channel(&guild_id, channel_id)
+        .set_playlist_skip_fcs(&guild_id, skip_fcs)
         .await
     {
         Ok(guild_settings) => {
-            ctx.say(format!("{}", guild_settings)).await?;
+            say_guild_settings(ctx, &guild_settings).await?;
 
             Ok(())
         }
@@ -955,7 +2121,51 @@ pub(crate) async fn cmd_set_clan_wars_soldier_role(
         .await
     {
         Ok(guild_settings) => {
-            say_without_ping(ctx, format!("{}", guild_settings).as_str(), true).await?;
+            say_guild_settings(ctx, &guild_settings).await?;
+
+            Ok(())
+        }
+        Err(e) => {
+            let message = format!("An error occurred: {}", e);
+            say_without_ping(ctx, message.as_str(), true).await?;
+
+            Ok(())
+        }
+    }
+}
+
+/// Set role mentioned when new clan wars maps are posted
+#[tracing::instrument(skip(ctx), level=tracing::Level::INFO, name="bot_command:bl-set-clan-wars-ping-role")]
+#[poise::command(
+    slash_command,
+    rename = "bl-set-clan-wars-ping-role",
+    ephemeral,
+    required_permissions = "MANAGE_ROLES",
+    default_member_permissions = "MANAGE_ROLES",
+    required_bot_permissions = "MANAGE_ROLES",
+    guild_only,
+    hide_in_help
+)]
+pub(crate) async fn cmd_set_clan_wars_ping_role(
+    ctx: Context<'_>,
+    #[description = "Role to mention when new clan wars maps are posted. Leave empty to disable."]
+    role: Option<Role>,
+) -> Result<(), Error> {
+    let guild_settings = get_guild_settings(ctx, true).await?;
+    if guild_settings.clan_settings.is_none() {
+        say_without_ping(ctx, "Clan is not set up in this guild.", true).await?;
+
+        return Ok(());
+    }
+
+    match ctx
+        .data()
+        .guild_settings_repository
+        .set_clan_wars_ping_role(&guild_settings.guild_id, role.map(|r| r.id))
+        .await
+    {
+        Ok(guild_settings) => {
+            say_guild_settings(ctx, &guild_settings).await?;
 
             Ok(())
         }
@@ -998,7 +2208,7 @@ pub(crate) async fn cmd_set_clan_commander_role(
         .await
     {
         Ok(guild_settings) => {
-            ctx.say(format!("{}", guild_settings)).await?;
+            say_guild_settings(ctx, &guild_settings).await?;
 
             Ok(())
         }
@@ -1011,11 +2221,7 @@ pub(crate) async fn cmd_set_clan_commander_role(
 }
 
 #[tracing::instrument(skip(ctx, message), level=tracing::Level::INFO, name="bot_command:commanders-order")]
-#[poise::command(
-    context_menu_command = "Commander's order",
-    guild_only,
-    member_cooldown = 5
-)]
+#[poise::command(context_menu_command = "Commander's order", guild_only)]
 pub(crate) async fn cmd_commanders_order(
     ctx: Context<'_>,
     #[description = "Message to analyze"] message: Message,
@@ -1130,11 +2336,7 @@ pub(crate) async fn cmd_commanders_order(
 }
 
 #[tracing::instrument(skip(ctx, message), level=tracing::Level::INFO, name="bot_command:revoke-commanders-order")]
-#[poise::command(
-    context_menu_command = "Revoke commander's order",
-    guild_only,
-    member_cooldown = 5
-)]
+#[poise::command(context_menu_command = "Revoke commander's order", guild_only)]
 pub(crate) async fn cmd_revoke_commanders_order(
     ctx: Context<'_>,
     #[description = "Message to analyze"] message: Message,
@@ -1210,11 +2412,7 @@ pub(crate) async fn cmd_revoke_commanders_order(
 }
 
 #[tracing::instrument(skip(ctx, message), level=tracing::Level::INFO, name="bot_command:remove-from-map-list")]
-#[poise::command(
-    context_menu_command = "Remove from the map list",
-    guild_only,
-    member_cooldown = 5
-)]
+#[poise::command(context_menu_command = "Remove from the map list", guild_only)]
 pub(crate) async fn cmd_remove_from_map_list(
     ctx: Context<'_>,
     #[description = "Message to analyze"] message: Message,
@@ -1329,11 +2527,7 @@ pub(crate) async fn cmd_remove_from_map_list(
 }
 
 #[tracing::instrument(skip(ctx, message), level=tracing::Level::INFO, name="bot_command:restore-to-map-list")]
-#[poise::command(
-    context_menu_command = "Restore to the map list",
-    guild_only,
-    member_cooldown = 5
-)]
+#[poise::command(context_menu_command = "Restore to the map list", guild_only)]
 pub(crate) async fn cmd_restore_to_map_list(
     ctx: Context<'_>,
     #[description = "Message to analyze"] message: Message,
@@ -1401,9 +2595,163 @@ pub(crate) async fn cmd_restore_to_map_list(
     }
 }
 
+// each map is checked on BL before it is removed
+const MAP_LIST_EXCLUDE_MAX_MAPS: usize = 25;
+
+/// Remove multiple maps from the clan wars map list at once
+#[tracing::instrument(skip(ctx), level=tracing::Level::INFO, name="bot_command:bl-set-clan-wars-exclude-maps")]
+#[poise::command(slash_command, rename = "bl-set-clan-wars-exclude-maps", guild_only)]
+pub(crate) async fn cmd_set_clan_wars_exclude_maps(
+    ctx: Context<'_>,
+    #[description = "Leaderboard links or IDs, separated by spaces or commas"] maps: String,
+) -> Result<(), Error> {
+    let leaderboard_ids = get_leaderboard_ids_from_list(&maps);
+
+    if leaderboard_ids.len() > MAP_LIST_EXCLUDE_MAX_MAPS {
+        say_without_ping(
+            ctx,
+            format!(
+                "Up to {} maps can be removed at once.",
+                MAP_LIST_EXCLUDE_MAX_MAPS
+            )
+            .as_str(),
+            true,
+        )
+        .await?;
+
+        return Ok(());
+    }
+
+    let (leaderboard_ids, clan_tag) =
+        match get_leaderboard_ids_for_commander(ctx, leaderboard_ids).await {
+            Ok((leaderboard_ids, clan_tag)) => (leaderboard_ids, clan_tag),
+            Err(e) => {
+                say_without_ping(ctx, format!("{}", e).as_str(), false).await?;
+
+                return Ok(());
+            }
+        };
+
+    let msg = ctx
+        .say(format!(
+            "Sure, give me a moment to check {} map(s).",
+            leaderboard_ids.len()
+        ))
+        .await?;
+
+    let mut added = vec![];
+    let mut already_present = 0;
+    let mut failed = vec![];
+
+    for leaderboard_id in leaderboard_ids {
+        match ctx
+            .data()
+            .maps_repository
+            .get_map_list_ban(&leaderboard_id, &clan_tag)
+            .await
+        {
+            Ok(Some(_)) => {
+                already_present += 1;
+
+                continue;
+            }
+            Ok(None) => {}
+            Err(err) => {
+                failed.push(format!("{}: {}", &leaderboard_id, err));
+
+                continue;
+            }
+        }
+
+        let leaderboard = match BL_CLIENT.clan().leaderboard(&leaderboard_id, &[]).await {
+            Ok(leaderboard) => leaderboard,
+            Err(err) => {
+                failed.push(format!("{}: {}", &leaderboard_id, err));
+
+                continue;
+            }
+        };
+
+        if leaderboard.difficulty.status != DifficultyStatus::Ranked
+            && leaderboard.difficulty.status != DifficultyStatus::Qualified
+            && leaderboard.difficulty.status != DifficultyStatus::Nominated
+        {
+            failed.push(format!(
+                "{}: leaderboard must have nominated, qualified or ranked status",
+                &leaderboard_id
+            ));
+
+            continue;
+        }
+
+        match ctx
+            .data()
+            .maps_repository
+            .save(BsMap::new(
+                ctx.author().id,
+                leaderboard,
+                BsMapType::MapListSkip,
+                None,
+                Some(clan_tag.clone()),
+            ))
+            .await
+        {
+            Ok(map) => added.push(map.to_string()),
+            Err(err) => failed.push(format!("{}: {}", &leaderboard_id, err)),
+        }
+    }
+
+    msg.edit(
+        ctx,
+        CreateReply::default()
+            .content(format_map_list_exclusions(&added, already_present, &failed))
+            .allowed_mentions(CreateAllowedMentions::new()),
+    )
+    .await?;
+
+    Ok(())
+}
+
+fn format_map_list_exclusions(
+    added: &[String],
+    already_present: usize,
+    failed: &[String],
+) -> String {
+    let mut lines = vec![format!(
+        "Removed from the map list: {}, already removed: {}.",
+        added.len(),
+        already_present
+    )];
+
+    lines.extend(added.iter().cloned());
+
+    if !failed.is_empty() {
+        lines.push("Failed:".to_owned());
+        lines.extend(failed.iter().cloned());
+    }
+
+    let mut content = lines.join("\n");
+    if content.chars().count() > MAX_DISCORD_MESSAGE_LENGTH {
+        content = content
+            .chars()
+            .take(MAX_DISCORD_MESSAGE_LENGTH - 3)
+            .collect::<String>()
+            + "...";
+    }
+
+    content
+}
+
 async fn get_leaderboard_id_for_commander(
     ctx: Context<'_>,
     message: Message,
+) -> Result<(Vec<String>, ClanTag), Error> {
+    get_leaderboard_ids_for_commander(ctx, get_leaderboard_ids_from_message(message)).await
+}
+
+async fn get_leaderboard_ids_for_commander(
+    ctx: Context<'_>,
+    leaderboard_ids: Vec<String>,
 ) -> Result<(Vec<String>, ClanTag), Error> {
     ctx.defer().await?;
 
@@ -1413,8 +2761,6 @@ async fn get_leaderboard_id_for_commander(
         return Err("Clan is not set up in this guild".to_owned().into());
     }
 
-    let leaderboard_ids = get_leaderboard_ids_from_message(message);
-
     if leaderboard_ids.is_empty() {
         return Err("I can't find any link to the leaderboard here."
             .to_owned()
@@ -1441,3 +2787,92 @@ async fn get_leaderboard_id_for_commander(
 
     Ok((leaderboard_ids, clan_settings.clan))
 }
+
+// replies to the user and returns None if the user is not a linked, primary member of the clan
+async fn get_clan_member(
+    ctx: Context<'_>,
+    guild_settings: &GuildSettings,
+    clan_tag: &ClanTag,
+) -> Result<Option<Player>, Error> {
+    let current_user = ctx.author();
+
+    let Some(player) = link_user_if_needed(
+        ctx,
+        &guild_settings.guild_id,
+        current_user,
+        guild_settings.requires_verified_profile,
+    )
+    .await
+    else {
+        say_profile_not_linked(
+            ctx,
+            &current_user.id,
+            guild_settings.requires_verified_profile,
+        )
+        .await?;
+
+        return Ok(None);
+    };
+
+    if !player.is_linked_to_guild(&guild_settings.guild_id) {
+        say_profile_not_linked(
+            ctx,
+            &current_user.id,
+            guild_settings.requires_verified_profile,
+        )
+        .await?;
+
+        return Ok(None);
+    }
+
+    let clan_membership_cache = &ctx.data().clan_membership_cache;
+    let clans = match clan_membership_cache.get(&player.id) {
+        Some(clans) => clans,
+        None => match fetch_player_from_bl(&player.id).await {
+            Ok(bl_player) => {
+                let clans = bl_player
+                    .clans
+                    .into_iter()
+                    .map(|clan| clan.tag)
+                    .collect::<Vec<_>>();
+                clan_membership_cache.insert(&player.id, clans.clone());
+
+                clans
+            }
+            Err(err) => {
+                say_without_ping(
+                    ctx,
+                    format!("Error: can not fetch player data from BL: {}", err).as_str(),
+                    true,
+                )
+                .await?;
+
+                return Ok(None);
+            }
+        },
+    };
+
+    if !clans.contains(clan_tag) {
+        say_without_ping(
+            ctx,
+            format!("You are not a member of the {} clan.", clan_tag).as_str(),
+            false,
+        )
+        .await?;
+
+        return Ok(None);
+    }
+
+    if clans.first().unwrap() != clan_tag {
+        say_without_ping(
+            ctx,
+            format!("You did not set clan {} as primary. Go to your profile and move the clan to the first position on the list.", clan_tag).as_str(),
+            true,
+        )
+        .await?;
+
+        return Ok(None);
+    }
+
+    Ok(Some(player))
+}
@@ -1,17 +1,553 @@
+use chrono_tz::Tz;
 use futures::Stream;
 use poise::serenity_prelude;
-use poise::serenity_prelude::{ChannelId, GuildId};
+use poise::serenity_prelude::{
+    ChannelId, CreateAllowedMentions, GuildId, Permissions, RoleId, User,
+};
+use poise::CreateReply;
 
-use crate::discord::bot::commands::player::say_without_ping;
-use crate::discord::bot::{Condition, GuildSettings, Metric, RequirementMetricValue};
+use crate::beatleader::error::Error as BlError;
+use crate::discord::bot::beatleader::clan::{clan_not_found_message, fetch_clan};
+use crate::discord::bot::commands::get_user_id_with_required_permission;
+use crate::discord::bot::commands::player::{say_profile_not_linked, say_without_ping};
+use crate::discord::bot::{
+    split_msg_in_parts, Condition, EmbedTheme, GuildSettings, Metric, RequirementMetricValue,
+    RoleMatchMode,
+};
+use crate::discord::worker::user_roles::apply_user_role_changes;
 use crate::discord::Context;
+use crate::embed::MapTriangleColors;
+use crate::storage::guild::AutoRoleParams;
 use crate::Error;
 
+#[derive(Debug, poise::ChoiceParameter, Default)]
+pub(crate) enum EmbedThemeChoice {
+    #[name = "Dark"]
+    #[default]
+    Dark,
+    #[name = "Light"]
+    Light,
+    #[name = "Clan color"]
+    ClanColor,
+}
+
 /// Display current bot settings
 #[tracing::instrument(skip(ctx), level=tracing::Level::INFO, name="bot_command:bl-show-settings")]
 #[poise::command(
     slash_command,
-    rename = "bl-show-settings",
+    rename = "bl-show-settings",
+    ephemeral,
+    required_permissions = "MANAGE_ROLES",
+    default_member_permissions = "MANAGE_ROLES",
+    required_bot_permissions = "MANAGE_ROLES",
+    guild_only,
+    hide_in_help
+)]
+pub(crate) async fn cmd_show_settings(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_settings = get_guild_settings(ctx, true).await?;
+
+    say_guild_settings(ctx, &guild_settings).await?;
+
+    Ok(())
+}
+
+/// Set or unset bot log channel
+#[tracing::instrument(skip(ctx), level=tracing::Level::INFO, name="bot_command:bl-set-log-channel")]
+#[poise::command(
+    slash_command,
+    rename = "bl-set-log-channel",
+    ephemeral,
+    required_permissions = "MANAGE_ROLES",
+    default_member_permissions = "MANAGE_ROLES",
+    required_bot_permissions = "MANAGE_ROLES",
+    guild_only,
+    hide_in_help
+)]
+pub(crate) async fn cmd_set_log_channel(
+    ctx: Context<'_>,
+    #[description = "The channel where the bot logs will be posted. Leave empty to disable logging."]
+    #[channel_types("Text")]
+    channel_id: Option<ChannelId>,
+) -> Result<(), Error> {
+    let guild_id = get_guild_id(ctx, true).await?;
+
+    match ctx
+        .data()
+        .guild_settings_repository
+        .set_bot_channel(&guild_id, channel_id)
+        .await
+    {
+        Ok(guild_settings) => {
+            say_guild_settings(ctx, &guild_settings).await?;
+
+            Ok(())
+        }
+        Err(e) => {
+            ctx.say(format!("An error occurred: {}", e)).await?;
+
+            Ok(())
+        }
+    }
+}
+
+/// Set or unset role changes log channel
+#[tracing::instrument(skip(ctx), level=tracing::Level::INFO, name="bot_command:bl-set-role-changes-channel")]
+#[poise::command(
+    slash_command,
+    rename = "bl-set-role-changes-channel",
+    ephemeral,
+    required_permissions = "MANAGE_ROLES",
+    default_member_permissions = "MANAGE_ROLES",
+    required_bot_permissions = "MANAGE_ROLES",
+    guild_only,
+    hide_in_help
+)]
+pub(crate) async fn cmd_set_role_changes_channel(
+    ctx: Context<'_>,
+    #[description = "The channel where the role changes summaries will be posted. Leave empty to use the bot log channel."]
+    #[channel_types("Text")]
+    channel_id: Option<ChannelId>,
+) -> Result<(), Error> {
+    let guild_id = get_guild_id(ctx, true).await?;
+
+    match ctx
+        .data()
+        .guild_settings_repository
+        .set_role_changes_channel(&guild_id, channel_id)
+        .await
+    {
+        Ok(guild_settings) => {
+            say_guild_settings(ctx, &guild_settings).await?;
+
+            Ok(())
+        }
+        Err(e) => {
+            ctx.say(format!("An error occurred: {}", e)).await?;
+
+            Ok(())
+        }
+    }
+}
+
+/// Set or unset errors log channel
+#[tracing::instrument(skip(ctx), level=tracing::Level::INFO, name="bot_command:bl-set-errors-channel")]
+#[poise::command(
+    slash_command,
+    rename = "bl-set-errors-channel",
+    ephemeral,
+    required_permissions = "MANAGE_ROLES",
+    default_member_permissions = "MANAGE_ROLES",
+    required_bot_permissions = "MANAGE_ROLES",
+    guild_only,
+    hide_in_help
+)]
+pub(crate) async fn cmd_set_errors_channel(
+    ctx: Context<'_>,
+    #[description = "The channel where the errors the bot runs into will be posted. Leave empty to use the bot log channel."]
+    #[channel_types("Text")]
+    channel_id: Option<ChannelId>,
+) -> Result<(), Error> {
+    let guild_id = get_guild_id(ctx, true).await?;
+
+    match ctx
+        .data()
+        .guild_settings_repository
+        .set_errors_channel(&guild_id, channel_id)
+        .await
+    {
+        Ok(guild_settings) => {
+            say_guild_settings(ctx, &guild_settings).await?;
+
+            Ok(())
+        }
+        Err(e) => {
+            ctx.say(format!("An error occurred: {}", e)).await?;
+
+            Ok(())
+        }
+    }
+}
+
+/// Set profile verification requirement
+#[tracing::instrument(skip(ctx), level=tracing::Level::INFO, name="bot_command:bl-set-profile-verification")]
+#[poise::command(
+    slash_command,
+    rename = "bl-set-profile-verification",
+    ephemeral,
+    required_permissions = "MANAGE_ROLES",
+    default_member_permissions = "MANAGE_ROLES",
+    required_bot_permissions = "MANAGE_ROLES",
+    guild_only,
+    hide_in_help
+)]
+pub(crate) async fn cmd_set_profile_verification(
+    ctx: Context<'_>,
+    #[description = "Does the bl-link command require a verified profile or not."]
+    requires_verified_profile: bool,
+) -> Result<(), Error> {
+    let guild_id = get_guild_id(ctx, true).await?;
+
+    match ctx
+        .data()
+        .guild_settings_repository
+        .set_verified_profile_requirement(&guild_id, requires_verified_profile)
+        .await
+    {
+        Ok(guild_settings) => {
+            say_guild_settings(ctx, &guild_settings).await?;
+
+            Ok(())
+        }
+        Err(e) => {
+            ctx.say(format!("An error occurred: {}", e)).await?;
+
+            Ok(())
+        }
+    }
+}
+
+/// Toggle DMs with profile verification steps
+#[tracing::instrument(skip(ctx), level=tracing::Level::INFO, name="bot_command:bl-set-verification-reminder")]
+#[poise::command(
+    slash_command,
+    rename = "bl-set-verification-reminder",
+    ephemeral,
+    required_permissions = "MANAGE_ROLES",
+    default_member_permissions = "MANAGE_ROLES",
+    required_bot_permissions = "MANAGE_ROLES",
+    guild_only,
+    hide_in_help
+)]
+pub(crate) async fn cmd_set_verification_reminder(
+    ctx: Context<'_>,
+    #[description = "DM unverified users the verification steps on join or on failed link."]
+    verification_reminder: bool,
+) -> Result<(), Error> {
+    let guild_id = get_guild_id(ctx, true).await?;
+
+    match ctx
+        .data()
+        .guild_settings_repository
+        .set_verification_reminder(&guild_id, verification_reminder)
+        .await
+    {
+        Ok(guild_settings) => {
+            say_guild_settings(ctx, &guild_settings).await?;
+
+            Ok(())
+        }
+        Err(e) => {
+            ctx.say(format!("An error occurred: {}", e)).await?;
+
+            Ok(())
+        }
+    }
+}
+
+/// Set roles granted to verified users when they link their profile
+#[tracing::instrument(skip(ctx), level=tracing::Level::INFO, name="bot_command:bl-set-welcome-roles")]
+#[poise::command(
+    slash_command,
+    rename = "bl-set-welcome-roles",
+    ephemeral,
+    required_permissions = "MANAGE_ROLES",
+    default_member_permissions = "MANAGE_ROLES",
+    required_bot_permissions = "MANAGE_ROLES",
+    guild_only,
+    hide_in_help
+)]
+pub(crate) async fn cmd_set_welcome_roles(
+    ctx: Context<'_>,
+    #[description = "Role granted on link, regardless of auto roles. Leave all empty to remove."]
+    role: Option<serenity_prelude::Role>,
+    #[description = "Another role granted on link"] role2: Option<serenity_prelude::Role>,
+    #[description = "Another role granted on link"] role3: Option<serenity_prelude::Role>,
+) -> Result<(), Error> {
+    let guild_id = get_guild_id(ctx, true).await?;
+
+    let mut welcome_roles = Vec::new();
+    for role_id in [role, role2, role3]
+        .into_iter()
+        .flatten()
+        .map(|role| role.id)
+    {
+        if !welcome_roles.contains(&role_id) {
+            welcome_roles.push(role_id);
+        }
+    }
+
+    match ctx
+        .data()
+        .guild_settings_repository
+        .set_welcome_roles(&guild_id, welcome_roles)
+        .await
+    {
+        Ok(guild_settings) => {
+            say_guild_settings(ctx, &guild_settings).await?;
+
+            Ok(())
+        }
+        Err(e) => {
+            ctx.say(format!("An error occurred: {}", e)).await?;
+
+            Ok(())
+        }
+    }
+}
+
+/// Toggle auto roles dry run mode
+#[tracing::instrument(skip(ctx), level=tracing::Level::INFO, name="bot_command:bl-set-auto-roles-dry-run")]
+#[poise::command(
+    slash_command,
+    rename = "bl-set-auto-roles-dry-run",
+    ephemeral,
+    required_permissions = "MANAGE_ROLES",
+    default_member_permissions = "MANAGE_ROLES",
+    required_bot_permissions = "MANAGE_ROLES",
+    guild_only,
+    hide_in_help
+)]
+pub(crate) async fn cmd_set_auto_roles_dry_run(
+    ctx: Context<'_>,
+    #[description = "Only log role changes to the bot log channel instead of applying them."]
+    dry_run: bool,
+) -> Result<(), Error> {
+    let guild_id = get_guild_id(ctx, true).await?;
+
+    match ctx
+        .data()
+        .guild_settings_repository
+        .set_auto_roles_dry_run(&guild_id, dry_run)
+        .await
+    {
+        Ok(guild_settings) => {
+            say_guild_settings(ctx, &guild_settings).await?;
+
+            Ok(())
+        }
+        Err(e) => {
+            ctx.say(format!("An error occurred: {}", e)).await?;
+
+            Ok(())
+        }
+    }
+}
+
+/// Set auto roles cooldown
+#[tracing::instrument(skip(ctx), level=tracing::Level::INFO, name="bot_command:bl-set-auto-role-cooldown")]
+#[poise::command(
+    slash_command,
+    rename = "bl-set-auto-role-cooldown",
+    ephemeral,
+    required_permissions = "MANAGE_ROLES",
+    default_member_permissions = "MANAGE_ROLES",
+    required_bot_permissions = "MANAGE_ROLES",
+    guild_only,
+    hide_in_help
+)]
+pub(crate) async fn cmd_set_auto_role_cooldown(
+    ctx: Context<'_>,
+    #[description = "Hours before a changed role can be changed again for the same user (0 disables it, default: 6)."]
+    #[max = 720]
+    hours: u32,
+) -> Result<(), Error> {
+    let guild_id = get_guild_id(ctx, true).await?;
+
+    match ctx
+        .data()
+        .guild_settings_repository
+        .set_auto_roles_cooldown(&guild_id, hours)
+        .await
+    {
+        Ok(guild_settings) => {
+            say_guild_settings(ctx, &guild_settings).await?;
+
+            Ok(())
+        }
+        Err(e) => {
+            ctx.say(format!("An error occurred: {}", e)).await?;
+
+            Ok(())
+        }
+    }
+}
+
+#[derive(Debug, poise::ChoiceParameter)]
+pub(crate) enum CooldownCommand {
+    #[name = "bl-profile"]
+    Profile,
+    #[name = "bl-profile-mini"]
+    ProfileMini,
+    #[name = "bl-clan-wars-playlist"]
+    ClanWarsPlaylist,
+    #[name = "bl-practice-playlist"]
+    PracticePlaylist,
+    #[name = "bl-set-clan-wars-exclude-maps"]
+    SetClanWarsExcludeMaps,
+    #[name = "Capture the map"]
+    CaptureMap,
+    #[name = "Commander's order"]
+    CommandersOrder,
+    #[name = "Revoke commander's order"]
+    RevokeCommandersOrder,
+    #[name = "Remove from the map list"]
+    RemoveFromMapList,
+    #[name = "Restore to the map list"]
+    RestoreToMapList,
+}
+
+/// Set per-member cooldown of a heavy command
+#[tracing::instrument(skip(ctx), level=tracing::Level::INFO, name="bot_command:bl-set-command-cooldown")]
+#[poise::command(
+    slash_command,
+    rename = "bl-set-command-cooldown",
+    ephemeral,
+    required_permissions = "MANAGE_ROLES",
+    default_member_permissions = "MANAGE_ROLES",
+    required_bot_permissions = "MANAGE_ROLES",
+    guild_only,
+    hide_in_help
+)]
+pub(crate) async fn cmd_set_command_cooldown(
+    ctx: Context<'_>,
+    #[description = "Command to set the cooldown of"] command: CooldownCommand,
+    #[description = "Seconds between uses by the same member (empty restores the default: 5, 0 disables it)."]
+    #[max = 3600]
+    seconds: Option<u32>,
+) -> Result<(), Error> {
+    let guild_id = get_guild_id(ctx, true).await?;
+
+    match ctx
+        .data()
+        .guild_settings_repository
+        .set_command_cooldown(&guild_id, poise::ChoiceParameter::name(&command), seconds)
+        .await
+    {
+        Ok(guild_settings) => {
+            say_guild_settings(ctx, &guild_settings).await?;
+
+            Ok(())
+        }
+        Err(e) => {
+            ctx.say(format!("An error occurred: {}", e)).await?;
+
+            Ok(())
+        }
+    }
+}
+
+/// Set minimum BL account age required for auto roles
+#[tracing::instrument(skip(ctx), level=tracing::Level::INFO, name="bot_command:bl-set-min-account-age")]
+#[poise::command(
+    slash_command,
+    rename = "bl-set-min-account-age",
+    ephemeral,
+    required_permissions = "MANAGE_ROLES",
+    default_member_permissions = "MANAGE_ROLES",
+    required_bot_permissions = "MANAGE_ROLES",
+    guild_only,
+    hide_in_help
+)]
+pub(crate) async fn cmd_set_min_account_age(
+    ctx: Context<'_>,
+    #[description = "Days since BL account creation before auto roles are granted (0 disables it, default: 0)."]
+    #[max = 3650]
+    days: u32,
+) -> Result<(), Error> {
+    let guild_id = get_guild_id(ctx, true).await?;
+
+    match ctx
+        .data()
+        .guild_settings_repository
+        .set_min_account_age(&guild_id, days)
+        .await
+    {
+        Ok(guild_settings) => {
+            say_guild_settings(ctx, &guild_settings).await?;
+
+            Ok(())
+        }
+        Err(e) => {
+            ctx.say(format!("An error occurred: {}", e)).await?;
+
+            Ok(())
+        }
+    }
+}
+
+/// Set the theme of the profile and score images
+#[tracing::instrument(skip(ctx), level=tracing::Level::INFO, name="bot_command:bl-set-embed-theme")]
+#[poise::command(
+    slash_command,
+    rename = "bl-set-embed-theme",
+    ephemeral,
+    required_permissions = "MANAGE_ROLES",
+    default_member_permissions = "MANAGE_ROLES",
+    required_bot_permissions = "MANAGE_ROLES",
+    guild_only,
+    hide_in_help
+)]
+pub(crate) async fn cmd_set_embed_theme(
+    ctx: Context<'_>,
+    #[description = "Theme (default: Dark)"] theme: EmbedThemeChoice,
+) -> Result<(), Error> {
+    let guild_settings = get_guild_settings(ctx, true).await?;
+
+    let embed_theme = match theme {
+        EmbedThemeChoice::Dark => EmbedTheme::Dark,
+        EmbedThemeChoice::Light => EmbedTheme::Light,
+        EmbedThemeChoice::ClanColor => {
+            let Some(clan_settings) = guild_settings.clan_settings.as_ref() else {
+                say_without_ping(ctx, "Clan is not set up in this guild.", true).await?;
+
+                return Ok(());
+            };
+
+            let color = match fetch_clan(&clan_settings.get_clan()).await {
+                Ok(clan) => clan.color,
+                Err(e) => {
+                    ctx.say(format!("Error: can not fetch clan data from BL: {}", e))
+                        .await?;
+
+                    return Ok(());
+                }
+            };
+
+            if ril::Rgba::from_hex(&color).is_err() {
+                say_without_ping(ctx, "Error: the clan has no valid color set on BL.", true)
+                    .await?;
+
+                return Ok(());
+            }
+
+            EmbedTheme::ClanColor(color)
+        }
+    };
+
+    match ctx
+        .data()
+        .guild_settings_repository
+        .set_embed_theme(&guild_settings.guild_id, embed_theme)
+        .await
+    {
+        Ok(guild_settings) => {
+            say_guild_settings(ctx, &guild_settings).await?;
+
+            Ok(())
+        }
+        Err(e) => {
+            ctx.say(format!("An error occurred: {}", e)).await?;
+
+            Ok(())
+        }
+    }
+}
+
+/// Set whether profiles are shown only to the user who requested them
+#[tracing::instrument(skip(ctx), level=tracing::Level::INFO, name="bot_command:bl-set-profile-visibility")]
+#[poise::command(
+    slash_command,
+    rename = "bl-set-profile-visibility",
     ephemeral,
     required_permissions = "MANAGE_ROLES",
     default_member_permissions = "MANAGE_ROLES",
@@ -19,19 +555,37 @@ use crate::Error;
     guild_only,
     hide_in_help
 )]
-pub(crate) async fn cmd_show_settings(ctx: Context<'_>) -> Result<(), Error> {
-    let guild_settings = get_guild_settings(ctx, true).await?;
+pub(crate) async fn cmd_set_profile_visibility(
+    ctx: Context<'_>,
+    #[description = "Show /bl-profile only to the user by default, users can still override it"]
+    private: bool,
+) -> Result<(), Error> {
+    let guild_id = get_guild_id(ctx, true).await?;
 
-    ctx.say(format!("{}", guild_settings)).await?;
+    match ctx
+        .data()
+        .guild_settings_repository
+        .set_profile_private(&guild_id, private)
+        .await
+    {
+        Ok(guild_settings) => {
+            say_guild_settings(ctx, &guild_settings).await?;
 
-    Ok(())
+            Ok(())
+        }
+        Err(e) => {
+            ctx.say(format!("An error occurred: {}", e)).await?;
+
+            Ok(())
+        }
+    }
 }
 
-/// Set or unset bot log channel
-#[tracing::instrument(skip(ctx), level=tracing::Level::INFO, name="bot_command:bl-set-log-channel")]
+/// Set the colors of the map triangle on the replay images
+#[tracing::instrument(skip(ctx), level=tracing::Level::INFO, name="bot_command:bl-set-map-triangle-colors")]
 #[poise::command(
     slash_command,
-    rename = "bl-set-log-channel",
+    rename = "bl-set-map-triangle-colors",
     ephemeral,
     required_permissions = "MANAGE_ROLES",
     default_member_permissions = "MANAGE_ROLES",
@@ -39,22 +593,20 @@ pub(crate) async fn cmd_show_settings(ctx: Context<'_>) -> Result<(), Error> {
     guild_only,
     hide_in_help
 )]
-pub(crate) async fn cmd_set_log_channel(
+pub(crate) async fn cmd_set_map_triangle_colors(
     ctx: Context<'_>,
-    #[description = "The channel where the bot logs will be posted. Leave empty to disable logging."]
-    #[channel_types("Text")]
-    channel_id: Option<ChannelId>,
+    #[description = "Colors (default: Rating)"] colors: MapTriangleColors,
 ) -> Result<(), Error> {
     let guild_id = get_guild_id(ctx, true).await?;
 
     match ctx
         .data()
         .guild_settings_repository
-        .set_bot_channel(&guild_id, channel_id)
+        .set_map_triangle_colors(&guild_id, colors)
         .await
     {
         Ok(guild_settings) => {
-            ctx.say(format!("{}", guild_settings)).await?;
+            say_guild_settings(ctx, &guild_settings).await?;
 
             Ok(())
         }
@@ -66,11 +618,11 @@ pub(crate) async fn cmd_set_log_channel(
     }
 }
 
-/// Set profile verification requirement
-#[tracing::instrument(skip(ctx), level=tracing::Level::INFO, name="bot_command:bl-set-profile-verification")]
+/// Set the number of decimal places of pp, acc and stars shown by the bot
+#[tracing::instrument(skip(ctx), level=tracing::Level::INFO, name="bot_command:bl-set-decimal-places")]
 #[poise::command(
     slash_command,
-    rename = "bl-set-profile-verification",
+    rename = "bl-set-decimal-places",
     ephemeral,
     required_permissions = "MANAGE_ROLES",
     default_member_permissions = "MANAGE_ROLES",
@@ -78,21 +630,79 @@ pub(crate) async fn cmd_set_log_channel(
     guild_only,
     hide_in_help
 )]
-pub(crate) async fn cmd_set_profile_verification(
+pub(crate) async fn cmd_set_decimal_places(
     ctx: Context<'_>,
-    #[description = "Does the bl-link command require a verified profile or not."]
-    requires_verified_profile: bool,
+    #[description = "Decimal places (empty restores the default: 2)"]
+    #[max = 3]
+    places: Option<u8>,
 ) -> Result<(), Error> {
     let guild_id = get_guild_id(ctx, true).await?;
 
     match ctx
         .data()
         .guild_settings_repository
-        .set_verified_profile_requirement(&guild_id, requires_verified_profile)
+        .set_decimal_places(&guild_id, places)
+        .await
+    {
+        Ok(guild_settings) => {
+            say_guild_settings(ctx, &guild_settings).await?;
+
+            Ok(())
+        }
+        Err(e) => {
+            ctx.say(format!("An error occurred: {}", e)).await?;
+
+            Ok(())
+        }
+    }
+}
+
+/// Set the timezone of dates shown by the bot
+#[tracing::instrument(skip(ctx), level=tracing::Level::INFO, name="bot_command:bl-set-timezone")]
+#[poise::command(
+    slash_command,
+    rename = "bl-set-timezone",
+    ephemeral,
+    required_permissions = "MANAGE_ROLES",
+    default_member_permissions = "MANAGE_ROLES",
+    guild_only,
+    hide_in_help
+)]
+pub(crate) async fn cmd_set_timezone(
+    ctx: Context<'_>,
+    #[description = "IANA timezone, e.g. Europe/Warsaw (default: UTC)"] timezone: Option<String>,
+) -> Result<(), Error> {
+    let guild_id = get_guild_id(ctx, true).await?;
+
+    let timezone = match timezone {
+        Some(timezone) => match timezone.trim().parse::<Tz>() {
+            Ok(timezone) => Some(timezone),
+            Err(_) => {
+                say_without_ping(
+                    ctx,
+                    format!(
+                        "Error: {} is not a valid IANA timezone, e.g. Europe/Warsaw.",
+                        timezone.trim()
+                    )
+                    .as_str(),
+                    true,
+                )
+                .await?;
+
+                return Ok(());
+            }
+        },
+        None => None,
+    };
+
+    match ctx
+        .data()
+        .guild_settings_repository
+        .set_timezone(&guild_id, timezone)
         .await
     {
         Ok(guild_settings) => {
-            ctx.say(format!("{}", guild_settings)).await?;
+            say_guild_settings(ctx, &guild_settings).await?;
 
             Ok(())
         }
@@ -105,6 +715,7 @@ pub(crate) async fn cmd_set_profile_verification(
 }
 
 /// Set conditions for automatic role assignment.
+#[allow(clippy::too_many_arguments)]
 #[tracing::instrument(skip(ctx), level=tracing::Level::INFO, name="bot_command:bl-add-auto-role")]
 #[poise::command(
     slash_command,
@@ -130,9 +741,24 @@ pub(crate) async fn cmd_add_auto_role(
     #[description = "Weight of auto role in the group (100, 200, etc.; the better role, the higher value)"]
     #[min = 1]
     weight: u32,
+    #[description = "Whether all or any of the role conditions must be met (default: all; existing role keeps its mode)"]
+    match_mode: Option<RoleMatchMode>,
+    #[description = "Tolerance in percent of the metric value, required by the Within condition"]
+    #[min = 0.0]
+    tolerance: Option<f64>,
+    #[description = "Role required first, e.g. Member for Veteran (existing role keeps its prerequisite)"]
+    required_role: Option<serenity_prelude::Role>,
 ) -> Result<(), Error> {
     let guild_id = get_guild_id(ctx, true).await?;
 
+    if required_role
+        .as_ref()
+        .is_some_and(|required_role| required_role.id == role.id)
+    {
+        ctx.say("Role can not require itself.").await?;
+        return Ok(());
+    }
+
     let metric_and_value = match RequirementMetricValue::new(metric, value.as_str()) {
         Ok(v) => v,
         Err(e) => {
@@ -141,6 +767,48 @@ pub(crate) async fn cmd_add_auto_role(
         }
     };
 
+    // length is already validated, so the network check is done only for a plausible tag
+    let clan_tag = match &metric_and_value {
+        RequirementMetricValue::Clan(tags) => tags.first().cloned(),
+        RequirementMetricValue::MainClan(tag) => Some(tag.clone()),
+        _ => None,
+    };
+    if let Some(clan_tag) = clan_tag {
+        match fetch_clan(&clan_tag).await {
+            Ok(_) => {}
+            Err(BlError::NotFound) => {
+                ctx.say(format!(
+                    "Invalid metric value: {}",
+                    clan_not_found_message(&clan_tag).await
+                ))
+                .await?;
+                return Ok(());
+            }
+            Err(e) => {
+                ctx.say(format!("Can not verify clan {}: {}", clan_tag, e))
+                    .await?;
+                return Ok(());
+            }
+        }
+    }
+
+    let tolerance = match (&condition, tolerance) {
+        (Condition::Within, None) => {
+            ctx.say("Within condition requires a tolerance.").await?;
+            return Ok(());
+        }
+        (Condition::Within, Some(_)) if !metric_and_value.supports_tolerance() => {
+            ctx.say("Within condition can only be used with a decimal metric (Total PP, Top PP, Top Acc, Top Stars, Acc consistency).").await?;
+            return Ok(());
+        }
+        (Condition::Within, Some(tolerance)) if !tolerance.is_finite() || tolerance < 0.0 => {
+            ctx.say("Invalid tolerance.").await?;
+            return Ok(());
+        }
+        (Condition::Within, tolerance) => tolerance,
+        (_, _) => None,
+    };
+
     match ctx
         .data()
         .guild_settings_repository
@@ -148,14 +816,19 @@ pub(crate) async fn cmd_add_auto_role(
             guild_id,
             group,
             role.id,
-            metric_and_value,
-            condition,
             weight,
+            AutoRoleParams {
+                metric_and_value,
+                condition,
+                tolerance,
+                match_mode,
+                required_role: required_role.map(|required_role| required_role.id),
+            },
         )
         .await
     {
         Ok(guild_settings) => {
-            ctx.say(format!("{}", guild_settings)).await?;
+            say_guild_settings(ctx, &guild_settings).await?;
 
             Ok(())
         }
@@ -196,7 +869,125 @@ pub(crate) async fn cmd_remove_auto_role(
         .await
     {
         Ok(guild_settings) => {
-            ctx.say(format!("{}", guild_settings)).await?;
+            say_guild_settings(ctx, &guild_settings).await?;
+
+            Ok(())
+        }
+        Err(e) => {
+            ctx.say(format!("An error occurred: {}", e)).await?;
+
+            Ok(())
+        }
+    }
+}
+
+/// Set or clear the role required first by an auto role.
+#[tracing::instrument(skip(ctx), level=tracing::Level::INFO, name="bot_command:bl-set-auto-role-prerequisite")]
+#[poise::command(
+    slash_command,
+    rename = "bl-set-auto-role-prerequisite",
+    ephemeral,
+    required_permissions = "MANAGE_ROLES",
+    default_member_permissions = "MANAGE_ROLES",
+    required_bot_permissions = "MANAGE_ROLES",
+    guild_only,
+    hide_in_help
+)]
+pub(crate) async fn cmd_set_auto_role_prerequisite(
+    ctx: Context<'_>,
+    #[description = "Group name of the auto role, e.g. `top-pp`."]
+    #[min_length = 1]
+    #[autocomplete = "autocomplete_role_group"]
+    group: String,
+    #[description = "Auto role to change."] role: serenity_prelude::Role,
+    #[description = "Role required first, e.g. Member for Veteran. Leave empty to clear."]
+    required_role: Option<serenity_prelude::Role>,
+) -> Result<(), Error> {
+    let guild_id = get_guild_id(ctx, true).await?;
+
+    if required_role
+        .as_ref()
+        .is_some_and(|required_role| required_role.id == role.id)
+    {
+        ctx.say("Role can not require itself.").await?;
+        return Ok(());
+    }
+
+    match ctx
+        .data()
+        .guild_settings_repository
+        .set_auto_role_required_role(
+            guild_id,
+            group,
+            role.id,
+            required_role.map(|required_role| required_role.id),
+        )
+        .await
+    {
+        Ok(guild_settings) => {
+            say_guild_settings(ctx, &guild_settings).await?;
+
+            Ok(())
+        }
+        Err(e) => {
+            ctx.say(format!("An error occurred: {}", e)).await?;
+
+            Ok(())
+        }
+    }
+}
+
+/// Set the order in which auto role groups are processed and displayed.
+#[tracing::instrument(skip(ctx), level=tracing::Level::INFO, name="bot_command:bl-reorder-role-groups")]
+#[poise::command(
+    slash_command,
+    rename = "bl-reorder-role-groups",
+    ephemeral,
+    required_permissions = "MANAGE_ROLES",
+    default_member_permissions = "MANAGE_ROLES",
+    required_bot_permissions = "MANAGE_ROLES",
+    guild_only,
+    hide_in_help
+)]
+pub(crate) async fn cmd_reorder_role_groups(
+    ctx: Context<'_>,
+    #[description = "Comma separated group names, e.g. `top-pp, rank`. Groups not listed are placed alphabetically after them."]
+    groups: String,
+) -> Result<(), Error> {
+    let guild_settings = get_guild_settings(ctx, true).await?;
+
+    let existing_groups = guild_settings.get_groups();
+
+    let group_order = groups
+        .split(',')
+        .map(|group| group.trim().to_owned())
+        .filter(|group| !group.is_empty())
+        .collect::<Vec<String>>();
+
+    let unknown_groups = group_order
+        .iter()
+        .filter(|group| !existing_groups.contains(group))
+        .cloned()
+        .collect::<Vec<String>>();
+
+    if !unknown_groups.is_empty() {
+        ctx.say(format!(
+            "Unknown role group(s): {}",
+            unknown_groups.join(", ")
+        ))
+        .await?;
+
+        return Ok(());
+    }
+
+    match ctx
+        .data()
+        .guild_settings_repository
+        .set_role_group_order(guild_settings.get_key(), group_order)
+        .await
+    {
+        Ok(guild_settings) => {
+            say_guild_settings(ctx, &guild_settings).await?;
 
             Ok(())
         }
@@ -208,6 +999,205 @@ pub(crate) async fn cmd_remove_auto_role(
     }
 }
 
+/// Explain which auto roles you would get and why
+#[tracing::instrument(skip(ctx), level=tracing::Level::INFO, name="bot_command:bl-explain-roles")]
+#[poise::command(slash_command, rename = "bl-explain-roles", ephemeral, guild_only)]
+pub(crate) async fn cmd_explain_roles(
+    ctx: Context<'_>,
+    #[description = "Discord user (role managers only, YOU if not specified)"] user: Option<User>,
+) -> Result<(), Error> {
+    ctx.defer_ephemeral().await?;
+
+    let user_id =
+        match get_user_id_with_required_permission(ctx, user, Permissions::MANAGE_ROLES).await {
+            Ok(user_id) => user_id,
+            Err(err) => {
+                say_without_ping(ctx, err.as_str(), true).await?;
+
+                return Ok(());
+            }
+        };
+
+    let guild_settings = get_guild_settings(ctx, true).await?;
+
+    let Some(player) = ctx.data().players_repository.get(&user_id).await else {
+        say_profile_not_linked(ctx, &user_id, guild_settings.requires_verified_profile).await?;
+
+        return Ok(());
+    };
+
+    let current_roles = match guild_settings.get_key().member(ctx, user_id).await {
+        Ok(member) => member.roles,
+        Err(err) => {
+            ctx.say(format!("An error occurred: {}", err)).await?;
+
+            return Ok(());
+        }
+    };
+
+    let role_changes = guild_settings.get_role_updates(&player, &current_roles);
+
+    let format_roles = |roles: &[RoleId]| {
+        if roles.is_empty() {
+            "None".to_owned()
+        } else {
+            roles
+                .iter()
+                .map(|role_id| format!("<@&{}>", role_id))
+                .collect::<Vec<_>>()
+                .join(", ")
+        }
+    };
+
+    let mut header = format!(
+        "# Auto roles of <@{}> ({})\nRoles to add: {}\nRoles to remove: {}",
+        user_id,
+        player.name,
+        format_roles(&role_changes.to_add),
+        format_roles(&role_changes.to_remove),
+    );
+    if !guild_settings.is_account_old_enough(&player) {
+        header.push_str("\n*BL account is too young, no roles are added*");
+    }
+    header.push_str("\nOnly the heaviest fulfilled role of each group is granted.");
+
+    let lines = guild_settings
+        .explain_roles(&player, &current_roles)
+        .into_iter()
+        .flat_map(|(role_group, roles)| {
+            std::iter::once(format!("### Group: __{}__", role_group))
+                .chain(roles.into_iter().map(|role| role.to_string()))
+        });
+
+    for content in split_msg_in_parts(std::iter::once(header).chain(lines)) {
+        ctx.send(
+            CreateReply::default()
+                .content(content)
+                .allowed_mentions(CreateAllowedMentions::new())
+                .ephemeral(true),
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// Recheck and update the user's auto roles right away
+#[tracing::instrument(skip(ctx), level=tracing::Level::INFO, name="bot_command:bl-recheck-roles")]
+#[poise::command(
+    slash_command,
+    rename = "bl-recheck-roles",
+    ephemeral,
+    required_permissions = "MANAGE_ROLES",
+    default_member_permissions = "MANAGE_ROLES",
+    required_bot_permissions = "MANAGE_ROLES",
+    guild_only,
+    hide_in_help
+)]
+pub(crate) async fn cmd_recheck_roles(
+    ctx: Context<'_>,
+    #[description = "Discord user (YOU if not specified)"] user: Option<User>,
+) -> Result<(), Error> {
+    ctx.defer_ephemeral().await?;
+
+    let user_id = user.map_or(ctx.author().id, |user| user.id);
+
+    let guild_settings = get_guild_settings(ctx, true).await?;
+    if !guild_settings.manages_roles() {
+        say_without_ping(
+            ctx,
+            "Roles are not managed by the bot on this server.",
+            true,
+        )
+        .await?;
+
+        return Ok(());
+    }
+
+    let Some(player) = ctx
+        .data()
+        .players_repository
+        .get(&user_id)
+        .await
+        .filter(|player| player.is_linked_to_guild(&guild_settings.guild_id))
+    else {
+        say_profile_not_linked(ctx, &user_id, guild_settings.requires_verified_profile).await?;
+
+        return Ok(());
+    };
+
+    // roles are checked against the current BL profile, not the one from the last worker run
+    let player = match ctx
+        .data()
+        .players_repository
+        .update_player_stats(&ctx.data().player_scores_repositories, &player, false)
+        .await
+    {
+        Ok(player) => player,
+        Err(err) => {
+            ctx.say(format!("An error occurred: {}", err)).await?;
+
+            return Ok(());
+        }
+    };
+
+    let current_roles = match guild_settings.get_key().member(ctx, user_id).await {
+        Ok(member) => member.roles,
+        Err(err) => {
+            ctx.say(format!("An error occurred: {}", err)).await?;
+
+            return Ok(());
+        }
+    };
+
+    let mut role_changes = guild_settings.get_role_updates(&player, &current_roles);
+
+    // the recheck is requested explicitly, so the auto roles cooldown does not apply
+    if let Err(err) = apply_user_role_changes(
+        &ctx.serenity_context().http,
+        &ctx.data().role_change_repository,
+        &guild_settings,
+        &mut role_changes,
+        false,
+    )
+    .await
+    {
+        ctx.say(format!("An error occurred: {}", err)).await?;
+
+        return Ok(());
+    }
+
+    let mut content = if role_changes.is_changed() {
+        role_changes.to_string()
+    } else {
+        format!("The roles of user <@{}> are up to date", user_id)
+    };
+    if !role_changes.failed.is_empty() {
+        content.push_str(
+            format!(
+                "\n**Failed roles:** {} (missing permissions or role above the bot's role?)",
+                role_changes
+                    .failed
+                    .iter()
+                    .map(|role_id| format!("<@&{}>", role_id))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+            .as_str(),
+        );
+    }
+
+    ctx.send(
+        CreateReply::default()
+            .content(content)
+            .allowed_mentions(CreateAllowedMentions::new())
+            .ephemeral(true),
+    )
+    .await?;
+
+    Ok(())
+}
+
 async fn autocomplete_role_group<'a>(
     ctx: Context<'_>,
     partial: &'a str,
@@ -239,6 +1229,24 @@ pub(crate) async fn get_guild_id(ctx: Context<'_>, ephemeral: bool) -> Result<Gu
     Ok(guild_id)
 }
 
+// guild settings can exceed the message length limit, so they may need to be sent in parts
+pub(crate) async fn say_guild_settings(
+    ctx: Context<'_>,
+    guild_settings: &GuildSettings,
+) -> Result<(), Error> {
+    // settings list role and channel mentions, which must not ping anyone
+    for content in guild_settings.to_messages() {
+        ctx.send(
+            CreateReply::default()
+                .content(content)
+                .allowed_mentions(CreateAllowedMentions::new()),
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
 pub(crate) async fn get_guild_settings(
     ctx: Context<'_>,
     ephemeral: bool,
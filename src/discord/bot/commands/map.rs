@@ -0,0 +1,130 @@
+use poise::serenity_prelude::{CreateAllowedMentions, CreateEmbed};
+use poise::CreateReply;
+
+use crate::beatleader::error::Error as BlError;
+use crate::beatleader::player::{DifficultyStatus, Leaderboard};
+use crate::discord::bot::commands::get_leaderboard_id;
+use crate::discord::bot::commands::player::say_without_ping;
+use crate::discord::Context;
+use crate::other::string_utils::{format_decimal, DEFAULT_DECIMAL_PLACES};
+use crate::{Error, BL_CLIENT};
+
+/// Show BL leaderboard info
+#[tracing::instrument(skip(ctx), level=tracing::Level::INFO, name="bot_command:bl-map")]
+#[poise::command(slash_command, rename = "bl-map", guild_only)]
+pub(crate) async fn cmd_map(
+    ctx: Context<'_>,
+    #[description = "Leaderboard link or id"] leaderboard: String,
+) -> Result<(), Error> {
+    let Some(leaderboard_id) = get_leaderboard_id(&leaderboard) else {
+        say_without_ping(ctx, "Provide a valid BL leaderboard link or id.", true).await?;
+
+        return Ok(());
+    };
+
+    ctx.defer().await?;
+
+    // the map can be shown also on servers not set up yet
+    let decimal_places = match ctx.guild_id() {
+        Some(guild_id) => ctx
+            .data()
+            .guild_settings_repository
+            .get(&guild_id)
+            .await
+            .map(|guild_settings| guild_settings.get_decimal_places())
+            .unwrap_or(DEFAULT_DECIMAL_PLACES),
+        None => DEFAULT_DECIMAL_PLACES,
+    };
+
+    match BL_CLIENT.clan().leaderboard(&leaderboard_id, &[]).await {
+        Ok(leaderboard) => {
+            ctx.send(
+                CreateReply::default()
+                    .embed(leaderboard_embed(&leaderboard, decimal_places))
+                    .allowed_mentions(CreateAllowedMentions::new()),
+            )
+            .await?;
+
+            Ok(())
+        }
+        Err(BlError::NotFound) => {
+            say_without_ping(ctx, "Leaderboard not found.", true).await?;
+
+            Ok(())
+        }
+        Err(err) => {
+            say_without_ping(
+                ctx,
+                format!("Error: can not fetch leaderboard data from BL: {}", err).as_str(),
+                true,
+            )
+            .await?;
+
+            Ok(())
+        }
+    }
+}
+
+fn leaderboard_embed(leaderboard: &Leaderboard, decimal_places: u8) -> CreateEmbed {
+    let song = &leaderboard.song;
+    let difficulty = &leaderboard.difficulty;
+
+    let mut embed = CreateEmbed::new()
+        .title(format!("{} {}", song.name, song.sub_name).trim().to_owned())
+        .url(format!(
+            "https://www.beatleader.com/leaderboard/global/{}/1",
+            leaderboard.id
+        ))
+        .description(format!("by {} • mapped by {}", song.author, song.mapper))
+        .field(
+            "Difficulty",
+            format!("{} / {}", difficulty.difficulty_name, difficulty.mode_name),
+            true,
+        )
+        .field("Status", difficulty.status.to_string(), true);
+
+    // unranked maps have no star rating
+    let is_rated = matches!(
+        difficulty.status,
+        DifficultyStatus::Ranked | DifficultyStatus::Qualified | DifficultyStatus::Nominated
+    ) && difficulty.stars > 0.0;
+
+    embed = if is_rated {
+        embed
+            .field(
+                "Stars",
+                format!("{}★", format_decimal(difficulty.stars, decimal_places)),
+                true,
+            )
+            .field(
+                "Pass rating",
+                format_decimal(difficulty.pass_rating, decimal_places),
+                true,
+            )
+            .field(
+                "Acc rating",
+                format_decimal(difficulty.acc_rating, decimal_places),
+                true,
+            )
+            .field(
+                "Tech rating",
+                format_decimal(difficulty.tech_rating, decimal_places),
+                true,
+            )
+    } else {
+        embed.field("Stars", "-", true)
+    };
+
+    if !song.cover_image.is_empty() {
+        embed = embed.thumbnail(song.cover_image.clone());
+    }
+
+    embed
+        .field("Duration", song.duration.to_string(), true)
+        .field("BPM", format!("{}", song.bpm), true)
+        .field(
+            "Notes",
+            format!("{} ({:.2} NPS)", difficulty.notes, difficulty.nps),
+            true,
+        )
+}
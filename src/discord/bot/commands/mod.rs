@@ -1,62 +1,189 @@
 use crate::discord::bot::commands::clan::{
-    cmd_capture, cmd_clan_wars_enlist, cmd_clan_wars_playlist, cmd_clan_wars_release,
-    cmd_commanders_order, cmd_remove_from_map_list, cmd_restore_to_map_list,
-    cmd_revoke_commanders_order, cmd_set_clan_commander_role,
-    cmd_set_clan_wars_contribution_channel, cmd_set_clan_wars_maps_channel,
-    cmd_set_clan_wars_soldier_role,
+    cmd_capture, cmd_clan_contribution, cmd_clan_wars_enlist, cmd_clan_wars_playlist,
+    cmd_clan_wars_release, cmd_commanders_order, cmd_my_playlists, cmd_remove_from_map_list,
+    cmd_restore_to_map_list, cmd_revoke_commanders_order, cmd_set_clan_commander_role,
+    cmd_set_clan_recap, cmd_set_clan_wars_contribution_channel, cmd_set_clan_wars_exclude_maps,
+    cmd_set_clan_wars_maps_channel, cmd_set_clan_wars_maps_count, cmd_set_clan_wars_ping_role,
+    cmd_set_clan_wars_soldier_role, cmd_set_clan_wars_star_range,
+    cmd_set_commander_order_min_stars, cmd_set_playlist_cover, cmd_set_playlist_skip_fcs,
 };
+use crate::discord::bot::commands::player::say_without_ping;
+use crate::discord::bot::DEFAULT_COMMAND_COOLDOWN_SECS;
 use crate::discord::{BotData, Context};
-pub(crate) use backup::{cmd_export, cmd_import};
-pub(crate) use clan::{cmd_clan_invitation, cmd_set_clan_invitation};
+pub(crate) use backup::{
+    cmd_compact_storage, cmd_export, cmd_export_roles, cmd_import, cmd_import_roles, cmd_stats,
+};
+pub(crate) use clan::{
+    cmd_clan_invitation, cmd_clan_oauth_revoke, cmd_clan_oauth_status, cmd_clan_stats,
+    cmd_oauth_diagnostics, cmd_set_clan_invitation,
+};
 pub(crate) use guild::{
-    cmd_add_auto_role, cmd_remove_auto_role, cmd_set_log_channel, cmd_set_profile_verification,
-    cmd_show_settings,
+    cmd_add_auto_role, cmd_explain_roles, cmd_recheck_roles, cmd_remove_auto_role,
+    cmd_reorder_role_groups, cmd_set_auto_role_cooldown, cmd_set_auto_role_prerequisite,
+    cmd_set_auto_roles_dry_run, cmd_set_command_cooldown, cmd_set_decimal_places,
+    cmd_set_embed_theme, cmd_set_errors_channel, cmd_set_log_channel, cmd_set_map_triangle_colors,
+    cmd_set_min_account_age, cmd_set_profile_verification, cmd_set_profile_visibility,
+    cmd_set_role_changes_channel, cmd_set_timezone, cmd_set_verification_reminder,
+    cmd_set_welcome_roles, cmd_show_settings,
+};
+pub(crate) use map::cmd_map;
+pub(crate) use player::{
+    cmd_find_player, cmd_link, cmd_pause_stats, cmd_practice_playlist, cmd_profile,
+    cmd_profile_mini, cmd_recent, cmd_refresh_all_scores, cmd_refresh_scores, cmd_replay,
+    cmd_subscribe_replay_watches, cmd_top1, cmd_unlink, cmd_watch_leaderboard, cmd_whois,
 };
-pub(crate) use player::{cmd_link, cmd_profile, cmd_refresh_scores, cmd_replay, cmd_unlink};
 use poise::serenity_prelude::{Message, Permissions, User, UserId};
 use regex::Match;
 pub(crate) use register::cmd_register;
+use std::time::Duration;
 
 pub(crate) mod backup;
 pub(crate) mod clan;
 pub(crate) mod guild;
+pub(crate) mod map;
 pub(crate) mod player;
 pub(crate) mod register;
 
 pub(crate) fn commands() -> Vec<poise::Command<BotData, crate::Error>> {
     vec![
         cmd_replay(),
-        cmd_profile(),
+        cmd_recent(),
+        with_guild_cooldown(cmd_profile()),
+        with_guild_cooldown(cmd_profile_mini()),
+        cmd_pause_stats(),
+        cmd_watch_leaderboard(),
+        cmd_top1(),
         cmd_link(),
         cmd_unlink(),
+        cmd_whois(),
+        cmd_find_player(),
+        cmd_map(),
+        cmd_subscribe_replay_watches(),
         cmd_show_settings(),
         cmd_add_auto_role(),
         cmd_remove_auto_role(),
+        cmd_set_auto_role_prerequisite(),
+        cmd_reorder_role_groups(),
+        cmd_explain_roles(),
+        cmd_recheck_roles(),
         cmd_set_log_channel(),
+        cmd_set_role_changes_channel(),
+        cmd_set_errors_channel(),
         cmd_set_profile_verification(),
+        cmd_set_verification_reminder(),
+        cmd_set_welcome_roles(),
+        cmd_set_auto_roles_dry_run(),
+        cmd_set_auto_role_cooldown(),
+        cmd_set_min_account_age(),
+        cmd_set_command_cooldown(),
+        cmd_set_embed_theme(),
+        cmd_set_map_triangle_colors(),
+        cmd_set_decimal_places(),
+        cmd_set_profile_visibility(),
+        cmd_set_timezone(),
         cmd_set_clan_invitation(),
+        cmd_clan_oauth_status(),
+        cmd_clan_oauth_revoke(),
+        cmd_oauth_diagnostics(),
         cmd_clan_invitation(),
-        cmd_clan_wars_playlist(),
+        cmd_clan_stats(),
+        with_guild_cooldown(cmd_clan_wars_playlist()),
+        with_guild_cooldown(cmd_practice_playlist()),
+        cmd_my_playlists(),
+        cmd_clan_contribution(),
         cmd_set_clan_wars_maps_channel(),
+        cmd_set_clan_wars_maps_count(),
+        cmd_set_clan_wars_star_range(),
         cmd_set_clan_wars_contribution_channel(),
+        cmd_set_clan_recap(),
+        cmd_set_playlist_cover(),
+        cmd_set_playlist_skip_fcs(),
+        cmd_set_commander_order_min_stars(),
         cmd_clan_wars_enlist(),
         cmd_clan_wars_release(),
         cmd_set_clan_wars_soldier_role(),
+        cmd_set_clan_wars_ping_role(),
         cmd_set_clan_commander_role(),
-        cmd_capture(),
-        cmd_commanders_order(),
-        cmd_revoke_commanders_order(),
-        cmd_remove_from_map_list(),
-        cmd_restore_to_map_list(),
+        with_guild_cooldown(cmd_capture()),
+        with_guild_cooldown(cmd_commanders_order()),
+        with_guild_cooldown(cmd_revoke_commanders_order()),
+        with_guild_cooldown(cmd_remove_from_map_list()),
+        with_guild_cooldown(cmd_restore_to_map_list()),
+        with_guild_cooldown(cmd_set_clan_wars_exclude_maps()),
         // cmd_invite_player(),
         cmd_register(),
         cmd_export(),
         cmd_import(),
+        cmd_export_roles(),
+        cmd_import_roles(),
+        cmd_compact_storage(),
+        cmd_stats(),
         cmd_refresh_scores(),
+        cmd_refresh_all_scores(),
         cmd_help(),
     ]
 }
 
+// heavy commands get a per-guild cooldown that admins can set with bl-set-command-cooldown
+fn with_guild_cooldown(
+    mut command: poise::Command<BotData, crate::Error>,
+) -> poise::Command<BotData, crate::Error> {
+    command
+        .checks
+        .push(|ctx| Box::pin(check_guild_cooldown(ctx)));
+
+    command
+}
+
+// context menu commands are identified by the name shown in the menu
+fn get_cooldown_command_name(command: &poise::Command<BotData, crate::Error>) -> &str {
+    command
+        .context_menu_name
+        .as_deref()
+        .unwrap_or(command.name.as_str())
+}
+
+// invocations are recorded by poise in the command's cooldown tracker even if there is no
+// static cooldown configured, so only the guild's cooldown needs to be checked here
+async fn check_guild_cooldown(ctx: Context<'_>) -> Result<bool, crate::Error> {
+    let Some(guild_id) = ctx.guild_id() else {
+        return Ok(true);
+    };
+
+    let cooldown = match ctx.data().guild_settings_repository.get(&guild_id).await {
+        Ok(guild_settings) => {
+            guild_settings.get_command_cooldown(get_cooldown_command_name(ctx.command()))
+        }
+        Err(_) => Duration::from_secs(DEFAULT_COMMAND_COOLDOWN_SECS as u64),
+    };
+
+    let remaining = ctx.command().cooldowns.lock().unwrap().remaining_cooldown(
+        ctx.cooldown_context(),
+        &poise::CooldownConfig {
+            member: Some(cooldown),
+            ..Default::default()
+        },
+    );
+
+    match remaining {
+        Some(remaining) => {
+            say_without_ping(
+                ctx,
+                format!(
+                    "You're too fast. Please wait {} seconds before retrying.",
+                    remaining.as_secs() + 1
+                )
+                .as_str(),
+                true,
+            )
+            .await?;
+
+            Ok(false)
+        }
+        None => Ok(true),
+    }
+}
+
 /// Shows help
 #[tracing::instrument(skip(ctx), level=tracing::Level::INFO, name="bot_command:bl-help")]
 #[poise::command(track_edits, slash_command, rename = "bl-help")]
@@ -120,12 +247,54 @@ pub(crate) fn get_leaderboard_ids_from_message(message: Message) -> Vec<String>
             .join("\n")
     );
 
+    get_leaderboard_ids_from_text(&contents)
+}
+
+pub(crate) fn get_leaderboard_ids_from_text(contents: &str) -> Vec<String> {
     regex::Regex::new(
         r"beatleader.(?:xyz|net|com)/leaderboard/.*?/(?<leaderboard_id>[^\/\?$)\s>]+)",
     )
     .unwrap()
-    .captures_iter(&contents)
+    .captures_iter(contents)
     .filter_map(|c| c.name("leaderboard_id"))
     .map(|m| m.as_str().to_string())
     .collect::<Vec<_>>()
 }
+
+pub(crate) fn get_leaderboard_id(link_or_id: &str) -> Option<String> {
+    if let Some(leaderboard_id) = get_leaderboard_ids_from_text(link_or_id).into_iter().next() {
+        return Some(leaderboard_id);
+    }
+
+    let leaderboard_id = link_or_id.trim();
+    if !leaderboard_id.is_empty() && leaderboard_id.chars().all(|c| c.is_ascii_alphanumeric()) {
+        Some(leaderboard_id.to_owned())
+    } else {
+        None
+    }
+}
+
+// links and ids separated by whitespace or commas, duplicates are skipped
+pub(crate) fn get_leaderboard_ids_from_list(links_or_ids: &str) -> Vec<String> {
+    let mut leaderboard_ids = vec![];
+
+    for leaderboard_id in links_or_ids
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter_map(get_leaderboard_id)
+    {
+        if !leaderboard_ids.contains(&leaderboard_id) {
+            leaderboard_ids.push(leaderboard_id);
+        }
+    }
+
+    leaderboard_ids
+}
+
+pub(crate) fn get_player_id_from_profile_link(profile_link: &str) -> String {
+    regex::Regex::new(r"beatleader.(?:xyz|net|com)/u/(?<player_id>[^\/\?$)\s>]+)")
+        .unwrap()
+        .captures(profile_link)
+        .and_then(|c| c.name("player_id"))
+        .map(|m| m.as_str().to_string())
+        .unwrap_or_else(|| profile_link.trim().to_string())
+}
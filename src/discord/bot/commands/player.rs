@@ -3,21 +3,32 @@ use std::convert::From;
 
 use crate::beatleader::player::{PlayerScoreParam, PlayerScoreSort};
 use crate::beatleader::{BlContext, List as BlList, SortOrder};
+use crate::config::PLAYLIST_MAX_SONGS_CEILING;
+use crate::discord::bot::beatleader::clan::{ClanWarsContext, Playlist, PlaylistMapStatus};
 use crate::discord::bot::beatleader::player::{
-    fetch_player_from_bl_by_user_id, Player as BotPlayer, Player,
+    fetch_player_from_bl, fetch_player_from_bl_by_user_id, rank_by_watched_replays,
+    Player as BotPlayer, Player, PROFILE_VERIFICATION_STEPS,
 };
 use crate::discord::bot::beatleader::score::{
-    fetch_ai_ratings, fetch_scores, MapRating, MapRatingModifier, Score,
+    fetch_ai_ratings, fetch_hand_stats, fetch_scores, fetch_top1_scores, group_top1_scores,
+    weekly_pause_report, MapRating, MapRatingModifier, Score,
 };
-use crate::discord::bot::commands::get_user_id_with_required_permission;
 use crate::discord::bot::commands::guild::{get_guild_id, get_guild_settings};
-use crate::discord::bot::get_binary_file;
-use crate::discord::Context;
-use crate::embed::{embed_profile, embed_score};
+use crate::discord::bot::commands::{
+    get_player_id_from_profile_link, get_user_id_with_required_permission,
+};
+use crate::discord::bot::{
+    get_binary_file, get_uncached_binary_file, GuildSettings, DISCORD_ATTACHMENT_MAX_SIZE,
+};
+use crate::discord::{BotData, Context};
+use crate::embed::{embed_profile, embed_score, Theme};
+use crate::other::string_utils::{capitalize, format_decimal};
 use crate::storage::StorageError;
 use crate::Error;
 use bytes::Bytes;
-use futures::FutureExt;
+use chrono::Utc;
+use chrono_tz::Tz;
+use futures::{FutureExt, StreamExt};
 use poise::serenity_prelude::{
     ComponentInteractionDataKind, CreateActionRow, CreateAttachment, CreateEmbed,
     CreateEmbedFooter, CreateMessage, CreateSelectMenu, CreateSelectMenuKind,
@@ -129,11 +140,7 @@ pub(crate) async fn cmd_link(
         }
     };
 
-    let mut player_id = bl_player_id;
-    let re = regex::Regex::new(r"beatleader.(?:xyz|net|com)/u/(?<player_id>[^\/\?$]+)").unwrap();
-    if let Some(caps) = re.captures(&player_id) {
-        player_id.clone_from(&caps["player_id"].to_owned())
-    }
+    let player_id = get_player_id_from_profile_link(&bl_player_id);
 
     ctx.defer().await?;
 
@@ -149,19 +156,43 @@ pub(crate) async fn cmd_link(
         .await
     {
         Ok(player) => {
-            let embed_image = get_player_embed(&player).await;
+            let guild_settings = ctx.data().guild_settings_repository.get(&guild_id).await;
+            let theme = guild_settings
+                .as_ref()
+                .map(|guild_settings| guild_settings.get_theme())
+                .unwrap_or_default();
+            let timezone = guild_settings
+                .as_ref()
+                .map(|guild_settings| guild_settings.get_timezone())
+                .unwrap_or(Tz::UTC);
+
+            if let Ok(guild_settings) = guild_settings.as_ref() {
+                grant_welcome_roles(ctx, guild_settings, &player).await;
+            }
+
+            let embed_image = get_player_embed(&player, &theme, &timezone).await;
 
             let mut reply = CreateReply::default()
                 .content(format!(
-                    "<@{}> has been linked to the BL profile",
-                    selected_user_id
+                    "<@{}> has been linked to the BL profile{}{}",
+                    selected_user_id,
+                    if player.is_verified {
+                        ""
+                    } else {
+                        ". The profile is **not verified**, servers requiring verified profiles will not accept it"
+                    },
+                    if player.is_verified {
+                        "".to_owned()
+                    } else {
+                        format!(". {}", PROFILE_VERIFICATION_STEPS)
+                    }
                 ))
                 // https://docs.rs/serenity/latest/serenity/builder/struct.CreateAllowedMentions.html
                 .allowed_mentions(CreateAllowedMentions::new().all_users(true))
                 .ephemeral(false);
 
             if embed_image.is_none() {
-                reply = add_profile_card(reply, player);
+                reply = add_profile_card(reply, player, &timezone, theme.decimal_places);
             } else if let Some(embed_buffer) = embed_image {
                 reply = reply.attachment(CreateAttachment::bytes(
                     Cow::<[u8]>::from(embed_buffer),
@@ -174,6 +205,16 @@ pub(crate) async fn cmd_link(
             Ok(())
         }
         Err(e) => {
+            if matches!(e, StorageError::ProfileNotVerified) {
+                remind_profile_verification(
+                    ctx.serenity_context(),
+                    ctx.data(),
+                    guild_id,
+                    selected_user_id,
+                )
+                .await;
+            }
+
             say_without_ping(ctx, format!("An error occurred: {}", e).as_str(), true).await?;
 
             Ok(())
@@ -181,6 +222,155 @@ pub(crate) async fn cmd_link(
     }
 }
 
+/// Find the Discord user linked to a Beat Leader profile.
+#[tracing::instrument(skip(ctx), level=tracing::Level::INFO, name="bot_command:bl-whois")]
+#[poise::command(
+    slash_command,
+    rename = "bl-whois",
+    ephemeral,
+    required_permissions = "MANAGE_ROLES",
+    default_member_permissions = "MANAGE_ROLES",
+    guild_only,
+    hide_in_help
+)]
+pub(crate) async fn cmd_whois(
+    ctx: Context<'_>,
+    #[description = "Beat Leader PlayerID or profile URL"] bl_player_id: String,
+) -> Result<(), Error> {
+    let guild_id = get_guild_id(ctx, true).await?;
+
+    let player_id = get_player_id_from_profile_link(&bl_player_id);
+
+    let players_repository = &ctx.data().players_repository;
+
+    let player = match players_repository.get_by_player_id(&player_id).await {
+        Some(player) => Some(player),
+        // profile URL may contain an alias instead of a player id, so resolve it using BL API
+        None => match fetch_player_from_bl(&player_id).await {
+            Ok(bl_player) if bl_player.id != player_id => {
+                players_repository.get_by_player_id(&bl_player.id).await
+            }
+            _ => None,
+        },
+    };
+
+    match player {
+        Some(player) if player.is_linked_to_guild(&guild_id) => {
+            say_without_ping(
+                ctx,
+                format!(
+                    "BL profile [{}](<https://www.beatleader.com/u/{}>) is linked to <@{}>",
+                    player.name, player.id, player.user_id
+                )
+                .as_str(),
+                true,
+            )
+            .await?
+        }
+        Some(player) => {
+            say_without_ping(
+                ctx,
+                format!(
+                    "BL profile [{}](<https://www.beatleader.com/u/{}>) is linked to a user who has not linked it on this server",
+                    player.name, player.id
+                )
+                .as_str(),
+                true,
+            )
+            .await?
+        }
+        None => {
+            say_without_ping(
+                ctx,
+                format!("No user is linked to BL profile {}", player_id).as_str(),
+                true,
+            )
+            .await?
+        }
+    }
+
+    Ok(())
+}
+
+const FIND_PLAYER_MAX_RESULTS: usize = 50;
+const FIND_PLAYER_PAGE_SIZE: usize = 10;
+
+/// Find linked players by a part of their Beat Leader name.
+#[tracing::instrument(skip(ctx), level=tracing::Level::INFO, name="bot_command:bl-find-player")]
+#[poise::command(
+    slash_command,
+    rename = "bl-find-player",
+    ephemeral,
+    required_permissions = "MANAGE_ROLES",
+    default_member_permissions = "MANAGE_ROLES",
+    guild_only,
+    hide_in_help
+)]
+pub(crate) async fn cmd_find_player(
+    ctx: Context<'_>,
+    #[description = "Part of the Beat Leader player name"]
+    #[min_length = 2]
+    name: String,
+) -> Result<(), Error> {
+    let guild_id = get_guild_id(ctx, true).await?;
+
+    let players = ctx
+        .data()
+        .players_repository
+        .search_by_name(&guild_id, name.trim())
+        .await;
+
+    if players.is_empty() {
+        say_without_ping(
+            ctx,
+            format!("No linked player name contains \"{}\"", name.trim()).as_str(),
+            true,
+        )
+        .await?;
+
+        return Ok(());
+    }
+
+    let lines = players
+        .iter()
+        .take(FIND_PLAYER_MAX_RESULTS)
+        .map(|player| {
+            format!(
+                "* [{}](<https://www.beatleader.com/u/{}>) is linked to <@{}>",
+                player.name, player.id, player.user_id
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let pages = lines
+        .chunks(FIND_PLAYER_PAGE_SIZE)
+        .map(|chunk| {
+            format!(
+                "Found {} player(s){}:\n{}",
+                players.len(),
+                if players.len() > FIND_PLAYER_MAX_RESULTS {
+                    format!(", first {} listed", FIND_PLAYER_MAX_RESULTS)
+                } else {
+                    "".to_owned()
+                },
+                chunk.join("\n")
+            )
+        })
+        .collect::<Vec<_>>();
+
+    if pages.len() == 1 {
+        say_without_ping(ctx, pages[0].as_str(), true).await?;
+    } else {
+        poise::builtins::paginate(
+            ctx,
+            &pages.iter().map(|page| page.as_str()).collect::<Vec<_>>(),
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
 /// Unlink your account from your Beat Leader profile.
 #[tracing::instrument(skip(ctx), level=tracing::Level::INFO, name="bot_command:bl-unlink")]
 #[poise::command(slash_command, rename = "bl-unlink", guild_only)]
@@ -200,13 +390,101 @@ pub(crate) async fn cmd_unlink(
             }
         };
 
+    let Some(player) = ctx
+        .data()
+        .players_repository
+        .get(&selected_user_id)
+        .await
+        .filter(|player| player.is_linked_to_guild(&guild_id))
+    else {
+        say_without_ping(
+            ctx,
+            format!("<@{}> is not linked by a bot.", selected_user_id).as_str(),
+            false,
+        )
+        .await?;
+
+        return Ok(());
+    };
+
+    let msg = ctx
+        .send(
+            CreateReply::default()
+                .content(format!(
+                    "Do you really want to unlink <@{}> from BL profile **{}**? Stored scores will also be removed if the profile is not linked on any other server.",
+                    selected_user_id, player.name
+                ))
+                .components(vec![CreateActionRow::Buttons(vec![
+                    serenity::CreateButton::new("unlink_confirm_btn")
+                        .label("Unlink")
+                        .style(serenity::ButtonStyle::Danger),
+                    serenity::CreateButton::new("unlink_cancel_btn")
+                        .label("Cancel")
+                        .style(serenity::ButtonStyle::Secondary),
+                ])])
+                .allowed_mentions(CreateAllowedMentions::new())
+                .ephemeral(true),
+        )
+        .await?;
+
+    let Some(mci) = serenity::ComponentInteractionCollector::new(ctx)
+        .author_id(ctx.author().id)
+        .message_id(msg.message().await?.id)
+        .timeout(std::time::Duration::from_secs(60))
+        .await
+    else {
+        msg.edit(
+            ctx,
+            CreateReply::default()
+                .content("Interaction timed out. Dismiss this message and try again.")
+                .components(vec![]),
+        )
+        .await?;
+
+        return Ok(());
+    };
+
+    mci.create_response(ctx, serenity::CreateInteractionResponse::Acknowledge)
+        .await?;
+
+    if mci.data.custom_id != "unlink_confirm_btn" {
+        msg.edit(
+            ctx,
+            CreateReply::default()
+                .content("Unlinking cancelled.")
+                .components(vec![]),
+        )
+        .await?;
+
+        return Ok(());
+    }
+
     match ctx
         .data()
         .players_repository
         .unlink(&guild_id, &selected_user_id)
         .await
     {
-        Ok(_) => {
+        Ok(player) => {
+            info!(
+                "User {} / BL player {} unlinked from guild {}.",
+                selected_user_id, player.name, guild_id
+            );
+
+            let removed = remove_unlinked_player_data(ctx, &guild_id, &player).await;
+
+            msg.edit(
+                ctx,
+                CreateReply::default()
+                    .content(if removed.is_empty() {
+                        "Unlinked, no other data to remove.".to_owned()
+                    } else {
+                        format!("Unlinked. Removed:\n{}", removed.join("\n"))
+                    })
+                    .components(vec![]),
+            )
+            .await?;
+
             ctx.send(
                 CreateReply::default()
                     .content(format!(
@@ -223,20 +501,22 @@ pub(crate) async fn cmd_unlink(
         }
         Err(e) => match e {
             StorageError::NotFound(_) => {
-                say_without_ping(
+                msg.edit(
                     ctx,
-                    format!("<@{}> is not linked by a bot.", selected_user_id).as_str(),
-                    false,
+                    CreateReply::default()
+                        .content(format!("<@{}> is not linked by a bot.", selected_user_id))
+                        .components(vec![]),
                 )
                 .await?;
 
                 Ok(())
             }
             _ => {
-                ctx.send(
+                msg.edit(
+                    ctx,
                     CreateReply::default()
                         .content(format!("An error has occurred: {}", e))
-                        .ephemeral(true),
+                        .components(vec![]),
                 )
                 .await?;
 
@@ -246,17 +526,173 @@ pub(crate) async fn cmd_unlink(
     }
 }
 
+// the player is already unlinked from the guild, so failures are only logged and reported
+async fn remove_unlinked_player_data(
+    ctx: Context<'_>,
+    guild_id: &GuildId,
+    player: &BotPlayer,
+) -> Vec<String> {
+    let mut removed = Vec::new();
+
+    let is_soldier = ctx
+        .data()
+        .guild_settings_repository
+        .get(guild_id)
+        .await
+        .ok()
+        .and_then(|guild_settings| guild_settings.get_clan_settings())
+        .is_some_and(|clan_settings| {
+            clan_settings
+                .get_clan_wars_soldiers()
+                .contains(&player.user_id)
+        });
+    if is_soldier {
+        match ctx
+            .data()
+            .guild_settings_repository
+            .remove_clan_wars_soldier(guild_id, player.user_id)
+            .await
+        {
+            Ok(_) => removed.push("- clan wars enlistment".to_owned()),
+            Err(err) => {
+                warn!(
+                    "Can not release user {} from clan wars in guild {}: {}",
+                    player.user_id, guild_id, err
+                );
+                removed.push(format!("- clan wars enlistment FAILED: {}", err));
+            }
+        }
+    }
+
+    // data shared between guilds is kept until the player is unlinked from all of them
+    if player.is_linked_to_any_guild() {
+        return removed;
+    }
+
+    for player_scores_repository in ctx.data().player_scores_repositories.all() {
+        match player_scores_repository.remove(&player.id).await {
+            Ok(true) => removed.push(format!(
+                "- stored {} scores",
+                player_scores_repository.bl_context
+            )),
+            Ok(false) => {}
+            Err(err) => {
+                warn!(
+                    "Can not remove BL player {} {} scores: {}",
+                    player.id, player_scores_repository.bl_context, err
+                );
+                removed.push(format!(
+                    "- stored {} scores FAILED: {}",
+                    player_scores_repository.bl_context, err
+                ));
+            }
+        }
+    }
+
+    if player.replay_watch_subscribed {
+        match ctx
+            .data()
+            .players_repository
+            .set_replay_watch_subscription(&player.user_id, false)
+            .await
+        {
+            Ok(_) => removed.push("- replay watches subscription".to_owned()),
+            Err(err) => {
+                warn!(
+                    "Can not remove user {} replay watches subscription: {}",
+                    player.user_id, err
+                );
+                removed.push(format!("- replay watches subscription FAILED: {}", err));
+            }
+        }
+    }
+
+    info!(
+        "User {} / BL player {} data removed after unlinking: {}",
+        player.user_id,
+        player.id,
+        removed.len()
+    );
+
+    removed
+}
+
+/// Get a direct message when your replays are watched
+#[tracing::instrument(skip(ctx), level=tracing::Level::INFO, name="bot_command:bl-subscribe-replay-watches")]
+#[poise::command(
+    slash_command,
+    rename = "bl-subscribe-replay-watches",
+    ephemeral,
+    guild_only
+)]
+pub(crate) async fn cmd_subscribe_replay_watches(
+    ctx: Context<'_>,
+    #[description = "Notify you about replay watches (default: true)"] enabled: Option<bool>,
+) -> Result<(), Error> {
+    let enabled = enabled.unwrap_or(true);
+
+    match ctx
+        .data()
+        .players_repository
+        .set_replay_watch_subscription(&ctx.author().id, enabled)
+        .await
+    {
+        Ok(_) => {
+            say_without_ping(
+                ctx,
+                if enabled {
+                    "You will get a direct message when your replays are watched."
+                } else {
+                    "You will no longer get replay watches notifications."
+                },
+                true,
+            )
+            .await?;
+
+            Ok(())
+        }
+        Err(StorageError::NotFound(_)) => {
+            say_without_ping(
+                ctx,
+                "You are not linked to any BL profile. Use ``/bl-link`` command first.",
+                true,
+            )
+            .await?;
+
+            Ok(())
+        }
+        Err(e) => {
+            ctx.send(
+                CreateReply::default()
+                    .content(format!("An error has occurred: {}", e))
+                    .ephemeral(true),
+            )
+            .await?;
+
+            Ok(())
+        }
+    }
+}
+
 /// Displays player's BL profile
 #[tracing::instrument(skip(ctx), level=tracing::Level::INFO, name="bot_command:bl-profile")]
 #[poise::command(slash_command, rename = "bl-profile", guild_only)]
 pub(crate) async fn cmd_profile(
     ctx: Context<'_>,
     #[description = "Discord user (YOU if not specified)"] user: Option<serenity::User>,
+    #[description = "Show the profile only to you (default: server setting, public if not set)"]
+    private: Option<bool>,
 ) -> Result<(), Error> {
-    ctx.defer().await?;
-
     let guild_settings = get_guild_settings(ctx, true).await?;
 
+    // the visibility of the response is set when it is deferred
+    let private = private.unwrap_or(guild_settings.is_profile_private());
+    if private {
+        ctx.defer_ephemeral().await?;
+    } else {
+        ctx.defer().await?;
+    }
+
     let selected_user = user.as_ref().unwrap_or_else(|| ctx.author());
 
     match link_user_if_needed(
@@ -279,14 +715,21 @@ pub(crate) async fn cmd_profile(
                 return Ok(());
             }
 
-            let embed_image = get_player_embed(&player).await;
+            let timezone = guild_settings.get_timezone();
+            let embed_image =
+                get_player_embed(&player, &guild_settings.get_theme(), &timezone).await;
 
             let mut reply = CreateReply::default()
                 .allowed_mentions(CreateAllowedMentions::new())
-                .ephemeral(false);
+                .ephemeral(private);
 
             if embed_image.is_none() {
-                reply = add_profile_card(reply, player);
+                reply = add_profile_card(
+                    reply,
+                    player,
+                    &timezone,
+                    guild_settings.get_decimal_places(),
+                );
             } else if let Some(embed_buffer) = embed_image {
                 reply = reply.attachment(CreateAttachment::bytes(
                     Cow::<[u8]>::from(embed_buffer),
@@ -311,6 +754,325 @@ pub(crate) async fn cmd_profile(
     }
 }
 
+/// Displays player's BL stats as text, without rendering the profile image
+#[tracing::instrument(skip(ctx), level=tracing::Level::INFO, name="bot_command:bl-profile-mini")]
+#[poise::command(slash_command, rename = "bl-profile-mini", guild_only)]
+pub(crate) async fn cmd_profile_mini(
+    ctx: Context<'_>,
+    #[description = "Discord user (YOU if not specified)"] user: Option<serenity::User>,
+) -> Result<(), Error> {
+    ctx.defer().await?;
+
+    let guild_settings = get_guild_settings(ctx, true).await?;
+
+    let selected_user = user.as_ref().unwrap_or_else(|| ctx.author());
+
+    match link_user_if_needed(
+        ctx,
+        &guild_settings.guild_id,
+        selected_user,
+        guild_settings.requires_verified_profile,
+    )
+    .await
+    {
+        Some(player) if player.is_linked_to_guild(&guild_settings.guild_id) => {
+            ctx.send(add_profile_mini_card(
+                CreateReply::default()
+                    .allowed_mentions(CreateAllowedMentions::new())
+                    .ephemeral(false),
+                player,
+                guild_settings.get_decimal_places(),
+            ))
+            .await?;
+
+            Ok(())
+        }
+        _ => {
+            say_profile_not_linked(
+                ctx,
+                &selected_user.id,
+                guild_settings.requires_verified_profile,
+            )
+            .await?;
+
+            Ok(())
+        }
+    }
+}
+
+const PAUSE_STATS_WEEKS: u32 = 8;
+
+/// Shows player's pauses in ranked plays week by week
+#[tracing::instrument(skip(ctx), level=tracing::Level::INFO, name="bot_command:bl-pause-stats")]
+#[poise::command(slash_command, rename = "bl-pause-stats", guild_only)]
+pub(crate) async fn cmd_pause_stats(
+    ctx: Context<'_>,
+    #[description = "Discord user (YOU if not specified)"] user: Option<serenity::User>,
+) -> Result<(), Error> {
+    ctx.defer().await?;
+
+    let guild_settings = get_guild_settings(ctx, true).await?;
+
+    let selected_user = user.as_ref().unwrap_or_else(|| ctx.author());
+
+    let player = match link_user_if_needed(
+        ctx,
+        &guild_settings.guild_id,
+        selected_user,
+        guild_settings.requires_verified_profile,
+    )
+    .await
+    {
+        Some(player) if player.is_linked_to_guild(&guild_settings.guild_id) => player,
+        _ => {
+            say_profile_not_linked(
+                ctx,
+                &selected_user.id,
+                guild_settings.requires_verified_profile,
+            )
+            .await?;
+
+            return Ok(());
+        }
+    };
+
+    let Some(player_scores) = ctx
+        .data()
+        .player_scores_repositories
+        .general()
+        .get(&player.id)
+        .await
+    else {
+        say_without_ping(
+            ctx,
+            "Ranked scores of the player have not been fetched yet, try again later.",
+            true,
+        )
+        .await?;
+
+        return Ok(());
+    };
+
+    let report = weekly_pause_report(&player_scores.scores, Utc::now(), PAUSE_STATS_WEEKS);
+
+    let weeks = report
+        .iter()
+        .map(|week| {
+            format!(
+                "<t:{}:d>: **{}** pause(s) in {} of {} play(s), {:.2} per play",
+                week.since.timestamp(),
+                week.pauses,
+                week.paused_plays,
+                week.plays,
+                week.pauses_per_play()
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    // the last week is compared with the average of the earlier weeks the player played in
+    let earlier_weeks = report
+        .iter()
+        .skip(1)
+        .filter(|week| week.plays > 0)
+        .collect::<Vec<_>>();
+    let trend = match report.first() {
+        Some(last_week) if last_week.plays > 0 && !earlier_weeks.is_empty() => {
+            let earlier_avg = earlier_weeks
+                .iter()
+                .map(|week| week.pauses_per_play())
+                .sum::<f64>()
+                / earlier_weeks.len() as f64;
+
+            match last_week.pauses_per_play().partial_cmp(&earlier_avg) {
+                Some(std::cmp::Ordering::Less) => "📉 Pausing less than before",
+                Some(std::cmp::Ordering::Greater) => "📈 Pausing more than before",
+                _ => "Pausing as much as before",
+            }
+        }
+        _ => "Not enough recent plays",
+    };
+
+    ctx.send(
+        CreateReply::default()
+            .allowed_mentions(CreateAllowedMentions::new())
+            .embed(
+                CreateEmbed::new()
+                    .title(format!("{} pause stats", player.name))
+                    .url(format!("https://www.beatleader.com/u/{}", player.id))
+                    .description(format!(
+                        "Total pauses in ranked scores: **{}**\nTrend: {}",
+                        player_scores
+                            .scores
+                            .iter()
+                            .map(|score| score.pauses)
+                            .sum::<u32>(),
+                        trend
+                    ))
+                    .field(format!("Last {} weeks", PAUSE_STATS_WEEKS), weeks, false),
+            ),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Shows the players of this server who watched the most replays
+#[tracing::instrument(skip(ctx), level=tracing::Level::INFO, name="bot_command:bl-watch-leaderboard")]
+#[poise::command(slash_command, rename = "bl-watch-leaderboard", guild_only)]
+pub(crate) async fn cmd_watch_leaderboard(ctx: Context<'_>) -> Result<(), Error> {
+    const TOP_PLAYERS: u32 = 10;
+
+    let guild_id = get_guild_id(ctx, true).await?;
+
+    let players = ctx
+        .data()
+        .players_repository
+        .all()
+        .await
+        .into_iter()
+        .filter(|player| player.is_linked_to_guild(&guild_id))
+        .collect::<Vec<_>>();
+
+    let ranking = rank_by_watched_replays(players, TOP_PLAYERS);
+
+    if ranking.is_empty() {
+        say_without_ping(
+            ctx,
+            "None of the players linked on this server has watched any replay yet.",
+            true,
+        )
+        .await?;
+
+        return Ok(());
+    }
+
+    let description = ranking
+        .into_iter()
+        .map(|(place, player)| {
+            format!(
+                "{}. [{}](<https://www.beatleader.com/u/{}>) (<@{}>): {}",
+                place, player.name, player.id, player.user_id, player.watched_replays
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    ctx.send(
+        CreateReply::default()
+            .allowed_mentions(CreateAllowedMentions::new())
+            .embed(
+                CreateEmbed::new()
+                    .title("Replays watched")
+                    .description(description)
+                    .footer(CreateEmbedFooter::new(
+                        "Number of other players' replays watched on BL",
+                    )),
+            ),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Shows #1 scores of the player grouped by difficulty
+#[tracing::instrument(skip(ctx), level=tracing::Level::INFO, name="bot_command:bl-top1")]
+#[poise::command(slash_command, rename = "bl-top1", guild_only)]
+pub(crate) async fn cmd_top1(
+    ctx: Context<'_>,
+    #[description = "Discord user (YOU if not specified)"] user: Option<User>,
+) -> Result<(), Error> {
+    const MAX_TOP1_SCORES: u32 = 500;
+    const SAMPLE_SONGS_COUNT: usize = 3;
+    // discord limit of fields per embed
+    const MAX_GROUPS: usize = 25;
+
+    ctx.defer().await?;
+
+    let guild_settings = get_guild_settings(ctx, true).await?;
+
+    let selected_user = user.as_ref().unwrap_or(ctx.author());
+
+    let Some(player) = link_user_if_needed(
+        ctx,
+        &guild_settings.guild_id,
+        selected_user,
+        guild_settings.requires_verified_profile,
+    )
+    .await
+    .filter(|player| player.is_linked_to_guild(&guild_settings.guild_id)) else {
+        say_profile_not_linked(
+            ctx,
+            &selected_user.id,
+            guild_settings.requires_verified_profile,
+        )
+        .await?;
+
+        return Ok(());
+    };
+
+    let top1_scores = match fetch_top1_scores(&player.id, MAX_TOP1_SCORES).await {
+        Ok(top1_scores) => top1_scores,
+        Err(e) => {
+            ctx.say(format!("Error fetching scores: {}", e)).await?;
+
+            return Ok(());
+        }
+    };
+
+    if top1_scores.is_empty() {
+        say_without_ping(
+            ctx,
+            format!("{} has no #1 scores yet.", player.name).as_str(),
+            false,
+        )
+        .await?;
+
+        return Ok(());
+    }
+
+    let groups = group_top1_scores(&top1_scores, SAMPLE_SONGS_COUNT);
+
+    let mut embed = CreateEmbed::new()
+        .title(format!("{} #1 scores: {}", player.name, top1_scores.len()))
+        .url(format!("https://www.beatleader.com/u/{}", player.id));
+
+    for group in groups.iter().take(MAX_GROUPS) {
+        embed = embed.field(
+            format!(
+                "{} ({}): {}",
+                group.difficulty_name, group.mode_name, group.count
+            ),
+            format!(
+                "{}{}",
+                group.sample_songs.join(", "),
+                if group.count as usize > group.sample_songs.len() {
+                    ", ..."
+                } else {
+                    ""
+                }
+            ),
+            false,
+        );
+    }
+
+    if top1_scores.len() as u32 >= MAX_TOP1_SCORES {
+        embed = embed.footer(CreateEmbedFooter::new(format!(
+            "Only the first {} #1 scores are included",
+            MAX_TOP1_SCORES
+        )));
+    }
+
+    ctx.send(
+        CreateReply::default()
+            .allowed_mentions(CreateAllowedMentions::new())
+            .embed(embed),
+    )
+    .await?;
+
+    Ok(())
+}
+
 pub(crate) async fn link_user_if_needed(
     ctx: Context<'_>,
     guild_id: &GuildId,
@@ -330,6 +1092,26 @@ pub(crate) async fn link_user_if_needed(
                 selected_user.id,
                 guild_id
             );
+            if !player.is_linked_to_guild(guild_id)
+                && !player.can_be_linked_to_guild(requires_verified_profile)
+            {
+                trace!(
+                    "User {} profile is not verified, not linking to the guild {}.",
+                    selected_user.id,
+                    guild_id
+                );
+
+                remind_profile_verification(
+                    ctx.serenity_context(),
+                    ctx.data(),
+                    *guild_id,
+                    selected_user.id,
+                )
+                .await;
+
+                return None;
+            }
+
             if !player.is_linked_to_guild(guild_id)
                 && ctx
                     .data()
@@ -344,41 +1126,171 @@ pub(crate) async fn link_user_if_needed(
                     guild_id
                 );
 
-                player.linked_guilds.push(*guild_id);
-            }
+                player.linked_guilds.push(*guild_id);
+            }
+
+            Some(player)
+        }
+        None => {
+            trace!(
+                "User {} is not linked yet, trying to fetch player from BL using Discord id...",
+                selected_user.id
+            );
+
+            if let Ok(bl_player) = fetch_player_from_bl_by_user_id(&selected_user.id).await {
+                trace!(
+                    "User {} is linked on the BL website, player name: {}. Trying to link...",
+                    selected_user.id,
+                    &bl_player.name
+                );
+
+                return match ctx
+                    .data()
+                    .players_repository
+                    .link_player(
+                        *guild_id,
+                        selected_user.id,
+                        bl_player,
+                        requires_verified_profile,
+                    )
+                    .await
+                {
+                    Ok(player) => Some(player),
+                    Err(StorageError::ProfileNotVerified) => {
+                        remind_profile_verification(
+                            ctx.serenity_context(),
+                            ctx.data(),
+                            *guild_id,
+                            selected_user.id,
+                        )
+                        .await;
+
+                        None
+                    }
+                    Err(_) => None,
+                };
+            };
+
+            None
+        }
+    }
+}
+
+/// Show the latest scores of a player
+#[tracing::instrument(skip(ctx), level=tracing::Level::INFO, name="bot_command:bl-recent")]
+#[poise::command(slash_command, rename = "bl-recent", guild_only)]
+pub(crate) async fn cmd_recent(
+    ctx: Context<'_>,
+    #[description = "Number of scores (max: 10, default: 5)"]
+    #[min = 1]
+    #[max = 10]
+    count: Option<u32>,
+    #[description = "BL context (General if not specified)"] context: Option<BlCommandContext>,
+    #[description = "Discord user (YOU if not specified)"] user: Option<serenity::User>,
+) -> Result<(), Error> {
+    ctx.defer().await?;
+
+    let count = count.unwrap_or(5).clamp(1, 10);
+
+    let guild_settings = get_guild_settings(ctx, true).await?;
+
+    let selected_user = user.as_ref().unwrap_or_else(|| ctx.author());
+
+    match link_user_if_needed(
+        ctx,
+        &guild_settings.guild_id,
+        selected_user,
+        guild_settings.requires_verified_profile,
+    )
+    .await
+    {
+        Some(player) => {
+            if !player.is_linked_to_guild(&guild_settings.guild_id) {
+                say_profile_not_linked(
+                    ctx,
+                    &selected_user.id,
+                    guild_settings.requires_verified_profile,
+                )
+                .await?;
+
+                return Ok(());
+            }
+
+            let player_score_context = (context.unwrap_or_default()).to_bl_context();
+
+            let player_scores = match fetch_scores(
+                &player.id,
+                &[
+                    PlayerScoreParam::Page(1),
+                    PlayerScoreParam::Count(count),
+                    PlayerScoreParam::Sort(PlayerScoreSort::Date),
+                    PlayerScoreParam::Order(SortOrder::Descending),
+                    PlayerScoreParam::Context(player_score_context.clone()),
+                ],
+            )
+            .await
+            {
+                Ok(player_scores) => {
+                    if player_scores.data.is_empty() {
+                        say_without_ping(ctx, "No scores found.", true).await?;
+                        return Ok(());
+                    }
+
+                    player_scores
+                }
+                Err(e) => {
+                    ctx.say(format!("Error fetching scores: {}", e)).await?;
+                    return Ok(());
+                }
+            };
 
-            Some(player)
-        }
-        None => {
-            trace!(
-                "User {} is not linked yet, trying to fetch player from BL using Discord id...",
-                selected_user.id
+            let embed = player_scores.data.iter().fold(
+                CreateEmbed::new()
+                    .author(
+                        serenity::CreateEmbedAuthor::new(player.name.clone())
+                            .icon_url(player.avatar.clone())
+                            .url(format!("https://www.beatleader.com/u/{}", player.id)),
+                    )
+                    .title(format!(
+                        "Recent scores ({})",
+                        capitalize(&player_score_context.to_string())
+                    )),
+                |embed, score| {
+                    embed.field(
+                        format!(
+                            "{} {} / {}",
+                            score.song_name, score.song_sub_name, score.difficulty_name
+                        ),
+                        format!(
+                            "[{}](https://replay.beatleader.com/?scoreId={}) • <t:{}:R>",
+                            score.compact_stats(guild_settings.get_decimal_places()),
+                            score.id,
+                            score.timeset.timestamp()
+                        ),
+                        false,
+                    )
+                },
             );
 
-            if let Ok(bl_player) = fetch_player_from_bl_by_user_id(&selected_user.id).await {
-                trace!(
-                    "User {} is linked on the BL website, player name: {}. Trying to link...",
-                    selected_user.id,
-                    &bl_player.name
-                );
+            ctx.send(
+                CreateReply::default()
+                    .embed(embed)
+                    .allowed_mentions(CreateAllowedMentions::new())
+                    .ephemeral(false),
+            )
+            .await?;
 
-                return match ctx
-                    .data()
-                    .players_repository
-                    .link_player(
-                        *guild_id,
-                        selected_user.id,
-                        bl_player,
-                        requires_verified_profile,
-                    )
-                    .await
-                {
-                    Ok(player) => Some(player),
-                    Err(_) => None,
-                };
-            };
+            Ok(())
+        }
+        None => {
+            say_profile_not_linked(
+                ctx,
+                &selected_user.id,
+                guild_settings.requires_verified_profile,
+            )
+            .await?;
 
-            None
+            Ok(())
         }
     }
 }
@@ -394,6 +1306,7 @@ pub(crate) async fn cmd_replay(
     #[description = "BL context (General if not specified)"] context: Option<BlCommandContext>,
     #[description = "Map name"] map_name: Option<String>,
     #[description = "Discord user (YOU if not specified)"] user: Option<serenity::User>,
+    #[description = "Attach the .bsor replay file"] attach_replay: Option<bool>,
 ) -> Result<(), Error> {
     ctx.defer_ephemeral().await?;
 
@@ -456,7 +1369,11 @@ pub(crate) async fn cmd_replay(
             let msg = ctx
                 .send(
                     CreateReply::default()
-                        .components(add_replay_components(&player_scores, &selected_ids))
+                        .components(add_replay_components(
+                            &player_scores,
+                            &selected_ids,
+                            guild_settings.get_decimal_places(),
+                        ))
                         .ephemeral(true),
                 )
                 .await?;
@@ -482,7 +1399,11 @@ pub(crate) async fn cmd_replay(
                         msg.edit(
                             ctx,
                             CreateReply::default()
-                                .components(add_replay_components(&player_scores, &score_ids))
+                                .components(add_replay_components(
+                                    &player_scores,
+                                    &score_ids,
+                                    guild_settings.get_decimal_places(),
+                                ))
                                 .ephemeral(true),
                         )
                         .await?;
@@ -498,6 +1419,8 @@ pub(crate) async fn cmd_replay(
                                 &player_scores,
                                 &player,
                                 &player_score_context,
+                                &guild_settings.get_theme(),
+                                attach_replay.unwrap_or(false),
                                 &msg,
                             )
                             .await?;
@@ -542,6 +1465,122 @@ pub(crate) async fn cmd_replay(
     }
 }
 
+/// Generate a playlist of ranked maps you have never played
+#[tracing::instrument(skip(ctx), level=tracing::Level::INFO, name="bot_command:bl-practice-playlist")]
+#[poise::command(slash_command, rename = "bl-practice-playlist", guild_only)]
+pub(crate) async fn cmd_practice_playlist(
+    ctx: Context<'_>,
+    #[description = "Maps count (default: 100)"] count: Option<u32>,
+    #[description = "Maps max stars (default: player's top stars)"] max_stars: Option<f64>,
+    #[description = "Maps status (default: Ranked)"] map_status: Option<PlaylistMapStatus>,
+    #[description = "Your custom playlist name (default: autogenerated"] playlist_name: Option<
+        String,
+    >,
+    #[description = "Scores context (default: General)"] context: Option<ClanWarsContext>,
+) -> Result<(), Error> {
+    ctx.defer().await?;
+
+    let bl_context: BlContext = context.unwrap_or_default().into();
+    if !ctx.data().player_scores_repositories.contains(&bl_context) {
+        say_without_ping(
+            ctx,
+            format!("Playlists in {} context are not available.", bl_context).as_str(),
+            true,
+        )
+        .await?;
+
+        return Ok(());
+    }
+
+    let max_count = ctx
+        .data()
+        .settings
+        .playlist_max_songs
+        .min(PLAYLIST_MAX_SONGS_CEILING);
+    let (count, clamped) = match count {
+        None => (100.min(max_count), false),
+        Some(0) => (100.min(max_count), false),
+        Some(v) if v > max_count => (max_count, true),
+        Some(v) => (v, false),
+    };
+
+    let guild_settings = get_guild_settings(ctx, true).await?;
+
+    let current_user = ctx.author();
+
+    let player = match link_user_if_needed(
+        ctx,
+        &guild_settings.guild_id,
+        current_user,
+        guild_settings.requires_verified_profile,
+    )
+    .await
+    {
+        Some(player) if player.is_linked_to_guild(&guild_settings.guild_id) => player,
+        _ => {
+            say_profile_not_linked(
+                ctx,
+                &current_user.id,
+                guild_settings.requires_verified_profile,
+            )
+            .await?;
+
+            return Ok(());
+        }
+    };
+
+    match Playlist::for_player_never_played(
+        &ctx.data().player_scores_repositories.clone(),
+        player,
+        bl_context,
+        count,
+        max_stars,
+        map_status,
+        playlist_name,
+    )
+    .await
+    {
+        Ok(playlist) => {
+            match serde_json::to_string::<Playlist>(&playlist) {
+                Ok(data_json) => {
+                    ctx.send(
+                        CreateReply::default()
+                            .content(if clamped {
+                                format!(
+                                    "Here's your practice playlist (maps count limited to {}):",
+                                    count
+                                )
+                            } else {
+                                "Here's your practice playlist:".to_owned()
+                            })
+                            .attachment(CreateAttachment::bytes(
+                                Cow::from(data_json.into_bytes()),
+                                format!(
+                                    "{}.json",
+                                    playlist
+                                        .get_title()
+                                        .replace([' ', '-', '/', '\\', ':'], "_")
+                                ),
+                            ))
+                            .ephemeral(true),
+                    )
+                    .await?;
+                }
+                Err(err) => {
+                    ctx.say(format!("An error occurred: {}", err)).await?;
+                }
+            };
+
+            Ok(())
+        }
+        Err(err) => {
+            say_without_ping(ctx, err.as_str(), false).await?;
+
+            Ok(())
+        }
+    }
+}
+
 /// Force refreshing all players scores
 #[tracing::instrument(skip(ctx), level=tracing::Level::INFO, name="bot_command:bl-refresh-scores")]
 #[poise::command(
@@ -554,17 +1593,170 @@ pub(crate) async fn cmd_replay(
     guild_only,
     hide_in_help
 )]
-pub(crate) async fn cmd_refresh_scores(ctx: Context<'_>) -> Result<(), Error> {
+pub(crate) async fn cmd_refresh_scores(
+    ctx: Context<'_>,
+    #[description = "Scores context (default: all configured contexts)"] context: Option<
+        BlCommandContext,
+    >,
+) -> Result<(), Error> {
+    let players_repository = &ctx.data().players_repository;
+    let player_scores_repositories = &ctx.data().player_scores_repositories;
+
+    // players stats are based on general context, so refreshing it refreshes all of them
+    let bl_context = context
+        .map(|context| context.to_bl_context())
+        .filter(|bl_context| *bl_context != BlContext::General);
+
+    let Some(bl_context) = bl_context else {
+        say_without_ping(ctx, "Please wait...", true).await?;
+
+        players_repository
+            .update_all_players_stats(player_scores_repositories, true, None)
+            .await?;
+        ctx.data().clan_membership_cache.clear();
+
+        let mut contexts = player_scores_repositories
+            .all()
+            .map(|repository| repository.bl_context.to_string())
+            .collect::<Vec<_>>();
+        contexts.sort_unstable();
+
+        say_without_ping(
+            ctx,
+            format!(
+                "All players scores refreshed (contexts: {}).",
+                contexts.join(", ")
+            )
+            .as_str(),
+            true,
+        )
+        .await?;
+
+        return Ok(());
+    };
+
+    let Some(player_scores_repository) = player_scores_repositories.get(&bl_context) else {
+        say_without_ping(
+            ctx,
+            format!(
+                "Scores in {} context are not stored on this bot.",
+                bl_context
+            )
+            .as_str(),
+            true,
+        )
+        .await?;
+
+        return Ok(());
+    };
+
     say_without_ping(ctx, "Please wait...", true).await?;
 
+    let updated = players_repository
+        .update_all_players_scores(player_scores_repository, true)
+        .await;
+
+    say_without_ping(
+        ctx,
+        format!(
+            "Players {} scores refreshed ({} player(s) updated).",
+            bl_context, updated
+        )
+        .as_str(),
+        true,
+    )
+    .await?;
+
+    Ok(())
+}
+
+const REFRESH_ALL_SCORES_PROGRESS_EVERY: usize = 10;
+
+/// Force refreshing scores of all players linked on this server
+#[tracing::instrument(skip(ctx), level=tracing::Level::INFO, name="bot_command:bl-refresh-all-scores")]
+#[poise::command(
+    slash_command,
+    rename = "bl-refresh-all-scores",
+    ephemeral,
+    required_permissions = "ADMINISTRATOR",
+    default_member_permissions = "ADMINISTRATOR",
+    guild_only,
+    hide_in_help
+)]
+pub(crate) async fn cmd_refresh_all_scores(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = get_guild_id(ctx, true).await?;
+
     let players_repository = &ctx.data().players_repository;
-    let player_scores_repository = &ctx.data().player_scores_repository;
+    let player_scores_repositories = &ctx.data().player_scores_repositories;
+
+    let players = players_repository
+        .all()
+        .await
+        .into_iter()
+        .filter(|player| player.is_linked_to_guild(&guild_id))
+        .collect::<Vec<_>>();
+
+    if players.is_empty() {
+        say_without_ping(ctx, "No players are linked on this server.", true).await?;
 
-    players_repository
-        .update_all_players_stats(player_scores_repository, true, None)
+        return Ok(());
+    }
+
+    ctx.data().clan_membership_cache.clear();
+
+    let total = players.len();
+
+    let msg = ctx
+        .say(format!("Refreshing scores of {} player(s)...", total))
         .await?;
 
-    say_without_ping(ctx, "All players scores refreshed.", true).await?;
+    let mut results =
+        players_repository.update_players_stats(player_scores_repositories, players, true);
+
+    let mut succeeded = 0;
+    let mut failed = vec![];
+    while let Some((player, result)) = results.next().await {
+        match result {
+            Ok(_) => succeeded += 1,
+            Err(err) => {
+                warn!(
+                    "Can not refresh user {} / BL player {} scores: {}",
+                    player.user_id, player.name, err
+                );
+
+                failed.push(player.name);
+            }
+        }
+
+        let processed = succeeded + failed.len();
+        // progress is informative only, the refresh goes on even if the message can not be updated
+        if processed < total && processed % REFRESH_ALL_SCORES_PROGRESS_EVERY == 0 {
+            if let Err(err) = msg
+                .edit(
+                    ctx,
+                    CreateReply::default().content(format!(
+                        "Refreshing scores of {} player(s)... {}/{}",
+                        total, processed, total
+                    )),
+                )
+                .await
+            {
+                warn!("Can not update scores refresh progress: {}", err);
+            }
+        }
+    }
+
+    let mut msg_contents = format!(
+        "Scores refreshed. Succeeded: {}, failed: {}.",
+        succeeded,
+        failed.len()
+    );
+    if !failed.is_empty() {
+        msg_contents.push_str(format!("\nFailed players: {}", failed.join(", ")).as_str());
+    }
+
+    msg.edit(ctx, CreateReply::default().content(msg_contents))
+        .await?;
 
     Ok(())
 }
@@ -572,6 +1764,7 @@ pub(crate) async fn cmd_refresh_scores(ctx: Context<'_>) -> Result<(), Error> {
 fn add_replay_components(
     player_scores: &BlList<Score>,
     selected_ids: &[String],
+    decimal_places: u8,
 ) -> Vec<CreateActionRow> {
     let select_max_len = 3.min(player_scores.data.len() as u8);
 
@@ -598,25 +1791,7 @@ fn add_replay_components(
                                 },
                                 s.id.to_string(),
                             )
-                            .description(format!(
-                                "{:.2}%{} / {:.2}pp / {}",
-                                s.accuracy,
-                                if s.modifiers.is_empty() {
-                                    "".to_owned()
-                                } else {
-                                    format!(" {}", s.modifiers)
-                                },
-                                s.pp,
-                                if s.mistakes == 0 {
-                                    "FC".to_owned()
-                                } else {
-                                    format!(
-                                        "{} mistake{}",
-                                        s.mistakes,
-                                        if s.mistakes > 1 { "s" } else { "" }
-                                    )
-                                }
-                            ))
+                            .description(s.compact_stats(decimal_places))
                             .default_selection(selected_ids.contains(&s.id.to_string()))
                         })
                         .collect::<Vec<_>>(),
@@ -633,12 +1808,15 @@ fn add_replay_components(
     ]
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn post_replays(
     ctx: Context<'_>,
     score_ids: &Vec<String>,
     player_scores: &BlList<Score>,
     player: &BotPlayer,
     bl_context: &BlContext,
+    theme: &Theme,
+    attach_replay: bool,
     msg: &ReplyHandle<'_>,
 ) -> Result<(), Error> {
     let mut msg_contents = "Loading player avatar...".to_owned();
@@ -741,7 +1919,16 @@ async fn post_replays(
             .await?;
 
         let embed_image = if !player_avatar.is_empty() {
-            embed_score(&score, player, player_avatar.as_ref()).await
+            let hand_stats = fetch_hand_stats(score.id).await;
+
+            embed_score(
+                &score,
+                player,
+                player_avatar.as_ref(),
+                hand_stats.as_ref(),
+                theme,
+            )
+            .await
         } else {
             None
         };
@@ -752,20 +1939,57 @@ async fn post_replays(
             msg_contents.push_str("FAILED\n");
         }
 
-        let send_message_result = ctx
-            .channel_id()
-            .send_message(
-                ctx,
-                score
-                    .add_embed_to_message(
-                        CreateMessage::new(),
-                        player,
-                        bl_context,
-                        embed_image.as_ref(),
-                    )
-                    .allowed_mentions(CreateAllowedMentions::new().all_users(true).all_roles(true)),
+        let mut replay_attachment = None;
+        let mut replay_link = None;
+
+        if attach_replay && !score.replay.is_empty() {
+            msg_contents.push_str("Downloading replay file...");
+
+            let msg_contents_clone = msg_contents.clone();
+            msg.edit(ctx, CreateReply::default().content(msg_contents_clone))
+                .await?;
+
+            match get_uncached_binary_file(&score.replay).await {
+                Ok(replay) if replay.len() <= DISCORD_ATTACHMENT_MAX_SIZE => {
+                    replay_attachment = Some(CreateAttachment::bytes(
+                        Cow::<[u8]>::from(replay.to_vec()),
+                        format!("{}.bsor", score.id),
+                    ));
+
+                    msg_contents.push_str("OK\n");
+                }
+                Ok(_) => {
+                    replay_link = Some(format!("Replay file: <{}>", score.replay));
+
+                    msg_contents.push_str("TOO LARGE, posting a link instead\n");
+                }
+                Err(err) => {
+                    warn!("Downloading replay file {} failed: {}", &score.replay, err);
+
+                    replay_link = Some(format!("Replay file: <{}>", score.replay));
+
+                    msg_contents.push_str("FAILED, posting a link instead\n");
+                }
+            }
+        }
+
+        let mut message = score
+            .add_embed_to_message(
+                CreateMessage::new(),
+                player,
+                bl_context,
+                embed_image.as_ref(),
+                theme.decimal_places,
             )
-            .await;
+            .allowed_mentions(CreateAllowedMentions::new().all_users(true).all_roles(true));
+        if let Some(ref replay_attachment) = replay_attachment {
+            message = message.add_file(replay_attachment.clone());
+        }
+        if let Some(ref replay_link) = replay_link {
+            message = message.content(replay_link);
+        }
+
+        let send_message_result = ctx.channel_id().send_message(ctx, message).await;
 
         if send_message_result.is_err() {
             warn!(
@@ -773,16 +1997,23 @@ async fn post_replays(
                 send_message_result.err()
             );
 
-            let reply = score
+            let mut reply = score
                 .add_embed_to_reply(
                     CreateReply::default(),
                     player,
                     bl_context,
                     embed_image.as_ref(),
+                    theme.decimal_places,
                 )
                 .allowed_mentions(CreateAllowedMentions::new().all_users(true).all_roles(true))
                 .reply(false)
                 .ephemeral(false);
+            if let Some(replay_attachment) = replay_attachment {
+                reply = reply.attachment(replay_attachment);
+            }
+            if let Some(replay_link) = replay_link {
+                reply = reply.content(replay_link);
+            }
             let reply_result = ctx.send(reply).await;
 
             if reply_result.is_err() {
@@ -810,7 +2041,12 @@ async fn post_replays(
     Ok(())
 }
 
-fn add_profile_card(reply: CreateReply, player: BotPlayer) -> CreateReply {
+fn add_profile_card(
+    reply: CreateReply,
+    player: BotPlayer,
+    timezone: &Tz,
+    decimal_places: u8,
+) -> CreateReply {
     let mut clans = player.clans.join(", ");
     if clans.is_empty() {
         clans = "None".to_string()
@@ -828,14 +2064,22 @@ fn add_profile_card(reply: CreateReply, player: BotPlayer) -> CreateReply {
             .url(format!("https://www.beatleader.com/u/{}", player.id))
             .thumbnail(player.avatar)
             .field("Rank", player.rank.to_string(), true)
-            .field("PP", format!("{:.2}", player.pp), true)
+            .field("PP", format_decimal(player.pp, decimal_places), true)
             .field("Country", player.country, true)
-            .field("Top PP", format!("{:.2}", player.top_pp), true)
-            .field("Top Acc", format!("{:.2}%", player.top_accuracy), true)
+            .field(
+                "Top PP",
+                format_decimal(player.top_pp, decimal_places),
+                true,
+            )
+            .field(
+                "Top Acc",
+                format!("{}%", format_decimal(player.top_accuracy, decimal_places)),
+                true,
+            )
             .field(
                 "Top Stars",
                 if player.last_scores_fetch.is_some() {
-                    format!("{:.2}⭐", player.top_stars)
+                    format!("{}⭐", format_decimal(player.top_stars, decimal_places))
                 } else {
                     "-".to_owned()
                 },
@@ -844,7 +2088,7 @@ fn add_profile_card(reply: CreateReply, player: BotPlayer) -> CreateReply {
             .field(
                 "+1pp",
                 if player.last_scores_fetch.is_some() {
-                    format!("{:.2}pp", player.plus_1pp)
+                    format!("{}pp", format_decimal(player.plus_1pp, decimal_places))
                 } else {
                     "-".to_owned()
                 },
@@ -853,10 +2097,13 @@ fn add_profile_card(reply: CreateReply, player: BotPlayer) -> CreateReply {
             .field(
                 "Last pause",
                 if player.last_scores_fetch.is_some() {
-                    if player.last_ranked_paused_at.is_some() {
+                    if let Some(last_ranked_paused_at) = player.last_ranked_paused_at {
                         format!(
-                            "<t:{}:R>",
-                            player.last_ranked_paused_at.unwrap().timestamp()
+                            "{} (<t:{}:R>)",
+                            last_ranked_paused_at
+                                .with_timezone(timezone)
+                                .format("%Y-%m-%d"),
+                            last_ranked_paused_at.timestamp()
                         )
                     } else {
                         "Never".to_owned()
@@ -881,6 +2128,23 @@ fn add_profile_card(reply: CreateReply, player: BotPlayer) -> CreateReply {
     )
 }
 
+fn add_profile_mini_card(reply: CreateReply, player: BotPlayer, decimal_places: u8) -> CreateReply {
+    reply.embed(
+        CreateEmbed::new()
+            .title(player.name)
+            .url(format!("https://www.beatleader.com/u/{}", player.id))
+            .description(format!(
+                "**{}pp** • #{} • {} #{}\nTop PP: {}pp • Top Acc: {}%",
+                format_decimal(player.pp, decimal_places),
+                player.rank,
+                player.country,
+                player.country_rank,
+                format_decimal(player.top_pp, decimal_places),
+                format_decimal(player.top_accuracy, decimal_places)
+            )),
+    )
+}
+
 pub(crate) async fn say_profile_not_linked(
     ctx: Context<'_>,
     user_id: &UserId,
@@ -890,8 +2154,9 @@ pub(crate) async fn say_profile_not_linked(
         ctx,
         if requires_verified_profile {
             format!(
-                "<@{}> is not linked by a bot nor is the Discord account linked on the BL site. This server requires a verified profile, so link your Discord account on the BL website first, then use this command again.",
-                user_id
+                "<@{}> is not linked by a bot nor is the Discord account linked on the BL site. This server requires a verified profile. {} Then use this command again.",
+                user_id,
+                PROFILE_VERIFICATION_STEPS
             )
         } else {
             format!(
@@ -907,6 +2172,72 @@ pub(crate) async fn say_profile_not_linked(
     Ok(())
 }
 
+// failures are only logged, the link itself has already succeeded
+async fn grant_welcome_roles(ctx: Context<'_>, guild_settings: &GuildSettings, player: &Player) {
+    if guild_settings.get_welcome_roles().is_empty() {
+        return;
+    }
+
+    let current_roles = match guild_settings.guild_id.member(ctx, player.user_id).await {
+        Ok(member) => member.roles,
+        Err(err) => {
+            warn!(
+                "Can not get user {} roles to grant welcome roles: {}",
+                player.user_id, err
+            );
+
+            return;
+        }
+    };
+
+    let mut role_changes = guild_settings.get_welcome_role_changes(player, &current_roles);
+    if let Err(err) = role_changes.apply(&ctx.serenity_context().http).await {
+        warn!(
+            "Can not grant welcome roles to user {}: {}",
+            player.user_id, err
+        );
+    }
+}
+
+// DMs the user the profile verification steps if the guild enabled it, once per guild
+pub(crate) async fn remind_profile_verification(
+    ctx: &serenity::Context,
+    data: &BotData,
+    guild_id: GuildId,
+    user_id: UserId,
+) {
+    let Ok(guild_settings) = data.guild_settings_repository.get(&guild_id).await else {
+        return;
+    };
+
+    if !guild_settings.is_verification_reminder_enabled()
+        || !data.verification_reminders.mark_reminded(guild_id, user_id)
+    {
+        return;
+    }
+
+    let guild_name = guild_id
+        .name(&ctx.cache)
+        .unwrap_or_else(|| "This server".to_owned());
+
+    let message = CreateMessage::new().content(format!(
+        "**{}** accepts verified Beat Leader profiles only. {} Then use the ``/bl-link`` command on the server.",
+        guild_name, PROFILE_VERIFICATION_STEPS
+    ));
+
+    // users may have DMs from server members disabled
+    match user_id.direct_message(ctx, message).await {
+        Ok(_) => info!(
+            "Verification reminder sent to user {} of the guild {}.",
+            user_id, guild_id
+        ),
+        Err(err) => warn!(
+            "Can not send verification reminder to user {} of the guild {}: {}",
+            user_id, guild_id, err
+        ),
+    }
+}
+
 pub(crate) async fn say_without_ping(
     ctx: Context<'_>,
     message: &str,
@@ -923,7 +2254,11 @@ pub(crate) async fn say_without_ping(
     Ok(())
 }
 
-pub(crate) async fn get_player_embed(player: &BotPlayer) -> Option<Vec<u8>> {
+pub(crate) async fn get_player_embed(
+    player: &BotPlayer,
+    theme: &Theme,
+    timezone: &Tz,
+) -> Option<Vec<u8>> {
     let player_avatar = get_binary_file(&player.avatar)
         .await
         .unwrap_or(Bytes::new());
@@ -945,6 +2280,8 @@ pub(crate) async fn get_player_embed(player: &BotPlayer) -> Option<Vec<u8>> {
             } else {
                 player_cover.as_ref()
             },
+            theme,
+            timezone,
         )
         .catch_unwind()
         .await
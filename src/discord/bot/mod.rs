@@ -8,7 +8,8 @@ use std::fmt::{Display, Formatter};
 use std::sync::Arc;
 
 use bytes::Bytes;
-use chrono::{DateTime, Duration, Utc};
+use chrono::{DateTime, Datelike, Duration, NaiveTime, TimeZone, Utc, Weekday};
+use chrono_tz::Tz;
 use futures::future::BoxFuture;
 use poise::serenity_prelude::{
     ChannelId, CreateAllowedMentions, CreateEmbed, CreateMessage, Message, UserId,
@@ -30,9 +31,12 @@ use crate::beatleader::error::Error as BlError;
 use crate::beatleader::oauth::{OAuthToken, OAuthTokenRepository};
 use crate::beatleader::player::PlayerId;
 use crate::beatleader::APP_USER_AGENT;
+use crate::discord::bot::beatleader::clan::ClanWarsSort;
+use crate::embed::{MapTriangleColors, Theme};
+use crate::other::string_utils::{format_decimal, DEFAULT_DECIMAL_PLACES, MAX_DECIMAL_PLACES};
 use crate::storage::player_oauth_token::PlayerOAuthTokenRepository;
 use crate::storage::{StorageKey, StorageValue};
-use crate::Error;
+use crate::{Error, BINARY_FILE_CACHE, METRICS};
 
 pub(crate) use commands::commands;
 
@@ -69,6 +73,18 @@ pub(crate) enum Metric {
     TopStars,
     #[name = "Last pause (days)"]
     LastPause,
+    #[name = "Total pauses (ranked)"]
+    TotalPauses,
+    #[name = "Acc consistency (std deviation)"]
+    AccConsistency,
+    #[name = "Days since last score"]
+    DaysSinceLastScore,
+    #[name = "Country"]
+    Country,
+    #[name = "Headset"]
+    Hmd,
+    #[name = "Platform"]
+    Platform,
 }
 
 impl From<&RequirementMetricValue> for Metric {
@@ -87,6 +103,12 @@ impl From<&RequirementMetricValue> for Metric {
             RequirementMetricValue::MainClan(_) => Metric::MainClan,
             RequirementMetricValue::TopStars(_) => Metric::TopStars,
             RequirementMetricValue::LastPause(_) => Metric::LastPause,
+            RequirementMetricValue::TotalPauses(_) => Metric::TotalPauses,
+            RequirementMetricValue::AccConsistency(_) => Metric::AccConsistency,
+            RequirementMetricValue::DaysSinceLastScore(_) => Metric::DaysSinceLastScore,
+            RequirementMetricValue::Country(_) => Metric::Country,
+            RequirementMetricValue::Hmd(_) => Metric::Hmd,
+            RequirementMetricValue::Platform(_) => Metric::Platform,
         }
     }
 }
@@ -100,12 +122,18 @@ pub(crate) enum Condition {
     BetterThan,
     #[name = "Equal to"]
     EqualTo,
+    #[name = "Not equal to"]
+    NotEqualTo,
     #[name = "Worse than or equal to"]
     WorseThanOrEqualTo,
     #[name = "Worse than"]
     WorseThan,
-    #[name = "Contains (clan metric only)"]
+    #[name = "Contains (clan, country, headset and platform metrics only)"]
     Contains,
+    #[name = "Excludes (clan, country, headset and platform metrics only)"]
+    Excludes,
+    #[name = "Within tolerance % of (decimal metric only)"]
+    Within,
 }
 
 impl std::fmt::Display for Condition {
@@ -117,9 +145,12 @@ impl std::fmt::Display for Condition {
                 Condition::BetterThanOrEqualTo => "better than or equal to",
                 Condition::BetterThan => "better than",
                 Condition::EqualTo => "equal to",
+                Condition::NotEqualTo => "not equal to",
                 Condition::WorseThanOrEqualTo => "worse than or equal to",
                 Condition::WorseThan => "worse than",
                 Condition::Contains => "contains",
+                Condition::Excludes => "excludes",
+                Condition::Within => "within",
             }
         )
     }
@@ -142,6 +173,61 @@ pub(crate) enum RequirementMetricValue {
     MainClan(String),
     TopStars(f64),
     LastPause(u32),
+    TotalPauses(u32),
+    AccConsistency(f64),
+    DaysSinceLastScore(u32),
+    // upper case country codes
+    Country(Vec<String>),
+    // headset names as entered, normalized when compared
+    Hmd(Vec<String>),
+    // normalized platform names
+    Platform(Vec<String>),
+}
+
+// lower case alphanumeric name without the vendor, so that e.g. "Oculus Quest 2",
+// "Meta Quest 2" and "quest2" are the same headset
+fn normalize_hmd(name: &str) -> String {
+    let name = name
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .collect::<String>()
+        .to_lowercase();
+
+    ["oculus", "meta", "valve", "htc", "hp"]
+        .iter()
+        .find_map(|vendor| name.strip_prefix(vendor))
+        .filter(|model| !model.is_empty())
+        .map_or_else(|| name.clone(), |model| model.to_owned())
+}
+
+fn is_same_hmd(required: &str, player_hmd: &str) -> bool {
+    normalize_hmd(required) == normalize_hmd(player_hmd)
+}
+
+// e.g. "Quest" matches every Quest model
+fn is_hmd_family(required: &str, player_hmd: &str) -> bool {
+    normalize_hmd(player_hmd).starts_with(&normalize_hmd(required))
+}
+
+pub(crate) const PLATFORMS: [&str; 3] = ["steam", "oculuspc", "quest"];
+
+// BL reports the Oculus PC store as "oculuspc" and standalone Quest as "oculus",
+// the game version may follow the platform name after a comma
+pub(crate) fn normalize_platform(name: &str) -> String {
+    let name = name
+        .split(',')
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .collect::<String>()
+        .to_lowercase();
+
+    match name.as_str() {
+        "oculuspc" | "oculusrift" | "rift" | "metapc" => "oculuspc".to_owned(),
+        "oculus" | "oculusquest" | "metaquest" | "quest" => "quest".to_owned(),
+        _ => name,
+    }
 }
 
 impl RequirementMetricValue {
@@ -176,6 +262,67 @@ impl RequirementMetricValue {
             }
             Metric::TopStars => Ok(RequirementMetricValue::TopStars(value.parse::<f64>()?)),
             Metric::LastPause => Ok(RequirementMetricValue::LastPause(value.parse::<u32>()?)),
+            Metric::TotalPauses => Ok(RequirementMetricValue::TotalPauses(value.parse::<u32>()?)),
+            Metric::AccConsistency => Ok(RequirementMetricValue::AccConsistency(
+                value.parse::<f64>()?,
+            )),
+            Metric::DaysSinceLastScore => Ok(RequirementMetricValue::DaysSinceLastScore(
+                value.parse::<u32>()?,
+            )),
+            Metric::Country => {
+                let countries = value
+                    .split(',')
+                    .map(|country| country.trim().to_uppercase())
+                    .filter(|country| !country.is_empty())
+                    .collect::<Vec<_>>();
+
+                if countries.is_empty()
+                    || countries.iter().any(|country| {
+                        country.len() != 2 || !country.chars().all(|c| c.is_ascii_alphabetic())
+                    })
+                {
+                    return Err(From::from(
+                        "country should be a 2-letter country code, multiple codes can be separated by commas",
+                    ));
+                }
+
+                Ok(RequirementMetricValue::Country(countries))
+            }
+            Metric::Hmd => {
+                let hmds = value
+                    .split(',')
+                    .map(|hmd| hmd.trim().to_owned())
+                    .filter(|hmd| !normalize_hmd(hmd).is_empty())
+                    .collect::<Vec<_>>();
+
+                if hmds.is_empty() {
+                    return Err(From::from(
+                        "headset name should not be empty, multiple names can be separated by commas",
+                    ));
+                }
+
+                Ok(RequirementMetricValue::Hmd(hmds))
+            }
+            Metric::Platform => {
+                let platforms = value
+                    .split(',')
+                    .map(normalize_platform)
+                    .filter(|platform| !platform.is_empty())
+                    .collect::<Vec<_>>();
+
+                if platforms.is_empty()
+                    || platforms
+                        .iter()
+                        .any(|platform| !PLATFORMS.contains(&platform.as_str()))
+                {
+                    return Err(From::from(format!(
+                        "platform should be one of: {}, multiple platforms can be separated by commas",
+                        PLATFORMS.join(", ")
+                    )));
+                }
+
+                Ok(RequirementMetricValue::Platform(platforms))
+            }
         }
     }
 
@@ -208,6 +355,140 @@ impl RequirementMetricValue {
             }
             RequirementMetricValue::TopStars(_) => false,
             RequirementMetricValue::LastPause(_) => false,
+            RequirementMetricValue::TotalPauses(_) => false,
+            RequirementMetricValue::AccConsistency(_) => false,
+            RequirementMetricValue::DaysSinceLastScore(_) => false,
+            RequirementMetricValue::Country(requirement_countries) => {
+                if let PlayerMetricValue::Country(player_country) = other {
+                    requirement_countries
+                        .iter()
+                        .any(|country| country.eq_ignore_ascii_case(player_country))
+                } else {
+                    false
+                }
+            }
+            RequirementMetricValue::Hmd(requirement_hmds) => {
+                if let PlayerMetricValue::Hmd(player_hmd) = other {
+                    requirement_hmds
+                        .iter()
+                        .any(|hmd| is_hmd_family(hmd, player_hmd))
+                } else {
+                    false
+                }
+            }
+            RequirementMetricValue::Platform(requirement_platforms) => {
+                if let PlayerMetricValue::Platform(player_platform) = other {
+                    requirement_platforms.contains(&normalize_platform(player_platform))
+                } else {
+                    false
+                }
+            }
+        }
+    }
+
+    pub fn is_excluded_by(&self, other: &PlayerMetricValue) -> bool {
+        match self {
+            RequirementMetricValue::TopPp(_) => false,
+            RequirementMetricValue::TopAcc(_) => false,
+            RequirementMetricValue::TotalPp(_) => false,
+            RequirementMetricValue::Rank(_) => false,
+            RequirementMetricValue::CountryRank(_) => false,
+            RequirementMetricValue::MaxStreak(_) => false,
+            RequirementMetricValue::Top1Count(_) => false,
+            RequirementMetricValue::MyReplaysWatched(_) => false,
+            RequirementMetricValue::ReplaysIWatched(_) => false,
+            RequirementMetricValue::MainClan(requirement_clan) => {
+                if let PlayerMetricValue::MainClan(player_clans) = other {
+                    player_clans.first() != Some(requirement_clan)
+                } else {
+                    false
+                }
+            }
+            RequirementMetricValue::Clan(requirement_clans) => {
+                if let PlayerMetricValue::Clan(player_clans) = other {
+                    !requirement_clans
+                        .iter()
+                        .any(|clan| player_clans.contains(clan))
+                } else {
+                    false
+                }
+            }
+            RequirementMetricValue::TopStars(_) => false,
+            RequirementMetricValue::LastPause(_) => false,
+            RequirementMetricValue::TotalPauses(_) => false,
+            RequirementMetricValue::AccConsistency(_) => false,
+            RequirementMetricValue::DaysSinceLastScore(_) => false,
+            RequirementMetricValue::Country(requirement_countries) => {
+                if let PlayerMetricValue::Country(player_country) = other {
+                    !requirement_countries
+                        .iter()
+                        .any(|country| country.eq_ignore_ascii_case(player_country))
+                } else {
+                    false
+                }
+            }
+            RequirementMetricValue::Hmd(requirement_hmds) => {
+                if let PlayerMetricValue::Hmd(player_hmd) = other {
+                    !requirement_hmds
+                        .iter()
+                        .any(|hmd| is_hmd_family(hmd, player_hmd))
+                } else {
+                    false
+                }
+            }
+            RequirementMetricValue::Platform(requirement_platforms) => {
+                if let PlayerMetricValue::Platform(player_platform) = other {
+                    !requirement_platforms.contains(&normalize_platform(player_platform))
+                } else {
+                    false
+                }
+            }
+        }
+    }
+
+    // the player value is within the tolerance when it differs from the required value by at most
+    // tolerance percent of the required value (boundary included), e.g. 5% of 95 means 90.25..=99.75;
+    // only decimal metrics are supported
+    pub fn is_within(&self, other: &PlayerMetricValue, tolerance: f64) -> bool {
+        let (required, actual) = match (self, other) {
+            (RequirementMetricValue::TopPp(v), PlayerMetricValue::TopPp(pv)) => (v, pv),
+            (RequirementMetricValue::TopAcc(v), PlayerMetricValue::TopAcc(pv)) => (v, pv),
+            (RequirementMetricValue::TotalPp(v), PlayerMetricValue::TotalPp(pv)) => (v, pv),
+            (RequirementMetricValue::TopStars(v), PlayerMetricValue::TopStars(pv)) => (v, pv),
+            (
+                RequirementMetricValue::AccConsistency(v),
+                PlayerMetricValue::AccConsistency(Some(pv)),
+            ) => (v, pv),
+            _ => return false,
+        };
+
+        if !tolerance.is_finite() || tolerance < 0.0 || !actual.is_finite() {
+            return false;
+        }
+
+        (actual - required).abs() <= required.abs() * tolerance / 100.0
+    }
+
+    pub fn supports_tolerance(&self) -> bool {
+        matches!(
+            self,
+            RequirementMetricValue::TopPp(_)
+                | RequirementMetricValue::TopAcc(_)
+                | RequirementMetricValue::TotalPp(_)
+                | RequirementMetricValue::TopStars(_)
+                | RequirementMetricValue::AccConsistency(_)
+        )
+    }
+
+    // compares the required days with the whole days since the player's last pause; a player
+    // who never paused is treated as having paused infinitely long ago, so they satisfy any
+    // "better than" requirement and are never equal to the required value
+    fn compare_last_pause(days: u32, last_pause: &Option<DateTime<Utc>>) -> Ordering {
+        match last_pause {
+            Some(last_pause_date) => {
+                (days as i64).cmp(&(Utc::now() - *last_pause_date).num_days().max(0))
+            }
+            None => Ordering::Less,
         }
     }
 
@@ -314,8 +595,50 @@ impl PartialEq<PlayerMetricValue> for RequirementMetricValue {
                 }
             }
             RequirementMetricValue::LastPause(v) => {
-                if let PlayerMetricValue::LastPause(Some(last_pause_date)) = other {
-                    (Utc::now() - Duration::days(*v as i64)) == *last_pause_date
+                if let PlayerMetricValue::LastPause(last_pause) = other {
+                    RequirementMetricValue::compare_last_pause(*v, last_pause) == Ordering::Equal
+                } else {
+                    false
+                }
+            }
+            RequirementMetricValue::TotalPauses(v) => {
+                if let PlayerMetricValue::TotalPauses(player_metric_value) = other {
+                    v == player_metric_value
+                } else {
+                    false
+                }
+            }
+            RequirementMetricValue::AccConsistency(v) => {
+                if let PlayerMetricValue::AccConsistency(player_metric_value) = other {
+                    player_metric_value.is_some_and(|player_metric_value| *v == player_metric_value)
+                } else {
+                    false
+                }
+            }
+            RequirementMetricValue::DaysSinceLastScore(v) => {
+                if let PlayerMetricValue::DaysSinceLastScore(player_metric_value) = other {
+                    v == player_metric_value
+                } else {
+                    false
+                }
+            }
+            RequirementMetricValue::Country(v) => {
+                if let PlayerMetricValue::Country(player_metric_value) = other {
+                    v.len() == 1 && v[0].eq_ignore_ascii_case(player_metric_value)
+                } else {
+                    false
+                }
+            }
+            RequirementMetricValue::Hmd(v) => {
+                if let PlayerMetricValue::Hmd(player_metric_value) = other {
+                    v.len() == 1 && is_same_hmd(&v[0], player_metric_value)
+                } else {
+                    false
+                }
+            }
+            RequirementMetricValue::Platform(v) => {
+                if let PlayerMetricValue::Platform(player_metric_value) = other {
+                    v.len() == 1 && v[0] == normalize_platform(player_metric_value)
                 } else {
                     false
                 }
@@ -419,14 +742,37 @@ impl PartialOrd<PlayerMetricValue> for RequirementMetricValue {
                 }
             }
             RequirementMetricValue::LastPause(v) => {
-                if let PlayerMetricValue::LastPause(Some(last_pause_date)) = other {
-                    RequirementMetricValue::reverse_ordering(
-                        (Utc::now() - Duration::days(*v as i64)).partial_cmp(last_pause_date),
-                    )
+                if let PlayerMetricValue::LastPause(last_pause) = other {
+                    Some(RequirementMetricValue::compare_last_pause(*v, last_pause))
+                } else {
+                    None
+                }
+            }
+            RequirementMetricValue::TotalPauses(v) => {
+                if let PlayerMetricValue::TotalPauses(player_metric_value) = other {
+                    RequirementMetricValue::reverse_ordering(v.partial_cmp(player_metric_value))
+                } else {
+                    None
+                }
+            }
+            RequirementMetricValue::AccConsistency(v) => {
+                // without enough scores to measure, the requirement is never met
+                if let PlayerMetricValue::AccConsistency(Some(player_metric_value)) = other {
+                    RequirementMetricValue::reverse_ordering(v.partial_cmp(player_metric_value))
+                } else {
+                    None
+                }
+            }
+            RequirementMetricValue::DaysSinceLastScore(v) => {
+                if let PlayerMetricValue::DaysSinceLastScore(player_metric_value) = other {
+                    RequirementMetricValue::reverse_ordering(v.partial_cmp(player_metric_value))
                 } else {
-                    Some(Ordering::Less)
+                    None
                 }
             }
+            RequirementMetricValue::Country(_v) => None,
+            RequirementMetricValue::Hmd(_v) => None,
+            RequirementMetricValue::Platform(_v) => None,
         }
     }
 }
@@ -448,6 +794,12 @@ pub enum PlayerMetricValue {
     MainClan(Vec<String>),
     TopStars(f64),
     LastPause(Option<DateTime<Utc>>),
+    TotalPauses(u32),
+    AccConsistency(Option<f64>),
+    DaysSinceLastScore(u32),
+    Country(String),
+    Hmd(String),
+    Platform(String),
 }
 
 impl From<&PlayerMetricValue> for Metric {
@@ -466,10 +818,69 @@ impl From<&PlayerMetricValue> for Metric {
             PlayerMetricValue::MainClan(_) => Metric::MainClan,
             PlayerMetricValue::TopStars(_) => Metric::TopStars,
             PlayerMetricValue::LastPause(_) => Metric::LastPause,
+            PlayerMetricValue::TotalPauses(_) => Metric::TotalPauses,
+            PlayerMetricValue::AccConsistency(_) => Metric::AccConsistency,
+            PlayerMetricValue::DaysSinceLastScore(_) => Metric::DaysSinceLastScore,
+            PlayerMetricValue::Country(_) => Metric::Country,
+            PlayerMetricValue::Hmd(_) => Metric::Hmd,
+            PlayerMetricValue::Platform(_) => Metric::Platform,
+        }
+    }
+}
+
+impl PlayerMetricValue {
+    pub fn to_string_with_decimal_places(&self, decimal_places: u8) -> String {
+        match self {
+            PlayerMetricValue::TopPp(v)
+            | PlayerMetricValue::TopAcc(v)
+            | PlayerMetricValue::TotalPp(v)
+            | PlayerMetricValue::TopStars(v) => format_decimal(*v, decimal_places),
+            PlayerMetricValue::Rank(v)
+            | PlayerMetricValue::CountryRank(v)
+            | PlayerMetricValue::MaxStreak(v)
+            | PlayerMetricValue::MyReplaysWatched(v)
+            | PlayerMetricValue::ReplaysIWatched(v) => v.to_string(),
+            PlayerMetricValue::Top1Count(v) => v.to_string(),
+            PlayerMetricValue::Clan(v) | PlayerMetricValue::MainClan(v) => {
+                if v.is_empty() {
+                    "none".to_owned()
+                } else {
+                    v.join(", ")
+                }
+            }
+            PlayerMetricValue::LastPause(v) => match v {
+                Some(last_pause) => format!("{} days ago", (Utc::now() - *last_pause).num_days()),
+                None => "never".to_owned(),
+            },
+            PlayerMetricValue::TotalPauses(v) => v.to_string(),
+            PlayerMetricValue::AccConsistency(v) => match v {
+                Some(acc_consistency) => format_decimal(*acc_consistency, decimal_places),
+                None => "not enough scores".to_owned(),
+            },
+            PlayerMetricValue::DaysSinceLastScore(v) => format!("{} days", v),
+            PlayerMetricValue::Country(v)
+            | PlayerMetricValue::Hmd(v)
+            | PlayerMetricValue::Platform(v) => {
+                if v.is_empty() {
+                    "unknown".to_owned()
+                } else {
+                    v.clone()
+                }
+            }
         }
     }
 }
 
+impl std::fmt::Display for PlayerMetricValue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            self.to_string_with_decimal_places(DEFAULT_DECIMAL_PLACES)
+        )
+    }
+}
+
 pub(crate) type RoleGroup = String;
 
 type RoleRequirementId = u32;
@@ -479,6 +890,9 @@ type RoleRequirementId = u32;
 pub struct Requirement {
     condition: Condition,
     value: RequirementMetricValue,
+    // percent of the value, used by the Within condition only
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    tolerance: Option<f64>,
 }
 
 impl Requirement {
@@ -489,99 +903,148 @@ impl Requirement {
             return false;
         }
 
+        // acc consistency is not measured without enough scores, so no condition is met
+        if matches!(player_metric, PlayerMetricValue::AccConsistency(None)) {
+            return false;
+        }
+
         match self.condition {
             Condition::WorseThan => self.value.gt(player_metric),
             Condition::WorseThanOrEqualTo => self.value.ge(player_metric),
             Condition::EqualTo => self.value.eq(player_metric),
+            Condition::NotEqualTo => !self.value.eq(player_metric),
             Condition::BetterThan => self.value.lt(player_metric),
             Condition::BetterThanOrEqualTo => self.value.le(player_metric),
             Condition::Contains => self.value.is_contained_by(player_metric),
+            Condition::Excludes => self.value.is_excluded_by(player_metric),
+            Condition::Within => self
+                .tolerance
+                .is_some_and(|tolerance| self.value.is_within(player_metric, tolerance)),
         }
     }
 }
 
 impl std::fmt::Display for Requirement {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let condition = match (&self.condition, self.tolerance) {
+            (Condition::Within, Some(tolerance)) => format!("within {}% of", tolerance),
+            (condition, _) => condition.to_string().to_lowercase(),
+        };
+
         write!(
             f,
             "{}",
             match &self.value {
-                RequirementMetricValue::TopPp(v) => format!(
-                    "**Top PP** *{}* **{}**",
-                    self.condition.to_string().to_lowercase(),
-                    v
-                ),
-                RequirementMetricValue::TopAcc(v) => format!(
-                    "**Top Acc** *{}* **{}**",
-                    self.condition.to_string().to_lowercase(),
-                    v
-                ),
-                RequirementMetricValue::TotalPp(v) => format!(
-                    "**Total PP** *{}* **{}**",
-                    self.condition.to_string().to_lowercase(),
-                    v
-                ),
-                RequirementMetricValue::Rank(v) => format!(
-                    "**Rank** *{}* **{}**",
-                    self.condition.to_string().to_lowercase(),
-                    v
-                ),
-                RequirementMetricValue::CountryRank(v) => format!(
-                    "**Country rank** *{}* **{}**",
-                    self.condition.to_string().to_lowercase(),
-                    v
-                ),
-                RequirementMetricValue::MaxStreak(v) => format!(
-                    "**Max streak** *{}* **{}**",
-                    self.condition.to_string().to_lowercase(),
-                    v
-                ),
-                RequirementMetricValue::Top1Count(v) => format!(
-                    "**#1 count** *{}* **{}**",
-                    self.condition.to_string().to_lowercase(),
-                    v
-                ),
-                RequirementMetricValue::MyReplaysWatched(v) => format!(
-                    "**My replays watched** *{}* **{}**",
-                    self.condition.to_string().to_lowercase(),
-                    v
-                ),
-                RequirementMetricValue::ReplaysIWatched(v) => format!(
-                    "**I watched replays** *{}* **{}**",
-                    self.condition.to_string().to_lowercase(),
-                    v
-                ),
-                RequirementMetricValue::Clan(v) => format!(
-                    "**Clan** *{}* **{}**",
-                    self.condition.to_string().to_lowercase(),
-                    v.join(", "),
-                ),
-                RequirementMetricValue::MainClan(v) => format!(
-                    "**Main Clan** *{}* **{}**",
-                    self.condition.to_string().to_lowercase(),
-                    v,
-                ),
-                RequirementMetricValue::TopStars(v) => format!(
-                    "**Top Stars** *{}* **{}**",
-                    self.condition.to_string().to_lowercase(),
-                    v
-                ),
-                RequirementMetricValue::LastPause(v) => format!(
-                    "**Last pause** *{}* **{} days**",
-                    self.condition.to_string().to_lowercase(),
-                    v
-                ),
+                RequirementMetricValue::TopPp(v) => format!("**Top PP** *{}* **{}**", condition, v),
+                RequirementMetricValue::TopAcc(v) =>
+                    format!("**Top Acc** *{}* **{}**", condition, v),
+                RequirementMetricValue::TotalPp(v) =>
+                    format!("**Total PP** *{}* **{}**", condition, v),
+                RequirementMetricValue::Rank(v) => format!("**Rank** *{}* **{}**", condition, v),
+                RequirementMetricValue::CountryRank(v) =>
+                    format!("**Country rank** *{}* **{}**", condition, v),
+                RequirementMetricValue::MaxStreak(v) =>
+                    format!("**Max streak** *{}* **{}**", condition, v),
+                RequirementMetricValue::Top1Count(v) =>
+                    format!("**#1 count** *{}* **{}**", condition, v),
+                RequirementMetricValue::MyReplaysWatched(v) =>
+                    format!("**My replays watched** *{}* **{}**", condition, v),
+                RequirementMetricValue::ReplaysIWatched(v) =>
+                    format!("**I watched replays** *{}* **{}**", condition, v),
+                RequirementMetricValue::Clan(v) =>
+                    format!("**Clan** *{}* **{}**", condition, v.join(", "),),
+                RequirementMetricValue::MainClan(v) =>
+                    format!("**Main Clan** *{}* **{}**", condition, v,),
+                RequirementMetricValue::TopStars(v) =>
+                    format!("**Top Stars** *{}* **{}**", condition, v),
+                RequirementMetricValue::LastPause(v) =>
+                    format!("**Last pause** *{}* **{} days**", condition, v),
+                RequirementMetricValue::TotalPauses(v) =>
+                    format!("**Total pauses** *{}* **{}**", condition, v),
+                RequirementMetricValue::AccConsistency(v) =>
+                    format!("**Acc consistency** *{}* **{}**", condition, v),
+                RequirementMetricValue::DaysSinceLastScore(v) =>
+                    format!("**Days since last score** *{}* **{} days**", condition, v),
+                RequirementMetricValue::Country(v) =>
+                    format!("**Country** *{}* **{}**", condition, v.join(", ")),
+                RequirementMetricValue::Hmd(v) =>
+                    format!("**Headset** *{}* **{}**", condition, v.join(", ")),
+                RequirementMetricValue::Platform(v) =>
+                    format!("**Platform** *{}* **{}**", condition, v.join(", ")),
+            }
+        )
+    }
+}
+
+#[derive(
+    Serialize, Deserialize, Default, Clone, Copy, Debug, PartialEq, poise::ChoiceParameter,
+)]
+#[serde(rename_all = "camelCase")]
+pub(crate) enum RoleMatchMode {
+    #[default]
+    #[name = "All conditions must be met"]
+    All,
+    #[name = "Any condition must be met"]
+    Any,
+}
+
+impl std::fmt::Display for RoleMatchMode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                RoleMatchMode::All => "all",
+                RoleMatchMode::Any => "any",
             }
         )
     }
 }
 
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub(crate) enum EmbedTheme {
+    #[default]
+    Dark,
+    Light,
+    // clan color is resolved when the theme is set, so it does not need to be fetched per render
+    ClanColor(String),
+}
+
+impl EmbedTheme {
+    pub fn to_theme(&self) -> Theme {
+        match self {
+            EmbedTheme::Dark => Theme::dark(),
+            EmbedTheme::Light => Theme::light(),
+            EmbedTheme::ClanColor(color) => match ril::Rgba::from_hex(color) {
+                Ok(accent) => Theme::dark().with_accent(accent),
+                Err(_) => Theme::dark(),
+            },
+        }
+    }
+}
+
+impl std::fmt::Display for EmbedTheme {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EmbedTheme::Dark => write!(f, "dark"),
+            EmbedTheme::Light => write!(f, "light"),
+            EmbedTheme::ClanColor(color) => write!(f, "clan color ({})", color),
+        }
+    }
+}
+
 #[derive(Default, Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct RoleSettings {
     role_id: RoleId,
     conditions: HashMap<RoleRequirementId, Requirement>,
     weight: u32,
+    #[serde(default)]
+    match_mode: RoleMatchMode,
+    // prerequisite role, the role is not fulfilled without it
+    #[serde(default)]
+    required_role: Option<RoleId>,
 }
 
 impl RoleSettings {
@@ -590,9 +1053,35 @@ impl RoleSettings {
             role_id,
             conditions: HashMap::new(),
             weight,
+            match_mode: RoleMatchMode::All,
+            required_role: None,
         }
     }
 
+    pub(crate) fn get_required_role(&self) -> Option<RoleId> {
+        self.required_role
+    }
+
+    pub(crate) fn set_required_role(&mut self, required_role: Option<RoleId>) {
+        self.required_role = required_role;
+    }
+
+    pub(crate) fn get_match_mode(&self) -> RoleMatchMode {
+        self.match_mode
+    }
+
+    pub(crate) fn set_match_mode(&mut self, match_mode: RoleMatchMode) {
+        self.match_mode = match_mode;
+    }
+
+    pub fn get_role_id(&self) -> RoleId {
+        self.role_id
+    }
+
+    pub fn set_role_id(&mut self, role_id: RoleId) {
+        self.role_id = role_id;
+    }
+
     fn get_next_condition_id(&self) -> RoleRequirementId {
         self.conditions
             .keys()
@@ -601,7 +1090,20 @@ impl RoleSettings {
     }
 
     pub(crate) fn add_requirement(&mut self, condition: Condition, value: RequirementMetricValue) {
-        let rc = Requirement { condition, value };
+        self.add_requirement_with_tolerance(condition, value, None);
+    }
+
+    pub(crate) fn add_requirement_with_tolerance(
+        &mut self,
+        condition: Condition,
+        value: RequirementMetricValue,
+        tolerance: Option<f64>,
+    ) {
+        let rc = Requirement {
+            condition,
+            value,
+            tolerance,
+        };
 
         self.conditions
             .entry(self.get_next_condition_id())
@@ -609,11 +1111,102 @@ impl RoleSettings {
     }
 
     pub fn is_fulfilled_for(&self, player: &Player) -> bool {
-        self.conditions.iter().all(|(_role_id, role_requirement)| {
-            role_requirement.is_fulfilled_for(
-                &player.get_metric_with_value(Metric::from(&role_requirement.value)),
-            )
-        })
+        self.check(player).fulfilled
+    }
+
+    // evaluates every requirement, so the result can be explained to the user
+    pub fn check(&self, player: &Player) -> RoleFulfillment {
+        let mut conditions = self.conditions.iter().collect::<Vec<_>>();
+        conditions.sort_unstable_by_key(|(condition_id, _)| **condition_id);
+
+        let requirements = conditions
+            .into_iter()
+            .map(|(_, requirement)| {
+                let player_value = player.get_metric_with_value(Metric::from(&requirement.value));
+
+                RequirementFulfillment {
+                    requirement: requirement.clone(),
+                    fulfilled: requirement.is_fulfilled_for(&player_value),
+                    player_value,
+                    decimal_places: DEFAULT_DECIMAL_PLACES,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        RoleFulfillment {
+            role_id: self.role_id,
+            weight: self.weight,
+            match_mode: self.match_mode,
+            // resolved by the guild settings, as it depends on other roles
+            required_role: self
+                .required_role
+                .map(|required_role| (required_role, true)),
+            fulfilled: match self.match_mode {
+                RoleMatchMode::All => requirements.iter().all(|r| r.fulfilled),
+                RoleMatchMode::Any => requirements.iter().any(|r| r.fulfilled),
+            },
+            requirements,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RequirementFulfillment {
+    pub requirement: Requirement,
+    pub player_value: PlayerMetricValue,
+    pub fulfilled: bool,
+    // of the player value, set by the guild settings
+    pub decimal_places: u8,
+}
+
+impl std::fmt::Display for RequirementFulfillment {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {} (player: {})",
+            if self.fulfilled { "✅" } else { "❌" },
+            self.requirement,
+            self.player_value
+                .to_string_with_decimal_places(self.decimal_places)
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RoleFulfillment {
+    pub role_id: RoleId,
+    pub weight: u32,
+    pub match_mode: RoleMatchMode,
+    // prerequisite role and whether it is met
+    pub required_role: Option<(RoleId, bool)>,
+    pub requirements: Vec<RequirementFulfillment>,
+    pub fulfilled: bool,
+}
+
+impl std::fmt::Display for RoleFulfillment {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "* {} <@&{}> (*weight: {}, match: {}*)\n{}",
+            if self.fulfilled { "✅" } else { "❌" },
+            self.role_id,
+            self.weight,
+            self.match_mode,
+            self.required_role
+                .iter()
+                .map(|(required_role, met)| format!(
+                    " * {} Requires <@&{}>",
+                    if *met { "✅" } else { "❌" },
+                    required_role
+                ))
+                .chain(
+                    self.requirements
+                        .iter()
+                        .map(|requirement| format!(" * {}", requirement))
+                )
+                .collect::<Vec<_>>()
+                .join("\n")
+        )
     }
 }
 
@@ -628,9 +1221,13 @@ impl std::fmt::Display for RoleSettings {
 
         write!(
             f,
-            "* <@&{}> (*weight: {}*)\n{}",
+            "* <@&{}> (*weight: {}, match: {}{}*)\n{}",
             self.role_id,
             self.weight,
+            self.match_mode,
+            self.required_role
+                .map(|required_role| format!(", requires: <@&{}>", required_role))
+                .unwrap_or_default(),
             cond_vec
                 .iter()
                 .map(|(_role_cond_id, role_cond)| format!(" * {}", role_cond))
@@ -647,11 +1244,14 @@ pub struct UserRoleChanges {
     pub name: String,
     pub to_add: Vec<RoleId>,
     pub to_remove: Vec<RoleId>,
+    pub dry_run: bool,
+    // roles that could not be added or removed
+    pub failed: Vec<RoleId>,
 }
 
 impl UserRoleChanges {
     pub async fn apply(
-        &self,
+        &mut self,
         http: &Arc<poise::serenity_prelude::Http>,
     ) -> Result<&UserRoleChanges, Error> {
         info!("Updating user {} ({}) roles...", self.user_id, self.name);
@@ -664,6 +1264,14 @@ impl UserRoleChanges {
             return Ok(self);
         }
 
+        if self.dry_run {
+            info!(
+                "Dry run, roles {:?} would be added to and roles {:?} removed from user {} ({}).",
+                self.to_add, self.to_remove, self.user_id, self.name
+            );
+            return Ok(self);
+        }
+
         info!(
             "{} role(s) to add to user {} ({})",
             self.to_add.len(),
@@ -687,6 +1295,7 @@ impl UserRoleChanges {
                     "Can not add role {} to user {} ({}): {}",
                     role_id, self.user_id, self.name, e
                 );
+                self.failed.push(*role_id);
                 continue;
             }
 
@@ -719,6 +1328,7 @@ impl UserRoleChanges {
                     "Can not remove role {} from user {} ({}): {}",
                     role_id, self.user_id, self.name, e
                 );
+                self.failed.push(*role_id);
                 continue;
             }
 
@@ -728,13 +1338,22 @@ impl UserRoleChanges {
             );
         }
 
+        // failed roles were not changed, so they are neither logged nor stored as changes
+        let failed = self.failed.clone();
+        self.skip_roles(&failed);
+
         Ok(self)
     }
 
     pub fn is_changed(&self) -> bool {
         !self.to_add.is_empty() || !self.to_remove.is_empty()
     }
-}
+
+    pub fn skip_roles(&mut self, role_ids: &[RoleId]) {
+        self.to_add.retain(|role_id| !role_ids.contains(role_id));
+        self.to_remove.retain(|role_id| !role_ids.contains(role_id));
+    }
+}
 impl std::fmt::Display for UserRoleChanges {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         let to_add_list = if !self.to_add.is_empty() {
@@ -761,17 +1380,35 @@ impl std::fmt::Display for UserRoleChanges {
             None
         };
 
+        // a dry run only reports the changes that would have been made
+        let (header, added_label, removed_label) = if self.dry_run {
+            (
+                format!(
+                    "*Dry run:* the roles of user <@{}> would be updated",
+                    self.user_id
+                ),
+                "Roles to add",
+                "Roles to remove",
+            )
+        } else {
+            (
+                format!("The roles of user <@{}> have been updated", self.user_id),
+                "Added roles",
+                "Removed roles",
+            )
+        };
+
         write!(
             f,
-            "The roles of user <@{}> have been updated{}{}",
-            self.user_id,
-            if to_add_list.is_some() {
-                format!("\n**Added roles:** {}", to_add_list.unwrap())
+            "{}{}{}",
+            header,
+            if let Some(to_add_list) = to_add_list {
+                format!("\n**{}:** {}", added_label, to_add_list)
             } else {
                 "".to_owned()
             },
-            if to_remove_list.is_some() {
-                format!("\n**Removed roles:** {}", to_remove_list.unwrap())
+            if let Some(to_remove_list) = to_remove_list {
+                format!("\n**{}:** {}", removed_label, to_remove_list)
             } else {
                 "".to_owned()
             },
@@ -779,6 +1416,74 @@ impl std::fmt::Display for UserRoleChanges {
     }
 }
 
+// upload limit of servers without boosts
+pub(crate) const DISCORD_ATTACHMENT_MAX_SIZE: usize = 10 * 1024 * 1024;
+
+#[derive(Default, Debug, Clone)]
+pub struct RoleChangesDigest {
+    dry_run: bool,
+    users_count: usize,
+    added: Vec<(RoleId, usize)>,
+    removed: Vec<(RoleId, usize)>,
+}
+
+impl RoleChangesDigest {
+    pub fn add(&mut self, role_changes: &UserRoleChanges) {
+        if !role_changes.is_changed() {
+            return;
+        }
+
+        self.dry_run = role_changes.dry_run;
+        self.users_count += 1;
+
+        Self::count(&mut self.added, &role_changes.to_add);
+        Self::count(&mut self.removed, &role_changes.to_remove);
+    }
+
+    fn count(counters: &mut Vec<(RoleId, usize)>, role_ids: &[RoleId]) {
+        for role_id in role_ids {
+            match counters.iter_mut().find(|(id, _)| id == role_id) {
+                Some((_, count)) => *count += 1,
+                None => counters.push((*role_id, 1)),
+            }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.users_count == 0
+    }
+
+    // splits the digest into messages that fit within Discord's message length limit
+    pub fn to_messages(&self) -> Vec<String> {
+        if self.is_empty() {
+            return vec![];
+        }
+
+        let header = format!(
+            "{}**Role changes summary:** {} user(s) {}",
+            if self.dry_run { "*Dry run:* " } else { "" },
+            self.users_count,
+            if self.dry_run {
+                "would be updated"
+            } else {
+                "updated"
+            }
+        );
+
+        let lines = self
+            .added
+            .iter()
+            .map(|(role_id, count)| format!("+<@&{}> ×{}", role_id, count))
+            .chain(
+                self.removed
+                    .iter()
+                    .map(|(role_id, count)| format!("-<@&{}> ×{}", role_id, count)),
+            );
+
+        split_msg_in_parts(std::iter::once(header).chain(lines))
+    }
+}
+
 #[derive(Default, Debug, Clone)]
 pub struct UserRoleStatus {
     should_have: Vec<RoleId>,
@@ -786,6 +1491,15 @@ pub struct UserRoleStatus {
 }
 
 impl UserRoleStatus {
+    fn unique<'a>(role_ids: impl Iterator<Item = &'a RoleId>) -> Vec<RoleId> {
+        let mut seen = HashSet::new();
+
+        role_ids
+            .filter(|role_id| seen.insert(**role_id))
+            .copied()
+            .collect()
+    }
+
     pub fn get_role_changes(
         &self,
         guild_id: GuildId,
@@ -796,44 +1510,65 @@ impl UserRoleStatus {
             guild_id,
             user_id: player.user_id,
             name: player.name.clone(),
-            to_add: self
-                .should_have
-                .iter()
-                .filter_map(|role_id| {
-                    if !current_roles.contains(role_id) {
-                        Some(role_id.to_owned())
-                    } else {
-                        None
-                    }
-                })
-                .collect::<HashSet<_>>()
-                .into_iter()
-                .collect(),
-            to_remove: current_roles
-                .iter()
-                .filter_map(|role_id| {
-                    if self.should_not_have.contains(role_id) {
-                        Some(role_id.to_owned())
-                    } else {
-                        None
-                    }
-                })
-                .collect::<HashSet<_>>()
-                .into_iter()
-                .collect(),
+            to_add: Self::unique(
+                self.should_have
+                    .iter()
+                    .filter(|role_id| !current_roles.contains(role_id)),
+            ),
+            to_remove: Self::unique(
+                self.should_not_have
+                    .iter()
+                    .filter(|role_id| current_roles.contains(role_id)),
+            ),
+            dry_run: false,
+            failed: Vec::new(),
         }
     }
 }
 
+const GUILD_SETTINGS_SCHEMA_VERSION: u32 = 2;
+pub(crate) const DEFAULT_AUTO_ROLES_COOLDOWN_HOURS: u32 = 6;
+pub(crate) const DEFAULT_COMMAND_COOLDOWN_SECS: u32 = 5;
+
 #[derive(Serialize, Default, Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 #[serde(default)]
 pub struct GuildSettings {
+    schema_version: u32,
     guild_id: GuildId,
     bot_channel_id: Option<ChannelId>,
+    // role changes and errors are posted to the bot log channel if their channels are not set
+    role_changes_channel_id: Option<ChannelId>,
+    errors_channel_id: Option<ChannelId>,
     requires_verified_profile: bool,
+    // users are sent the verification steps on join or on failed link, if the guild requires verified profiles
+    verification_reminder: bool,
+    auto_roles_dry_run: bool,
+    auto_roles_cooldown_hours: u32,
     role_groups: HashMap<RoleGroup, HashMap<RoleId, RoleSettings>>,
+    group_order: Vec<RoleGroup>,
     clan_settings: Option<ClanSettings>,
+    embed_theme: EmbedTheme,
+    map_triangle_colors: MapTriangleColors,
+    // decimal places of pp, acc and stars in embeds and command replies, 2 if not set
+    decimal_places: Option<u8>,
+    // IANA timezone name, UTC if not set
+    timezone: Option<String>,
+    // auto roles are not granted to younger BL accounts, 0 disables the check
+    min_account_age_days: u32,
+    // /bl-profile replies are ephemeral by default, users can still override it per command
+    profile_private: bool,
+    // granted to verified players when they link, independently of the auto roles, never removed
+    welcome_roles: Vec<RoleId>,
+    // cooldown in seconds per command name, overrides the command's default cooldown
+    command_cooldowns: HashMap<String, u32>,
+    // base64 encoded clan wars playlist covers, the default cover is used if not set
+    playlist_cover: Option<String>,
+    playlist_type_covers: HashMap<ClanWarsSort, String>,
+    // FC'd maps are skipped in clan wars playlists unless the FC status is requested explicitly
+    playlist_skip_fcs: bool,
+    // commander's orders below it are not injected into clan wars playlists
+    commander_order_min_stars: Option<f64>,
 }
 
 impl StorageKey for GuildId {}
@@ -841,12 +1576,33 @@ impl StorageValue<GuildId> for GuildSettings {
     fn get_key(&self) -> GuildId {
         self.guild_id
     }
+
+    fn migrate(&mut self) -> bool {
+        if self.schema_version >= GUILD_SETTINGS_SCHEMA_VERSION {
+            return false;
+        }
+
+        // v0 -> v1: role group order is left unset, so the alphabetical order used so far
+        // still applies, also to the groups added later
+
+        // v1 -> v2: auto roles cooldown, existing guilds keep updating roles without it,
+        // only new guilds get the default one
+        if self.schema_version < 2 {
+            self.auto_roles_cooldown_hours = 0;
+        }
+
+        self.schema_version = GUILD_SETTINGS_SCHEMA_VERSION;
+
+        true
+    }
 }
 
 impl GuildSettings {
     pub fn new(guild_id: GuildId) -> Self {
         Self {
+            schema_version: GUILD_SETTINGS_SCHEMA_VERSION,
             guild_id,
+            auto_roles_cooldown_hours: DEFAULT_AUTO_ROLES_COOLDOWN_HOURS,
             ..Default::default()
         }
     }
@@ -863,6 +1619,22 @@ impl GuildSettings {
         self.bot_channel_id = channel_id;
     }
 
+    pub fn get_role_changes_channel(&self) -> Option<ChannelId> {
+        self.role_changes_channel_id.or(self.bot_channel_id)
+    }
+
+    pub fn set_role_changes_channel(&mut self, channel_id: Option<ChannelId>) {
+        self.role_changes_channel_id = channel_id;
+    }
+
+    pub fn get_errors_channel(&self) -> Option<ChannelId> {
+        self.errors_channel_id.or(self.bot_channel_id)
+    }
+
+    pub fn set_errors_channel(&mut self, channel_id: Option<ChannelId>) {
+        self.errors_channel_id = channel_id;
+    }
+
     pub fn set_clan_wars_maps_channel(&mut self, channel_id: Option<ChannelId>) {
         if let Some(ref mut clan_settings) = self.clan_settings {
             clan_settings.set_clan_wars_maps_channel(channel_id);
@@ -875,6 +1647,18 @@ impl GuildSettings {
         }
     }
 
+    pub fn set_clan_wars_maps_count(&mut self, count: Option<u16>) {
+        if let Some(ref mut clan_settings) = self.clan_settings {
+            clan_settings.set_clan_wars_maps_count(count);
+        }
+    }
+
+    pub fn set_clan_wars_star_range(&mut self, min_stars: Option<f64>, max_stars: Option<f64>) {
+        if let Some(ref mut clan_settings) = self.clan_settings {
+            clan_settings.set_clan_wars_star_range(min_stars, max_stars);
+        }
+    }
+
     pub fn set_clan_wars_posted_at(&mut self, posted_at: DateTime<Utc>) {
         if let Some(ref mut clan_settings) = self.clan_settings {
             clan_settings.set_clan_wars_posted_at(posted_at);
@@ -899,6 +1683,18 @@ impl GuildSettings {
         }
     }
 
+    pub fn set_clan_recap_schedule(&mut self, schedule: Option<ClanRecapSchedule>) {
+        if let Some(ref mut clan_settings) = self.clan_settings {
+            clan_settings.set_clan_recap_schedule(schedule);
+        }
+    }
+
+    pub fn set_clan_recap_posted_at(&mut self, posted_at: Option<DateTime<Utc>>) {
+        if let Some(ref mut clan_settings) = self.clan_settings {
+            clan_settings.set_clan_recap_posted_at(posted_at);
+        }
+    }
+
     pub fn add_clan_wars_soldier(&mut self, user_id: UserId) {
         if let Some(ref mut clan_settings) = self.clan_settings {
             clan_settings.add_clan_wars_soldier(user_id);
@@ -917,6 +1713,12 @@ impl GuildSettings {
         }
     }
 
+    pub fn set_clan_wars_ping_role(&mut self, role_id: Option<RoleId>) {
+        if let Some(ref mut clan_settings) = self.clan_settings {
+            clan_settings.set_clan_wars_ping_role(role_id);
+        }
+    }
+
     pub fn manages_roles(&self) -> bool {
         !self.role_groups.is_empty()
             || (self.clan_settings.is_some()
@@ -941,6 +1743,192 @@ impl GuildSettings {
         self.requires_verified_profile = requires_verified_profile;
     }
 
+    pub fn is_verification_reminder_enabled(&self) -> bool {
+        self.requires_verified_profile && self.verification_reminder
+    }
+
+    pub fn set_verification_reminder(&mut self, verification_reminder: bool) {
+        self.verification_reminder = verification_reminder;
+    }
+
+    pub fn is_auto_roles_dry_run(&self) -> bool {
+        self.auto_roles_dry_run
+    }
+
+    pub fn set_auto_roles_dry_run(&mut self, auto_roles_dry_run: bool) {
+        self.auto_roles_dry_run = auto_roles_dry_run;
+    }
+
+    pub fn is_profile_private(&self) -> bool {
+        self.profile_private
+    }
+
+    pub fn set_profile_private(&mut self, profile_private: bool) {
+        self.profile_private = profile_private;
+    }
+
+    pub fn get_welcome_roles(&self) -> &[RoleId] {
+        &self.welcome_roles
+    }
+
+    pub fn set_welcome_roles(&mut self, welcome_roles: Vec<RoleId>) {
+        self.welcome_roles = welcome_roles;
+    }
+
+    pub(crate) fn get_welcome_role_changes(
+        &self,
+        player: &Player,
+        current_roles: &[RoleId],
+    ) -> UserRoleChanges {
+        UserRoleChanges {
+            guild_id: self.guild_id,
+            user_id: player.user_id,
+            name: player.name.clone(),
+            to_add: if player.is_verified {
+                UserRoleStatus::unique(
+                    self.welcome_roles
+                        .iter()
+                        .filter(|role_id| !current_roles.contains(role_id)),
+                )
+            } else {
+                vec![]
+            },
+            to_remove: vec![],
+            dry_run: self.auto_roles_dry_run,
+            failed: vec![],
+        }
+    }
+
+    pub fn get_auto_roles_cooldown(&self) -> Duration {
+        Duration::hours(self.auto_roles_cooldown_hours as i64)
+    }
+
+    pub fn set_auto_roles_cooldown(&mut self, hours: u32) {
+        self.auto_roles_cooldown_hours = hours;
+    }
+
+    pub fn get_command_cooldown(&self, command_name: &str) -> TimeDuration {
+        TimeDuration::from_secs(
+            *self
+                .command_cooldowns
+                .get(command_name)
+                .unwrap_or(&DEFAULT_COMMAND_COOLDOWN_SECS) as u64,
+        )
+    }
+
+    // None restores the command's default cooldown
+    pub fn set_command_cooldown(&mut self, command_name: &str, secs: Option<u32>) {
+        match secs {
+            Some(secs) => {
+                self.command_cooldowns.insert(command_name.to_owned(), secs);
+            }
+            None => {
+                self.command_cooldowns.remove(command_name);
+            }
+        }
+    }
+
+    // the cover of the playlist type takes precedence over the guild-wide one
+    pub fn get_playlist_cover(&self, playlist_type: &ClanWarsSort) -> Option<String> {
+        self.playlist_type_covers
+            .get(playlist_type)
+            .or(self.playlist_cover.as_ref())
+            .cloned()
+    }
+
+    // None playlist type sets the guild-wide cover, None cover restores the default one
+    pub fn set_playlist_cover(
+        &mut self,
+        playlist_type: Option<ClanWarsSort>,
+        cover: Option<String>,
+    ) {
+        match (playlist_type, cover) {
+            (None, cover) => self.playlist_cover = cover,
+            (Some(playlist_type), Some(cover)) => {
+                self.playlist_type_covers.insert(playlist_type, cover);
+            }
+            (Some(playlist_type), None) => {
+                self.playlist_type_covers.remove(&playlist_type);
+            }
+        }
+    }
+
+    pub fn is_playlist_skip_fcs(&self) -> bool {
+        self.playlist_skip_fcs
+    }
+
+    pub fn set_playlist_skip_fcs(&mut self, playlist_skip_fcs: bool) {
+        self.playlist_skip_fcs = playlist_skip_fcs;
+    }
+
+    pub fn get_commander_order_min_stars(&self) -> Option<f64> {
+        self.commander_order_min_stars
+    }
+
+    pub fn set_commander_order_min_stars(&mut self, min_stars: Option<f64>) {
+        self.commander_order_min_stars = min_stars;
+    }
+
+    pub fn get_min_account_age_days(&self) -> u32 {
+        self.min_account_age_days
+    }
+
+    pub fn set_min_account_age_days(&mut self, days: u32) {
+        self.min_account_age_days = days;
+    }
+
+    // accounts of unknown age can not be verified, so they are treated as too young
+    pub(crate) fn is_account_old_enough(&self, player: &Player) -> bool {
+        self.min_account_age_days == 0
+            || player
+                .account_age_days()
+                .is_some_and(|age| age >= self.min_account_age_days)
+    }
+
+    pub fn get_embed_theme(&self) -> &EmbedTheme {
+        &self.embed_theme
+    }
+
+    pub fn set_embed_theme(&mut self, embed_theme: EmbedTheme) {
+        self.embed_theme = embed_theme;
+    }
+
+    pub fn get_map_triangle_colors(&self) -> MapTriangleColors {
+        self.map_triangle_colors
+    }
+
+    pub fn set_map_triangle_colors(&mut self, map_triangle_colors: MapTriangleColors) {
+        self.map_triangle_colors = map_triangle_colors;
+    }
+
+    pub fn get_decimal_places(&self) -> u8 {
+        self.decimal_places
+            .unwrap_or(DEFAULT_DECIMAL_PLACES)
+            .min(MAX_DECIMAL_PLACES)
+    }
+
+    pub fn set_decimal_places(&mut self, decimal_places: Option<u8>) {
+        self.decimal_places = decimal_places.map(|places| places.min(MAX_DECIMAL_PLACES));
+    }
+
+    pub fn get_theme(&self) -> Theme {
+        self.embed_theme
+            .to_theme()
+            .with_triangle_colors(self.map_triangle_colors)
+            .with_decimal_places(self.get_decimal_places())
+    }
+
+    pub fn get_timezone(&self) -> Tz {
+        self.timezone
+            .as_ref()
+            .and_then(|timezone| timezone.parse::<Tz>().ok())
+            .unwrap_or(Tz::UTC)
+    }
+
+    pub fn set_timezone(&mut self, timezone: Option<Tz>) {
+        self.timezone = timezone.map(|timezone| timezone.name().to_owned());
+    }
+
     pub fn add(&mut self, role_group: RoleGroup, role_settings: RoleSettings) -> &mut Self {
         let role_settings_clone = role_settings.clone();
         self.role_groups
@@ -953,6 +1941,24 @@ impl GuildSettings {
         self
     }
 
+    pub fn get_auto_roles(&self) -> Vec<(RoleGroup, RoleSettings)> {
+        self.get_ordered_groups()
+            .into_iter()
+            .flat_map(|rg| {
+                let mut roles = self
+                    .role_groups
+                    .get(&rg)
+                    .map(|rs| rs.values().cloned().collect::<Vec<_>>())
+                    .unwrap_or_default();
+                roles.sort_unstable_by(|a, b| {
+                    b.weight.cmp(&a.weight).then(b.role_id.cmp(&a.role_id))
+                });
+
+                roles.into_iter().map(move |rs| (rg.clone(), rs))
+            })
+            .collect()
+    }
+
     pub fn merge(&mut self, role_group: RoleGroup, role_settings: RoleSettings) -> &mut Self {
         let role_settings_clone = role_settings.clone();
         self.role_groups
@@ -962,16 +1968,53 @@ impl GuildSettings {
             .and_modify(|rs| {
                 rs.weight = role_settings.weight;
 
-                role_settings
-                    .conditions
-                    .values()
-                    .for_each(|rc| rs.add_requirement(rc.condition.clone(), rc.value.clone()));
+                role_settings.conditions.values().for_each(|rc| {
+                    rs.add_requirement_with_tolerance(
+                        rc.condition.clone(),
+                        rc.value.clone(),
+                        rc.tolerance,
+                    )
+                });
             })
             .or_insert(role_settings_clone);
 
         self
     }
 
+    pub(crate) fn set_role_required_role(
+        &mut self,
+        role_group: &RoleGroup,
+        role_id: RoleId,
+        required_role: Option<RoleId>,
+    ) -> &mut Self {
+        if let Some(rs) = self
+            .role_groups
+            .get_mut(role_group)
+            .and_then(|role_settings| role_settings.get_mut(&role_id))
+        {
+            rs.set_required_role(required_role);
+        }
+
+        self
+    }
+
+    pub(crate) fn set_role_match_mode(
+        &mut self,
+        role_group: &RoleGroup,
+        role_id: RoleId,
+        match_mode: RoleMatchMode,
+    ) -> &mut Self {
+        if let Some(rs) = self
+            .role_groups
+            .get_mut(role_group)
+            .and_then(|role_settings| role_settings.get_mut(&role_id))
+        {
+            rs.set_match_mode(match_mode);
+        }
+
+        self
+    }
+
     pub fn remove(&mut self, role_group: RoleGroup, role_id: RoleId) {
         let role_group_clone = role_group.clone();
 
@@ -1006,10 +2049,107 @@ impl GuildSettings {
         self.all_roles().iter().any(|&&r| r == role_id)
     }
 
+    /// Moves the settings to another guild, returns ids of the roles that could not be mapped
+    /// (they are removed from the settings)
+    pub fn rehome(
+        &mut self,
+        guild_id: GuildId,
+        map_role: impl Fn(RoleId) -> Option<RoleId>,
+    ) -> Vec<RoleId> {
+        let mut unmapped = Vec::new();
+
+        self.guild_id = guild_id;
+        // channels are not shared between guilds
+        self.bot_channel_id = None;
+        self.role_changes_channel_id = None;
+        self.errors_channel_id = None;
+
+        for roles in self.role_groups.values_mut() {
+            *roles = std::mem::take(roles)
+                .into_values()
+                .filter_map(|mut role_settings| match map_role(role_settings.role_id) {
+                    Some(role_id) => {
+                        role_settings.role_id = role_id;
+                        // a prerequisite left pointing to the old guild would never be met
+                        if let Some(required_role) = role_settings.required_role {
+                            role_settings.required_role = map_role(required_role);
+                            if role_settings.required_role.is_none() {
+                                unmapped.push(required_role);
+                            }
+                        }
+                        Some((role_id, role_settings))
+                    }
+                    None => {
+                        unmapped.push(role_settings.role_id);
+                        None
+                    }
+                })
+                .collect();
+        }
+        self.role_groups.retain(|_, roles| !roles.is_empty());
+
+        self.welcome_roles = std::mem::take(&mut self.welcome_roles)
+            .into_iter()
+            .filter_map(|role_id| {
+                let mapped = map_role(role_id);
+                if mapped.is_none() {
+                    unmapped.push(role_id);
+                }
+                mapped
+            })
+            .collect();
+
+        if let Some(ref mut clan_settings) = self.clan_settings {
+            unmapped.extend(clan_settings.rehome(&map_role));
+        }
+
+        unmapped.sort_unstable();
+        unmapped.dedup();
+
+        unmapped
+    }
+
     pub fn get_groups(&self) -> Vec<String> {
-        self.role_groups.keys().cloned().collect()
+        self.get_ordered_groups()
+    }
+
+    // groups not present in the order list are appended alphabetically
+    pub fn get_ordered_groups(&self) -> Vec<RoleGroup> {
+        let mut groups = Vec::with_capacity(self.role_groups.len());
+
+        for role_group in self.group_order.iter() {
+            if self.role_groups.contains_key(role_group) && !groups.contains(role_group) {
+                groups.push(role_group.clone());
+            }
+        }
+
+        let mut remaining = self
+            .role_groups
+            .keys()
+            .filter(|role_group| !groups.contains(role_group))
+            .cloned()
+            .collect::<Vec<RoleGroup>>();
+        remaining.sort_unstable();
+
+        groups.append(&mut remaining);
+
+        groups
+    }
+
+    pub fn set_group_order(&mut self, group_order: Vec<RoleGroup>) {
+        let mut order = Vec::with_capacity(group_order.len());
+
+        for role_group in group_order {
+            if !order.contains(&role_group) {
+                order.push(role_group);
+            }
+        }
+
+        self.group_order = order;
     }
 
+    /// Within each group only the fulfilled role with the highest weight is granted. Roles with
+    /// equal weight are ordered by recency, so the most recently created role (highest id) wins.
     pub(crate) fn get_role_updates(
         &self,
         player: &Player,
@@ -1024,20 +2164,25 @@ impl GuildSettings {
 
         let mut ru = UserRoleStatus::default();
 
+        let fulfillment = self.get_roles_fulfillment(player, current_roles);
+
         let mut auto_role_changes = self
-            .role_groups
-            .values()
-            .map(|roles| {
+            .get_ordered_groups()
+            .iter()
+            .filter_map(|role_group| Some((role_group, self.role_groups.get(role_group)?)))
+            .map(|(role_group, roles)| {
                 let mut roles_fulfillment = roles
                     .iter()
                     .map(|(role_id, role_settings)| RoleFulfillmentStatus {
                         role_id: *role_id,
-                        fulfilled: role_settings.is_fulfilled_for(player),
+                        fulfilled: fulfillment[&(role_group.clone(), *role_id)],
                         weight: role_settings.weight,
                     })
                     .collect::<Vec<RoleFulfillmentStatus>>();
 
-                roles_fulfillment.sort_unstable_by(|a, b| b.weight.cmp(&a.weight));
+                roles_fulfillment.sort_unstable_by(|a, b| {
+                    b.weight.cmp(&a.weight).then(b.role_id.cmp(&a.role_id))
+                });
 
                 let role_updates = &mut UserRoleStatus::default();
 
@@ -1062,6 +2207,15 @@ impl GuildSettings {
             })
             .get_role_changes(self.guild_id, player, current_roles);
 
+        // welcome roles may be configured as auto roles too, they are kept even if not fulfilled
+        auto_role_changes
+            .to_remove
+            .retain(|role_id| !self.welcome_roles.contains(role_id));
+
+        if !self.is_account_old_enough(player) {
+            auto_role_changes.to_add.clear();
+        }
+
         let soldier_role_changes = self.get_soldier_role_changes(player, current_roles);
         if !soldier_role_changes.to_add.is_empty() || !soldier_role_changes.to_remove.is_empty() {
             auto_role_changes.to_add.extend(soldier_role_changes.to_add);
@@ -1070,25 +2224,174 @@ impl GuildSettings {
                 .extend(soldier_role_changes.to_remove);
         }
 
+        auto_role_changes.dry_run = self.auto_roles_dry_run;
+
         auto_role_changes
     }
 
-    pub(crate) fn get_soldier_role_changes(
+    // roles of every group, heaviest first, as they are evaluated by get_role_updates
+    // a role with an unmet prerequisite is not fulfilled, which cascades to the roles requiring it;
+    // a prerequisite being an auto role must be granted after the update, i.e. win its group,
+    // any other role must be already granted
+    fn get_roles_fulfillment(
         &self,
         player: &Player,
         current_roles: &[RoleId],
-    ) -> UserRoleChanges {
-        if self.clan_settings.is_none()
-            || self.clan_settings.as_ref().unwrap().soldier_role.is_none()
-        {
-            return UserRoleChanges {
-                guild_id: self.guild_id,
-                user_id: player.user_id,
-                name: player.name.clone(),
-                to_add: Vec::new(),
-                to_remove: Vec::new(),
-            };
-        }
+    ) -> HashMap<(RoleGroup, RoleId), bool> {
+        let player_fulfillment = self
+            .role_groups
+            .iter()
+            .flat_map(|(role_group, roles)| {
+                roles.iter().map(|(role_id, role_settings)| {
+                    (
+                        (role_group.clone(), *role_id),
+                        role_settings.is_fulfilled_for(player),
+                    )
+                })
+            })
+            .collect::<HashMap<_, _>>();
+
+        // a prerequisite may win its group again once a heavier role loses fulfillment, so every
+        // pass starts over from the player fulfillment; the limit guards against circular prerequisites
+        let mut fulfillment = player_fulfillment.clone();
+        for _ in 0..=player_fulfillment.len() {
+            let granted_roles = self.get_granted_roles(player, &fulfillment, current_roles);
+
+            let next_fulfillment = player_fulfillment
+                .iter()
+                .map(|((role_group, role_id), fulfilled)| {
+                    let required_role_met = self.role_groups[role_group][role_id]
+                        .required_role
+                        .is_none_or(|required_role| {
+                            self.is_required_role_met(required_role, &granted_roles, current_roles)
+                        });
+
+                    (
+                        (role_group.clone(), *role_id),
+                        *fulfilled && required_role_met,
+                    )
+                })
+                .collect::<HashMap<_, _>>();
+
+            if next_fulfillment == fulfillment {
+                break;
+            }
+
+            fulfillment = next_fulfillment;
+        }
+
+        fulfillment
+    }
+
+    // auto roles the user holds after the update, the heaviest fulfilled role of every group
+    fn get_granted_roles(
+        &self,
+        player: &Player,
+        fulfillment: &HashMap<(RoleGroup, RoleId), bool>,
+        current_roles: &[RoleId],
+    ) -> Vec<RoleId> {
+        let account_old_enough = self.is_account_old_enough(player);
+
+        self.role_groups
+            .iter()
+            .filter_map(|(role_group, roles)| {
+                roles
+                    .values()
+                    .filter(|role_settings| {
+                        fulfillment[&(role_group.clone(), role_settings.role_id)]
+                    })
+                    .max_by(|a, b| a.weight.cmp(&b.weight).then(a.role_id.cmp(&b.role_id)))
+                    .map(|role_settings| role_settings.role_id)
+            })
+            // young accounts are not granted new auto roles
+            .filter(|role_id| account_old_enough || current_roles.contains(role_id))
+            .collect()
+    }
+
+    fn is_required_role_met(
+        &self,
+        required_role: RoleId,
+        granted_roles: &[RoleId],
+        current_roles: &[RoleId],
+    ) -> bool {
+        if self
+            .role_groups
+            .values()
+            .any(|roles| roles.contains_key(&required_role))
+        {
+            return granted_roles.contains(&required_role);
+        }
+
+        current_roles.contains(&required_role)
+    }
+
+    pub(crate) fn explain_roles(
+        &self,
+        player: &Player,
+        current_roles: &[RoleId],
+    ) -> Vec<(RoleGroup, Vec<RoleFulfillment>)> {
+        let fulfillment = self.get_roles_fulfillment(player, current_roles);
+        let granted_roles = self.get_granted_roles(player, &fulfillment, current_roles);
+
+        self.get_ordered_groups()
+            .into_iter()
+            .filter_map(|role_group| {
+                let roles = self.role_groups.get(&role_group)?;
+
+                let mut roles_fulfillment = roles
+                    .values()
+                    .map(|role_settings| {
+                        let mut role_fulfillment = role_settings.check(player);
+                        role_fulfillment
+                            .requirements
+                            .iter_mut()
+                            .for_each(|requirement| {
+                                requirement.decimal_places = self.get_decimal_places()
+                            });
+                        role_fulfillment.fulfilled =
+                            fulfillment[&(role_group.clone(), role_settings.role_id)];
+                        role_fulfillment.required_role =
+                            role_settings.required_role.map(|required_role| {
+                                (
+                                    required_role,
+                                    self.is_required_role_met(
+                                        required_role,
+                                        &granted_roles,
+                                        current_roles,
+                                    ),
+                                )
+                            });
+
+                        role_fulfillment
+                    })
+                    .collect::<Vec<_>>();
+                roles_fulfillment.sort_unstable_by(|a, b| {
+                    b.weight.cmp(&a.weight).then(b.role_id.cmp(&a.role_id))
+                });
+
+                Some((role_group, roles_fulfillment))
+            })
+            .collect()
+    }
+
+    pub(crate) fn get_soldier_role_changes(
+        &self,
+        player: &Player,
+        current_roles: &[RoleId],
+    ) -> UserRoleChanges {
+        if self.clan_settings.is_none()
+            || self.clan_settings.as_ref().unwrap().soldier_role.is_none()
+        {
+            return UserRoleChanges {
+                guild_id: self.guild_id,
+                user_id: player.user_id,
+                name: player.name.clone(),
+                to_add: Vec::new(),
+                to_remove: Vec::new(),
+                dry_run: false,
+                failed: Vec::new(),
+            };
+        }
 
         let clan_settings = self.clan_settings.as_ref().unwrap();
         let soldier_role = clan_settings.soldier_role.unwrap();
@@ -1116,53 +2419,137 @@ impl GuildSettings {
             } else {
                 Vec::new()
             },
+            dry_run: false,
+            failed: Vec::new(),
         }
     }
 }
 
-impl std::fmt::Display for GuildSettings {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        let mut rg_vec = self
-            .role_groups
-            .iter()
-            .collect::<Vec<(&RoleGroup, &HashMap<RoleId, RoleSettings>)>>();
-        rg_vec.sort_unstable_by(|a, b| Ord::cmp(a.0, b.0));
-
-        write!(
-            f,
-            "# __Current settings__\nBot log channel: {}\nVerified profiles only: {}\nClan setting: {}\n## Auto roles:\n{}",
+impl GuildSettings {
+    fn format_header(&self) -> String {
+        format!(
+            "# __Current settings__\nBot log channel: {}\nRole changes channel: {}\nErrors channel: {}\nVerified profiles only: {}\nVerification reminder: {}\nWelcome roles: {}\nAuto roles dry run: {}\nAuto roles cooldown: {}\nMin. account age: {}\nCommand cooldowns: {}\nPlaylist cover: {}\nSkip FCs in playlists: {}\nCommander's orders min. stars: {}\nEmbed theme: {}\nMap triangle colors: {}\nDecimal places: {}\nProfile visibility: {}\nTimezone: {}\nClan setting: {}\n## Auto roles:",
             self.bot_channel_id.map_or_else(
                 || "**None**".to_owned(),
                 |channel_id| format!("<#{}>", channel_id.to_owned())
             ),
+            self.role_changes_channel_id.map_or_else(
+                || "Bot log channel".to_owned(),
+                |channel_id| format!("<#{}>", channel_id.to_owned())
+            ),
+            self.errors_channel_id.map_or_else(
+                || "Bot log channel".to_owned(),
+                |channel_id| format!("<#{}>", channel_id.to_owned())
+            ),
             if self.requires_verified_profile {"Yes"} else {"No"},
-            if self.clan_settings.is_some() {self.clan_settings.clone().unwrap().to_string()} else {"Not set up".to_owned()},
-            {
-                let roles = rg_vec
+            match (self.verification_reminder, self.requires_verified_profile) {
+                (false, _) => "No",
+                (true, true) => "Yes",
+                (true, false) => "Yes, but verified profiles are not required",
+            },
+            if self.welcome_roles.is_empty() {
+                "None".to_owned()
+            } else {
+                self.welcome_roles
                     .iter()
-                    .map(|(rg, rs_hm)| {
-                        let mut rs_vec = rs_hm.values().cloned().collect::<Vec<RoleSettings>>();
-                        rs_vec.sort_unstable_by(|a, b| Ord::cmp(&b.weight, &a.weight));
-
-                        format!(
-                            "### Group: __{}__\n{}",
-                            rg,
-                            rs_vec
-                                .iter()
-                                .map(|rs| format!("{}", rs))
-                                .fold(String::new(), |out, rs| out + &*format!("{}\n", rs))
-                                .trim_end()
-                        )
-                    })
-                    .fold(String::new(), |out, rg| out + &*format!("{}\n", rg))
-                    .trim()
-                    .to_owned();
+                    .map(|role_id| format!("<@&{}>", role_id))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            },
+            if self.auto_roles_dry_run {"Yes"} else {"No"},
+            if self.auto_roles_cooldown_hours > 0 {format!("{}h", self.auto_roles_cooldown_hours)} else {"None".to_owned()},
+            if self.min_account_age_days > 0 {format!("{} days", self.min_account_age_days)} else {"None".to_owned()},
+            self.format_command_cooldowns(),
+            self.format_playlist_covers(),
+            if self.playlist_skip_fcs {"Yes"} else {"No"},
+            self.commander_order_min_stars.map_or_else(|| "None".to_owned(), |min_stars| format!("{}*", format_decimal(min_stars, self.get_decimal_places()))),
+            self.embed_theme,
+            self.map_triangle_colors,
+            self.get_decimal_places(),
+            if self.profile_private {"Private"} else {"Public"},
+            self.get_timezone(),
+            self.clan_settings.as_ref().map_or_else(|| "Not set up".to_owned(), |clan_settings| clan_settings.to_string_with_decimal_places(self.get_decimal_places())),
+        )
+    }
 
-                if !roles.is_empty() {
-                    roles
-                } else {
-                    "None".to_owned()
-                }
+    fn format_command_cooldowns(&self) -> String {
+        let default = format!("Default ({}s)", DEFAULT_COMMAND_COOLDOWN_SECS);
+
+        if self.command_cooldowns.is_empty() {
+            return default;
+        }
+
+        let mut cooldowns = self
+            .command_cooldowns
+            .iter()
+            .map(|(name, secs)| format!("{} {}s", name, secs))
+            .collect::<Vec<_>>();
+        cooldowns.sort_unstable();
+
+        format!("{}, other commands: {}", cooldowns.join(", "), default)
+    }
+
+    fn format_playlist_covers(&self) -> String {
+        let mut covers = self
+            .playlist_type_covers
+            .keys()
+            .map(|playlist_type| playlist_type.to_string())
+            .collect::<Vec<_>>();
+        covers.sort_unstable();
+
+        match (self.playlist_cover.is_some(), covers.is_empty()) {
+            (false, true) => "Default".to_owned(),
+            (true, true) => "Custom".to_owned(),
+            (false, false) => format!("Custom for {}", covers.join(", ")),
+            (true, false) => format!("Custom, own one for {}", covers.join(", ")),
+        }
+    }
+
+    fn format_role_groups(&self) -> Vec<String> {
+        self.get_ordered_groups()
+            .into_iter()
+            .filter_map(|rg| self.role_groups.get(&rg).map(|rs_hm| (rg, rs_hm)))
+            .map(|(rg, rs_hm)| {
+                let mut rs_vec = rs_hm.values().cloned().collect::<Vec<RoleSettings>>();
+                rs_vec.sort_unstable_by(|a, b| Ord::cmp(&b.weight, &a.weight));
+
+                format!(
+                    "### Group: __{}__\n{}",
+                    rg,
+                    rs_vec
+                        .iter()
+                        .map(|rs| format!("{}", rs))
+                        .fold(String::new(), |out, rs| out + &*format!("{}\n", rs))
+                        .trim_end()
+                )
+            })
+            .collect()
+    }
+
+    // splits the settings into messages that fit within Discord's message length limit,
+    // a role group is split only if it does not fit into a single message by itself
+    pub fn to_messages(&self) -> Vec<String> {
+        let mut role_groups = self.format_role_groups();
+        if role_groups.is_empty() {
+            role_groups.push("None".to_owned());
+        }
+
+        split_msg_in_parts(std::iter::once(self.format_header()).chain(role_groups))
+    }
+}
+
+impl std::fmt::Display for GuildSettings {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let roles = self.format_role_groups().join("\n");
+
+        write!(
+            f,
+            "{}\n{}",
+            self.format_header(),
+            if !roles.is_empty() {
+                roles
+            } else {
+                "None".to_owned()
             }
         )
     }
@@ -1189,6 +2576,67 @@ pub struct ClanSettings {
     soldiers: Vec<UserId>,
     #[serde(rename = "clanCommanderRole")]
     commander_role: Option<RoleId>,
+    clan_wars_min_stars: Option<f64>,
+    clan_wars_max_stars: Option<f64>,
+    // overrides the clan_wars_maps_count setting if set
+    clan_wars_maps_count: Option<u16>,
+    // mentioned when new clan wars maps are posted
+    clan_wars_ping_role: Option<RoleId>,
+    // weekly recap is disabled if not set
+    clan_recap_schedule: Option<ClanRecapSchedule>,
+    clan_recap_posted_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ClanRecapSchedule {
+    pub day: Weekday,
+    // in the guild timezone
+    pub hour: u32,
+}
+
+impl ClanRecapSchedule {
+    pub fn new(day: Weekday, hour: u32) -> Self {
+        Self {
+            day,
+            hour: hour.min(23),
+        }
+    }
+
+    /// Returns the start of the latest scheduled window that is not later than now
+    pub fn last_window(&self, now: DateTime<Utc>, timezone: Tz) -> DateTime<Utc> {
+        let local_now = now.with_timezone(&timezone);
+        let days_back =
+            (7 + local_now.weekday().num_days_from_monday() - self.day.num_days_from_monday()) % 7;
+
+        let mut date = local_now.date_naive() - Duration::days(days_back as i64);
+        loop {
+            let naive = date.and_time(NaiveTime::MIN) + Duration::hours(self.hour as i64);
+            // the hour may not exist on the day of DST change, use the first one after the gap
+            let window = timezone
+                .from_local_datetime(&naive)
+                .earliest()
+                .or_else(|| {
+                    timezone
+                        .from_local_datetime(&(naive + Duration::hours(1)))
+                        .earliest()
+                })
+                .map(|window| window.with_timezone(&Utc))
+                .unwrap_or(now);
+
+            if window <= now {
+                return window;
+            }
+
+            date -= Duration::days(7);
+        }
+    }
+}
+
+impl std::fmt::Display for ClanRecapSchedule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "every {} at {:02}:00", self.day, self.hour)
+    }
 }
 
 impl ClanSettings {
@@ -1214,9 +2662,19 @@ impl ClanSettings {
             soldier_role: None,
             soldiers: Vec::new(),
             commander_role: None,
+            clan_wars_min_stars: None,
+            clan_wars_max_stars: None,
+            clan_wars_maps_count: None,
+            clan_wars_ping_role: None,
+            clan_recap_schedule: None,
+            clan_recap_posted_at: None,
         }
     }
 
+    pub fn get_user_id(&self) -> UserId {
+        self.user_id
+    }
+
     pub fn get_owner(&self) -> PlayerId {
         self.owner_id.clone()
     }
@@ -1252,6 +2710,50 @@ impl ClanSettings {
         self.commander_role = role_id;
     }
 
+    pub fn get_clan_wars_star_range(&self) -> (Option<f64>, Option<f64>) {
+        (self.clan_wars_min_stars, self.clan_wars_max_stars)
+    }
+
+    pub fn set_clan_wars_star_range(&mut self, min_stars: Option<f64>, max_stars: Option<f64>) {
+        self.clan_wars_min_stars = min_stars;
+        self.clan_wars_max_stars = max_stars;
+    }
+
+    pub fn get_clan_wars_maps_count(&self) -> Option<u16> {
+        self.clan_wars_maps_count
+    }
+
+    pub fn set_clan_wars_maps_count(&mut self, count: Option<u16>) {
+        self.clan_wars_maps_count = count;
+    }
+
+    fn rehome(&mut self, map_role: impl Fn(RoleId) -> Option<RoleId>) -> Vec<RoleId> {
+        let mut unmapped = Vec::new();
+
+        self.clan_wars_maps_channel_id = None;
+        self.clan_wars_contribution_channel_id = None;
+
+        for role in [
+            &mut self.soldier_role,
+            &mut self.commander_role,
+            &mut self.clan_wars_ping_role,
+        ] {
+            if let Some(role_id) = *role {
+                *role = map_role(role_id);
+                if role.is_none() {
+                    unmapped.push(role_id);
+                }
+            }
+        }
+
+        unmapped
+    }
+
+    pub fn is_in_clan_wars_star_range(&self, stars: f64) -> bool {
+        self.clan_wars_min_stars.is_none_or(|min| stars >= min)
+            && self.clan_wars_max_stars.is_none_or(|max| stars <= max)
+    }
+
     pub fn get_clan_wars_contribution_channel(&self) -> Option<ChannelId> {
         self.clan_wars_contribution_channel_id
     }
@@ -1299,17 +2801,40 @@ impl ClanSettings {
         self.soldier_role = role_id;
     }
 
+    pub fn get_clan_wars_ping_role(&self) -> Option<RoleId> {
+        self.clan_wars_ping_role
+    }
+
+    pub fn set_clan_wars_ping_role(&mut self, role_id: Option<RoleId>) {
+        self.clan_wars_ping_role = role_id;
+    }
+
     pub fn get_clan_wars_soldiers(&self) -> &Vec<UserId> {
         &self.soldiers
     }
+
+    pub fn get_clan_recap_schedule(&self) -> Option<ClanRecapSchedule> {
+        self.clan_recap_schedule
+    }
+
+    pub fn set_clan_recap_schedule(&mut self, schedule: Option<ClanRecapSchedule>) {
+        self.clan_recap_schedule = schedule;
+    }
+
+    pub fn get_clan_recap_posted_at(&self) -> Option<DateTime<Utc>> {
+        self.clan_recap_posted_at
+    }
+
+    pub fn set_clan_recap_posted_at(&mut self, posted_at: Option<DateTime<Utc>>) {
+        self.clan_recap_posted_at = posted_at;
+    }
 }
 
-impl std::fmt::Display for ClanSettings {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+impl ClanSettings {
+    pub fn to_string_with_decimal_places(&self, decimal_places: u8) -> String {
         if self.oauth_token_is_set {
-            write!(
-                f,
-                "Set up for the clan {}. Users can{} send themselves invitations.\nClan wars maps channel: {}\nClan wars contribution channel: {}\nClan wars commander role: {}\nClan wars soldier role: {}",
+            format!(
+                "Set up for the clan {}. Users can{} send themselves invitations.\nClan wars maps channel: {}\nClan wars maps stars: {}\nClan wars maps count: {}\nClan wars contribution channel: {}\nClan wars commander role: {}\nClan wars soldier role: {}\nClan wars ping role: {}\nClan weekly recap: {}",
                 self.clan,
                 if !self.supports_self_invitation() {
                     " NOT"
@@ -1320,6 +2845,18 @@ impl std::fmt::Display for ClanSettings {
                     || "**None**".to_owned(),
                     |channel_id| format!("<#{}>", channel_id)
                 ),
+                match (self.clan_wars_min_stars, self.clan_wars_max_stars) {
+                    (None, None) => "Any".to_owned(),
+                    (Some(min), None) => format!("{}★ or more", format_decimal(min, decimal_places)),
+                    (None, Some(max)) => format!("up to {}★", format_decimal(max, decimal_places)),
+                    (Some(min), Some(max)) => format!(
+                        "{}★ - {}★",
+                        format_decimal(min, decimal_places),
+                        format_decimal(max, decimal_places)
+                    ),
+                },
+                self.clan_wars_maps_count
+                    .map_or_else(|| "Default".to_owned(), |count| count.to_string()),
                 self.clan_wars_contribution_channel_id.map_or_else(
                     || "**None**".to_owned(),
                     |channel_id| format!("<#{}>", channel_id)
@@ -1332,13 +2869,31 @@ impl std::fmt::Display for ClanSettings {
                     || "**None**".to_owned(),
                     |role_id| format!("<@&{}>", role_id)
                 ),
+                self.clan_wars_ping_role.map_or_else(
+                    || "**None**".to_owned(),
+                    |role_id| format!("<@&{}>", role_id)
+                ),
+                self.clan_recap_schedule.map_or_else(
+                    || "**Disabled**".to_owned(),
+                    |schedule| schedule.to_string()
+                ),
             )
         } else {
-            write!(f, "Unfinished setup for clan {}!", self.get_clan())
+            format!("Unfinished setup for clan {}!", self.get_clan())
         }
     }
 }
 
+impl std::fmt::Display for ClanSettings {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            self.to_string_with_decimal_places(DEFAULT_DECIMAL_PLACES)
+        )
+    }
+}
+
 #[derive(Clone)]
 pub(crate) struct GuildOAuthTokenRepository {
     owner_id: PlayerId,
@@ -1371,7 +2926,7 @@ impl OAuthTokenRepository for GuildOAuthTokenRepository {
             None => {
                 trace!("No OAuth token in repository.");
 
-                Err(BlError::OAuthStorage)
+                Ok(None)
             }
         }
     }
@@ -1398,6 +2953,36 @@ impl OAuthTokenRepository for GuildOAuthTokenRepository {
 }
 
 pub async fn get_binary_file(url: &str) -> crate::beatleader::Result<Bytes> {
+    get_binary_file_with_content_type(url)
+        .await
+        .map(|(bytes, _content_type)| bytes)
+}
+
+pub async fn get_binary_file_with_content_type(
+    url: &str,
+) -> crate::beatleader::Result<(Bytes, Option<String>)> {
+    if let Some(cached) = BINARY_FILE_CACHE.get(url) {
+        trace!("Binary file {} found in cache.", url);
+        METRICS.record_binary_file_cache_hit();
+
+        return Ok(cached);
+    }
+    METRICS.record_binary_file_cache_miss();
+
+    let (bytes, content_type) = fetch_binary_file(url).await?;
+    BINARY_FILE_CACHE.insert(url, bytes.clone(), content_type.clone());
+
+    Ok((bytes, content_type))
+}
+
+// large one-off downloads (e.g. replays) would only evict the small files the cache is meant for
+pub async fn get_uncached_binary_file(url: &str) -> crate::beatleader::Result<Bytes> {
+    fetch_binary_file(url)
+        .await
+        .map(|(bytes, _content_type)| bytes)
+}
+
+async fn fetch_binary_file(url: &str) -> crate::beatleader::Result<(Bytes, Option<String>)> {
     trace!("Fetching binary file, url: {}", url);
 
     let client_builder = reqwest::Client::builder()
@@ -1448,10 +3033,18 @@ pub async fn get_binary_file(url: &str) -> crate::beatleader::Result<Bytes> {
             reqwest_middleware::Error::Reqwest(err) => Err(BlError::Network(err)),
         },
         Ok(response) => match response.status().as_u16() {
-            200..=299 => match response.bytes().await {
-                Ok(b) => Ok(b),
-                Err(_err) => Err(BlError::Unknown),
-            },
+            200..=299 => {
+                let content_type = response
+                    .headers()
+                    .get(reqwest::header::CONTENT_TYPE)
+                    .and_then(|value| value.to_str().ok())
+                    .map(|value| value.to_lowercase());
+
+                match response.bytes().await {
+                    Ok(b) => Ok((b, content_type)),
+                    Err(_err) => Err(BlError::Unknown),
+                }
+            }
             401 | 403 => Err(BlError::Unauthorized),
             404 => Err(BlError::NotFound),
             400..=499 => Err(BlError::Client(
@@ -1463,46 +3056,90 @@ pub async fn get_binary_file(url: &str) -> crate::beatleader::Result<Bytes> {
     }
 }
 
-const MAX_DISCORD_MESSAGE_LENGTH: usize = 2000;
+pub(crate) const MAX_DISCORD_MESSAGE_LENGTH: usize = 2000;
 pub(crate) async fn post_long_msg_in_parts(
     global_ctx: &serenity::Context,
     channel_id: ChannelId,
     embed: Vec<String>,
 ) -> Result<(), poise::serenity_prelude::Error> {
-    let len = embed.len();
-
-    let mut current_str = String::with_capacity(MAX_DISCORD_MESSAGE_LENGTH);
+    for current_str in split_msg_in_parts(embed) {
+        channel_id
+            .send_message(
+                global_ctx.clone(),
+                CreateMessage::new()
+                    .embed(CreateEmbed::new().description(current_str))
+                    .allowed_mentions(CreateAllowedMentions::new()),
+            )
+            .await?;
+    }
 
-    for (idx, str) in embed.iter().enumerate() {
-        if current_str.len() + str.len() >= MAX_DISCORD_MESSAGE_LENGTH || idx == len - 1 {
-            current_str = current_str + str;
+    Ok(())
+}
 
-            channel_id
-                .send_message(
-                    global_ctx.clone(),
-                    CreateMessage::new()
-                        .embed(CreateEmbed::new().description(current_str))
-                        .allowed_mentions(CreateAllowedMentions::new()),
-                )
-                .await?;
+// joins the parts with new lines into messages that fit within Discord's message length limit;
+// messages are split between the parts, a part too long for a single message between its lines
+pub(crate) fn split_msg_in_parts(parts: impl IntoIterator<Item = String>) -> Vec<String> {
+    let mut messages = Vec::new();
+    let mut current_str: Option<String> = None;
 
-            current_str = String::with_capacity(MAX_DISCORD_MESSAGE_LENGTH);
+    for part in parts {
+        let lines = if part.chars().count() > MAX_DISCORD_MESSAGE_LENGTH {
+            part.lines().map(|line| line.to_owned()).collect()
         } else {
-            current_str = current_str + str;
+            vec![part]
+        };
+
+        for line in lines {
+            current_str = match current_str {
+                Some(mut str)
+                    if str.chars().count() + line.chars().count() < MAX_DISCORD_MESSAGE_LENGTH =>
+                {
+                    str.push('\n');
+                    str.push_str(&line);
+
+                    Some(str)
+                }
+                Some(str) => {
+                    messages.push(str);
+
+                    Some(line)
+                }
+                None => Some(line),
+            };
         }
     }
 
-    Ok(())
+    messages.extend(current_str);
+
+    messages
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::discord::bot::beatleader::player::Player;
+    use std::collections::HashMap;
+
+    use crate::beatleader::player::{AccuracyTracker, DifficultyStatus};
+    use crate::discord::bot::beatleader::clan::{
+        ClanWarsFc, ClanWarsSort, Playlist, PlaylistMapStatus, PLAYLIST_COVER_MAX_BYTES,
+    };
+    use crate::discord::bot::beatleader::player::{
+        rank_by_watched_replays, Player, NO_SCORES_DAYS_SINCE_LAST_SCORE,
+    };
+    use crate::discord::bot::beatleader::score::{
+        get_acc_consistency, group_top1_scores, weekly_pause_report, HandStats, Score,
+    };
+    use crate::discord::bot::commands::get_leaderboard_ids_from_list;
     use crate::discord::bot::{
-        Condition, GuildId, GuildSettings, Metric, PlayerMetricValue, Requirement,
-        RequirementMetricValue, RoleId, RoleRequirementId, RoleSettings,
+        ChannelId, ClanRecapSchedule, ClanSettings, Condition, GuildId, GuildSettings, Metric,
+        PlayerMetricValue, Requirement, RequirementMetricValue, RoleChangesDigest, RoleId,
+        RoleMatchMode, RoleRequirementId, RoleSettings, UserId, UserRoleChanges,
+        DEFAULT_AUTO_ROLES_COOLDOWN_HOURS, DEFAULT_COMMAND_COOLDOWN_SECS,
+        GUILD_SETTINGS_SCHEMA_VERSION, MAX_DISCORD_MESSAGE_LENGTH,
     };
-    use chrono::{Duration, Utc};
+    use crate::other::string_utils::format_decimal;
+    use crate::storage::StorageValue;
+    use chrono::{Duration, TimeZone, Utc, Weekday};
+    use chrono_tz::Tz;
 
     fn create_5kpp_ss_50_country_role_settings() -> RoleSettings {
         let mut rs = RoleSettings::new(RoleId::new(1), 100);
@@ -1680,6 +3317,37 @@ mod tests {
         assert!(requirement_metric < no_pause);
         assert!(requirement_metric < more_than_30_days_ago);
         assert!(requirement_metric > less_than_30_days_ago);
+        assert_ne!(requirement_metric, no_pause);
+        assert_eq!(
+            requirement_metric.partial_cmp(&PlayerMetricValue::TopPp(100.0)),
+            None
+        );
+    }
+
+    #[test]
+    fn it_treats_never_paused_as_paused_infinitely_long_ago() {
+        let no_pause = PlayerMetricValue::LastPause(None);
+        let requirement = |condition| Requirement {
+            condition,
+            value: RequirementMetricValue::LastPause(30),
+            tolerance: Some(10.0),
+        };
+
+        assert!(requirement(Condition::BetterThan).is_fulfilled_for(&no_pause));
+        assert!(requirement(Condition::BetterThanOrEqualTo).is_fulfilled_for(&no_pause));
+        assert!(!requirement(Condition::EqualTo).is_fulfilled_for(&no_pause));
+        assert!(requirement(Condition::NotEqualTo).is_fulfilled_for(&no_pause));
+        assert!(!requirement(Condition::WorseThanOrEqualTo).is_fulfilled_for(&no_pause));
+        assert!(!requirement(Condition::WorseThan).is_fulfilled_for(&no_pause));
+        assert!(!requirement(Condition::Contains).is_fulfilled_for(&no_pause));
+        assert!(!requirement(Condition::Excludes).is_fulfilled_for(&no_pause));
+        assert!(!requirement(Condition::Within).is_fulfilled_for(&no_pause));
+
+        let exactly_30_days_ago =
+            PlayerMetricValue::LastPause(Some(Utc::now() - Duration::days(30)));
+        assert!(requirement(Condition::EqualTo).is_fulfilled_for(&exactly_30_days_ago));
+        assert!(requirement(Condition::WorseThanOrEqualTo).is_fulfilled_for(&exactly_30_days_ago));
+        assert!(!requirement(Condition::BetterThan).is_fulfilled_for(&exactly_30_days_ago));
     }
 
     #[test]
@@ -1687,6 +3355,7 @@ mod tests {
         let requirement = Requirement {
             condition: Condition::BetterThanOrEqualTo,
             value: RequirementMetricValue::TopPp(100.0),
+            tolerance: None,
         };
         assert!(requirement.is_fulfilled_for(&PlayerMetricValue::TopPp(100.0)));
         assert!(requirement.is_fulfilled_for(&PlayerMetricValue::TopPp(150.0)));
@@ -1695,6 +3364,7 @@ mod tests {
         let requirement = Requirement {
             condition: Condition::BetterThanOrEqualTo,
             value: RequirementMetricValue::Rank(100),
+            tolerance: None,
         };
         assert!(requirement.is_fulfilled_for(&PlayerMetricValue::Rank(100)));
         assert!(requirement.is_fulfilled_for(&PlayerMetricValue::Rank(90)));
@@ -1704,6 +3374,7 @@ mod tests {
         let requirement = Requirement {
             condition: Condition::BetterThanOrEqualTo,
             value: RequirementMetricValue::LastPause(30),
+            tolerance: None,
         };
         let no_pause = PlayerMetricValue::LastPause(None);
         let exactly_30_days_ago =
@@ -1720,36 +3391,432 @@ mod tests {
     }
 
     #[test]
-    fn it_generates_next_role_condition_id() {
-        let rs = create_5kpp_ss_50_country_role_settings();
-
-        assert_eq!(rs.conditions.len(), 3);
-
-        let mut vec = rs
-            .conditions
-            .into_keys()
-            .collect::<Vec<RoleRequirementId>>();
-        vec.sort_unstable();
+    fn it_check_if_acc_consistency_requirement_is_fulfilled() {
+        // lower deviation is better
+        let requirement = Requirement {
+            condition: Condition::BetterThanOrEqualTo,
+            value: RequirementMetricValue::AccConsistency(1.5),
+            tolerance: None,
+        };
+        assert!(requirement.is_fulfilled_for(&PlayerMetricValue::AccConsistency(Some(1.5))));
+        assert!(requirement.is_fulfilled_for(&PlayerMetricValue::AccConsistency(Some(0.8))));
+        assert!(!requirement.is_fulfilled_for(&PlayerMetricValue::AccConsistency(Some(2.1))));
+        assert!(!requirement.is_fulfilled_for(&PlayerMetricValue::AccConsistency(None)));
 
-        assert_eq!(vec, [1, 2, 3]);
+        let requirement = Requirement {
+            condition: Condition::NotEqualTo,
+            ..requirement
+        };
+        assert!(!requirement.is_fulfilled_for(&PlayerMetricValue::AccConsistency(None)));
     }
 
     #[test]
-    fn it_can_get_player_metric_value_from_player() {
-        let player = Player {
-            pp: 12000.0,
-            top_pp: 400.0,
-            top_accuracy: 91.0,
-            country_rank: 20,
-            rank: 1000,
-            total_replay_watched: 200,
-            watched_replays: 1000,
-            top1_count: 10,
-            top_stars: 11.5,
-            max_streak: 5,
-            last_ranked_paused_at: None,
-            clans: vec!["Clan1".to_string()],
-            ..Default::default()
+    fn it_check_if_days_since_last_score_requirement_is_fulfilled() {
+        // fewer days since last score is better
+        let requirement = Requirement {
+            condition: Condition::BetterThanOrEqualTo,
+            value: RequirementMetricValue::DaysSinceLastScore(30),
+            tolerance: None,
+        };
+        assert!(requirement.is_fulfilled_for(&PlayerMetricValue::DaysSinceLastScore(30)));
+        assert!(requirement.is_fulfilled_for(&PlayerMetricValue::DaysSinceLastScore(2)));
+        assert!(!requirement.is_fulfilled_for(&PlayerMetricValue::DaysSinceLastScore(31)));
+        assert!(
+            !requirement.is_fulfilled_for(&PlayerMetricValue::DaysSinceLastScore(
+                NO_SCORES_DAYS_SINCE_LAST_SCORE
+            ))
+        );
+    }
+
+    #[test]
+    fn it_check_if_within_requirement_is_fulfilled() {
+        let requirement = Requirement {
+            condition: Condition::Within,
+            value: RequirementMetricValue::TopAcc(100.0),
+            tolerance: Some(0.5),
+        };
+        assert!(requirement.is_fulfilled_for(&PlayerMetricValue::TopAcc(100.0)));
+        assert!(requirement.is_fulfilled_for(&PlayerMetricValue::TopAcc(99.5)));
+        assert!(requirement.is_fulfilled_for(&PlayerMetricValue::TopAcc(100.5)));
+        assert!(requirement.is_fulfilled_for(&PlayerMetricValue::TopAcc(99.51)));
+        assert!(requirement.is_fulfilled_for(&PlayerMetricValue::TopAcc(100.49)));
+        assert!(!requirement.is_fulfilled_for(&PlayerMetricValue::TopAcc(99.49)));
+        assert!(!requirement.is_fulfilled_for(&PlayerMetricValue::TopAcc(100.51)));
+        assert!(!requirement.is_fulfilled_for(&PlayerMetricValue::TopAcc(f64::NAN)));
+        assert!(!requirement.is_fulfilled_for(&PlayerMetricValue::TopPp(100.0)));
+
+        // tolerance is relative to the required value
+        let requirement = Requirement {
+            condition: Condition::Within,
+            value: RequirementMetricValue::TopStars(10.0),
+            tolerance: Some(5.0),
+        };
+        assert!(requirement.is_fulfilled_for(&PlayerMetricValue::TopStars(9.5)));
+        assert!(requirement.is_fulfilled_for(&PlayerMetricValue::TopStars(10.5)));
+        assert!(!requirement.is_fulfilled_for(&PlayerMetricValue::TopStars(9.49)));
+        assert!(!requirement.is_fulfilled_for(&PlayerMetricValue::TopStars(10.51)));
+
+        let requirement = Requirement {
+            condition: Condition::Within,
+            value: RequirementMetricValue::TopAcc(95.0),
+            tolerance: Some(0.0),
+        };
+        assert!(requirement.is_fulfilled_for(&PlayerMetricValue::TopAcc(95.0)));
+        assert!(!requirement.is_fulfilled_for(&PlayerMetricValue::TopAcc(95.01)));
+
+        // missing tolerance or non-decimal metric never matches
+        let requirement = Requirement {
+            condition: Condition::Within,
+            value: RequirementMetricValue::TopAcc(95.0),
+            tolerance: None,
+        };
+        assert!(!requirement.is_fulfilled_for(&PlayerMetricValue::TopAcc(95.0)));
+
+        let requirement = Requirement {
+            condition: Condition::Within,
+            value: RequirementMetricValue::Rank(100),
+            tolerance: Some(10.0),
+        };
+        assert!(!requirement.is_fulfilled_for(&PlayerMetricValue::Rank(100)));
+    }
+
+    #[test]
+    fn it_matches_country_codes_case_insensitively() {
+        let value = RequirementMetricValue::new(Metric::Country, "pl, De").unwrap();
+        assert_eq!(
+            value,
+            RequirementMetricValue::Country(vec!["PL".to_string(), "DE".to_string()])
+        );
+
+        assert!(RequirementMetricValue::new(Metric::Country, "POL").is_err());
+        assert!(RequirementMetricValue::new(Metric::Country, "").is_err());
+
+        let requirement = Requirement {
+            condition: Condition::Contains,
+            value,
+            tolerance: None,
+        };
+
+        assert!(requirement.is_fulfilled_for(&PlayerMetricValue::Country("de".to_string())));
+        assert!(requirement.is_fulfilled_for(&PlayerMetricValue::Country("PL".to_string())));
+        assert!(!requirement.is_fulfilled_for(&PlayerMetricValue::Country("US".to_string())));
+
+        let requirement = Requirement {
+            condition: Condition::EqualTo,
+            value: RequirementMetricValue::new(Metric::Country, "pl").unwrap(),
+            tolerance: None,
+        };
+
+        assert!(requirement.is_fulfilled_for(&PlayerMetricValue::Country("Pl".to_string())));
+        assert!(!requirement.is_fulfilled_for(&PlayerMetricValue::Country("DE".to_string())));
+
+        let requirement = Requirement {
+            condition: Condition::Excludes,
+            value: RequirementMetricValue::new(Metric::Country, "PL").unwrap(),
+            tolerance: None,
+        };
+
+        assert!(!requirement.is_fulfilled_for(&PlayerMetricValue::Country("pl".to_string())));
+        assert!(requirement.is_fulfilled_for(&PlayerMetricValue::Country("DE".to_string())));
+    }
+
+    #[test]
+    fn it_matches_hmd_name_variants() {
+        let requirement = Requirement {
+            condition: Condition::EqualTo,
+            value: RequirementMetricValue::new(Metric::Hmd, "Oculus Quest 2").unwrap(),
+            tolerance: None,
+        };
+
+        assert!(requirement.is_fulfilled_for(&PlayerMetricValue::Hmd("Quest 2".to_string())));
+        assert!(requirement.is_fulfilled_for(&PlayerMetricValue::Hmd("meta quest2".to_string())));
+        assert!(!requirement.is_fulfilled_for(&PlayerMetricValue::Hmd("Quest 3".to_string())));
+
+        let requirement = Requirement {
+            condition: Condition::Contains,
+            value: RequirementMetricValue::new(Metric::Hmd, "quest, index").unwrap(),
+            tolerance: None,
+        };
+
+        assert!(requirement.is_fulfilled_for(&PlayerMetricValue::Hmd("Quest 3S".to_string())));
+        assert!(requirement.is_fulfilled_for(&PlayerMetricValue::Hmd("Quest Pro".to_string())));
+        assert!(requirement.is_fulfilled_for(&PlayerMetricValue::Hmd("Valve Index".to_string())));
+        assert!(!requirement.is_fulfilled_for(&PlayerMetricValue::Hmd("Rift S".to_string())));
+
+        let requirement = Requirement {
+            condition: Condition::Excludes,
+            value: RequirementMetricValue::new(Metric::Hmd, "Quest").unwrap(),
+            tolerance: None,
+        };
+
+        assert!(!requirement.is_fulfilled_for(&PlayerMetricValue::Hmd("Quest 2".to_string())));
+        assert!(requirement.is_fulfilled_for(&PlayerMetricValue::Hmd("Vive".to_string())));
+
+        assert!(RequirementMetricValue::new(Metric::Hmd, " , ").is_err());
+    }
+
+    #[test]
+    fn it_matches_platforms() {
+        assert_eq!(
+            RequirementMetricValue::new(Metric::Platform, "Steam, Oculus PC, meta quest").unwrap(),
+            RequirementMetricValue::Platform(vec![
+                "steam".to_string(),
+                "oculuspc".to_string(),
+                "quest".to_string()
+            ])
+        );
+        assert!(RequirementMetricValue::new(Metric::Platform, "xbox").is_err());
+        assert!(RequirementMetricValue::new(Metric::Platform, " , ").is_err());
+
+        let steam = Requirement {
+            condition: Condition::EqualTo,
+            value: RequirementMetricValue::new(Metric::Platform, "steam").unwrap(),
+            tolerance: None,
+        };
+
+        assert!(steam.is_fulfilled_for(&PlayerMetricValue::Platform("steam".to_string())));
+        assert!(steam.is_fulfilled_for(&PlayerMetricValue::Platform("steam,1.29.1".to_string())));
+        assert!(!steam.is_fulfilled_for(&PlayerMetricValue::Platform("oculuspc".to_string())));
+
+        let oculus_pc = Requirement {
+            condition: Condition::EqualTo,
+            value: RequirementMetricValue::new(Metric::Platform, "oculuspc").unwrap(),
+            tolerance: None,
+        };
+
+        assert!(oculus_pc.is_fulfilled_for(&PlayerMetricValue::Platform("oculuspc".to_string())));
+        assert!(!oculus_pc.is_fulfilled_for(&PlayerMetricValue::Platform("oculus".to_string())));
+
+        let quest = Requirement {
+            condition: Condition::EqualTo,
+            value: RequirementMetricValue::new(Metric::Platform, "Quest").unwrap(),
+            tolerance: None,
+        };
+
+        assert!(quest.is_fulfilled_for(&PlayerMetricValue::Platform("oculus".to_string())));
+        assert!(!quest.is_fulfilled_for(&PlayerMetricValue::Platform("steam".to_string())));
+
+        let pc = Requirement {
+            condition: Condition::Contains,
+            value: RequirementMetricValue::new(Metric::Platform, "steam, oculuspc").unwrap(),
+            tolerance: None,
+        };
+
+        assert!(pc.is_fulfilled_for(&PlayerMetricValue::Platform("steam".to_string())));
+        assert!(pc.is_fulfilled_for(&PlayerMetricValue::Platform("oculuspc".to_string())));
+        assert!(!pc.is_fulfilled_for(&PlayerMetricValue::Platform("oculus".to_string())));
+        // unknown platform reported by BL
+        assert!(!pc.is_fulfilled_for(&PlayerMetricValue::Platform("".to_string())));
+
+        let not_quest = Requirement {
+            condition: Condition::Excludes,
+            value: RequirementMetricValue::new(Metric::Platform, "quest").unwrap(),
+            tolerance: None,
+        };
+
+        assert!(!not_quest.is_fulfilled_for(&PlayerMetricValue::Platform("oculus".to_string())));
+        assert!(not_quest.is_fulfilled_for(&PlayerMetricValue::Platform("steam".to_string())));
+    }
+
+    #[test]
+    fn it_returns_only_new_replay_watches() {
+        let mut player = Player {
+            total_replay_watched: 10,
+            ..Default::default()
+        };
+        assert_eq!(player.get_new_replay_watches(), None);
+
+        player.replay_watch_notified_count = Some(10);
+        assert_eq!(player.get_new_replay_watches(), None);
+
+        player.replay_watch_notified_count = Some(7);
+        assert_eq!(player.get_new_replay_watches(), Some(3));
+
+        player.replay_watch_notified_count = Some(12);
+        assert_eq!(player.get_new_replay_watches(), None);
+    }
+
+    #[test]
+    fn it_check_if_excludes_requirement_is_fulfilled() {
+        let requirement = Requirement {
+            condition: Condition::Excludes,
+            value: RequirementMetricValue::Clan(vec!["ClanX".to_string()]),
+            tolerance: None,
+        };
+        assert!(!requirement.is_fulfilled_for(&PlayerMetricValue::Clan(vec!["ClanX".to_string()])));
+        assert!(!requirement.is_fulfilled_for(&PlayerMetricValue::Clan(vec![
+            "Other".to_string(),
+            "ClanX".to_string()
+        ])));
+        assert!(requirement.is_fulfilled_for(&PlayerMetricValue::Clan(vec!["Other".to_string()])));
+        assert!(requirement.is_fulfilled_for(&PlayerMetricValue::Clan(vec![])));
+
+        let requirement = Requirement {
+            condition: Condition::Excludes,
+            value: RequirementMetricValue::MainClan("ClanX".to_string()),
+            tolerance: None,
+        };
+        assert!(
+            !requirement.is_fulfilled_for(&PlayerMetricValue::MainClan(vec![
+                "ClanX".to_string(),
+                "Other".to_string()
+            ]))
+        );
+        assert!(
+            requirement.is_fulfilled_for(&PlayerMetricValue::MainClan(vec![
+                "Other".to_string(),
+                "ClanX".to_string()
+            ]))
+        );
+        assert!(requirement.is_fulfilled_for(&PlayerMetricValue::MainClan(vec![])));
+
+        let requirement = Requirement {
+            condition: Condition::Excludes,
+            value: RequirementMetricValue::TopPp(100.0),
+            tolerance: None,
+        };
+        assert!(!requirement.is_fulfilled_for(&PlayerMetricValue::TopPp(90.0)));
+    }
+
+    #[test]
+    fn it_check_if_not_equal_requirement_is_fulfilled() {
+        let requirement = Requirement {
+            condition: Condition::NotEqualTo,
+            value: RequirementMetricValue::CountryRank(1),
+            tolerance: None,
+        };
+        assert!(!requirement.is_fulfilled_for(&PlayerMetricValue::CountryRank(1)));
+        assert!(requirement.is_fulfilled_for(&PlayerMetricValue::CountryRank(2)));
+        assert!(requirement.is_fulfilled_for(&PlayerMetricValue::CountryRank(0)));
+
+        let requirement = Requirement {
+            condition: Condition::NotEqualTo,
+            value: RequirementMetricValue::TopPp(100.0),
+            tolerance: None,
+        };
+        assert!(!requirement.is_fulfilled_for(&PlayerMetricValue::TopPp(100.0)));
+        assert!(requirement.is_fulfilled_for(&PlayerMetricValue::TopPp(150.0)));
+        assert!(requirement.is_fulfilled_for(&PlayerMetricValue::TopPp(90.0)));
+
+        let requirement = Requirement {
+            condition: Condition::NotEqualTo,
+            value: RequirementMetricValue::Clan(vec!["Clan1".to_string()]),
+            tolerance: None,
+        };
+        assert!(!requirement.is_fulfilled_for(&PlayerMetricValue::Clan(vec![
+            "Other".to_string(),
+            "Clan1".to_string()
+        ])));
+        assert!(requirement.is_fulfilled_for(&PlayerMetricValue::Clan(vec!["Other".to_string()])));
+        assert!(requirement.is_fulfilled_for(&PlayerMetricValue::Clan(vec![])));
+
+        // metric mismatch never fulfills the requirement
+        assert!(!requirement.is_fulfilled_for(&PlayerMetricValue::TopPp(100.0)));
+    }
+
+    #[test]
+    fn it_generates_next_role_condition_id() {
+        let rs = create_5kpp_ss_50_country_role_settings();
+
+        assert_eq!(rs.conditions.len(), 3);
+
+        let mut vec = rs
+            .conditions
+            .into_keys()
+            .collect::<Vec<RoleRequirementId>>();
+        vec.sort_unstable();
+
+        assert_eq!(vec, [1, 2, 3]);
+    }
+
+    #[test]
+    fn it_gets_hand_stats_only_with_swing_data_of_both_hands() {
+        let tracker = AccuracyTracker {
+            left_preswing: 1.2,
+            right_preswing: 0.8,
+            left_postswing: 1.0,
+            right_postswing: 0.6,
+            ..Default::default()
+        };
+
+        let hand_stats = HandStats::from_tracker(&tracker).unwrap();
+        assert_eq!(hand_stats.left_swing(), 1.0);
+        assert!((hand_stats.right_swing() - 0.7).abs() < f64::EPSILON);
+
+        let one_saber = AccuracyTracker {
+            left_preswing: 1.2,
+            left_postswing: 1.0,
+            ..Default::default()
+        };
+        assert_eq!(HandStats::from_tracker(&one_saber), None);
+    }
+
+    #[test]
+    fn it_does_not_accept_unverified_profiles_if_verification_is_required() {
+        let mut player = Player::default();
+
+        assert!(player.can_be_linked_to_guild(false));
+        assert!(!player.can_be_linked_to_guild(true));
+
+        player.is_verified = true;
+        assert!(player.can_be_linked_to_guild(true));
+    }
+
+    #[test]
+    fn it_ranks_players_by_watched_replays() {
+        let players = [("A", 5), ("B", 10), ("C", 0), ("D", 5), ("E", 3), ("F", 10)]
+            .into_iter()
+            .map(|(name, watched_replays)| Player {
+                name: name.to_owned(),
+                watched_replays,
+                ..Default::default()
+            })
+            .collect::<Vec<_>>();
+
+        let ranking = |top| {
+            rank_by_watched_replays(players.clone(), top)
+                .into_iter()
+                .map(|(place, player)| (place, player.name))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            ranking(10),
+            vec![
+                (1, "B".to_owned()),
+                (1, "F".to_owned()),
+                (3, "A".to_owned()),
+                (3, "D".to_owned()),
+                (5, "E".to_owned()),
+            ]
+        );
+        assert_eq!(
+            ranking(3),
+            vec![
+                (1, "B".to_owned()),
+                (1, "F".to_owned()),
+                (3, "A".to_owned()),
+                (3, "D".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_can_get_player_metric_value_from_player() {
+        let player = Player {
+            pp: 12000.0,
+            top_pp: 400.0,
+            top_accuracy: 91.0,
+            country_rank: 20,
+            rank: 1000,
+            total_replay_watched: 200,
+            watched_replays: 1000,
+            top1_count: 10,
+            top_stars: 11.5,
+            max_streak: 5,
+            last_ranked_paused_at: None,
+            clans: vec!["Clan1".to_string()],
+            ..Default::default()
         };
 
         assert_eq!(
@@ -1809,6 +3876,42 @@ mod tests {
         );
     }
 
+    #[test]
+    fn it_can_get_acc_consistency_metric_value_from_player() {
+        let player = Player {
+            acc_consistency: Some(1.25),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            player.get_metric_with_value(Metric::AccConsistency),
+            PlayerMetricValue::AccConsistency(Some(1.25))
+        );
+
+        assert_eq!(
+            Player::default().get_metric_with_value(Metric::AccConsistency),
+            PlayerMetricValue::AccConsistency(None)
+        );
+    }
+
+    #[test]
+    fn it_can_get_days_since_last_score_metric_value_from_player() {
+        assert_eq!(
+            Player::default().get_metric_with_value(Metric::DaysSinceLastScore),
+            PlayerMetricValue::DaysSinceLastScore(NO_SCORES_DAYS_SINCE_LAST_SCORE)
+        );
+
+        let active_player = Player {
+            total_play_count: 100,
+            last_score_time: Utc::now() - Duration::days(3) - Duration::hours(1),
+            ..Default::default()
+        };
+        assert_eq!(
+            active_player.get_metric_with_value(Metric::DaysSinceLastScore),
+            PlayerMetricValue::DaysSinceLastScore(3)
+        );
+    }
+
     #[test]
     fn it_check_if_player_metric_fulfills_role_setting_conditions() {
         let rs_5k = create_5kpp_ss_50_country_role_settings();
@@ -1860,6 +3963,74 @@ mod tests {
         assert!(!rs_no_pause.is_fulfilled_for(&player));
     }
 
+    #[test]
+    fn it_checks_role_conditions_according_to_match_mode() {
+        let mut rs = RoleSettings::new(RoleId::new(1), 100);
+        rs.add_requirement(
+            Condition::BetterThanOrEqualTo,
+            RequirementMetricValue::Rank(1000),
+        );
+        rs.add_requirement(
+            Condition::BetterThanOrEqualTo,
+            RequirementMetricValue::TotalPp(8000.0),
+        );
+
+        let rank_only = Player {
+            rank: 500,
+            pp: 7000.0,
+            ..Default::default()
+        };
+        let pp_only = Player {
+            rank: 1500,
+            pp: 9000.0,
+            ..Default::default()
+        };
+        let both = Player {
+            rank: 500,
+            pp: 9000.0,
+            ..Default::default()
+        };
+        let none = Player {
+            rank: 1500,
+            pp: 7000.0,
+            ..Default::default()
+        };
+
+        assert_eq!(rs.get_match_mode(), RoleMatchMode::All);
+        assert!(!rs.is_fulfilled_for(&rank_only));
+        assert!(!rs.is_fulfilled_for(&pp_only));
+        assert!(rs.is_fulfilled_for(&both));
+        assert!(!rs.is_fulfilled_for(&none));
+
+        rs.set_match_mode(RoleMatchMode::Any);
+        assert!(rs.is_fulfilled_for(&rank_only));
+        assert!(rs.is_fulfilled_for(&pp_only));
+        assert!(rs.is_fulfilled_for(&both));
+        assert!(!rs.is_fulfilled_for(&none));
+        assert!(rs.to_string().contains("match: any"));
+    }
+
+    #[test]
+    fn it_keeps_match_mode_of_existing_role_on_merge() {
+        let mut gs = create_empty_guild_settings();
+
+        let mut rs = create_10kpp_role_settings();
+        rs.set_match_mode(RoleMatchMode::Any);
+        gs.merge("pp".to_string(), rs);
+
+        gs.merge("pp".to_string(), create_10kpp_role_settings());
+        assert_eq!(
+            gs.role_groups.get("pp").unwrap()[&RoleId::new(2)].get_match_mode(),
+            RoleMatchMode::Any
+        );
+
+        gs.set_role_match_mode(&"pp".to_string(), RoleId::new(2), RoleMatchMode::All);
+        assert_eq!(
+            gs.role_groups.get("pp").unwrap()[&RoleId::new(2)].get_match_mode(),
+            RoleMatchMode::All
+        );
+    }
+
     #[test]
     fn it_can_add_role_settings_to_guild() {
         let gs = create_guild_settings();
@@ -1920,46 +4091,631 @@ mod tests {
     }
 
     #[test]
-    fn it_can_check_if_role_exists_in_guild_role_group() {
-        let gs = create_guild_settings();
-
-        assert!(!gs.contains_in_group("invalid".to_string(), RoleId::new(1)));
-        assert!(!gs.contains_in_group("rank".to_string(), RoleId::new(1000)));
-        assert!(gs.contains_in_group("rank".to_string(), RoleId::new(3)));
-        assert!(gs.contains_in_group("rank".to_string(), RoleId::new(5)));
-    }
+    fn it_orders_role_groups() {
+        let mut gs = create_guild_settings();
 
-    #[test]
-    fn it_can_get_all_roles_set_in_guild() {
-        let gs = create_guild_settings();
+        assert_eq!(
+            gs.get_ordered_groups(),
+            vec!["clan", "main-clan", "no-pause", "pp", "rank"]
+        );
 
-        let mut roles = gs.all_roles();
-        roles.sort_unstable();
+        gs.set_group_order(vec![
+            "rank".to_string(),
+            "invalid".to_string(),
+            "pp".to_string(),
+            "rank".to_string(),
+        ]);
 
         assert_eq!(
-            roles,
-            vec![
-                &RoleId::new(1),
-                &RoleId::new(2),
-                &RoleId::new(3),
-                &RoleId::new(4),
-                &RoleId::new(5),
-                &RoleId::new(6),
-                &RoleId::new(7),
-                &RoleId::new(8)
-            ]
+            gs.get_ordered_groups(),
+            vec!["rank", "pp", "clan", "main-clan", "no-pause"]
         );
     }
 
     #[test]
-    fn it_can_check_if_role_exists_in_any_guild_role_group() {
-        let gs = create_guild_settings();
+    fn it_resolves_role_updates_deterministically() {
+        let mut gs = create_guild_settings();
+        gs.set_group_order(vec!["rank".to_string(), "pp".to_string()]);
+
+        let player = Player {
+            pp: 10000.0,
+            top_accuracy: 91.0,
+            rank: 500,
+            country_rank: 20,
+            clans: vec!["Clan1".to_string()],
+            last_ranked_paused_at: Some(Utc::now() - Duration::days(50)),
+            ..Default::default()
+        };
+
+        let current_roles = vec![RoleId::new(1), RoleId::new(3), RoleId::new(5)];
+
+        for _ in 0..10 {
+            let roles_updates = gs.get_role_updates(&player, &current_roles);
+
+            assert_eq!(
+                roles_updates.to_add,
+                vec![
+                    RoleId::new(4),
+                    RoleId::new(2),
+                    RoleId::new(6),
+                    RoleId::new(8),
+                    RoleId::new(7)
+                ]
+            );
+            assert_eq!(
+                roles_updates.to_remove,
+                vec![RoleId::new(5), RoleId::new(3), RoleId::new(1)]
+            );
+        }
+    }
+
+    #[test]
+    fn it_breaks_role_weight_ties_by_recency() {
+        let mut gs = GuildSettings::new(GuildId::new(1));
+
+        for role_id in [10, 30, 20] {
+            let mut rs = RoleSettings::new(RoleId::new(role_id), 100);
+            rs.add_requirement(
+                Condition::BetterThanOrEqualTo,
+                RequirementMetricValue::TotalPp(5000.0),
+            );
+            gs.add("pp".to_string(), rs);
+        }
+
+        let player = Player {
+            pp: 10000.0,
+            ..Default::default()
+        };
+
+        for _ in 0..10 {
+            let roles_updates = gs.get_role_updates(&player, &[RoleId::new(10)]);
+
+            assert_eq!(roles_updates.to_add, vec![RoleId::new(30)]);
+            assert_eq!(roles_updates.to_remove, vec![RoleId::new(10)]);
+        }
+    }
+
+    fn create_role_chain_guild_settings() -> GuildSettings {
+        let mut gs = create_empty_guild_settings();
+
+        // member <- veteran <- legend, each in its own group
+        let mut member = RoleSettings::new(RoleId::new(10), 100);
+        member.add_requirement(
+            Condition::BetterThanOrEqualTo,
+            RequirementMetricValue::TotalPp(1000.0),
+        );
+        let mut veteran = RoleSettings::new(RoleId::new(20), 100);
+        veteran.add_requirement(
+            Condition::BetterThanOrEqualTo,
+            RequirementMetricValue::TopAcc(90.0),
+        );
+        veteran.set_required_role(Some(RoleId::new(10)));
+        let mut legend = RoleSettings::new(RoleId::new(30), 100);
+        legend.add_requirement(
+            Condition::BetterThanOrEqualTo,
+            RequirementMetricValue::Rank(100),
+        );
+        legend.set_required_role(Some(RoleId::new(20)));
+
+        gs.add("member".to_string(), member)
+            .add("veteran".to_string(), veteran)
+            .add("legend".to_string(), legend);
+
+        gs
+    }
+
+    #[test]
+    fn it_grants_role_only_with_its_prerequisite() {
+        let gs = create_role_chain_guild_settings();
+
+        let player = Player {
+            pp: 5000.0,
+            top_accuracy: 95.0,
+            rank: 50,
+            ..Default::default()
+        };
+
+        let mut roles_updates = gs.get_role_updates(&player, &[]);
+        roles_updates.to_add.sort_unstable();
+        assert_eq!(
+            roles_updates.to_add,
+            vec![RoleId::new(10), RoleId::new(20), RoleId::new(30)]
+        );
+        assert!(roles_updates.to_remove.is_empty());
+    }
+
+    #[test]
+    fn it_cascades_prerequisite_removal_to_dependent_roles() {
+        let gs = create_role_chain_guild_settings();
+
+        // still fulfills veteran and legend requirements, but not the member one
+        let player = Player {
+            pp: 500.0,
+            top_accuracy: 95.0,
+            rank: 50,
+            ..Default::default()
+        };
+
+        let current_roles = vec![RoleId::new(10), RoleId::new(20), RoleId::new(30)];
+        let mut roles_updates = gs.get_role_updates(&player, &current_roles);
+        roles_updates.to_remove.sort_unstable();
+        assert!(roles_updates.to_add.is_empty());
+        assert_eq!(roles_updates.to_remove, current_roles);
+
+        let explained = gs.explain_roles(&player, &current_roles);
+        let (_, legend_roles) = explained
+            .iter()
+            .find(|(role_group, _)| role_group == "legend")
+            .unwrap();
+        assert!(!legend_roles[0].fulfilled);
+        assert_eq!(
+            legend_roles[0].required_role,
+            Some((RoleId::new(20), false))
+        );
+        assert!(legend_roles[0].to_string().contains("❌ Requires <@&20>"));
+    }
+
+    #[test]
+    fn it_requires_prerequisite_to_win_its_group() {
+        let mut gs = create_role_chain_guild_settings();
+
+        // heavier role in the member group, so the member role itself is never granted
+        let mut elite = RoleSettings::new(RoleId::new(11), 200);
+        elite.add_requirement(
+            Condition::BetterThanOrEqualTo,
+            RequirementMetricValue::TotalPp(4000.0),
+        );
+        gs.add("member".to_string(), elite);
+
+        let player = Player {
+            pp: 5000.0,
+            top_accuracy: 95.0,
+            rank: 50,
+            ..Default::default()
+        };
+
+        let roles_updates = gs.get_role_updates(&player, &[]);
+        assert_eq!(roles_updates.to_add, vec![RoleId::new(11)]);
+
+        let explained = gs.explain_roles(&player, &[]);
+        let (_, veteran_roles) = explained
+            .iter()
+            .find(|(role_group, _)| role_group == "veteran")
+            .unwrap();
+        assert!(!veteran_roles[0].fulfilled);
+        assert_eq!(
+            veteran_roles[0].required_role,
+            Some((RoleId::new(10), false))
+        );
+    }
+
+    #[test]
+    fn it_requires_non_auto_prerequisite_role_to_be_granted() {
+        let mut gs = create_empty_guild_settings();
+
+        let mut rs = RoleSettings::new(RoleId::new(20), 100);
+        rs.add_requirement(
+            Condition::BetterThanOrEqualTo,
+            RequirementMetricValue::TotalPp(1000.0),
+        );
+        rs.set_required_role(Some(RoleId::new(99)));
+        gs.add("pp".to_string(), rs);
+
+        let player = Player {
+            pp: 5000.0,
+            ..Default::default()
+        };
+
+        assert!(gs.get_role_updates(&player, &[]).to_add.is_empty());
+        assert_eq!(
+            gs.get_role_updates(&player, &[RoleId::new(99)]).to_add,
+            vec![RoleId::new(20)]
+        );
+    }
+
+    #[test]
+    fn it_marks_role_updates_as_dry_run() {
+        let mut gs = create_guild_settings();
+
+        let player = Player {
+            pp: 10000.0,
+            top_accuracy: 91.0,
+            rank: 500,
+            country_rank: 20,
+            clans: vec!["Clan1".to_string()],
+            last_ranked_paused_at: Some(Utc::now() - Duration::days(50)),
+            ..Default::default()
+        };
+
+        let roles_updates = gs.get_role_updates(&player, &[]);
+        assert!(!roles_updates.dry_run);
+        assert!(roles_updates.to_string().starts_with("The roles of user"));
+
+        gs.set_auto_roles_dry_run(true);
+
+        let roles_updates = gs.get_role_updates(&player, &[]);
+        assert!(roles_updates.dry_run);
+        assert!(roles_updates.is_changed());
+        assert!(roles_updates.to_string().starts_with("*Dry run:*"));
+    }
+
+    #[test]
+    fn it_withholds_auto_roles_from_young_accounts() {
+        let mut gs = create_guild_settings();
+
+        let mut player = Player {
+            pp: 10000.0,
+            top_accuracy: 91.0,
+            rank: 500,
+            country_rank: 20,
+            clans: vec!["Clan1".to_string()],
+            last_ranked_paused_at: Some(Utc::now() - Duration::days(50)),
+            account_created_at: Some(Utc::now() - Duration::days(10)),
+            ..Default::default()
+        };
+
+        let qualifying_roles = gs.get_role_updates(&player, &[]).to_add;
+        assert!(!qualifying_roles.is_empty());
+
+        gs.set_min_account_age_days(30);
+
+        assert!(gs.get_role_updates(&player, &[]).to_add.is_empty());
+
+        player.account_created_at = None;
+        assert!(gs.get_role_updates(&player, &[]).to_add.is_empty());
+
+        player.account_created_at = Some(Utc::now() - Duration::days(30));
+        assert_eq!(gs.get_role_updates(&player, &[]).to_add, qualifying_roles);
+    }
+
+    #[test]
+    fn it_skips_roles_in_cooldown() {
+        let mut roles_updates = UserRoleChanges {
+            to_add: vec![RoleId::new(1), RoleId::new(2)],
+            to_remove: vec![RoleId::new(3)],
+            ..Default::default()
+        };
+
+        roles_updates.skip_roles(&[RoleId::new(2)]);
+        assert_eq!(roles_updates.to_add, vec![RoleId::new(1)]);
+        assert_eq!(roles_updates.to_remove, vec![RoleId::new(3)]);
+
+        roles_updates.skip_roles(&[RoleId::new(1), RoleId::new(3)]);
+        assert!(!roles_updates.is_changed());
+    }
+
+    #[test]
+    fn it_checks_clan_wars_star_range() {
+        let mut clan_settings = ClanSettings::default();
+        assert!(clan_settings.is_in_clan_wars_star_range(0.0));
+        assert!(clan_settings.is_in_clan_wars_star_range(15.0));
+
+        clan_settings.set_clan_wars_star_range(Some(5.0), Some(9.0));
+        assert!(!clan_settings.is_in_clan_wars_star_range(4.99));
+        assert!(clan_settings.is_in_clan_wars_star_range(5.0));
+        assert!(clan_settings.is_in_clan_wars_star_range(9.0));
+        assert!(!clan_settings.is_in_clan_wars_star_range(9.01));
+
+        clan_settings.set_clan_wars_star_range(None, Some(9.0));
+        assert!(clan_settings.is_in_clan_wars_star_range(0.0));
+        assert!(!clan_settings.is_in_clan_wars_star_range(10.0));
+    }
+
+    #[test]
+    fn it_paginates_role_changes_digest() {
+        let mut digest = RoleChangesDigest::default();
+        assert!(digest.to_messages().is_empty());
+
+        for user_id in 1..=3 {
+            digest.add(&UserRoleChanges {
+                user_id: UserId::new(user_id),
+                to_add: vec![RoleId::new(1)],
+                to_remove: vec![RoleId::new(2)],
+                ..Default::default()
+            });
+        }
+        digest.add(&UserRoleChanges {
+            user_id: UserId::new(4),
+            ..Default::default()
+        });
+
+        assert_eq!(
+            digest.to_messages(),
+            vec!["**Role changes summary:** 3 user(s) updated\n+<@&1> ×3\n-<@&2> ×3"]
+        );
+
+        let mut digest = RoleChangesDigest::default();
+        digest.add(&UserRoleChanges {
+            to_add: (1..=200).map(RoleId::new).collect(),
+            ..Default::default()
+        });
+
+        let messages = digest.to_messages();
+        assert!(messages.len() > 1);
+        assert!(messages
+            .iter()
+            .all(|m| m.chars().count() <= MAX_DISCORD_MESSAGE_LENGTH));
+        assert_eq!(
+            messages.iter().map(|m| m.lines().count()).sum::<usize>(),
+            201
+        );
+    }
+
+    #[test]
+    fn it_paginates_guild_settings_at_role_group_boundaries() {
+        let mut gs = GuildSettings::new(GuildId::new(1));
+
+        assert_eq!(gs.to_messages(), vec![gs.to_string()]);
+
+        for group in 1..=40 {
+            for role in 1..=5 {
+                let mut rs = create_5kpp_ss_50_country_role_settings();
+                rs.set_role_id(RoleId::new(group * 100 + role));
+
+                gs.add(format!("group-{}", group), rs);
+            }
+        }
+
+        assert!(gs.to_string().chars().count() > MAX_DISCORD_MESSAGE_LENGTH);
+
+        let messages = gs.to_messages();
+        assert!(messages.len() > 1);
+        assert!(messages
+            .iter()
+            .all(|m| m.chars().count() <= MAX_DISCORD_MESSAGE_LENGTH));
+        assert!(messages
+            .iter()
+            .skip(1)
+            .all(|m| m.starts_with("### Group: __")));
+        assert_eq!(messages.join("\n"), gs.to_string());
+    }
+
+    #[test]
+    fn it_migrates_v0_guild_settings() {
+        let v0_json = r#"{
+            "guildId": "1",
+            "botChannelId": "10",
+            "requiresVerifiedProfile": true,
+            "roleGroups": {
+                "rank": {
+                    "3": {
+                        "roleId": "3",
+                        "conditions": {"1": {"condition": "betterThanOrEqualTo", "value": {"rank": 1000}}},
+                        "weight": 100
+                    }
+                },
+                "pp": {
+                    "2": {
+                        "roleId": "2",
+                        "conditions": {"1": {"condition": "betterThanOrEqualTo", "value": {"totalPp": 10000.0}}},
+                        "weight": 200
+                    }
+                }
+            },
+            "clanSettings": null
+        }"#;
+
+        let mut gs = serde_json::from_str::<GuildSettings>(v0_json).unwrap();
+        assert_eq!(gs.schema_version, 0);
+
+        assert!(gs.migrate());
+        assert_eq!(gs.schema_version, GUILD_SETTINGS_SCHEMA_VERSION);
+        assert!(gs.group_order.is_empty());
+        assert_eq!(gs.get_ordered_groups(), vec!["pp", "rank"]);
+        assert_eq!(gs.get_key(), GuildId::new(1));
+        assert!(gs.requires_verified_profile);
+        assert!(gs.contains_in_group("pp".to_string(), RoleId::new(2)));
+        assert!(gs.contains_in_group("rank".to_string(), RoleId::new(3)));
+        assert!(gs.get_auto_roles_cooldown().is_zero());
+
+        // already migrated
+        assert!(!gs.migrate());
+
+        let gs = GuildSettings::new(GuildId::new(2));
+        assert_eq!(
+            gs.get_auto_roles_cooldown(),
+            Duration::hours(DEFAULT_AUTO_ROLES_COOLDOWN_HOURS as i64)
+        );
+        assert!(!gs.clone().migrate());
+    }
+
+    #[test]
+    fn it_can_check_if_role_exists_in_guild_role_group() {
+        let gs = create_guild_settings();
+
+        assert!(!gs.contains_in_group("invalid".to_string(), RoleId::new(1)));
+        assert!(!gs.contains_in_group("rank".to_string(), RoleId::new(1000)));
+        assert!(gs.contains_in_group("rank".to_string(), RoleId::new(3)));
+        assert!(gs.contains_in_group("rank".to_string(), RoleId::new(5)));
+    }
+
+    #[test]
+    fn it_can_get_all_roles_set_in_guild() {
+        let gs = create_guild_settings();
+
+        let mut roles = gs.all_roles();
+        roles.sort_unstable();
+
+        assert_eq!(
+            roles,
+            vec![
+                &RoleId::new(1),
+                &RoleId::new(2),
+                &RoleId::new(3),
+                &RoleId::new(4),
+                &RoleId::new(5),
+                &RoleId::new(6),
+                &RoleId::new(7),
+                &RoleId::new(8)
+            ]
+        );
+    }
+
+    #[test]
+    fn it_falls_back_to_bot_log_channel() {
+        let mut gs = GuildSettings::new(GuildId::new(1));
+        assert_eq!(gs.get_role_changes_channel(), None);
+
+        gs.set_channel(Some(ChannelId::new(10)));
+        gs.set_errors_channel(Some(ChannelId::new(20)));
+
+        assert_eq!(gs.get_role_changes_channel(), Some(ChannelId::new(10)));
+        assert_eq!(gs.get_errors_channel(), Some(ChannelId::new(20)));
+    }
+
+    #[test]
+    fn it_overrides_command_cooldown() {
+        let default_cooldown = std::time::Duration::from_secs(DEFAULT_COMMAND_COOLDOWN_SECS as u64);
+
+        let mut gs = GuildSettings::new(GuildId::new(1));
+        assert_eq!(gs.get_command_cooldown("bl-profile"), default_cooldown);
+
+        gs.set_command_cooldown("bl-profile", Some(30));
+        assert_eq!(
+            gs.get_command_cooldown("bl-profile"),
+            std::time::Duration::from_secs(30)
+        );
+        assert_eq!(
+            gs.get_command_cooldown("bl-practice-playlist"),
+            default_cooldown
+        );
+
+        gs.set_command_cooldown("bl-profile", Some(0));
+        assert!(gs.get_command_cooldown("bl-profile").is_zero());
+
+        gs.set_command_cooldown("bl-profile", None);
+        assert_eq!(gs.get_command_cooldown("bl-profile"), default_cooldown);
+    }
+
+    #[test]
+    fn it_rehomes_prerequisite_roles() {
+        let mut gs = create_guild_settings();
+        gs.set_role_required_role(&"rank".to_string(), RoleId::new(4), Some(RoleId::new(2)))
+            .set_role_required_role(&"pp".to_string(), RoleId::new(2), Some(RoleId::new(9)));
+
+        let unmapped = gs.rehome(GuildId::new(2000), |role_id| {
+            (role_id.get() % 2 == 0).then(|| RoleId::new(role_id.get() + 100))
+        });
+
+        assert!(unmapped.contains(&RoleId::new(9)));
+        assert_eq!(
+            unmapped.iter().filter(|role_id| role_id.get() == 9).count(),
+            1
+        );
+
+        let required_roles = gs
+            .get_auto_roles()
+            .into_iter()
+            .map(|(_, rs)| (rs.get_role_id(), rs.get_required_role()))
+            .collect::<HashMap<_, _>>();
+        assert_eq!(required_roles[&RoleId::new(104)], Some(RoleId::new(102)));
+        // prerequisites that can not be mapped are cleared
+        assert_eq!(required_roles[&RoleId::new(102)], None);
+    }
+
+    #[test]
+    fn it_rehomes_welcome_roles() {
+        let mut gs = create_guild_settings();
+        gs.set_welcome_roles(vec![RoleId::new(20), RoleId::new(21)]);
+
+        let unmapped = gs.rehome(GuildId::new(2000), |role_id| {
+            (role_id.get() % 2 == 0).then(|| RoleId::new(role_id.get() + 100))
+        });
+
+        assert!(unmapped.contains(&RoleId::new(21)));
+        assert_eq!(gs.get_welcome_roles(), &[RoleId::new(120)]);
+    }
+
+    #[test]
+    fn it_rehomes_guild_settings() {
+        let mut gs = create_guild_settings();
+        gs.set_channel(Some(ChannelId::new(10)));
+
+        let mut unmapped = gs.rehome(GuildId::new(2000), |role_id| {
+            (role_id.get() % 2 == 0).then(|| RoleId::new(role_id.get() + 100))
+        });
+        unmapped.sort_unstable();
+
+        assert_eq!(gs.get_key(), GuildId::new(2000));
+        assert_eq!(gs.get_channel(), None);
+        assert_eq!(
+            unmapped,
+            vec![
+                RoleId::new(1),
+                RoleId::new(3),
+                RoleId::new(5),
+                RoleId::new(7)
+            ]
+        );
+
+        let mut roles = gs.all_roles();
+        roles.sort_unstable();
+        assert_eq!(
+            roles,
+            vec![
+                &RoleId::new(102),
+                &RoleId::new(104),
+                &RoleId::new(106),
+                &RoleId::new(108)
+            ]
+        );
+        assert!(gs
+            .get_auto_roles()
+            .iter()
+            .all(|(_, rs)| gs.contains(rs.get_role_id())));
+    }
+
+    #[test]
+    fn it_can_check_if_role_exists_in_any_guild_role_group() {
+        let gs = create_guild_settings();
 
         assert!(!gs.contains(RoleId::new(1000)));
         assert!(gs.contains(RoleId::new(1)));
         assert!(gs.contains(RoleId::new(5)));
     }
 
+    #[test]
+    fn it_explains_role_requirements() {
+        let gs = create_guild_settings();
+
+        let player = Player {
+            pp: 7000.0,
+            top_accuracy: 91.0,
+            country_rank: 20,
+            ..Default::default()
+        };
+
+        let explained = gs.explain_roles(&player, &[]);
+        let (_, pp_roles) = explained
+            .iter()
+            .find(|(role_group, _)| role_group == "pp")
+            .unwrap();
+
+        // heaviest role first
+        assert_eq!(
+            pp_roles
+                .iter()
+                .map(|role| (role.role_id, role.fulfilled))
+                .collect::<Vec<_>>(),
+            vec![(RoleId::new(2), false), (RoleId::new(1), true)]
+        );
+        assert_eq!(pp_roles[0].requirements.len(), 1);
+        assert!(!pp_roles[0].requirements[0].fulfilled);
+        assert_eq!(
+            pp_roles[0].requirements[0].player_value,
+            PlayerMetricValue::TotalPp(7000.0)
+        );
+        assert_eq!(
+            pp_roles[0].requirements[0].to_string(),
+            "❌ **Total PP** *better than or equal to* **10000** (player: 7000.00)"
+        );
+        assert!(pp_roles[1].requirements.iter().all(|r| r.fulfilled));
+
+        // the boolean check agrees with the detailed one
+        let rs = create_10kpp_role_settings();
+        assert_eq!(rs.is_fulfilled_for(&player), rs.check(&player).fulfilled);
+    }
+
     #[test]
     fn it_resolves_which_roles_should_be_added_and_removed() {
         let gs = create_guild_settings();
@@ -2082,4 +4838,493 @@ mod tests {
             vec![RoleId::new(3), RoleId::new(6)]
         );
     }
+
+    #[test]
+    fn it_finds_last_clan_recap_window() {
+        let schedule = ClanRecapSchedule::new(Weekday::Mon, 18);
+        let timezone = Tz::Europe__Warsaw;
+
+        assert_eq!(
+            schedule.last_window(
+                Utc.with_ymd_and_hms(2026, 10, 16, 12, 0, 0).unwrap(),
+                timezone
+            ),
+            Utc.with_ymd_and_hms(2026, 10, 12, 16, 0, 0).unwrap()
+        );
+        assert_eq!(
+            schedule.last_window(
+                Utc.with_ymd_and_hms(2026, 10, 12, 16, 0, 0).unwrap(),
+                timezone
+            ),
+            Utc.with_ymd_and_hms(2026, 10, 12, 16, 0, 0).unwrap()
+        );
+        assert_eq!(
+            schedule.last_window(
+                Utc.with_ymd_and_hms(2026, 10, 12, 15, 59, 0).unwrap(),
+                timezone
+            ),
+            Utc.with_ymd_and_hms(2026, 10, 5, 16, 0, 0).unwrap()
+        );
+
+        // 2:00 does not exist on the day of the switch to summer time
+        assert_eq!(
+            ClanRecapSchedule::new(Weekday::Sun, 2).last_window(
+                Utc.with_ymd_and_hms(2026, 3, 29, 12, 0, 0).unwrap(),
+                timezone
+            ),
+            Utc.with_ymd_and_hms(2026, 3, 29, 1, 0, 0).unwrap()
+        );
+    }
+
+    fn create_score(rank: u32, song_name: &str, mode_name: &str, difficulty: (&str, u32)) -> Score {
+        let mut score = serde_json::from_value::<Score>(serde_json::json!({
+            "id": 1, "playerId": "1", "accuracy": 95.0, "fcAccuracy": 95.0, "accLeft": 115.0,
+            "accRight": 115.0, "pp": 300.0, "fcPp": 300.0, "rank": 1, "mistakes": 0,
+            "maxStreak": 100, "maxCombo": 500, "pauses": 0, "fullCombo": true, "modifiers": "",
+            "leaderboardId": "1", "songName": "", "songSubName": "", "songMapper": "",
+            "songAuthor": "", "songCover": "", "songBpm": 120.0, "songDuration": 120.0,
+            "songHash": "", "difficultyName": "", "difficultyNps": 5.0,
+            "difficultyOriginalRating": null, "difficultyRating": null,
+            "difficultyModeName": "", "difficultyValue": 0, "timeset": "1700000000",
+            "timepost": 1700000000
+        }))
+        .unwrap();
+
+        score.rank = rank;
+        score.song_name = song_name.to_owned();
+        score.difficulty_mode_name = mode_name.to_owned();
+        score.difficulty_name = difficulty.0.to_owned();
+        score.difficulty_value = difficulty.1;
+
+        score
+    }
+
+    #[test]
+    fn it_groups_top1_scores_by_difficulty() {
+        let scores = vec![
+            create_score(1, "A", "Standard", ("Expert", 7)),
+            create_score(1, "B", "Standard", ("ExpertPlus", 9)),
+            create_score(1, "C", "Standard", ("Expert", 7)),
+            create_score(1, "D", "Standard", ("Expert", 7)),
+            create_score(1, "E", "OneSaber", ("ExpertPlus", 9)),
+            create_score(2, "F", "Standard", ("Hard", 5)),
+        ];
+
+        let groups = group_top1_scores(&scores, 2);
+
+        assert_eq!(
+            groups
+                .iter()
+                .map(|group| (
+                    group.difficulty_name.as_str(),
+                    group.mode_name.as_str(),
+                    group.count
+                ))
+                .collect::<Vec<_>>(),
+            vec![
+                ("Expert", "Standard", 3),
+                ("ExpertPlus", "OneSaber", 1),
+                ("ExpertPlus", "Standard", 1),
+            ]
+        );
+        assert_eq!(groups[0].sample_songs, vec!["A", "C"]);
+
+        assert!(group_top1_scores(&[], 2).is_empty());
+    }
+
+    #[test]
+    fn it_falls_back_to_guild_playlist_cover() {
+        let mut gs = create_empty_guild_settings();
+        assert_eq!(gs.get_playlist_cover(&ClanWarsSort::ToHold), None);
+
+        gs.set_playlist_cover(None, Some("base64,guild".to_owned()));
+        gs.set_playlist_cover(
+            Some(ClanWarsSort::ToConquer),
+            Some("base64,conquer".to_owned()),
+        );
+
+        assert_eq!(
+            gs.get_playlist_cover(&ClanWarsSort::ToConquer),
+            Some("base64,conquer".to_owned())
+        );
+        assert_eq!(
+            gs.get_playlist_cover(&ClanWarsSort::ToHold),
+            Some("base64,guild".to_owned())
+        );
+
+        gs.set_playlist_cover(Some(ClanWarsSort::ToConquer), None);
+        assert_eq!(
+            gs.get_playlist_cover(&ClanWarsSort::ToConquer),
+            Some("base64,guild".to_owned())
+        );
+    }
+
+    #[test]
+    fn it_validates_playlist_cover() {
+        let mut png = Vec::new();
+        ril::Image::new(1, 1, ril::Rgba::black())
+            .encode(ril::ImageFormat::Png, &mut png)
+            .unwrap();
+
+        assert!(Playlist::encode_cover(&png)
+            .unwrap()
+            .starts_with("base64,iVBOR"));
+        assert!(Playlist::encode_cover(b"not an image").is_err());
+        assert!(Playlist::encode_cover(&vec![0; PLAYLIST_COVER_MAX_BYTES + 1]).is_err());
+    }
+
+    #[test]
+    fn it_resolves_playlist_fc_status() {
+        let mut gs = create_empty_guild_settings();
+        assert!(!gs.is_playlist_skip_fcs());
+        assert_eq!(ClanWarsFc::resolve(None, gs.is_playlist_skip_fcs()), None);
+
+        gs.set_playlist_skip_fcs(true);
+        assert_eq!(
+            ClanWarsFc::resolve(None, gs.is_playlist_skip_fcs()),
+            Some(false)
+        );
+
+        // explicit request argument overrides the guild default
+        assert_eq!(
+            ClanWarsFc::resolve(Some(ClanWarsFc::NoMatter), gs.is_playlist_skip_fcs()),
+            None
+        );
+        assert_eq!(
+            ClanWarsFc::resolve(Some(ClanWarsFc::Fc), gs.is_playlist_skip_fcs()),
+            Some(true)
+        );
+
+        gs.set_playlist_skip_fcs(false);
+        assert_eq!(
+            ClanWarsFc::resolve(Some(ClanWarsFc::NotFc), gs.is_playlist_skip_fcs()),
+            Some(false)
+        );
+
+        // refreshed playlists keep the resolved status
+        for fc_status in [None, Some(false), Some(true)] {
+            assert_eq!(ClanWarsFc::resolve(Some(fc_status.into()), true), fc_status);
+        }
+    }
+
+    #[test]
+    fn it_filters_playlist_maps_by_clan_pp_diff() {
+        // no limits
+        assert!(Playlist::clan_pp_diff_matches(0.0, 0.0, 0.0));
+        assert!(Playlist::clan_pp_diff_matches(1000.0, 0.0, 0.0));
+
+        // min is inclusive
+        assert!(!Playlist::clan_pp_diff_matches(4.99, 5.0, 0.0));
+        assert!(Playlist::clan_pp_diff_matches(5.0, 5.0, 0.0));
+
+        // max is inclusive
+        assert!(Playlist::clan_pp_diff_matches(50.0, 5.0, 50.0));
+        assert!(!Playlist::clan_pp_diff_matches(50.01, 5.0, 50.0));
+    }
+
+    #[test]
+    fn it_skips_commander_orders_below_guild_min_stars() {
+        let mut gs = GuildSettings::new(GuildId::new(1));
+
+        // no floor by default
+        assert_eq!(gs.get_commander_order_min_stars(), None);
+        assert!(Playlist::commander_order_stars_match(1.5, 0.0, 0.0));
+
+        gs.set_commander_order_min_stars(Some(6.0));
+        let min_stars = gs.get_commander_order_min_stars().unwrap_or(0.0);
+
+        // the order under the floor is excluded, the floor is inclusive
+        assert!(!Playlist::commander_order_stars_match(5.99, min_stars, 0.0));
+        assert!(Playlist::commander_order_stars_match(6.0, min_stars, 0.0));
+        assert!(Playlist::commander_order_stars_match(12.0, min_stars, 0.0));
+
+        // the player's max stars still applies
+        assert!(Playlist::commander_order_stars_match(9.0, min_stars, 9.0));
+        assert!(!Playlist::commander_order_stars_match(9.01, min_stars, 9.0));
+
+        assert!(gs
+            .format_header()
+            .contains("Commander's orders min. stars: 6.00*"));
+    }
+
+    #[test]
+    fn it_filters_playlist_maps_by_status() {
+        let statuses = [
+            DifficultyStatus::Unranked,
+            DifficultyStatus::Nominated,
+            DifficultyStatus::Qualified,
+            DifficultyStatus::Ranked,
+            DifficultyStatus::Unrankable,
+            DifficultyStatus::Outdated,
+            DifficultyStatus::InEvent,
+            DifficultyStatus::Unknown,
+        ];
+
+        for status in &statuses {
+            assert!(PlaylistMapStatus::matches(None, status));
+        }
+
+        for (map_status, expected) in [
+            (PlaylistMapStatus::Ranked, DifficultyStatus::Ranked),
+            (PlaylistMapStatus::Qualified, DifficultyStatus::Qualified),
+            (PlaylistMapStatus::Nominated, DifficultyStatus::Nominated),
+        ] {
+            for status in &statuses {
+                assert_eq!(
+                    PlaylistMapStatus::matches(Some(&map_status), status),
+                    *status == expected,
+                    "{} map status filter on {} map",
+                    map_status,
+                    status
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn it_reminds_verification_only_if_verified_profiles_are_required() {
+        let mut gs = create_empty_guild_settings();
+        assert!(!gs.is_verification_reminder_enabled());
+
+        gs.set_verification_reminder(true);
+        assert!(!gs.is_verification_reminder_enabled());
+
+        gs.set_verified_profile_requirement(true);
+        assert!(gs.is_verification_reminder_enabled());
+
+        gs.set_verification_reminder(false);
+        assert!(!gs.is_verification_reminder_enabled());
+    }
+
+    #[test]
+    fn it_expires_playlists_unused_for_ttl() {
+        let now = Utc::now();
+        let ttl = Duration::days(90);
+
+        let mut playlist = Playlist::default();
+        // not stored yet
+        assert!(!playlist.is_unused_for(ttl, now));
+
+        playlist.created_at = Some(now - Duration::days(100));
+        assert!(playlist.is_unused_for(ttl, now));
+
+        playlist.last_accessed = Some(now - Duration::days(10));
+        assert!(!playlist.is_unused_for(ttl, now));
+
+        playlist.last_accessed = Some(now - Duration::days(91));
+        assert!(playlist.is_unused_for(ttl, now));
+    }
+
+    #[test]
+    fn it_compares_total_pauses() {
+        let requirement = |condition| Requirement {
+            condition,
+            value: RequirementMetricValue::TotalPauses(5),
+            tolerance: None,
+        };
+
+        let clean = PlayerMetricValue::TotalPauses(0);
+        let same = PlayerMetricValue::TotalPauses(5);
+        let pausing = PlayerMetricValue::TotalPauses(20);
+
+        // fewer pauses are better
+        assert!(requirement(Condition::BetterThanOrEqualTo).is_fulfilled_for(&clean));
+        assert!(requirement(Condition::BetterThanOrEqualTo).is_fulfilled_for(&same));
+        assert!(!requirement(Condition::BetterThanOrEqualTo).is_fulfilled_for(&pausing));
+        assert!(!requirement(Condition::BetterThan).is_fulfilled_for(&same));
+        assert!(requirement(Condition::WorseThan).is_fulfilled_for(&pausing));
+        assert!(requirement(Condition::EqualTo).is_fulfilled_for(&same));
+        assert!(!requirement(Condition::Within).is_fulfilled_for(&same));
+
+        let player = Player {
+            total_ranked_pauses: 3,
+            ..Default::default()
+        };
+        assert_eq!(
+            player.get_metric_with_value(Metric::TotalPauses),
+            PlayerMetricValue::TotalPauses(3)
+        );
+    }
+
+    #[test]
+    fn it_reports_weekly_pauses() {
+        let now = Utc.with_ymd_and_hms(2026, 5, 20, 12, 0, 0).unwrap();
+        let score = |days_ago: i64, pauses: u32| {
+            let mut score = create_score(1, "A", "Standard", ("Expert", 7));
+            score.timepost = now - Duration::days(days_ago);
+            score.pauses = pauses;
+
+            score
+        };
+
+        let report = weekly_pause_report(
+            &[
+                score(0, 2),
+                score(6, 0),
+                score(8, 1),
+                score(8, 3),
+                score(30, 5),
+                // outside of the report
+                score(100, 10),
+                score(-1, 10),
+            ],
+            now,
+            3,
+        );
+
+        assert_eq!(report.len(), 3);
+        assert_eq!(
+            report
+                .iter()
+                .map(|week| (week.plays, week.paused_plays, week.pauses))
+                .collect::<Vec<_>>(),
+            vec![(2, 1, 2), (2, 2, 4), (0, 0, 0)]
+        );
+        assert_eq!(report[0].since, now - Duration::weeks(1));
+        assert_eq!(report[0].pauses_per_play(), 1.0);
+        assert_eq!(report[2].pauses_per_play(), 0.0);
+    }
+
+    #[test]
+    fn it_measures_acc_consistency_only_with_enough_scores() {
+        let scores = (0..10)
+            .map(|idx| {
+                let mut score = create_score(1, "A", "Standard", ("Expert", 7));
+                score.accuracy = if idx % 2 == 0 { 0.9 } else { 0.8 };
+
+                score
+            })
+            .collect::<Vec<_>>();
+        let top_scores = scores.iter().collect::<Vec<_>>();
+
+        assert_eq!(get_acc_consistency(&top_scores[..1]), None);
+        assert_eq!(get_acc_consistency(&top_scores[..9]), None);
+        assert!(get_acc_consistency(&top_scores).is_some_and(|v| v > 0.0));
+    }
+
+    #[test]
+    fn it_grants_welcome_roles_to_verified_players_only() {
+        let mut gs = create_empty_guild_settings();
+        gs.set_welcome_roles(vec![RoleId::new(10), RoleId::new(20)]);
+
+        let mut player = Player {
+            is_verified: true,
+            ..Default::default()
+        };
+
+        let role_changes = gs.get_welcome_role_changes(&player, &[RoleId::new(20)]);
+        assert_eq!(role_changes.to_add, vec![RoleId::new(10)]);
+        assert!(role_changes.to_remove.is_empty());
+        assert!(!role_changes.dry_run);
+
+        player.is_verified = false;
+        assert!(!gs.get_welcome_role_changes(&player, &[]).is_changed());
+    }
+
+    #[test]
+    fn it_does_not_remove_welcome_roles_by_auto_roles() {
+        let mut gs = create_empty_guild_settings();
+
+        let mut rs = RoleSettings::new(RoleId::new(10), 100);
+        rs.add_requirement(
+            Condition::BetterThanOrEqualTo,
+            RequirementMetricValue::TotalPp(1000.0),
+        );
+        gs.add("pp".to_string(), rs);
+
+        let player = Player {
+            pp: 500.0,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            gs.get_role_updates(&player, &[RoleId::new(10)]).to_remove,
+            vec![RoleId::new(10)]
+        );
+
+        gs.set_welcome_roles(vec![RoleId::new(10)]);
+        assert!(gs
+            .get_role_updates(&player, &[RoleId::new(10)])
+            .to_remove
+            .is_empty());
+    }
+
+    #[test]
+    fn it_shows_profiles_publicly_by_default() {
+        let mut gs = create_empty_guild_settings();
+        assert!(!gs.is_profile_private());
+        assert!(gs.to_string().contains("Profile visibility: Public"));
+
+        gs.set_profile_private(true);
+        assert!(gs.is_profile_private());
+        assert!(gs.to_string().contains("Profile visibility: Private"));
+    }
+
+    #[test]
+    fn it_formats_player_metric_values_with_decimal_places() {
+        assert_eq!(PlayerMetricValue::TopPp(123.4567).to_string(), "123.46");
+        assert_eq!(
+            PlayerMetricValue::TopPp(123.4567).to_string_with_decimal_places(0),
+            "123"
+        );
+        assert_eq!(
+            PlayerMetricValue::AccConsistency(Some(1.23456)).to_string_with_decimal_places(3),
+            "1.235"
+        );
+        assert_eq!(
+            PlayerMetricValue::Rank(5).to_string_with_decimal_places(0),
+            "5"
+        );
+    }
+
+    #[test]
+    fn it_formats_with_guild_decimal_places() {
+        let mut gs = create_empty_guild_settings();
+        assert_eq!(gs.get_decimal_places(), 2);
+        assert_eq!(gs.get_theme().decimal_places, 2);
+        assert_eq!(format_decimal(95.4567, gs.get_decimal_places()), "95.46");
+
+        gs.set_decimal_places(Some(0));
+        assert_eq!(gs.get_theme().decimal_places, 0);
+        assert_eq!(format_decimal(95.4567, gs.get_decimal_places()), "95");
+
+        gs.set_decimal_places(Some(5));
+        assert_eq!(format_decimal(95.4567, gs.get_decimal_places()), "95.457");
+
+        gs.set_decimal_places(None);
+        assert_eq!(gs.get_decimal_places(), 2);
+
+        let mut rs = RoleSettings::new(RoleId::new(1), 100);
+        rs.add_requirement(
+            Condition::BetterThanOrEqualTo,
+            RequirementMetricValue::TopAcc(90.0),
+        );
+        gs.add("acc".to_string(), rs);
+        gs.set_decimal_places(Some(0));
+
+        let player = Player {
+            top_accuracy: 95.4567,
+            ..Default::default()
+        };
+        let explained = gs.explain_roles(&player, &[]);
+        assert!(explained[0].1[0].requirements[0]
+            .to_string()
+            .ends_with("(player: 95)"));
+    }
+
+    #[test]
+    fn it_parses_leaderboard_ids_from_list() {
+        assert_eq!(
+            get_leaderboard_ids_from_list(
+                "https://www.beatleader.com/leaderboard/clanranking/3b9c991/1, 1a2b3c91\n\
+                 https://beatleader.xyz/leaderboard/global/4f5e6d71/2 1a2b3c91 not-an-id"
+            ),
+            vec![
+                "3b9c991".to_owned(),
+                "1a2b3c91".to_owned(),
+                "4f5e6d71".to_owned()
+            ]
+        );
+        assert!(get_leaderboard_ids_from_list("  ,\n ").is_empty());
+    }
 }
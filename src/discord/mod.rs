@@ -1,30 +1,42 @@
+use std::future::Future;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 pub(crate) use poise::serenity_prelude as serenity;
-use poise::serenity_prelude::{ActivityData, ClientBuilder};
+use poise::serenity_prelude::{ActivityData, ClientBuilder, GatewayError};
 use poise::Framework;
 use tokio::signal;
 use tokio_util::sync::CancellationToken;
 use tokio_util::task::TaskTracker;
-use tracing::{info, warn};
+use tracing::{debug, error, info, warn};
 
 use worker::oauth::BlOauthTokenRefreshWorker;
 
 use crate::beatleader::oauth::OAuthAppCredentials;
 use crate::config::Settings;
 use crate::discord::worker::clan_contribution::BlClanContributionWorker;
+use crate::discord::worker::clan_map_captures::BlClanMapCapturesWorker;
 use crate::discord::worker::clan_peak::BlClanPeakWorker;
+use crate::discord::worker::clan_recap::BlClanRecapWorker;
 use crate::discord::worker::clan_wars::BlClanWarsMapsWorker;
 use crate::discord::worker::player_stats::BlPlayersStatsWorker;
+use crate::discord::worker::replay_watches::BlReplayWatchesWorker;
 use crate::discord::worker::user_roles::UserRolesWorker;
+use crate::other::clan_membership_cache::{ClanMembershipCache, CLAN_MEMBERSHIP_CACHE_TTL};
+use crate::other::verification_reminders::VerificationReminders;
 use crate::persist::CommonData;
 use crate::storage::bsmaps::BsMapsRepository;
+use crate::storage::clan_map_capture::ClanMapCaptureRepository;
 use crate::storage::clan_peak::ClanPeakRepository;
+use crate::storage::clan_recap::ClanRecapRepository;
+use crate::storage::clan_wars_post::ClanWarsPostRepository;
 use crate::storage::guild::GuildSettingsRepository;
 use crate::storage::player::PlayerRepository;
 use crate::storage::player_oauth_token::PlayerOAuthTokenRepository;
-use crate::storage::player_scores::PlayerScoresRepository;
+use crate::storage::player_scores::PlayerScoresRepositories;
 use crate::storage::playlist::PlaylistRepository;
+use crate::storage::role_change::RoleChangeRepository;
+use crate::{BL_CLIENT, METRICS};
 
 pub mod bot;
 mod worker;
@@ -32,11 +44,17 @@ mod worker;
 pub(crate) struct BotData {
     pub guild_settings_repository: Arc<GuildSettingsRepository>,
     pub players_repository: Arc<PlayerRepository>,
-    pub player_scores_repository: Arc<PlayerScoresRepository>,
+    pub player_scores_repositories: Arc<PlayerScoresRepositories>,
     pub player_oauth_token_repository: Arc<PlayerOAuthTokenRepository>,
     pub playlists_repository: Arc<PlaylistRepository>,
     pub maps_repository: Arc<BsMapsRepository>,
     pub clan_peak_repository: Arc<ClanPeakRepository>,
+    pub clan_map_capture_repository: Arc<ClanMapCaptureRepository>,
+    pub role_change_repository: Arc<RoleChangeRepository>,
+    pub clan_wars_post_repository: Arc<ClanWarsPostRepository>,
+    pub clan_recap_repository: Arc<ClanRecapRepository>,
+    pub clan_membership_cache: Arc<ClanMembershipCache>,
+    pub verification_reminders: Arc<VerificationReminders>,
     pub settings: Settings,
 }
 
@@ -58,11 +76,17 @@ impl From<CommonData> for BotData {
         Self {
             guild_settings_repository: value.guild_settings_repository,
             players_repository: value.players_repository,
-            player_scores_repository: value.player_scores_repository,
+            player_scores_repositories: value.player_scores_repositories,
             player_oauth_token_repository: value.player_oauth_token_repository,
             playlists_repository: value.playlists_repository,
             maps_repository: value.maps_repository,
             clan_peak_repository: value.clan_peak_repository,
+            clan_map_capture_repository: value.clan_map_capture_repository,
+            role_change_repository: value.role_change_repository,
+            clan_wars_post_repository: value.clan_wars_post_repository,
+            clan_recap_repository: value.clan_recap_repository,
+            clan_membership_cache: Arc::new(ClanMembershipCache::new(CLAN_MEMBERSHIP_CACHE_TTL)),
+            verification_reminders: Arc::new(VerificationReminders::new()),
             settings: value.settings,
         }
     }
@@ -70,6 +94,36 @@ impl From<CommonData> for BotData {
 
 pub(crate) type Context<'a> = poise::Context<'a, BotData, crate::Error>;
 
+// runs the task every interval until the token is cancelled
+async fn run_every<F, Fut>(name: &str, interval: Duration, token: CancellationToken, mut task: F)
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = ()>,
+{
+    info!("Run {} every {:?}", name, interval);
+
+    loop {
+        task().await;
+
+        tokio::select! {
+            _ = token.cancelled() => {
+                warn!("{} is shutting down...", name);
+                break;
+            }
+            _ = tokio::time::sleep(interval) => {}
+        }
+    }
+
+    warn!("{} shut down.", name);
+}
+
+// the client is restarted after transient gateway errors, the process panics after that many attempts
+const CLIENT_START_MAX_ATTEMPTS: u32 = 8;
+const CLIENT_RESTART_INITIAL_DELAY: Duration = Duration::from_secs(5);
+const CLIENT_RESTART_MAX_DELAY: Duration = Duration::from_secs(5 * 60);
+// a client running at least that long before failing starts counting attempts and backoff anew
+const CLIENT_STABLE_RUN_DURATION: Duration = Duration::from_secs(30 * 60);
+
 pub struct DiscordClient {
     client: serenity::Client,
     tracker: TaskTracker,
@@ -89,6 +143,8 @@ impl DiscordClient {
             pre_command: |ctx| {
                 Box::pin(async move {
                     info!("Executing command {}...", ctx.command().qualified_name);
+
+                    METRICS.record_command(&ctx.command().qualified_name);
                 })
             },
             // This code is run after a command if it was successful (returned Ok)
@@ -97,6 +153,29 @@ impl DiscordClient {
                     info!("Executed command {}!", ctx.command().qualified_name);
                 })
             },
+            event_handler: |ctx, event, _framework, data| {
+                Box::pin(async move {
+                    if let serenity::FullEvent::GuildMemberAddition { new_member } = event {
+                        if !new_member.user.bot
+                            && !data
+                                .players_repository
+                                .get(&new_member.user.id)
+                                .await
+                                .is_some_and(|player| player.is_verified)
+                        {
+                            bot::commands::player::remind_profile_verification(
+                                ctx,
+                                data,
+                                new_member.guild_id,
+                                new_member.user.id,
+                            )
+                            .await;
+                        }
+                    }
+
+                    Ok(())
+                })
+            },
             on_error: |error| {
                 Box::pin(async move {
                     match error {
@@ -106,6 +185,16 @@ impl DiscordClient {
                         poise::FrameworkError::Command { error, ctx, .. } => {
                             info!("Error in command `{}`: {:?}", ctx.command().name, error,);
                         }
+                        // the guild cooldown check has already replied to the user
+                        poise::FrameworkError::CommandCheckFailed {
+                            error: None, ctx, ..
+                        } => {
+                            debug!(
+                                "Command `{}` check failed for user {}.",
+                                ctx.command().name,
+                                ctx.author().name
+                            );
+                        }
                         error => {
                             if let Err(e) = poise::builtins::on_error(error).await {
                                 info!("Error while handling error: {}", e)
@@ -120,11 +209,15 @@ impl DiscordClient {
         let tracker_clone = tracker.clone();
         let token_clone = token.clone();
 
+        // member join events are sent only with the privileged intent enabled
+        let intents = if settings.guild_members_intent {
+            serenity::GatewayIntents::non_privileged() | serenity::GatewayIntents::GUILD_MEMBERS
+        } else {
+            serenity::GatewayIntents::non_privileged() // | serenity::GatewayIntents::MESSAGE_CONTENT
+        };
+
         Self {
-            client: ClientBuilder::new(
-                settings.discord_token.clone(),
-                serenity::GatewayIntents::non_privileged(), // | serenity::GatewayIntents::MESSAGE_CONTENT
-            )
+            client: ClientBuilder::new(settings.discord_token.clone(), intents)
             .framework(
                 Framework::builder()
                     .options(options)
@@ -140,7 +233,9 @@ impl DiscordClient {
 
                             let bl_oauth_token_refresh_worker = BlOauthTokenRefreshWorker::new(
                                 data.clone().into(),
-                                chrono::Duration::seconds(settings.refresh_interval as i64 + 30),
+                                chrono::Duration::seconds(
+                                    settings.get_oauth_refresh_interval() as i64 + 30,
+                                ),
                                 token_clone.clone(),
                             );
                             let bl_clan_wars_maps_worker = BlClanWarsMapsWorker::new(
@@ -151,6 +246,11 @@ impl DiscordClient {
                             );
                             let bl_players_stats_worker =
                                 BlPlayersStatsWorker::new(data.clone().into(), token_clone.clone());
+                            let bl_replay_watches_worker = BlReplayWatchesWorker::new(
+                                ctx.clone(),
+                                data.clone().into(),
+                                token_clone.clone(),
+                            );
                             let discord_user_roles_worker = UserRolesWorker::new(
                                 ctx.clone(),
                                 data.clone().into(),
@@ -171,25 +271,100 @@ impl DiscordClient {
                                 token_clone.clone(),
                             );
 
+                            let bl_clan_map_captures_worker = BlClanMapCapturesWorker::new(
+                                ctx.clone(),
+                                data.clone().into(),
+                                token_clone.clone(),
+                            );
+
+                            let bl_clan_recap_worker = BlClanRecapWorker::new(
+                                ctx.clone(),
+                                data.clone().into(),
+                                token_clone.clone(),
+                            );
+
                             let data: BotData = data.into();
 
+                            // tasks run on independent timers, so the expensive player stats
+                            // and roles update does not hold back the cheap ones
+                            let oauth_refresh_interval =
+                                Duration::from_secs(settings.get_oauth_refresh_interval());
+                            let oauth_token = token_clone.clone();
+                            tracker_clone.spawn(async move {
+                                run_every(
+                                    "BL oauth token refresh task",
+                                    oauth_refresh_interval,
+                                    oauth_token,
+                                    || async {
+                                        let started_at = Instant::now();
+                                        bl_oauth_token_refresh_worker.run().await;
+                                        METRICS.record_worker_run(
+                                            "oauth_token_refresh",
+                                            started_at.elapsed(),
+                                        );
+                                    },
+                                )
+                                .await;
+                            });
+
+                            let clan_peak_interval =
+                                Duration::from_secs(settings.get_clan_peak_interval());
+                            let clan_peak_token = token_clone.clone();
+                            tracker_clone.spawn(async move {
+                                run_every(
+                                    "BL clan peak task",
+                                    clan_peak_interval,
+                                    clan_peak_token,
+                                    || async {
+                                        let started_at = Instant::now();
+                                        bl_clan_peak_worker.run().await;
+                                        METRICS.record_worker_run("clan_peak", started_at.elapsed());
+                                    },
+                                )
+                                .await;
+                            });
+
                             tracker_clone.spawn(async move {
                                 let interval =
                                     std::time::Duration::from_secs(settings.refresh_interval);
                                 info!("Run tasks that update data every {:?}", interval);
 
                                 'outer: loop {
-                                    bl_oauth_token_refresh_worker.run().await;
+                                    let started_at = Instant::now();
+                                    bl_clan_map_captures_worker.run().await;
+                                    METRICS.record_worker_run("clan_map_captures", started_at.elapsed());
+
+                                    let started_at = Instant::now();
+                                    let bot_players = bl_players_stats_worker.run().await;
+                                    METRICS.record_worker_run("player_stats", started_at.elapsed());
 
-                                    bl_clan_peak_worker.run().await;
+                                    if let Ok(bot_players) = bot_players {
+                                        let started_at = Instant::now();
+                                        bl_replay_watches_worker.run(&bot_players).await;
+                                        METRICS.record_worker_run("replay_watches", started_at.elapsed());
 
-                                    if let Ok(bot_players) = bl_players_stats_worker.run().await {
+                                        let started_at = Instant::now();
                                         discord_user_roles_worker.run(bot_players).await;
+                                        METRICS.record_worker_run("user_roles", started_at.elapsed());
                                     }
 
+                                    let started_at = Instant::now();
                                     bl_clan_contribution_maps_worker.run().await;
+                                    METRICS.record_worker_run("clan_contribution", started_at.elapsed());
 
+                                    let started_at = Instant::now();
+                                    bl_clan_recap_worker.run().await;
+                                    METRICS.record_worker_run("clan_recap", started_at.elapsed());
+
+                                    let started_at = Instant::now();
                                     bl_clan_wars_maps_worker.run().await;
+                                    METRICS.record_worker_run("clan_wars_maps", started_at.elapsed());
+
+                                    let rate_limiter_stats = BL_CLIENT.rate_limiter_stats();
+                                    info!(
+                                        "BL API requests sent so far: {}, blocked by rate limiter: {}",
+                                        rate_limiter_stats.requests, rate_limiter_stats.blocked
+                                    );
 
                                     tokio::select! {
                                         _ = token_clone.cancelled() => {
@@ -216,6 +391,8 @@ impl DiscordClient {
     }
 
     pub async fn start(mut self) {
+        let token = self.token.clone();
+
         #[cfg(windows)]
         let shard_manager_clone_win = self.client.shard_manager.clone();
 
@@ -270,9 +447,60 @@ impl DiscordClient {
             }
         });
 
-        self.client
-            .start()
-            .await
-            .expect("Can not start discord client");
+        let mut attempt = 0;
+        loop {
+            let started_at = Instant::now();
+            let err = match self.client.start().await {
+                Ok(_) => break,
+                Err(err) => err,
+            };
+
+            if token.is_cancelled() {
+                break;
+            }
+
+            // unrelated outages over the process lifetime should not add up to the limit
+            if started_at.elapsed() >= CLIENT_STABLE_RUN_DURATION {
+                attempt = 0;
+            }
+
+            attempt += 1;
+            if attempt >= CLIENT_START_MAX_ATTEMPTS || Self::is_permanent_failure(&err) {
+                panic!(
+                    "Can not start discord client after {} attempt(s): {}",
+                    attempt, err
+                );
+            }
+
+            let delay = CLIENT_RESTART_INITIAL_DELAY
+                .saturating_mul(2u32.saturating_pow(attempt - 1))
+                .min(CLIENT_RESTART_MAX_DELAY);
+
+            error!(
+                "Discord client stopped due to an error: {}. Restarting in {}s (attempt {}/{})...",
+                err,
+                delay.as_secs(),
+                attempt + 1,
+                CLIENT_START_MAX_ATTEMPTS
+            );
+
+            tokio::select! {
+                _ = token.cancelled() => break,
+                _ = tokio::time::sleep(delay) => {}
+            }
+        }
+    }
+
+    // errors that will not go away by reconnecting
+    fn is_permanent_failure(err: &serenity::Error) -> bool {
+        matches!(
+            err,
+            serenity::Error::Gateway(
+                GatewayError::InvalidAuthentication
+                    | GatewayError::NoAuthentication
+                    | GatewayError::InvalidGatewayIntents
+                    | GatewayError::DisallowedGatewayIntents
+            )
+        )
     }
 }
@@ -6,7 +6,7 @@ use chrono::Utc;
 use cli_table::{format::Justify, Cell, ColorChoice, Table};
 use poise::serenity_prelude::{
     AutoArchiveDuration, ChannelType, CreateAllowedMentions, CreateAttachment, CreateMessage,
-    CreateThread,
+    CreateThread, UserId,
 };
 use serde::{Deserialize, Serialize};
 use tokio_util::sync::CancellationToken;
@@ -64,7 +64,7 @@ impl BlClanContributionWorker {
             context,
             guild_settings_repository: data.guild_settings_repository,
             player_repository: data.players_repository,
-            player_scores_repository: data.player_scores_repository,
+            player_scores_repository: Arc::clone(data.player_scores_repositories.general()),
             refresh_interval,
             token,
         }
@@ -104,132 +104,24 @@ impl BlClanContributionWorker {
                                     clan_settings.get_clan()
                                 );
 
-                                let mut soldiers = HashMap::<PlayerId, Player>::new();
-                                for user_id in clan_settings.get_clan_wars_soldiers().iter() {
-                                    match self.player_repository.get(user_id).await {
-                                        Some(player) => {
-                                            if player
-                                                .is_primary_clan_member(&clan_settings.get_clan())
-                                            {
-                                                soldiers.insert(player.id.clone(), player);
-                                            }
-                                        }
-                                        None => {
-                                            tracing::warn!(
-                                                "Can not get player for user @{}",
-                                                user_id
-                                            )
-                                        }
-                                    };
-                                }
-
-                                match self
-                                    .get_stats(
-                                        clan_settings.get_clan(),
-                                        ClanWarsSort::ToHold,
-                                        None,
-                                        &soldiers,
-                                    )
-                                    .await
+                                match calculate_clan_contribution(
+                                    clan_settings.get_clan(),
+                                    clan_settings.get_clan_wars_soldiers(),
+                                    &self.player_repository,
+                                    &self.player_scores_repository,
+                                )
+                                .await
                                 {
-                                    Some(mut captured_clan_stats) => {
+                                    Some(ClanContribution {
+                                        captured_clan_stats,
+                                        bonus_maps_count,
+                                    }) => {
                                         tracing::info!(
                                             "{} clan contribution captured maps found. Posting contribution to channel #{}",
                                             captured_clan_stats.maps_count,
                                             clan_wars_channel_id
                                         );
 
-                                        const MIN_BONUS_MAPS_COUNT: u32 = 20;
-                                        const MAX_BONUS_MAPS_COUNT: u32 = 50;
-                                        let conquer_clan_stats = match self
-                                            .get_stats(
-                                                clan_settings.get_clan(),
-                                                ClanWarsSort::ToConquer,
-                                                Some(
-                                                    if captured_clan_stats.maps_count
-                                                        < MIN_BONUS_MAPS_COUNT
-                                                    {
-                                                        MIN_BONUS_MAPS_COUNT
-                                                    } else {
-                                                        captured_clan_stats
-                                                            .maps_count
-                                                            .min(MAX_BONUS_MAPS_COUNT)
-                                                    },
-                                                ),
-                                                &soldiers,
-                                            )
-                                            .await
-                                        {
-                                            None => None,
-                                            Some(conquer_clan_stats) => {
-                                                tracing::info!(
-                                                    "{} clan contribution maps to conquer found.",
-                                                    conquer_clan_stats.maps_count,
-                                                );
-
-                                                Some((
-                                                    conquer_clan_stats.maps_count,
-                                                    conquer_clan_stats
-                                                        .soldiers
-                                                        .into_iter()
-                                                        .map(|s| (s.player.id.clone(), s))
-                                                        .collect::<HashMap<_, _>>(),
-                                                ))
-                                            }
-                                        };
-
-                                        let bonus_maps_count = conquer_clan_stats
-                                            .as_ref()
-                                            .map_or_else(|| 0, |cs| cs.0);
-
-                                        captured_clan_stats.soldiers.iter_mut().for_each(|s| {
-                                            if let Some(bonus_stats) = &conquer_clan_stats {
-                                                if let Some(bonus) = bonus_stats.1.get(&s.player.id)
-                                                {
-                                                    s.bonus_maps_count = bonus.maps_count;
-                                                    s.bonus_points = bonus.points;
-                                                    s.total_points = s.points
-                                                        + CLAN_WEIGHT_COEFFICIENT * bonus.points;
-                                                } else {
-                                                    s.total_points = s.points;
-                                                }
-                                            } else {
-                                                s.total_points = s.points;
-                                            }
-                                        });
-
-                                        if let Some(bonus_stats) = &conquer_clan_stats {
-                                            bonus_stats.1.iter().for_each(|(player_id, stats)| {
-                                                if !captured_clan_stats
-                                                    .soldiers
-                                                    .iter()
-                                                    .any(|s| &s.player.id == player_id)
-                                                {
-                                                    captured_clan_stats.soldiers.extend(vec![
-                                                        ClanSoldierStats {
-                                                            player: stats.player.clone(),
-                                                            maps_count: 0,
-                                                            total_pp: 0.0,
-                                                            total_weighted_pp: 0.0,
-                                                            efficiency: 0.0,
-                                                            map_percentages: 0.0,
-                                                            points: 0.0,
-                                                            bonus_maps_count: stats.maps_count,
-                                                            bonus_points: stats.points,
-                                                            total_points: stats.points
-                                                                * CLAN_WEIGHT_COEFFICIENT,
-                                                        },
-                                                    ]);
-                                                }
-                                            });
-                                        }
-
-                                        captured_clan_stats.soldiers.sort_unstable_by(|a, b| {
-                                            b.total_points
-                                                .partial_cmp(&a.total_points)
-                                                .unwrap_or(Ordering::Equal)
-                                        });
-
                                         let table = captured_clan_stats
                                             .soldiers
                                             .iter()
@@ -341,7 +233,7 @@ impl BlClanContributionWorker {
                                                             .enumerate()
                                                             .map(|(idx, s)| {
                                                                 format!(
-                                                                    "{:0pad$}. {} **{:.2} points**\n",
+                                                                    "{:0pad$}. {} **{:.2} points**",
                                                                     idx + 1,
                                                                     s.player.name.clone(),
                                                                     s.total_points,
@@ -406,187 +298,308 @@ impl BlClanContributionWorker {
             }
         }
     }
+}
 
-    async fn get_stats(
-        &self,
-        clan_tag: ClanTag,
-        sort: ClanWarsSort,
-        count: Option<u32>,
-        soldiers: &HashMap<PlayerId, Player>,
-    ) -> Option<ClanStats> {
-        match ClanWars::fetch(clan_tag.clone(), sort.clone(), count, true, None).await {
-            Ok(mut clan_wars) => {
-                let mut clan_stats = ClanStats {
-                    clan_tag,
-                    ..Default::default()
-                };
+#[derive(Debug, Clone)]
+pub(crate) struct ClanContribution {
+    pub captured_clan_stats: ClanStats,
+    pub bonus_maps_count: u32,
+}
 
-                // get all relevant leaderboard ids
-                let leaderboard_ids = clan_wars
-                    .maps
-                    .iter()
-                    .map(|m| m.map.leaderboard.id.clone())
-                    .collect::<Vec<_>>();
-
-                // fetch soldiers scores for relevant leaderboards and add to the clan wars
-                for (player_id, player) in soldiers.iter() {
-                    match self.player_scores_repository.get(player_id).await {
-                        Some(player_scores) => player_scores
-                            .scores
-                            .into_iter()
-                            .filter_map(|score| {
-                                if !leaderboard_ids.contains(&score.leaderboard_id) {
-                                    return None;
-                                }
+// soldiers of captured clan stats are sorted by total points, including bonus points for the maps
+// to conquer
+pub(crate) async fn calculate_clan_contribution(
+    clan_tag: ClanTag,
+    soldier_user_ids: &[UserId],
+    player_repository: &PlayerRepository,
+    player_scores_repository: &PlayerScoresRepository,
+) -> Option<ClanContribution> {
+    const MIN_BONUS_MAPS_COUNT: u32 = 20;
+    const MAX_BONUS_MAPS_COUNT: u32 = 50;
+
+    let mut soldiers = HashMap::<PlayerId, Player>::new();
+    for user_id in soldier_user_ids.iter() {
+        match player_repository.get(user_id).await {
+            Some(player) => {
+                if player.is_primary_clan_member(&clan_tag) {
+                    soldiers.insert(player.id.clone(), player);
+                }
+            }
+            None => {
+                tracing::warn!("Can not get player for user @{}", user_id)
+            }
+        };
+    }
 
-                                Some((
-                                    score.leaderboard_id,
-                                    ClanMapScore {
-                                        id: 0,
-                                        player_id: score.player_id.clone(),
-                                        player: ClanPlayer {
-                                            id: score.player_id.clone(),
-                                            name: player.name.clone(),
-                                            avatar: player.avatar.clone(),
-                                            country: player.country.clone(),
-                                            rank: player.rank,
-                                            country_rank: player.country_rank,
-                                            pp: player.pp,
-                                        },
-                                        accuracy: score.accuracy,
-                                        pp: score.pp,
-                                        rank: score.rank,
-                                        bad_cuts: 0,
-                                        bomb_cuts: 0,
-                                        missed_notes: 0,
-                                        walls_hit: 0,
-                                        full_combo: score.full_combo,
-                                        modifiers: score.modifiers,
-                                        timeset: score.timeset,
-                                        timepost: score.timepost,
-                                    },
-                                ))
-                            })
-                            .collect::<HashMap<LeaderboardId, ClanMapScore>>(),
-                        None => HashMap::new(),
-                    }
+    let mut captured_clan_stats = get_stats(
+        player_scores_repository,
+        clan_tag.clone(),
+        ClanWarsSort::ToHold,
+        None,
+        &soldiers,
+    )
+    .await?;
+
+    let conquer_clan_stats = match get_stats(
+        player_scores_repository,
+        clan_tag,
+        ClanWarsSort::ToConquer,
+        Some(if captured_clan_stats.maps_count < MIN_BONUS_MAPS_COUNT {
+            MIN_BONUS_MAPS_COUNT
+        } else {
+            captured_clan_stats.maps_count.min(MAX_BONUS_MAPS_COUNT)
+        }),
+        &soldiers,
+    )
+    .await
+    {
+        None => None,
+        Some(conquer_clan_stats) => {
+            tracing::info!(
+                "{} clan contribution maps to conquer found.",
+                conquer_clan_stats.maps_count,
+            );
+
+            Some((
+                conquer_clan_stats.maps_count,
+                conquer_clan_stats
+                    .soldiers
                     .into_iter()
-                    .for_each(|(leaderboard_id, clan_map_score)| {
-                        match clan_wars
-                            .maps
-                            .iter()
-                            .position(|m| m.map.leaderboard.id == leaderboard_id)
-                        {
-                            None => {
-                                tracing::warn!(
-                                    "Can not find an index for clan wars maps for leaderboardId {}",
-                                    &leaderboard_id
-                                );
-                            }
-                            Some(idx) => {
-                                clan_wars.maps[idx].scores.push(clan_map_score);
+                    .map(|s| (s.player.id.clone(), s))
+                    .collect::<HashMap<_, _>>(),
+            ))
+        }
+    };
+
+    let bonus_maps_count = conquer_clan_stats.as_ref().map_or_else(|| 0, |cs| cs.0);
+
+    captured_clan_stats.soldiers.iter_mut().for_each(|s| {
+        if let Some(bonus_stats) = &conquer_clan_stats {
+            if let Some(bonus) = bonus_stats.1.get(&s.player.id) {
+                s.bonus_maps_count = bonus.maps_count;
+                s.bonus_points = bonus.points;
+                s.total_points = s.points + CLAN_WEIGHT_COEFFICIENT * bonus.points;
+            } else {
+                s.total_points = s.points;
+            }
+        } else {
+            s.total_points = s.points;
+        }
+    });
+
+    if let Some(bonus_stats) = &conquer_clan_stats {
+        bonus_stats.1.iter().for_each(|(player_id, stats)| {
+            if !captured_clan_stats
+                .soldiers
+                .iter()
+                .any(|s| &s.player.id == player_id)
+            {
+                captured_clan_stats.soldiers.extend(vec![ClanSoldierStats {
+                    player: stats.player.clone(),
+                    maps_count: 0,
+                    total_pp: 0.0,
+                    total_weighted_pp: 0.0,
+                    efficiency: 0.0,
+                    map_percentages: 0.0,
+                    points: 0.0,
+                    bonus_maps_count: stats.maps_count,
+                    bonus_points: stats.points,
+                    total_points: stats.points * CLAN_WEIGHT_COEFFICIENT,
+                }]);
+            }
+        });
+    }
+
+    captured_clan_stats.soldiers.sort_unstable_by(|a, b| {
+        b.total_points
+            .partial_cmp(&a.total_points)
+            .unwrap_or(Ordering::Equal)
+    });
+
+    Some(ClanContribution {
+        captured_clan_stats,
+        bonus_maps_count,
+    })
+}
+
+async fn get_stats(
+    player_scores_repository: &PlayerScoresRepository,
+    clan_tag: ClanTag,
+    sort: ClanWarsSort,
+    count: Option<u32>,
+    soldiers: &HashMap<PlayerId, Player>,
+) -> Option<ClanStats> {
+    match ClanWars::fetch(clan_tag.clone(), sort.clone(), count, true, None).await {
+        Ok(mut clan_wars) => {
+            let mut clan_stats = ClanStats {
+                clan_tag,
+                ..Default::default()
+            };
+
+            // get all relevant leaderboard ids
+            let leaderboard_ids = clan_wars
+                .maps
+                .iter()
+                .map(|m| m.map.leaderboard.id.clone())
+                .collect::<Vec<_>>();
+
+            // fetch soldiers scores for relevant leaderboards and add to the clan wars
+            for (player_id, player) in soldiers.iter() {
+                match player_scores_repository.get(player_id).await {
+                    Some(player_scores) => player_scores
+                        .scores
+                        .into_iter()
+                        .filter_map(|score| {
+                            if !leaderboard_ids.contains(&score.leaderboard_id) {
+                                return None;
                             }
-                        };
-                    });
-                }
 
-                // sort scores for all clan wars maps by pp desc
-                for map in clan_wars.maps.iter_mut() {
-                    map.scores.sort_unstable_by(|a, b| {
-                        b.pp.partial_cmp(&a.pp).unwrap_or(Ordering::Equal)
-                    });
+                            Some((
+                                score.leaderboard_id,
+                                ClanMapScore {
+                                    id: 0,
+                                    player_id: score.player_id.clone(),
+                                    player: ClanPlayer {
+                                        id: score.player_id.clone(),
+                                        name: player.name.clone(),
+                                        avatar: player.avatar.clone(),
+                                        country: player.country.clone(),
+                                        rank: player.rank,
+                                        country_rank: player.country_rank,
+                                        pp: player.pp,
+                                    },
+                                    accuracy: score.accuracy,
+                                    pp: score.pp,
+                                    rank: score.rank,
+                                    bad_cuts: 0,
+                                    bomb_cuts: 0,
+                                    missed_notes: 0,
+                                    walls_hit: 0,
+                                    full_combo: score.full_combo,
+                                    modifiers: score.modifiers,
+                                    timeset: score.timeset,
+                                    timepost: score.timepost,
+                                },
+                            ))
+                        })
+                        .collect::<HashMap<LeaderboardId, ClanMapScore>>(),
+                    None => HashMap::new(),
                 }
+                .into_iter()
+                .for_each(|(leaderboard_id, clan_map_score)| {
+                    match clan_wars
+                        .maps
+                        .iter()
+                        .position(|m| m.map.leaderboard.id == leaderboard_id)
+                    {
+                        None => {
+                            tracing::warn!(
+                                "Can not find an index for clan wars maps for leaderboardId {}",
+                                &leaderboard_id
+                            );
+                        }
+                        Some(idx) => {
+                            clan_wars.maps[idx].scores.push(clan_map_score);
+                        }
+                    };
+                });
+            }
 
-                drop(leaderboard_ids);
+            // sort scores for all clan wars maps by pp desc
+            for map in clan_wars.maps.iter_mut() {
+                map.scores
+                    .sort_unstable_by(|a, b| b.pp.partial_cmp(&a.pp).unwrap_or(Ordering::Equal));
+            }
 
-                let mut player_stats = HashMap::<PlayerId, ClanSoldierStats>::new();
+            drop(leaderboard_ids);
 
-                for map in clan_wars.maps.into_iter() {
-                    clan_stats.maps_count += 1;
+            let mut player_stats = HashMap::<PlayerId, ClanSoldierStats>::new();
 
-                    let max_map_pp = if !map.scores.is_empty() {
-                        map.scores.first().unwrap().pp
-                    } else {
-                        0.0
-                    };
+            for map in clan_wars.maps.into_iter() {
+                clan_stats.maps_count += 1;
 
-                    for (idx, score) in map.scores.into_iter().enumerate() {
-                        let weight = CLAN_WEIGHT_COEFFICIENT.powi(idx as i32);
-                        let weighted_pp = score.pp * weight;
-
-                        clan_stats.total_pp += weighted_pp;
-
-                        player_stats
-                            .entry(score.player_id)
-                            .and_modify(|s| {
-                                s.maps_count += 1;
-                                s.total_pp += score.pp;
-                                s.total_weighted_pp += weighted_pp;
-                                s.map_percentages += if max_map_pp > 0.0 {
-                                    score.pp / max_map_pp
-                                } else {
-                                    0.0
-                                };
-                            })
-                            .or_insert(ClanSoldierStats {
-                                player: score.player,
-                                maps_count: 1,
-                                total_pp: score.pp,
-                                total_weighted_pp: weighted_pp,
-                                map_percentages: if max_map_pp > 0.0 {
-                                    score.pp / max_map_pp
-                                } else {
-                                    0.0
-                                },
-                                points: 0.0,
-                                efficiency: 0.0,
-                                bonus_maps_count: 0,
-                                bonus_points: 0.0,
-                                total_points: 0.0,
-                            });
-                    }
+                let max_map_pp = if !map.scores.is_empty() {
+                    map.scores.first().unwrap().pp
+                } else {
+                    0.0
+                };
+
+                for (idx, score) in map.scores.into_iter().enumerate() {
+                    let weight = CLAN_WEIGHT_COEFFICIENT.powi(idx as i32);
+                    let weighted_pp = score.pp * weight;
+
+                    clan_stats.total_pp += weighted_pp;
+
+                    player_stats
+                        .entry(score.player_id)
+                        .and_modify(|s| {
+                            s.maps_count += 1;
+                            s.total_pp += score.pp;
+                            s.total_weighted_pp += weighted_pp;
+                            s.map_percentages += if max_map_pp > 0.0 {
+                                score.pp / max_map_pp
+                            } else {
+                                0.0
+                            };
+                        })
+                        .or_insert(ClanSoldierStats {
+                            player: score.player,
+                            maps_count: 1,
+                            total_pp: score.pp,
+                            total_weighted_pp: weighted_pp,
+                            map_percentages: if max_map_pp > 0.0 {
+                                score.pp / max_map_pp
+                            } else {
+                                0.0
+                            },
+                            points: 0.0,
+                            efficiency: 0.0,
+                            bonus_maps_count: 0,
+                            bonus_points: 0.0,
+                            total_points: 0.0,
+                        });
                 }
+            }
 
-                clan_stats.soldiers = player_stats
-                    .into_values()
-                    .filter_map(|mut s| {
-                        if !soldiers.contains_key(&s.player.id) {
-                            return None;
-                        }
+            clan_stats.soldiers = player_stats
+                .into_values()
+                .filter_map(|mut s| {
+                    if !soldiers.contains_key(&s.player.id) {
+                        return None;
+                    }
 
-                        s.efficiency = if s.total_pp > 0.0 {
-                            s.total_weighted_pp / s.total_pp * 100.0
-                        } else {
-                            0.0
-                        };
-
-                        s.points = if clan_stats.maps_count > 0 {
-                            // map 0-1 range into 70-98% acc
-                            let percent_of_maps_played =
-                                (s.maps_count as f64 / clan_stats.maps_count as f64) / 100.0 * 0.28
-                                    + 0.7;
-                            s.map_percentages
-                                * curve_at_value(percent_of_maps_played)
-                                * std::f64::consts::PI
-                        } else {
-                            0.0
-                        };
+                    s.efficiency = if s.total_pp > 0.0 {
+                        s.total_weighted_pp / s.total_pp * 100.0
+                    } else {
+                        0.0
+                    };
+
+                    s.points = if clan_stats.maps_count > 0 {
+                        // map 0-1 range into 70-98% acc
+                        let percent_of_maps_played =
+                            (s.maps_count as f64 / clan_stats.maps_count as f64) / 100.0 * 0.28
+                                + 0.7;
+                        s.map_percentages
+                            * curve_at_value(percent_of_maps_played)
+                            * std::f64::consts::PI
+                    } else {
+                        0.0
+                    };
 
-                        Some(s)
-                    })
-                    .collect();
+                    Some(s)
+                })
+                .collect();
 
-                Some(clan_stats)
-            }
-            Err(err) => {
-                tracing::error!(
-                    "Can not fetch clan contribution map list ({}): {:?}",
-                    sort,
-                    err
-                );
-
-                None
-            }
+            Some(clan_stats)
+        }
+        Err(err) => {
+            tracing::error!(
+                "Can not fetch clan contribution map list ({}): {:?}",
+                sort,
+                err
+            );
+
+            None
         }
     }
 }
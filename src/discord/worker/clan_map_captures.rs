@@ -0,0 +1,250 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use chrono::Utc;
+use poise::serenity_prelude::{ChannelId, CreateAllowedMentions, CreateEmbed, CreateMessage};
+use tokio_util::sync::CancellationToken;
+
+use crate::beatleader::clan::{ClanId, ClanMap, ClanTag};
+use crate::discord::bot::beatleader::clan::fetch_clan_captured_maps;
+use crate::discord::{serenity, BotData};
+use crate::storage::clan_map_capture::{
+    ClanMapCapture, ClanMapCaptureKey, ClanMapCaptureRepository,
+};
+use crate::storage::guild::GuildSettingsRepository;
+
+const MAX_EMBEDS_PER_MESSAGE: usize = 10;
+
+pub struct BlClanMapCapturesWorker {
+    context: serenity::Context,
+    guild_settings_repository: Arc<GuildSettingsRepository>,
+    clan_map_capture_repository: Arc<ClanMapCaptureRepository>,
+    token: CancellationToken,
+}
+
+impl BlClanMapCapturesWorker {
+    pub fn new(context: serenity::Context, data: BotData, token: CancellationToken) -> Self {
+        Self {
+            context,
+            guild_settings_repository: data.guild_settings_repository,
+            clan_map_capture_repository: data.clan_map_capture_repository,
+            token,
+        }
+    }
+
+    pub async fn run(&self) {
+        // the same clan can be set up on many guilds, so fetch its maps only once
+        let mut clans = HashMap::<ClanId, (ClanTag, Vec<ChannelId>)>::new();
+        for guild in self.guild_settings_repository.all().await {
+            if let Some(clan_settings) = guild.get_clan_settings() {
+                if let Some(clan_wars_channel_id) = clan_settings.get_clan_wars_maps_channel() {
+                    clans
+                        .entry(clan_settings.get_clan_id())
+                        .or_insert_with(|| (clan_settings.get_clan(), vec![]))
+                        .1
+                        .push(clan_wars_channel_id);
+                }
+            }
+        }
+
+        for (clan_id, (clan_tag, channel_ids)) in clans {
+            if self.token.is_cancelled() {
+                tracing::warn!("Clan map captures worker cancelled.");
+                return;
+            }
+
+            tracing::info!("Checking clan {} map captures...", &clan_tag);
+
+            let captured_maps = match fetch_clan_captured_maps(&clan_tag).await {
+                Ok(captured_maps) => captured_maps,
+                Err(err) => {
+                    tracing::error!("Can not fetch {} clan captured maps: {}", &clan_tag, err);
+                    continue;
+                }
+            };
+
+            let previous = self.clan_map_capture_repository.by_clan(clan_id).await;
+            // nothing is stored for a clan checked for the first time, do not flood the channel;
+            // captures stored before the check marker was introduced count as a check
+            let is_first_check =
+                previous.is_empty() && !self.clan_map_capture_repository.is_checked(clan_id).await;
+
+            let (captured, lost) =
+                get_clan_map_captures_diff(clan_id, &clan_tag, previous, captured_maps);
+
+            tracing::info!(
+                "Clan {} map captures: {} captured, {} lost{}.",
+                &clan_tag,
+                captured.len(),
+                lost.len(),
+                if is_first_check {
+                    " (first check, nothing will be posted)"
+                } else {
+                    ""
+                }
+            );
+
+            let mut embeds = Vec::with_capacity(captured.len() + lost.len());
+            if !is_first_check {
+                embeds.extend(captured.iter().map(|capture| {
+                    CreateEmbed::new()
+                        .description(format!("🏆 {} captured {}", &clan_tag, capture))
+                        .color(0x2ecc71)
+                }));
+            }
+            embeds.extend(lost.iter().map(|capture| {
+                CreateEmbed::new()
+                    .description(format!("💀 {} lost {}", &clan_tag, capture))
+                    .color(0xe74c3c)
+            }));
+
+            // store the new state first so that a failed post does not repeat the notifications
+            if let Err(err) = self.clan_map_capture_repository.set_all(captured).await {
+                tracing::error!("Can not store {} clan captured maps: {}", &clan_tag, err);
+                continue;
+            }
+
+            if let Err(err) = self
+                .clan_map_capture_repository
+                .remove_all(
+                    &lost
+                        .into_iter()
+                        .map(|capture| capture.key)
+                        .collect::<Vec<_>>(),
+                )
+                .await
+            {
+                tracing::error!("Can not remove {} clan lost maps: {}", &clan_tag, err);
+                continue;
+            }
+
+            if is_first_check {
+                if let Err(err) = self.clan_map_capture_repository.set_checked(clan_id).await {
+                    tracing::error!("Can not mark {} clan maps as checked: {}", &clan_tag, err);
+                    continue;
+                }
+            }
+
+            for channel_id in channel_ids {
+                for chunk in embeds.chunks(MAX_EMBEDS_PER_MESSAGE) {
+                    let message = CreateMessage::new()
+                        .embeds(chunk.to_vec())
+                        .allowed_mentions(CreateAllowedMentions::new());
+
+                    if let Err(err) = channel_id.send_message(&self.context, message).await {
+                        tracing::error!(
+                            "Can not post {} clan map captures to channel #{}: {}",
+                            &clan_tag,
+                            channel_id,
+                            err
+                        );
+                        break;
+                    }
+                }
+            }
+
+            tracing::info!("Clan {} map captures checked.", &clan_tag);
+        }
+    }
+}
+
+// maps captured since the previous check and the previously held maps that are lost now
+fn get_clan_map_captures_diff(
+    clan_id: ClanId,
+    clan_tag: &ClanTag,
+    previous: Vec<ClanMapCapture>,
+    captured_maps: Vec<ClanMap>,
+) -> (Vec<ClanMapCapture>, Vec<ClanMapCapture>) {
+    let previous_ids = previous
+        .iter()
+        .map(|capture| capture.key.leaderboard_id.clone())
+        .collect::<HashSet<_>>();
+    let current_ids = captured_maps
+        .iter()
+        .map(|map| map.leaderboard.id.clone())
+        .collect::<HashSet<_>>();
+
+    let captured = captured_maps
+        .into_iter()
+        .filter(|map| !previous_ids.contains(&map.leaderboard.id))
+        .map(|map| ClanMapCapture {
+            key: ClanMapCaptureKey {
+                clan_id,
+                leaderboard_id: map.leaderboard.id,
+            },
+            clan_tag: clan_tag.clone(),
+            song_name: map.leaderboard.song.name,
+            difficulty_name: map.leaderboard.difficulty.difficulty_name,
+            captured_at: Utc::now(),
+        })
+        .collect::<Vec<_>>();
+    let lost = previous
+        .into_iter()
+        .filter(|capture| !current_ids.contains(&capture.key.leaderboard_id))
+        .collect::<Vec<_>>();
+
+    (captured, lost)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::beatleader::clan::ClanMap;
+    use crate::beatleader::player::Leaderboard;
+    use crate::discord::worker::clan_map_captures::get_clan_map_captures_diff;
+    use crate::storage::clan_map_capture::{ClanMapCapture, ClanMapCaptureKey};
+
+    fn capture(leaderboard_id: &str) -> ClanMapCapture {
+        ClanMapCapture {
+            key: ClanMapCaptureKey {
+                clan_id: 1,
+                leaderboard_id: leaderboard_id.to_owned(),
+            },
+            ..Default::default()
+        }
+    }
+
+    fn clan_map(leaderboard_id: &str) -> ClanMap {
+        ClanMap {
+            leaderboard: Leaderboard {
+                id: leaderboard_id.to_owned(),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    fn leaderboard_ids(captures: &[ClanMapCapture]) -> Vec<&str> {
+        captures
+            .iter()
+            .map(|capture| capture.key.leaderboard_id.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn it_diffs_clan_map_captures() {
+        let (captured, lost) = get_clan_map_captures_diff(
+            1,
+            &"ABC".to_owned(),
+            vec![capture("a"), capture("b")],
+            vec![clan_map("b"), clan_map("c")],
+        );
+
+        assert_eq!(leaderboard_ids(&captured), vec!["c"]);
+        assert_eq!(captured[0].key.clan_id, 1);
+        assert_eq!(captured[0].clan_tag, "ABC");
+        assert_eq!(leaderboard_ids(&lost), vec!["a"]);
+    }
+
+    #[test]
+    fn it_diffs_clan_map_captures_from_no_maps() {
+        let (captured, lost) =
+            get_clan_map_captures_diff(1, &"ABC".to_owned(), vec![], vec![clan_map("a")]);
+        assert_eq!(leaderboard_ids(&captured), vec!["a"]);
+        assert!(lost.is_empty());
+
+        let (captured, lost) =
+            get_clan_map_captures_diff(1, &"ABC".to_owned(), vec![capture("a")], vec![]);
+        assert!(captured.is_empty());
+        assert_eq!(leaderboard_ids(&lost), vec!["a"]);
+    }
+}
@@ -0,0 +1,413 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use chrono::Utc;
+use poise::serenity_prelude::{CreateAllowedMentions, CreateEmbed, CreateMessage, GuildId};
+use tokio_util::sync::CancellationToken;
+
+use crate::beatleader::clan::ClanTag;
+use crate::discord::bot::beatleader::player::Player;
+use crate::discord::bot::ClanSettings;
+use crate::discord::worker::clan_contribution::calculate_clan_contribution;
+use crate::discord::{serenity, BotData};
+use crate::storage::clan_map_capture::{ClanMapCapture, ClanMapCaptureRepository};
+use crate::storage::clan_recap::{ClanRecapRepository, ClanRecapSnapshot};
+use crate::storage::guild::GuildSettingsRepository;
+use crate::storage::player::PlayerRepository;
+use crate::storage::player_scores::PlayerScoresRepository;
+
+// embed field values are limited to 1024 characters, map links are long
+const MAX_MAPS_LISTED: usize = 8;
+const MAX_CONTRIBUTORS_LISTED: usize = 5;
+const MAX_CLIMBERS_LISTED: usize = 5;
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ClanRecap {
+    pub captured: Vec<ClanMapCapture>,
+    pub lost: Vec<ClanMapCapture>,
+    // player name, previous and current rank, the biggest climb first
+    pub climbers: Vec<(String, u32, u32)>,
+}
+
+impl ClanRecap {
+    // maps captured and lost again between recaps are not included
+    pub fn new(
+        previous: &ClanRecapSnapshot,
+        current: &ClanRecapSnapshot,
+        players: &[Player],
+    ) -> Self {
+        let previous_ids = previous
+            .captures
+            .iter()
+            .map(|capture| &capture.key.leaderboard_id)
+            .collect::<HashSet<_>>();
+        let current_ids = current
+            .captures
+            .iter()
+            .map(|capture| &capture.key.leaderboard_id)
+            .collect::<HashSet<_>>();
+
+        let mut climbers = players
+            .iter()
+            .filter_map(|player| {
+                let previous_rank = *previous.player_ranks.get(&player.id)?;
+
+                (player.rank > 0 && previous_rank > player.rank)
+                    .then(|| (player.name.clone(), previous_rank, player.rank))
+            })
+            .collect::<Vec<_>>();
+        climbers.sort_unstable_by(|a, b| (b.1 - b.2).cmp(&(a.1 - a.2)).then(a.2.cmp(&b.2)));
+        climbers.truncate(MAX_CLIMBERS_LISTED);
+
+        Self {
+            captured: current
+                .captures
+                .iter()
+                .filter(|capture| !previous_ids.contains(&capture.key.leaderboard_id))
+                .cloned()
+                .collect(),
+            lost: previous
+                .captures
+                .iter()
+                .filter(|capture| !current_ids.contains(&capture.key.leaderboard_id))
+                .cloned()
+                .collect(),
+            climbers,
+        }
+    }
+}
+
+pub struct BlClanRecapWorker {
+    context: serenity::Context,
+    guild_settings_repository: Arc<GuildSettingsRepository>,
+    player_repository: Arc<PlayerRepository>,
+    player_scores_repository: Arc<PlayerScoresRepository>,
+    clan_map_capture_repository: Arc<ClanMapCaptureRepository>,
+    clan_recap_repository: Arc<ClanRecapRepository>,
+    token: CancellationToken,
+}
+
+impl BlClanRecapWorker {
+    pub fn new(context: serenity::Context, data: BotData, token: CancellationToken) -> Self {
+        Self {
+            context,
+            guild_settings_repository: data.guild_settings_repository,
+            player_repository: data.players_repository,
+            player_scores_repository: Arc::clone(data.player_scores_repositories.general()),
+            clan_map_capture_repository: data.clan_map_capture_repository,
+            clan_recap_repository: data.clan_recap_repository,
+            token,
+        }
+    }
+
+    pub async fn run(&self) {
+        for guild in self.guild_settings_repository.all().await {
+            if self.token.is_cancelled() {
+                tracing::warn!("Clan recap worker cancelled.");
+                return;
+            }
+
+            let Some(clan_settings) = guild.get_clan_settings() else {
+                continue;
+            };
+            let Some(schedule) = clan_settings.get_clan_recap_schedule() else {
+                continue;
+            };
+            let Some(channel_id) = clan_settings.get_clan_wars_maps_channel() else {
+                continue;
+            };
+
+            let guild_id = guild.get_key();
+            let clan_tag = clan_settings.get_clan();
+            let window = schedule.last_window(Utc::now(), guild.get_timezone());
+            let previous = self.clan_recap_repository.get(&guild_id).await;
+
+            let previous = match (clan_settings.get_clan_recap_posted_at(), previous) {
+                (Some(posted_at), _) if posted_at >= window => {
+                    tracing::info!("Clan {} recap do not require posting yet.", &clan_tag);
+
+                    continue;
+                }
+                (Some(_), Some(previous)) => previous,
+                _ => {
+                    // recap just enabled, take the snapshot to compare the first recap against
+                    tracing::info!(
+                        "No previous clan {} recap snapshot, taking a new one without posting...",
+                        &clan_tag
+                    );
+
+                    if self.set_posted_at(&guild_id, &clan_tag).await {
+                        self.take_snapshot(&guild_id, &clan_settings).await;
+                    }
+
+                    continue;
+                }
+            };
+
+            // set the posted time first, so the recap is not posted again after a restart
+            if !self.set_posted_at(&guild_id, &clan_tag).await {
+                continue;
+            }
+
+            tracing::info!(
+                "Posting clan {} recap to channel #{}...",
+                &clan_tag,
+                channel_id
+            );
+
+            let Some(current) = self.take_snapshot(&guild_id, &clan_settings).await else {
+                continue;
+            };
+
+            let players = self.clan_players(&guild_id, &clan_tag).await;
+            let recap = ClanRecap::new(&previous, &current, &players);
+
+            let top_contributors = calculate_clan_contribution(
+                clan_tag.clone(),
+                clan_settings.get_clan_wars_soldiers(),
+                &self.player_repository,
+                &self.player_scores_repository,
+            )
+            .await
+            .map(|contribution| {
+                contribution
+                    .captured_clan_stats
+                    .soldiers
+                    .into_iter()
+                    .take(MAX_CONTRIBUTORS_LISTED)
+                    .map(|soldier| (soldier.player.name, soldier.total_points))
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+
+            let embed = CreateEmbed::new()
+                .title(format!("{} weekly recap", &clan_tag))
+                .description(format!(
+                    "Since <t:{}:f>, currently holding {} map(s)",
+                    previous.taken_at.timestamp(),
+                    current.captures.len()
+                ))
+                .field(
+                    format!("🏆 Captured maps: {}", recap.captured.len()),
+                    format_maps(&recap.captured),
+                    false,
+                )
+                .field(
+                    format!("💀 Lost maps: {}", recap.lost.len()),
+                    format_maps(&recap.lost),
+                    false,
+                )
+                .field(
+                    "Top contributors",
+                    if top_contributors.is_empty() {
+                        "None".to_owned()
+                    } else {
+                        top_contributors
+                            .iter()
+                            .enumerate()
+                            .map(|(idx, (name, points))| {
+                                format!("{}. {} **{:.2} points**", idx + 1, name, points)
+                            })
+                            .collect::<Vec<_>>()
+                            .join("\n")
+                    },
+                    false,
+                )
+                .field(
+                    "Biggest rank climbers",
+                    if recap.climbers.is_empty() {
+                        "None".to_owned()
+                    } else {
+                        recap
+                            .climbers
+                            .iter()
+                            .map(|(name, previous_rank, rank)| {
+                                format!(
+                                    "{} #{} → **#{}** (+{})",
+                                    name,
+                                    previous_rank,
+                                    rank,
+                                    previous_rank - rank
+                                )
+                            })
+                            .collect::<Vec<_>>()
+                            .join("\n")
+                    },
+                    false,
+                )
+                .color(0x3498db);
+
+            let message = CreateMessage::new()
+                .embed(embed)
+                .allowed_mentions(CreateAllowedMentions::new());
+
+            match channel_id.send_message(&self.context, message).await {
+                Ok(_) => tracing::info!("Clan {} recap posted.", &clan_tag),
+                Err(err) => tracing::error!(
+                    "Can not post clan {} recap to channel #{}: {}",
+                    &clan_tag,
+                    channel_id,
+                    err
+                ),
+            }
+        }
+    }
+
+    async fn set_posted_at(&self, guild_id: &GuildId, clan_tag: &ClanTag) -> bool {
+        match self
+            .guild_settings_repository
+            .set_clan_recap_posted_at(guild_id, Utc::now())
+            .await
+        {
+            Ok(_) => true,
+            Err(err) => {
+                tracing::error!(
+                    "Can not set clan recap posted time for clan {}: {:?}",
+                    clan_tag,
+                    err
+                );
+
+                false
+            }
+        }
+    }
+
+    async fn clan_players(&self, guild_id: &GuildId, clan_tag: &ClanTag) -> Vec<Player> {
+        self.player_repository
+            .all()
+            .await
+            .into_iter()
+            .filter(|player| {
+                player.is_linked_to_guild(guild_id) && player.is_primary_clan_member(clan_tag)
+            })
+            .collect()
+    }
+
+    async fn take_snapshot(
+        &self,
+        guild_id: &GuildId,
+        clan_settings: &ClanSettings,
+    ) -> Option<ClanRecapSnapshot> {
+        let clan_tag = clan_settings.get_clan();
+
+        let snapshot = ClanRecapSnapshot {
+            guild_id: *guild_id,
+            clan_tag: clan_tag.clone(),
+            taken_at: Utc::now(),
+            player_ranks: self
+                .clan_players(guild_id, &clan_tag)
+                .await
+                .into_iter()
+                .map(|player| (player.id, player.rank))
+                .collect(),
+            captures: self
+                .clan_map_capture_repository
+                .by_clan(clan_settings.get_clan_id())
+                .await,
+        };
+
+        match self.clan_recap_repository.set(snapshot).await {
+            Ok(snapshot) => Some(snapshot),
+            Err(err) => {
+                tracing::error!("Can not store clan {} recap snapshot: {}", &clan_tag, err);
+
+                None
+            }
+        }
+    }
+}
+
+fn format_maps(captures: &[ClanMapCapture]) -> String {
+    if captures.is_empty() {
+        return "None".to_owned();
+    }
+
+    let mut lines = captures
+        .iter()
+        .take(MAX_MAPS_LISTED)
+        .map(|capture| capture.to_string())
+        .collect::<Vec<_>>();
+
+    if captures.len() > MAX_MAPS_LISTED {
+        lines.push(format!("...and {} more", captures.len() - MAX_MAPS_LISTED));
+    }
+
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::beatleader::clan::ClanId;
+    use crate::discord::bot::beatleader::player::Player;
+    use crate::discord::worker::clan_recap::ClanRecap;
+    use crate::storage::clan_map_capture::{ClanMapCapture, ClanMapCaptureKey};
+    use crate::storage::clan_recap::ClanRecapSnapshot;
+
+    fn capture(leaderboard_id: &str) -> ClanMapCapture {
+        ClanMapCapture {
+            key: ClanMapCaptureKey {
+                clan_id: ClanId::default(),
+                leaderboard_id: leaderboard_id.to_owned(),
+            },
+            ..Default::default()
+        }
+    }
+
+    fn player(id: &str, rank: u32) -> Player {
+        Player {
+            id: id.to_owned(),
+            name: id.to_owned(),
+            rank,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn it_compares_recap_snapshots() {
+        let previous = ClanRecapSnapshot {
+            player_ranks: HashMap::from([
+                ("1".to_owned(), 100),
+                ("2".to_owned(), 50),
+                ("3".to_owned(), 500),
+            ]),
+            captures: vec![capture("a"), capture("b")],
+            ..Default::default()
+        };
+        let current = ClanRecapSnapshot {
+            captures: vec![capture("b"), capture("c")],
+            ..Default::default()
+        };
+        let players = vec![
+            player("1", 90),
+            player("2", 60),
+            player("3", 400),
+            player("4", 10),
+        ];
+
+        let recap = ClanRecap::new(&previous, &current, &players);
+
+        assert_eq!(
+            recap
+                .captured
+                .iter()
+                .map(|c| c.key.leaderboard_id.as_str())
+                .collect::<Vec<_>>(),
+            vec!["c"]
+        );
+        assert_eq!(
+            recap
+                .lost
+                .iter()
+                .map(|c| c.key.leaderboard_id.as_str())
+                .collect::<Vec<_>>(),
+            vec!["a"]
+        );
+        // players who dropped or joined since the previous recap are not climbers
+        assert_eq!(
+            recap.climbers,
+            vec![("3".to_owned(), 500, 400), ("1".to_owned(), 100, 90)]
+        );
+    }
+}
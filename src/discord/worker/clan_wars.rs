@@ -1,25 +1,28 @@
 use std::cmp::Ordering;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 
 use chrono::Utc;
 use poise::serenity_prelude::{
     AutoArchiveDuration, ChannelId, ChannelType, CreateAllowedMentions, CreateEmbed, CreateMessage,
-    CreateThread, UserId,
+    CreateThread, GuildId, UserId,
 };
 use tokio_util::sync::CancellationToken;
 
 use crate::beatleader::oauth::OAuthAppCredentials;
-use crate::beatleader::player::PlayerId;
-use crate::discord::bot::beatleader::clan::{ClanWars, ClanWarsSort};
+use crate::beatleader::player::{LeaderboardId, PlayerId};
+use crate::discord::bot::beatleader::clan::{ClanMapWithScores, ClanWars, ClanWarsSort};
 use crate::discord::bot::ClanSettings;
 use crate::discord::{serenity, BotData};
 use crate::storage::bsmaps::BsMapsRepository;
+use crate::storage::clan_wars_post::{ClanWarsPostKey, ClanWarsPostRepository};
 use crate::storage::guild::GuildSettingsRepository;
 use crate::storage::player::PlayerRepository;
 use crate::storage::player_oauth_token::PlayerOAuthTokenRepository;
 use crate::storage::player_scores::PlayerScoresRepository;
 
+const CLAN_WARS_POST_RETENTION_DAYS: i64 = 30;
+
 pub struct BlClanWarsMapsWorker {
     context: serenity::Context,
     guild_settings_repository: Arc<GuildSettingsRepository>,
@@ -27,6 +30,7 @@ pub struct BlClanWarsMapsWorker {
     player_scores_repository: Arc<PlayerScoresRepository>,
     player_oauth_token_repository: Arc<PlayerOAuthTokenRepository>,
     maps_repository: Arc<BsMapsRepository>,
+    clan_wars_post_repository: Arc<ClanWarsPostRepository>,
     oauth_credentials: Option<OAuthAppCredentials>,
     refresh_interval: chrono::Duration,
     token: CancellationToken,
@@ -46,9 +50,10 @@ impl BlClanWarsMapsWorker {
             context,
             guild_settings_repository: data.guild_settings_repository,
             player_repository: data.players_repository,
-            player_scores_repository: data.player_scores_repository,
+            player_scores_repository: Arc::clone(data.player_scores_repositories.general()),
             player_oauth_token_repository: data.player_oauth_token_repository,
             maps_repository: data.maps_repository,
+            clan_wars_post_repository: data.clan_wars_post_repository,
             oauth_credentials,
             refresh_interval,
             token,
@@ -57,6 +62,18 @@ impl BlClanWarsMapsWorker {
     }
 
     pub async fn run(&self) {
+        // posts are forgotten when their maps leave the map list, this only cleans up after guilds that stopped posting
+        match self
+            .clan_wars_post_repository
+            .remove_posted_before(
+                Utc::now() - chrono::Duration::days(CLAN_WARS_POST_RETENTION_DAYS),
+            )
+            .await
+        {
+            Ok(removed) => tracing::debug!("{} expired clan wars posts removed.", removed),
+            Err(err) => tracing::error!("Can not remove expired clan wars posts: {}", err),
+        }
+
         for guild in self.guild_settings_repository.all().await {
             if let Some(clan_settings) = guild.get_clan_settings() {
                 if let Some(clan_wars_channel_id) = clan_settings.get_clan_wars_maps_channel() {
@@ -116,12 +133,25 @@ impl BlClanWarsMapsWorker {
                                 match ClanWars::fetch(
                                     clan_settings.get_clan(),
                                     ClanWarsSort::ToConquer,
-                                    Some(self.count.into()),
+                                    Some(
+                                        clan_settings
+                                            .get_clan_wars_maps_count()
+                                            .unwrap_or(self.count)
+                                            .into(),
+                                    ),
                                     false,
                                     skip_leaderboard_ids,
                                 )
                                 .await
-                                {
+                                .map(|mut clan_wars| {
+                                    clan_wars.maps.retain(|map| {
+                                        clan_settings.is_in_clan_wars_star_range(
+                                            map.map.leaderboard.difficulty.stars,
+                                        )
+                                    });
+
+                                    clan_wars
+                                }) {
                                     Ok(mut clan_wars) if !clan_wars.maps.is_empty() => {
                                         clan_wars.maps.sort_unstable_by(|a, b| {
                                             a.pp_boundary
@@ -129,9 +159,49 @@ impl BlClanWarsMapsWorker {
                                                 .unwrap_or(Ordering::Equal)
                                         });
 
+                                        self.remove_unlisted_posts(&guild.get_key(), &clan_wars)
+                                            .await;
+
+                                        // maps already announced and still on the list, e.g. before a restart, are neither posted nor pinged again
+                                        let posted = self
+                                            .clan_wars_post_repository
+                                            .posted_leaderboard_ids(&guild.get_key())
+                                            .await;
+                                        let new_maps =
+                                            get_new_clan_wars_maps(&posted, &clan_wars.maps)
+                                                .into_iter()
+                                                .map(|map| {
+                                                    (
+                                                        ClanWarsPostKey {
+                                                            guild_id: guild.get_key(),
+                                                            leaderboard_id: map
+                                                                .map
+                                                                .leaderboard
+                                                                .id
+                                                                .clone(),
+                                                        },
+                                                        map,
+                                                    )
+                                                })
+                                                .collect::<Vec<_>>();
+
+                                        tracing::debug!(
+                                            "{} clan wars maps already posted, skipping.",
+                                            clan_wars.maps.len() - new_maps.len()
+                                        );
+
+                                        if new_maps.is_empty() {
+                                            tracing::info!(
+                                                "No new clan wars maps for the clan {}.",
+                                                clan_settings.get_clan()
+                                            );
+
+                                            continue;
+                                        }
+
                                         tracing::info!(
                                             "{} clan wars maps found. Posting maps to channel #{}",
-                                            clan_wars.maps.len(),
+                                            new_maps.len(),
                                             clan_wars_channel_id
                                         );
 
@@ -184,8 +254,36 @@ impl BlClanWarsMapsWorker {
                                             }
                                         };
 
-                                        for map in clan_wars.maps.iter() {
-                                            let map_description = map.to_string();
+                                        if let Some(ping_role) =
+                                            clan_settings.get_clan_wars_ping_role()
+                                        {
+                                            // a single role mention per announcement, soldiers in the map embeds are not pinged
+                                            let message = CreateMessage::new()
+                                                .content(format!(
+                                                    "<@&{}> {} new clan wars map(s)",
+                                                    ping_role,
+                                                    new_maps.len()
+                                                ))
+                                                .allowed_mentions(
+                                                    CreateAllowedMentions::new()
+                                                        .roles(vec![ping_role]),
+                                                );
+
+                                            if let Err(err) = channel_id
+                                                .send_message(&self.context, message)
+                                                .await
+                                            {
+                                                tracing::error!(
+                                                    "Can not ping clan wars role on the channel #{}: {}",
+                                                    channel_id,
+                                                    err
+                                                );
+                                            }
+                                        }
+
+                                        for (post_key, map) in new_maps {
+                                            let map_description =
+                                                map.to_post_string(guild.get_decimal_places());
 
                                             let not_played_by_soldiers = soldiers
                                                 .iter()
@@ -213,23 +311,40 @@ impl BlClanWarsMapsWorker {
                                                 map_description
                                             };
 
-                                            if let Err(err) = post_msg(
+                                            match post_msg(
                                                 &self.context,
                                                 channel_id,
                                                 description.as_str(),
                                             )
                                             .await
                                             {
-                                                tracing::error!(
-                                                    "Can not post clan wars map message to the channel #{}: {}",
-                                                    channel_id,
-                                                    err
-                                                );
+                                                Ok(_) => {
+                                                    if let Err(err) = self
+                                                        .clan_wars_post_repository
+                                                        .set_posted_at(post_key, Utc::now())
+                                                        .await
+                                                    {
+                                                        tracing::error!(
+                                                            "Can not store clan wars map post: {}",
+                                                            err
+                                                        );
+                                                    }
+                                                }
+                                                Err(err) => {
+                                                    tracing::error!(
+                                                        "Can not post clan wars map message to the channel #{}: {}",
+                                                        channel_id,
+                                                        err
+                                                    );
+                                                }
                                             }
                                         }
                                     }
-                                    Ok(_) => {
+                                    Ok(clan_wars) => {
                                         tracing::warn!("No clan wars maps found",);
+
+                                        self.remove_unlisted_posts(&guild.get_key(), &clan_wars)
+                                            .await;
                                     }
                                     Err(err) => {
                                         tracing::error!(
@@ -263,6 +378,26 @@ impl BlClanWarsMapsWorker {
         }
     }
 
+    async fn remove_unlisted_posts(&self, guild_id: &GuildId, clan_wars: &ClanWars) {
+        let listed = clan_wars
+            .maps
+            .iter()
+            .map(|map| map.map.leaderboard.id.clone())
+            .collect::<Vec<_>>();
+
+        match self
+            .clan_wars_post_repository
+            .remove_unlisted(guild_id, &listed)
+            .await
+        {
+            Ok(removed) => tracing::debug!(
+                "{} clan wars posts of maps no longer on the list removed.",
+                removed
+            ),
+            Err(err) => tracing::error!("Can not remove unlisted clan wars posts: {}", err),
+        }
+    }
+
     pub(crate) async fn get_clan_soldiers(
         &self,
         clan_settings: &ClanSettings,
@@ -283,3 +418,50 @@ impl BlClanWarsMapsWorker {
         soldiers
     }
 }
+
+// maps of the current batch that are not in the previously posted set, only these are posted and pinged
+fn get_new_clan_wars_maps<'a>(
+    posted: &HashSet<LeaderboardId>,
+    maps: &'a [ClanMapWithScores],
+) -> Vec<&'a ClanMapWithScores> {
+    maps.iter()
+        .filter(|map| !posted.contains(&map.map.leaderboard.id))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use crate::discord::bot::beatleader::clan::{AccBoundary, ClanMapWithScores};
+    use crate::discord::worker::clan_wars::get_new_clan_wars_maps;
+
+    fn clan_map(leaderboard_id: &str) -> ClanMapWithScores {
+        let mut map = ClanMapWithScores {
+            map: Default::default(),
+            scores: vec![],
+            pp_boundary: 0.0,
+            acc_boundary: AccBoundary::default(),
+        };
+        map.map.leaderboard.id = leaderboard_id.to_owned();
+
+        map
+    }
+
+    #[test]
+    fn it_finds_only_maps_not_posted_before() {
+        let maps = vec![clan_map("a"), clan_map("b"), clan_map("c")];
+
+        let posted = HashSet::from(["a".to_owned(), "b".to_owned(), "c".to_owned()]);
+        assert!(get_new_clan_wars_maps(&posted, &maps).is_empty());
+
+        let posted = HashSet::from(["a".to_owned(), "x".to_owned()]);
+        assert_eq!(
+            get_new_clan_wars_maps(&posted, &maps)
+                .iter()
+                .map(|map| map.map.leaderboard.id.as_str())
+                .collect::<Vec<_>>(),
+            vec!["b", "c"]
+        );
+    }
+}
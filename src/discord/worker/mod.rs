@@ -1,6 +1,9 @@
 pub(crate) mod clan_contribution;
+pub(crate) mod clan_map_captures;
 pub(crate) mod clan_peak;
+pub(crate) mod clan_recap;
 pub(crate) mod clan_wars;
 pub(crate) mod oauth;
 pub(crate) mod player_stats;
+pub(crate) mod replay_watches;
 pub(crate) mod user_roles;
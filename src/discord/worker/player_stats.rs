@@ -5,12 +5,13 @@ use tokio_util::sync::CancellationToken;
 
 use crate::discord::BotData;
 use crate::storage::player::PlayerRepository;
-use crate::storage::player_scores::PlayerScoresRepository;
+use crate::storage::player_scores::PlayerScoresRepositories;
 use crate::storage::StorageError;
+use crate::METRICS;
 
 pub struct BlPlayersStatsWorker {
     players_repository: Arc<PlayerRepository>,
-    player_scores_repository: Arc<PlayerScoresRepository>,
+    player_scores_repositories: Arc<PlayerScoresRepositories>,
     token: CancellationToken,
 }
 
@@ -18,18 +19,24 @@ impl BlPlayersStatsWorker {
     pub fn new(data: BotData, token: CancellationToken) -> Self {
         Self {
             players_repository: data.players_repository,
-            player_scores_repository: data.player_scores_repository,
+            player_scores_repositories: data.player_scores_repositories,
             token,
         }
     }
 
+    // players whose update failed are left out, so the roles are updated for the rest of them
     pub async fn run(&self) -> Result<Vec<Player>, StorageError> {
-        self.players_repository
+        let update = self
+            .players_repository
             .update_all_players_stats(
-                &self.player_scores_repository,
+                &self.player_scores_repositories,
                 false,
                 Some(self.token.clone()),
             )
-            .await
+            .await?;
+
+        METRICS.record_worker_items("player_stats", update.players.len(), update.failed);
+
+        Ok(update.players)
     }
 }
@@ -0,0 +1,89 @@
+use std::sync::Arc;
+
+use poise::serenity_prelude::{CreateAllowedMentions, CreateMessage};
+use tokio_util::sync::CancellationToken;
+
+use crate::discord::bot::beatleader::player::Player;
+use crate::discord::{serenity, BotData};
+use crate::storage::player::PlayerRepository;
+
+pub struct BlReplayWatchesWorker {
+    context: serenity::Context,
+    players_repository: Arc<PlayerRepository>,
+    token: CancellationToken,
+}
+
+impl BlReplayWatchesWorker {
+    pub fn new(context: serenity::Context, data: BotData, token: CancellationToken) -> Self {
+        Self {
+            context,
+            players_repository: data.players_repository,
+            token,
+        }
+    }
+
+    pub async fn run(&self, bot_players: &[Player]) {
+        for bot_player in bot_players
+            .iter()
+            .filter(|player| player.replay_watch_subscribed)
+        {
+            if self.token.is_cancelled() {
+                return;
+            }
+
+            // the first count after subscribing and decreases (e.g. BL recount) only reset the
+            // notified count, so only real increases are notified
+            if bot_player.replay_watch_notified_count == Some(bot_player.total_replay_watched) {
+                continue;
+            }
+
+            if let Some(new_watches) = bot_player.get_new_replay_watches() {
+                tracing::debug!(
+                    "User {} ({}) replays were watched {} more times, notifying...",
+                    &bot_player.user_id,
+                    &bot_player.name,
+                    new_watches
+                );
+
+                if let Err(err) = bot_player
+                    .user_id
+                    .direct_message(
+                        &self.context,
+                        CreateMessage::new()
+                            .content(format!(
+                                "🎬 Your replays were watched {} more time{} (**{}** in total)",
+                                new_watches,
+                                if new_watches == 1 { "" } else { "s" },
+                                bot_player.total_replay_watched
+                            ))
+                            .allowed_mentions(CreateAllowedMentions::new()),
+                    )
+                    .await
+                {
+                    tracing::warn!(
+                        "Can not notify user {} ({}) about replay watches: {}",
+                        &bot_player.user_id,
+                        &bot_player.name,
+                        err
+                    );
+                }
+            }
+
+            if let Err(err) = self
+                .players_repository
+                .set_replay_watch_notified_count(
+                    &bot_player.user_id,
+                    bot_player.total_replay_watched,
+                )
+                .await
+            {
+                tracing::error!(
+                    "Can not store user {} ({}) replay watches count: {}",
+                    &bot_player.user_id,
+                    &bot_player.name,
+                    err
+                );
+            }
+        }
+    }
+}
@@ -2,23 +2,28 @@ use std::borrow::Cow;
 use std::collections::HashMap;
 use std::sync::Arc;
 
+use chrono::Utc;
 use poise::serenity_prelude::prelude::SerenityError;
 use poise::serenity_prelude::{
-    http, CreateAllowedMentions, CreateAttachment, CreateMessage, ErrorResponse, GuildId,
+    http, ChannelId, CreateAllowedMentions, CreateAttachment, CreateEmbed, CreateMessage,
+    ErrorResponse, GuildId, Http,
 };
 use tokio_util::sync::CancellationToken;
 
 use crate::discord::bot::beatleader::player::Player;
 use crate::discord::bot::commands::player::get_player_embed;
-use crate::discord::bot::{GuildSettings, UserRoleChanges};
+use crate::discord::bot::{split_msg_in_parts, GuildSettings, RoleChangesDigest, UserRoleChanges};
 use crate::discord::{serenity, BotData};
 use crate::storage::guild::GuildSettingsRepository;
 use crate::storage::player::PlayerRepository;
+use crate::storage::role_change::{RoleChangeKey, RoleChangeRepository};
+use crate::Error;
 
 pub struct UserRolesWorker {
     context: serenity::Context,
     guild_settings_repository: Arc<GuildSettingsRepository>,
     players_repository: Arc<PlayerRepository>,
+    role_change_repository: Arc<RoleChangeRepository>,
     token: CancellationToken,
 }
 
@@ -28,6 +33,7 @@ impl UserRolesWorker {
             context,
             guild_settings_repository: data.guild_settings_repository,
             players_repository: data.players_repository,
+            role_change_repository: data.role_change_repository,
             token,
         }
     }
@@ -35,6 +41,8 @@ impl UserRolesWorker {
     pub async fn run(&self, bot_players: Vec<Player>) {
         tracing::info!("Updating players roles ({})...", bot_players.len());
 
+        self.remove_expired_role_changes().await;
+
         let mut current_players_roles = Vec::new();
         for bot_player in bot_players {
             tracing::debug!(
@@ -147,80 +155,256 @@ impl UserRolesWorker {
             })
             .collect::<Vec<UserRoleChanges>>();
 
-        for rc in role_changes {
-            match rc.apply(&self.context.http).await {
-                Ok(rc) => {
-                    if rc.is_changed() {
-                        if let Some(bot_channel_id) = guilds
-                            .get(&rc.guild_id)
-                            .map_or_else(|| None, |guild_settings| guild_settings.get_channel())
-                        {
-                            tracing::info!("Logging changes to channel #{}", bot_channel_id);
-
-                            match self.players_repository.get(&rc.user_id).await {
-                                Some(player) => {
-                                    let embed_image = get_player_embed(&player).await;
-
-                                    let mut message = CreateMessage::new()
-                                        .content(format!("{}", rc))
-                                        .allowed_mentions(CreateAllowedMentions::new());
-
-                                    if let Some(embed_buffer) = embed_image {
-                                        message = message.add_file(CreateAttachment::bytes(
-                                            Cow::<[u8]>::from(embed_buffer),
-                                            "embed.png".to_string(),
-                                        ));
-                                    }
+        let mut digests: HashMap<GuildId, RoleChangesDigest> = HashMap::new();
+        let mut errors: HashMap<GuildId, Vec<String>> = HashMap::new();
 
-                                    match bot_channel_id
-                                        .send_message(self.context.clone(), message)
-                                        .await
-                                    {
-                                        Ok(_) => {}
-                                        Err(err) => {
-                                            tracing::info!(
-                                                "Can not post log update to channel #{}: {}",
-                                                bot_channel_id,
-                                                err
-                                            );
-                                        }
-                                    };
-                                }
-                                None => {
-                                    match bot_channel_id
-                                        .send_message(
-                                            self.context.clone(),
-                                            CreateMessage::new()
-                                                .content(format!("{}", rc))
-                                                .allowed_mentions(CreateAllowedMentions::new()),
-                                        )
-                                        .await
-                                    {
-                                        Ok(_) => {}
-                                        Err(err) => {
-                                            tracing::info!(
-                                                "Can not post log update to channel #{}: {}",
-                                                bot_channel_id,
-                                                err
-                                            );
-                                        }
-                                    };
-                                }
-                            }
+        for mut rc in role_changes {
+            let Some(guild_settings) = guilds.get(&rc.guild_id) else {
+                continue;
+            };
+
+            match apply_user_role_changes(
+                &self.context.http,
+                &self.role_change_repository,
+                guild_settings,
+                &mut rc,
+                true,
+            )
+            .await
+            {
+                Ok(()) => {
+                    if !rc.failed.is_empty() {
+                        errors.entry(rc.guild_id).or_default().push(format!(
+                            "Can not update roles {} of user <@{}>",
+                            rc.failed
+                                .iter()
+                                .map(|role_id| format!("<@&{}>", role_id))
+                                .collect::<Vec<_>>()
+                                .join(", "),
+                            rc.user_id
+                        ));
+                    }
+
+                    if rc.is_changed() {
+                        if let Some(channel_id) = guild_settings.get_role_changes_channel() {
+                            self.post_user_role_changes(channel_id, guild_settings, &rc)
+                                .await;
                         }
                     }
+
+                    // each guild also gets a summary of the whole run
+                    digests.entry(rc.guild_id).or_default().add(&rc);
                 }
                 Err(e) => {
                     tracing::error!("Failed to update roles for user {}: {}", rc.user_id, e);
+
+                    errors.entry(rc.guild_id).or_default().push(format!(
+                        "Can not update roles of user <@{}>: {}",
+                        rc.user_id, e
+                    ));
                 }
             }
 
+            // changes already applied are still summarized
             if self.token.is_cancelled() {
                 tracing::warn!("User roles task is shutting down...");
-                return;
+                break;
             }
         }
 
+        for (guild_id, digest) in digests {
+            let Some(channel_id) = guilds
+                .get(&guild_id)
+                .and_then(|guild_settings| guild_settings.get_role_changes_channel())
+            else {
+                continue;
+            };
+
+            tracing::info!("Posting role changes summary to channel #{}", channel_id);
+
+            self.post_messages(channel_id, digest.to_messages(), "role changes summary")
+                .await;
+        }
+
+        for (guild_id, lines) in errors {
+            let Some(channel_id) = guilds
+                .get(&guild_id)
+                .and_then(|guild_settings| guild_settings.get_errors_channel())
+            else {
+                continue;
+            };
+
+            tracing::info!("Posting role update errors to channel #{}", channel_id);
+
+            self.post_messages(
+                channel_id,
+                split_msg_in_parts(
+                    std::iter::once(
+                        "**Role update errors** (missing permissions or role above the bot's role?)"
+                            .to_owned(),
+                    )
+                    .chain(lines),
+                ),
+                "role update errors",
+            )
+            .await;
+        }
+
         tracing::info!("Players roles updated.");
     }
+
+    async fn remove_expired_role_changes(&self) {
+        let cooldowns = self
+            .guild_settings_repository
+            .all()
+            .await
+            .into_iter()
+            .map(|guild_settings| {
+                (
+                    guild_settings.get_key(),
+                    guild_settings.get_auto_roles_cooldown(),
+                )
+            })
+            .collect::<HashMap<_, _>>();
+
+        match self
+            .role_change_repository
+            .remove_expired(&cooldowns, Utc::now())
+            .await
+        {
+            Ok(removed) => tracing::debug!("{} expired role changes removed.", removed),
+            Err(err) => tracing::error!("Can not remove expired role changes: {}", err),
+        }
+    }
+
+    async fn post_user_role_changes(
+        &self,
+        channel_id: ChannelId,
+        guild_settings: &GuildSettings,
+        rc: &UserRoleChanges,
+    ) {
+        tracing::info!("Logging changes to channel #{}", channel_id);
+
+        let mut message = CreateMessage::new()
+            .content(format!("{}", rc))
+            .allowed_mentions(CreateAllowedMentions::new());
+
+        // dry run changes are posted as a plain summary, without the profile embed
+        if !rc.dry_run {
+            if let Some(player) = self.players_repository.get(&rc.user_id).await {
+                if let Some(embed_buffer) = get_player_embed(
+                    &player,
+                    &guild_settings.get_theme(),
+                    &guild_settings.get_timezone(),
+                )
+                .await
+                {
+                    message = message.add_file(CreateAttachment::bytes(
+                        Cow::<[u8]>::from(embed_buffer),
+                        "embed.png".to_string(),
+                    ));
+                }
+            }
+        }
+
+        if let Err(err) = channel_id.send_message(self.context.clone(), message).await {
+            tracing::info!(
+                "Can not post log update to channel #{}: {}",
+                channel_id,
+                err
+            );
+        }
+    }
+
+    async fn post_messages(&self, channel_id: ChannelId, messages: Vec<String>, what: &str) {
+        for content in messages {
+            if let Err(err) = channel_id
+                .send_message(
+                    self.context.clone(),
+                    CreateMessage::new()
+                        .embed(CreateEmbed::new().description(content))
+                        .allowed_mentions(CreateAllowedMentions::new()),
+                )
+                .await
+            {
+                tracing::info!("Can not post {} to channel #{}: {}", what, channel_id, err);
+                break;
+            }
+        }
+    }
+}
+
+// applies role changes of a single user and stores them for the auto roles cooldown;
+// roles changed within the cooldown are skipped if it is respected
+pub(crate) async fn apply_user_role_changes(
+    http: &Arc<Http>,
+    role_change_repository: &RoleChangeRepository,
+    guild_settings: &GuildSettings,
+    rc: &mut UserRoleChanges,
+    respect_cooldown: bool,
+) -> Result<(), Error> {
+    if respect_cooldown {
+        skip_roles_in_cooldown(role_change_repository, rc, guild_settings).await;
+    }
+
+    rc.apply(http).await?;
+
+    if rc.is_changed() && !rc.dry_run {
+        store_role_changes(role_change_repository, rc).await;
+    }
+
+    Ok(())
+}
+
+async fn skip_roles_in_cooldown(
+    role_change_repository: &RoleChangeRepository,
+    rc: &mut UserRoleChanges,
+    guild_settings: &GuildSettings,
+) {
+    let cooldown = guild_settings.get_auto_roles_cooldown();
+    if cooldown.is_zero() || !rc.is_changed() {
+        return;
+    }
+
+    let mut roles_in_cooldown = vec![];
+    for role_id in rc.to_add.iter().chain(rc.to_remove.iter()) {
+        let key = RoleChangeKey {
+            guild_id: rc.guild_id,
+            user_id: rc.user_id,
+            role_id: *role_id,
+        };
+
+        if let Some(changed_at) = role_change_repository.get_changed_at(&key).await {
+            if changed_at + cooldown > Utc::now() {
+                roles_in_cooldown.push(*role_id);
+            }
+        }
+    }
+
+    if !roles_in_cooldown.is_empty() {
+        tracing::debug!(
+            "Roles {:?} of user {} ({}) changed recently, skipping.",
+            roles_in_cooldown,
+            rc.user_id,
+            rc.name
+        );
+
+        rc.skip_roles(&roles_in_cooldown);
+    }
+}
+
+async fn store_role_changes(role_change_repository: &RoleChangeRepository, rc: &UserRoleChanges) {
+    let changed_at = Utc::now();
+
+    for role_id in rc.to_add.iter().chain(rc.to_remove.iter()) {
+        let key = RoleChangeKey {
+            guild_id: rc.guild_id,
+            user_id: rc.user_id,
+            role_id: *role_id,
+        };
+
+        if let Err(err) = role_change_repository.set_changed_at(key, changed_at).await {
+            tracing::error!("Can not store role change {}: {}", key, err);
+        }
+    }
 }
@@ -25,6 +25,8 @@ lazy_static! {
             .collect(),
         y_offset: 0.0,
     };
+    // loaded on the first use only, most of the texts can be drawn with Roboto
+    pub(crate) static ref NOTO_FONT_FAMILY: FontFamily = load_noto_fonts();
 }
 
 pub(crate) struct FontWithRange {
@@ -300,7 +302,7 @@ pub(crate) fn draw_multilang_text<T>(
     }
 }
 
-pub(super) fn load_noto_fonts() -> FontFamily {
+fn load_noto_fonts() -> FontFamily {
     FontFamily {
         fonts: NOTO_FONT_PATHS
             .par_iter()
@@ -1,9 +1,11 @@
+use std::fmt::{Display, Formatter};
 use std::ops::DerefMut;
 
 use ril::{
     Draw, Font, HorizontalAnchor, Image, Line, OverlayMode, Polygon, Rgba, TextLayout, TextSegment,
     VerticalAnchor, WrapStyle,
 };
+use serde::{Deserialize, Serialize};
 
 use crate::discord::bot::beatleader::score::MapRating;
 use crate::embed::clamp;
@@ -32,11 +34,71 @@ impl Vertex {
     }
 }
 
+// upper bounds of the star rating tiers (low, mid, high), anything above is extreme
+const STAR_TIERS: [(f64, Rgba); 3] = [
+    (
+        6.0,
+        Rgba {
+            r: 59,
+            g: 178,
+            b: 115,
+            a: 255,
+        },
+    ),
+    (
+        9.0,
+        Rgba {
+            r: 240,
+            g: 185,
+            b: 40,
+            a: 255,
+        },
+    ),
+    (
+        12.0,
+        Rgba {
+            r: 220,
+            g: 60,
+            b: 60,
+            a: 255,
+        },
+    ),
+];
+const EXTREME_STAR_TIER_COLOR: Rgba = Rgba {
+    r: 155,
+    g: 80,
+    b: 220,
+    a: 255,
+};
+
+#[derive(
+    Serialize, Deserialize, Default, Clone, Copy, Debug, PartialEq, poise::ChoiceParameter,
+)]
+#[serde(rename_all = "camelCase")]
+pub(crate) enum MapTriangleColors {
+    // mix of tech, pass and acc ratings
+    #[name = "Rating"]
+    #[default]
+    Rating,
+    #[name = "Star tier"]
+    StarTier,
+}
+
+impl Display for MapTriangleColors {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MapTriangleColors::Rating => write!(f, "rating"),
+            MapTriangleColors::StarTier => write!(f, "star tier"),
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct MapTriangle {
     position: Vertex,
     size: u32,
     map_rating: MapRating,
+    colors: MapTriangleColors,
     overlay: Option<OverlayMode>,
 }
 
@@ -46,6 +108,7 @@ impl MapTriangle {
             position,
             size,
             map_rating: MapRating::default(),
+            colors: MapTriangleColors::default(),
             overlay: None,
         }
     }
@@ -68,6 +131,11 @@ impl MapTriangle {
         self
     }
 
+    pub const fn with_colors(mut self, colors: MapTriangleColors) -> Self {
+        self.colors = colors;
+        self
+    }
+
     #[allow(dead_code)]
     pub const fn with_overlay_mode(mut self, mode: OverlayMode) -> Self {
         self.overlay = Some(mode);
@@ -81,6 +149,7 @@ impl Default for MapTriangle {
             position: Vertex::default(),
             size: 60,
             map_rating: MapRating::default(),
+            colors: MapTriangleColors::default(),
             overlay: None,
         }
     }
@@ -120,7 +189,10 @@ impl Draw<Rgba> for MapTriangle {
             ),
         ])
         .with_overlay_mode(overlay)
-        .with_fill(get_map_rating_color(&self.map_rating))
+        .with_fill(match self.colors {
+            MapTriangleColors::Rating => get_map_rating_color(&self.map_rating),
+            MapTriangleColors::StarTier => get_star_tier_color(self.map_rating.stars),
+        })
         .with_antialiased(true);
 
         image.draw(triangle);
@@ -261,6 +333,13 @@ fn get_map_rating_color(map_rating: &MapRating) -> Rgba {
     )
 }
 
+pub(super) fn get_star_tier_color(stars: f64) -> Rgba {
+    STAR_TIERS
+        .iter()
+        .find(|(max_stars, _)| stars < *max_stars)
+        .map_or(EXTREME_STAR_TIER_COLOR, |(_, color)| *color)
+}
+
 fn get_triangle_circumcenter(v1: &Vertex, v2: &Vertex, v3: &Vertex) -> Option<Vertex> {
     let d = Vertex::vector(v1, v2);
     let e = Vertex::vector(v1, v3);
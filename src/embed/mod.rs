@@ -1,31 +1,58 @@
+use chrono_tz::Tz;
 use relativetime::RelativeTime;
 use ril::prelude::*;
 
 use map_triangle::Vertex;
 
+use crate::beatleader::clan::Clan;
 use crate::beatleader::player::DifficultyStatus;
 use crate::discord::bot::beatleader::player::Player;
 use crate::discord::bot::beatleader::score::MapRatingModifier;
-use crate::discord::bot::beatleader::score::Score;
-use crate::discord::bot::get_binary_file;
+use crate::discord::bot::beatleader::score::{HandStats, Score};
+use crate::discord::bot::get_binary_file_with_content_type;
 use crate::embed::blur::gaussian_blur;
 use crate::embed::font::{
-    could_be_drawn, draw_multilang_text, draw_text_segment, load_noto_fonts, split_text_by_fonts,
-    FontFamily, ROBOTO_FONT_FAMILY,
+    could_be_drawn, draw_multilang_text, draw_text_segment, split_text_by_fonts, NOTO_FONT_FAMILY,
+    ROBOTO_FONT_FAMILY,
 };
 use crate::embed::map_triangle::MapTriangle;
 use crate::embed::utils::{draw_rounded_rectangle, Corner};
+use crate::other::string_utils::format_decimal;
+
+pub(crate) use map_triangle::MapTriangleColors;
+pub(crate) use theme::Theme;
 
 mod blur;
 mod font;
 mod map_triangle;
+mod theme;
 mod triangle;
 mod utils;
 
+// badges have their own background, so their text does not depend on the theme
+const BADGE_TEXT: Rgba = Rgba::white();
+
+const LEFT_HAND_COLOR: Rgba = Rgba {
+    r: 232,
+    g: 72,
+    b: 72,
+    a: 224,
+};
+const RIGHT_HAND_COLOR: Rgba = Rgba {
+    r: 72,
+    g: 136,
+    b: 232,
+    a: 224,
+};
+
+const AVATAR_PLACEHOLDER: &[u8] = include_bytes!("./assets/avatar-placeholder.png");
+
 pub async fn embed_score(
     score: &Score,
     player: &Player,
     player_avatar_bytes: &[u8],
+    hand_stats: Option<&HandStats>,
+    theme: &Theme,
 ) -> Option<Vec<u8>> {
     const FONT_SIZE: f32 = 32.0;
     const WIDTH: u32 = 512;
@@ -33,7 +60,6 @@ pub async fn embed_score(
     const AVATAR_SIZE: u32 = 128;
     const BORDER_SIZE: u32 = 28;
     const BORDER_RADIUS: u32 = 32;
-    const BLUR_RADIUS_BORDER: f32 = 25.0;
     const BLUR_RADIUS: f32 = 5.0;
     const PADDING: u32 = 8;
 
@@ -43,107 +69,30 @@ pub async fn embed_score(
 
     let roboto_font = &ROBOTO_FONT_FAMILY.fonts[0].font;
 
-    // load background
-    let bg_bytes = get_binary_file(&score.song_cover)
+    // load background, fall back to a solid color if the cover can not be fetched or decoded
+    let (bg_bytes, bg_content_type) = get_binary_file_with_content_type(&score.song_cover)
         .await
-        .unwrap_or(bytes::Bytes::new());
-    if bg_bytes.is_empty() {
-        return None;
-    }
-
-    let Ok(mut bg) = Image::<Rgba>::from_bytes_inferred(bg_bytes.as_ref()) else {
-        return None;
-    };
-
-    // resize background to WIDTH x WIDTH and crop WIDTH x HEIGHT from the center
-    bg.resize(WIDTH, WIDTH, ResizeAlgorithm::Lanczos3);
-    let bg_y = (WIDTH - HEIGHT) / 2;
-    bg.crop(0, bg_y, WIDTH, bg_y + HEIGHT);
-
-    // blur the background
-    let mut bg_border = bg.clone();
-    gaussian_blur(
-        &mut bg_border.data,
-        WIDTH as usize,
-        HEIGHT as usize,
-        BLUR_RADIUS_BORDER,
-    );
-    gaussian_blur(&mut bg.data, WIDTH as usize, HEIGHT as usize, BLUR_RADIUS);
+        .unwrap_or((bytes::Bytes::new(), None));
 
-    let res = std::panic::catch_unwind(|| {
-        // load avatar
-        let Ok(mut avatar) = Image::<Rgba>::from_bytes_inferred(player_avatar_bytes) else {
-            return None;
-        };
-        avatar.resize(AVATAR_SIZE, AVATAR_SIZE, ResizeAlgorithm::Lanczos3);
-
-        Some(avatar)
-    });
-    if res.is_err() || res.as_ref().unwrap().is_none() {
-        return None;
+    let bg = if bg_bytes.is_empty() {
+        None
+    } else {
+        decode_image(bg_bytes.as_ref(), bg_content_type.as_deref())
     }
+    .unwrap_or_else(|| Image::<Rgba>::new(WIDTH, WIDTH, theme.background));
 
-    let avatar = res.unwrap().unwrap();
-
-    // create image
-    let mut image = Image::<Rgba>::new(WIDTH, HEIGHT, Rgba::new(66, 66, 66, 1))
-        .with_overlay_mode(OverlayMode::Merge);
+    // load avatar
+    let avatar = decode_avatar(player_avatar_bytes, AVATAR_SIZE)?;
 
-    // add rounded corners mask & paste background blurred with BLUR_RADIUS_BORDER
-    let mut bg_mask = Image::new(WIDTH, HEIGHT, L::new(0));
-    draw_rounded_rectangle(
-        &mut bg_mask,
-        L::new(255),
+    let mut image = draw_card_background(
+        bg,
         WIDTH,
         HEIGHT,
+        BORDER_SIZE,
         BORDER_RADIUS,
-        &[
-            Corner::TopLeft,
-            Corner::TopRight,
-            Corner::BottomLeft,
-            Corner::BottomRight,
-        ],
+        BLUR_RADIUS,
+        theme,
     );
-    bg_border.mask_alpha(&bg_mask);
-    image.paste(0, 0, &bg_border);
-
-    // add rounder corners inner mask & paste background blurred with BLUR_RADIUS
-    let mut bg_mask = Image::new(WIDTH, HEIGHT, L::new(0));
-    draw_rounded_rectangle(
-        &mut bg_mask,
-        L::new(255),
-        WIDTH - BORDER_SIZE,
-        HEIGHT - BORDER_SIZE,
-        BORDER_RADIUS,
-        &[
-            Corner::TopLeft,
-            Corner::TopRight,
-            Corner::BottomLeft,
-            Corner::BottomRight,
-        ],
-    );
-    bg.mask_alpha(&bg_mask);
-    image.paste(BORDER_SIZE / 2, BORDER_SIZE / 2, &bg);
-
-    let mut overlay = Image::new(
-        WIDTH - BORDER_SIZE,
-        HEIGHT - BORDER_SIZE,
-        Rgba::transparent(),
-    );
-    draw_rounded_rectangle(
-        &mut overlay,
-        Rgba::new(1, 1, 1, 64),
-        WIDTH - BORDER_SIZE,
-        HEIGHT - BORDER_SIZE,
-        BORDER_RADIUS,
-        &[
-            Corner::TopLeft,
-            Corner::TopRight,
-            Corner::BottomLeft,
-            Corner::BottomRight,
-        ],
-    );
-    image.paste(BORDER_SIZE / 2, BORDER_SIZE / 2, &overlay);
 
     // paste masked avatar
     let mut mask = Image::new(AVATAR_SIZE, AVATAR_SIZE, BitPixel::off());
@@ -160,8 +109,8 @@ pub async fn embed_score(
     {
         let difficulty_rating = score.difficulty_score_rating.as_ref().unwrap();
         let stars = format!(
-            "{:.2}*{}",
-            difficulty_rating.stars,
+            "{}*{}",
+            format_decimal(difficulty_rating.stars, theme.decimal_places),
             if difficulty_rating.modifier != MapRatingModifier::None {
                 format!(" ({})", difficulty_rating.modifier)
             } else {
@@ -173,7 +122,7 @@ pub async fn embed_score(
         difficulty_desc.push_str(shorten_difficulty_name(score.difficulty_name.as_str()).as_str());
     }
     let mut difficulty_text_segment =
-        TextSegment::new(roboto_font, difficulty_desc, Rgba::white()).with_size(small_font_size);
+        TextSegment::new(roboto_font, difficulty_desc, BADGE_TEXT).with_size(small_font_size);
     let difficulty_text_layout = TextLayout::new()
         .with_wrap(WrapStyle::None)
         .with_segment(&difficulty_text_segment);
@@ -207,21 +156,17 @@ pub async fn embed_score(
         &difficulty,
     );
 
-    let mut noto_fonts_option: Option<FontFamily> = None;
-
     let song_name = format!("{} {}", score.song_name, score.song_sub_name);
     let text = song_name.as_str();
     let mut text_fonts = split_text_by_fonts(text, &ROBOTO_FONT_FAMILY);
     if !could_be_drawn(&text_fonts) {
-        noto_fonts_option = Some(load_noto_fonts());
-
-        text_fonts = split_text_by_fonts(text, noto_fonts_option.as_ref().unwrap());
+        text_fonts = split_text_by_fonts(text, &NOTO_FONT_FAMILY);
     }
 
     draw_multilang_text(
         &mut image,
         text_fonts,
-        Rgba::white(),
+        theme.text,
         smaller_font_size,
         BORDER_SIZE / 2 + BORDER_RADIUS / 2,
         BORDER_SIZE / 2 + BORDER_RADIUS / 4,
@@ -232,7 +177,7 @@ pub async fn embed_score(
 
     draw_text_segment(
         &mut image,
-        &mut TextSegment::new(roboto_font, score.song_mapper.clone(), Rgba::white())
+        &mut TextSegment::new(roboto_font, score.song_mapper.clone(), theme.text)
             .with_size(small_font_size),
         BORDER_SIZE / 2 + BORDER_RADIUS / 2,
         BORDER_SIZE / 2 + BORDER_RADIUS / 4 + PADDING + smaller_font_size as u32,
@@ -255,7 +200,7 @@ pub async fn embed_score(
                 score.song_bpm as f64 * speed_multiplier,
                 score.difficulty_nps * speed_multiplier,
             ),
-            Rgba::white(),
+            theme.text,
         )
         .with_size(small_font_size),
         BORDER_SIZE / 2 + BORDER_RADIUS / 2,
@@ -273,17 +218,13 @@ pub async fn embed_score(
     let text = player.name.as_str();
     let mut text_fonts = split_text_by_fonts(text, &ROBOTO_FONT_FAMILY);
     if !could_be_drawn(&text_fonts) {
-        if noto_fonts_option.is_none() {
-            noto_fonts_option = Some(load_noto_fonts());
-        }
-
-        text_fonts = split_text_by_fonts(text, noto_fonts_option.as_ref().unwrap());
+        text_fonts = split_text_by_fonts(text, &NOTO_FONT_FAMILY);
     }
 
     draw_multilang_text(
         &mut image,
         text_fonts,
-        Rgba::white(),
+        theme.text,
         FONT_SIZE,
         BORDER_SIZE / 2 + BORDER_RADIUS / 2,
         HEIGHT - BORDER_SIZE / 2 - BORDER_SIZE / 4 - BORDER_RADIUS / 4 - FONT_SIZE as u32,
@@ -312,8 +253,8 @@ pub async fn embed_score(
         &mut image,
         &mut TextSegment::new(
             roboto_font,
-            format!("{:.2}%", score.accuracy),
-            Rgba::white(),
+            format!("{}%", format_decimal(score.accuracy, theme.decimal_places)),
+            theme.text,
         )
         .with_size(big_font_size),
         stats_pos_x,
@@ -328,16 +269,19 @@ pub async fn embed_score(
         &mut TextSegment::new(
             roboto_font,
             format!(
-                "{}{:.2} / {:.2}",
+                "{}{} / {}",
                 if score.mistakes > 0 {
-                    format!("{:.2}% FC • ", score.fc_accuracy)
+                    format!(
+                        "{}% FC • ",
+                        format_decimal(score.fc_accuracy, theme.decimal_places)
+                    )
                 } else {
                     "".to_string()
                 },
-                score.acc_left,
-                score.acc_right
+                format_decimal(score.acc_left, theme.decimal_places),
+                format_decimal(score.acc_right, theme.decimal_places)
             ),
-            Rgba::white(),
+            theme.text,
         )
         .with_size(small_font_size),
         stats_pos_x,
@@ -385,7 +329,7 @@ pub async fn embed_score(
                     "No pauses".to_string()
                 }
             ),
-            Rgba::white(),
+            theme.text,
         )
         .with_size(small_font_size),
         stats_pos_x,
@@ -395,6 +339,30 @@ pub async fn embed_score(
         stats_width,
     );
 
+    // swing completeness of both hands as thin bars below the score details, fitted into
+    // the space above the rank, so the card dimensions do not change
+    if let Some(hand_stats) = hand_stats {
+        const BAR_HEIGHT: u32 = 4;
+
+        let bar_width = (stats_width - PADDING) / 2;
+        let bar_pos_y =
+            acc_pos_y + big_font_size as u32 + PADDING + small_font_size as u32 * 2 + PADDING;
+
+        for (idx, (swing, color)) in [
+            (hand_stats.left_swing(), LEFT_HAND_COLOR),
+            (hand_stats.right_swing(), RIGHT_HAND_COLOR),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            image.paste(
+                stats_pos_x + idx as u32 * (bar_width + PADDING),
+                bar_pos_y,
+                &draw_bar(bar_width, BAR_HEIGHT, swing, color, theme),
+            );
+        }
+    }
+
     draw_text_segment(
         &mut image,
         &mut TextSegment::new(
@@ -405,12 +373,24 @@ pub async fn embed_score(
                 if score.difficulty_status == DifficultyStatus::Ranked
                     || score.difficulty_status == DifficultyStatus::Qualified
                 {
-                    format!(" • {:.2}pp", score.pp)
+                    format!(
+                        " • {}pp{}",
+                        format_decimal(score.pp, theme.decimal_places),
+                        match score.difficulty_score_rating.as_ref() {
+                            Some(difficulty_rating) if difficulty_rating.stars > 0.0 => {
+                                format!(
+                                    " • {}*",
+                                    format_decimal(difficulty_rating.stars, theme.decimal_places)
+                                )
+                            }
+                            _ => "".to_string(),
+                        }
+                    )
                 } else {
                     "".to_string()
                 }
             ),
-            Rgba::white(),
+            theme.text,
         )
         .with_size(FONT_SIZE),
         stats_pos_x,
@@ -428,7 +408,8 @@ pub async fn embed_score(
             .has_individual_rating()
     {
         let map_triangle = MapTriangle::new(Vertex::new(436, 74), 50)
-            .with_map_rating(score.difficulty_score_rating.as_ref().unwrap().clone());
+            .with_map_rating(score.difficulty_score_rating.as_ref().unwrap().clone())
+            .with_colors(theme.triangle_colors);
         image.draw(&map_triangle);
     }
 
@@ -448,6 +429,8 @@ pub async fn embed_profile(
     player: &Player,
     player_avatar_bytes: &[u8],
     player_cover_bytes: &[u8],
+    theme: &Theme,
+    timezone: &Tz,
 ) -> Option<Vec<u8>> {
     const FONT_SIZE: f32 = 32.0;
     const WIDTH: u32 = 512;
@@ -455,7 +438,6 @@ pub async fn embed_profile(
     const AVATAR_SIZE: u32 = 128;
     const BORDER_SIZE: u32 = 28;
     const BORDER_RADIUS: u32 = 32;
-    const BLUR_RADIUS_BORDER: f32 = 25.0;
     const BLUR_RADIUS: f32 = 7.5;
     const PADDING: u32 = 8;
 
@@ -466,90 +448,23 @@ pub async fn embed_profile(
     let roboto_font = &ROBOTO_FONT_FAMILY.fonts[0].font;
 
     // load background
-    let Ok(mut bg) = Image::<Rgba>::from_bytes_inferred(player_cover_bytes) else {
+    let Ok(bg) = Image::<Rgba>::from_bytes_inferred(player_cover_bytes) else {
         return None;
     };
 
-    // resize background to WIDTH x WIDTH and crop WIDTH x HEIGHT from the center
-    bg.resize(WIDTH, WIDTH, ResizeAlgorithm::Lanczos3);
-    let bg_y = (WIDTH - HEIGHT) / 2;
-    bg.crop(0, bg_y, WIDTH, bg_y + HEIGHT);
+    // load avatar, the card is still posted with a placeholder if it can not be decoded
+    let avatar = decode_avatar(player_avatar_bytes, AVATAR_SIZE)
+        .or_else(|| decode_avatar(AVATAR_PLACEHOLDER, AVATAR_SIZE))?;
 
-    // blur the background
-    let mut bg_border = bg.clone();
-    gaussian_blur(
-        &mut bg_border.data,
-        WIDTH as usize,
-        HEIGHT as usize,
-        BLUR_RADIUS_BORDER,
-    );
-    gaussian_blur(&mut bg.data, WIDTH as usize, HEIGHT as usize, BLUR_RADIUS);
-
-    // load avatar
-    let Ok(mut avatar) = Image::<Rgba>::from_bytes_inferred(player_avatar_bytes) else {
-        return None;
-    };
-    avatar.resize(AVATAR_SIZE, AVATAR_SIZE, ResizeAlgorithm::Lanczos3);
-
-    // create image
-    let mut image = Image::<Rgba>::new(WIDTH, HEIGHT, Rgba::new(66, 66, 66, 1))
-        .with_overlay_mode(OverlayMode::Merge);
-
-    // add rounded corners mask & paste background blurred with BLUR_RADIUS_BORDER
-    let mut bg_mask = Image::new(WIDTH, HEIGHT, L::new(0));
-    draw_rounded_rectangle(
-        &mut bg_mask,
-        L::new(255),
+    let mut image = draw_card_background(
+        bg,
         WIDTH,
         HEIGHT,
+        BORDER_SIZE,
         BORDER_RADIUS,
-        &[
-            Corner::TopLeft,
-            Corner::TopRight,
-            Corner::BottomLeft,
-            Corner::BottomRight,
-        ],
-    );
-    bg_border.mask_alpha(&bg_mask);
-    image.paste(0, 0, &bg_border);
-
-    // add rounder corners inner mask & paste background blurred with BLUR_RADIUS
-    let mut bg_mask = Image::new(WIDTH, HEIGHT, L::new(0));
-    draw_rounded_rectangle(
-        &mut bg_mask,
-        L::new(255),
-        WIDTH - BORDER_SIZE,
-        HEIGHT - BORDER_SIZE,
-        BORDER_RADIUS,
-        &[
-            Corner::TopLeft,
-            Corner::TopRight,
-            Corner::BottomLeft,
-            Corner::BottomRight,
-        ],
-    );
-    bg.mask_alpha(&bg_mask);
-    image.paste(BORDER_SIZE / 2, BORDER_SIZE / 2, &bg);
-
-    let mut overlay = Image::new(
-        WIDTH - BORDER_SIZE,
-        HEIGHT - BORDER_SIZE,
-        Rgba::transparent(),
-    );
-    draw_rounded_rectangle(
-        &mut overlay,
-        Rgba::new(1, 1, 1, 64),
-        WIDTH - BORDER_SIZE,
-        HEIGHT - BORDER_SIZE,
-        BORDER_RADIUS,
-        &[
-            Corner::TopLeft,
-            Corner::TopRight,
-            Corner::BottomLeft,
-            Corner::BottomRight,
-        ],
+        BLUR_RADIUS,
+        theme,
     );
-    image.paste(BORDER_SIZE / 2, BORDER_SIZE / 2, &overlay);
 
     // paste masked avatar
     let mut mask = Image::new(AVATAR_SIZE, AVATAR_SIZE, BitPixel::off());
@@ -562,7 +477,7 @@ pub async fn embed_profile(
 
     if !player.is_verified {
         let mut not_verified_text_segment =
-            TextSegment::new(roboto_font, "Not verified", Rgba::white()).with_size(small_font_size);
+            TextSegment::new(roboto_font, "Not verified", BADGE_TEXT).with_size(small_font_size);
         let not_verified_text_layout = TextLayout::new()
             .with_wrap(WrapStyle::None)
             .with_segment(&not_verified_text_segment);
@@ -599,7 +514,7 @@ pub async fn embed_profile(
 
     draw_text_segment(
         &mut image,
-        &mut TextSegment::new(roboto_font, format!("#{}", player.rank), Rgba::white())
+        &mut TextSegment::new(roboto_font, format!("#{}", player.rank), theme.text)
             .with_size(FONT_SIZE),
         BORDER_SIZE / 2 + BORDER_RADIUS / 2,
         HEIGHT
@@ -619,7 +534,7 @@ pub async fn embed_profile(
         &mut TextSegment::new(
             roboto_font,
             format!("#{} peak", player.peak_rank),
-            Rgba::white(),
+            theme.text,
         )
         .with_size(small_font_size),
         BORDER_SIZE / 2 + BORDER_RADIUS / 2,
@@ -637,22 +552,16 @@ pub async fn embed_profile(
         AVATAR_SIZE + AVATAR_SIZE / 2,
     );
 
-    let mut noto_fonts_option = None;
-
     let text = &player.name;
     let mut text_fonts = split_text_by_fonts(text, &ROBOTO_FONT_FAMILY);
     if !could_be_drawn(&text_fonts) {
-        if noto_fonts_option.is_none() {
-            noto_fonts_option = Some(load_noto_fonts());
-        }
-
-        text_fonts = split_text_by_fonts(text, noto_fonts_option.as_ref().unwrap());
+        text_fonts = split_text_by_fonts(text, &NOTO_FONT_FAMILY);
     }
 
     draw_multilang_text(
         &mut image,
         text_fonts,
-        Rgba::white(),
+        theme.text,
         FONT_SIZE,
         BORDER_SIZE / 2 + BORDER_RADIUS / 2,
         HEIGHT - BORDER_SIZE / 2 - BORDER_SIZE / 4 - BORDER_RADIUS / 4 - FONT_SIZE as u32,
@@ -667,8 +576,12 @@ pub async fn embed_profile(
     let stats_pos_y = BORDER_SIZE / 2 + BORDER_RADIUS / 2 + (FONT_SIZE * 1.25) as u32;
     draw_text_segment(
         &mut image,
-        &mut TextSegment::new(roboto_font, format!("{:.2}pp", player.pp), Rgba::white())
-            .with_size(big_font_size),
+        &mut TextSegment::new(
+            roboto_font,
+            format!("{}pp", format_decimal(player.pp, theme.decimal_places)),
+            theme.text,
+        )
+        .with_size(big_font_size),
         stats_pos_x,
         stats_pos_y,
         stats_width,
@@ -681,15 +594,18 @@ pub async fn embed_profile(
         &mut TextSegment::new(
             roboto_font,
             format!(
-                "{:.2} top pp{}",
-                player.top_pp,
+                "{} top pp{}",
+                format_decimal(player.top_pp, theme.decimal_places),
                 if player.last_scores_fetch.is_some() {
-                    format!(" • {:.2} +1pp", player.plus_1pp)
+                    format!(
+                        " • {} +1pp",
+                        format_decimal(player.plus_1pp, theme.decimal_places)
+                    )
                 } else {
                     "".to_owned()
                 }
             ),
-            Rgba::white(),
+            theme.text,
         )
         .with_size(small_font_size),
         stats_pos_x,
@@ -704,15 +620,18 @@ pub async fn embed_profile(
         &mut TextSegment::new(
             roboto_font,
             format!(
-                "{}{:.2}% avg acc",
+                "{}{}% avg acc",
                 if player.last_scores_fetch.is_some() {
-                    format!("{:.2}* top stars • ", player.top_stars)
+                    format!(
+                        "{}* top stars • ",
+                        format_decimal(player.top_stars, theme.decimal_places)
+                    )
                 } else {
                     "".to_owned()
                 },
-                player.avg_ranked_accuracy
+                format_decimal(player.avg_ranked_accuracy, theme.decimal_places)
             ),
-            Rgba::white(),
+            theme.text,
         )
         .with_size(small_font_size),
         stats_pos_x,
@@ -728,16 +647,22 @@ pub async fn embed_profile(
             &mut image,
             &mut TextSegment::new(
                 roboto_font,
-                if player.last_ranked_paused_at.is_some() {
-                    let mut relative_time = player.last_ranked_paused_at.unwrap().to_relative();
+                if let Some(last_ranked_paused_at) = player.last_ranked_paused_at {
+                    let mut relative_time = last_ranked_paused_at.to_relative();
                     if relative_time == "1 months ago" {
                         "1 month ago".clone_into(&mut relative_time);
                     }
-                    format!("Last paused {}", relative_time)
+                    format!(
+                        "Last paused {} ({})",
+                        relative_time,
+                        last_ranked_paused_at
+                            .with_timezone(timezone)
+                            .format("%Y-%m-%d")
+                    )
                 } else {
                     "Never paused".to_owned()
                 },
-                Rgba::white(),
+                theme.text,
             )
             .with_size(small_font_size),
             stats_pos_x,
@@ -757,7 +682,7 @@ pub async fn embed_profile(
     if !player.clans.is_empty() {
         draw_text_segment(
             &mut image,
-            &mut TextSegment::new(roboto_font, player.clans.join(" • "), Rgba::white())
+            &mut TextSegment::new(roboto_font, player.clans.join(" • "), theme.text)
                 .with_size(small_font_size),
             stats_pos_x,
             stats_pos_y
@@ -784,6 +709,352 @@ pub async fn embed_profile(
     None
 }
 
+pub async fn embed_clan_summary(
+    clan: &Clan,
+    rank: Option<u32>,
+    clan_icon_bytes: &[u8],
+    decimal_places: u8,
+) -> Option<Vec<u8>> {
+    const FONT_SIZE: f32 = 32.0;
+    const WIDTH: u32 = 512;
+    const HEIGHT: u32 = 296;
+    const ICON_SIZE: u32 = 128;
+    const BORDER_SIZE: u32 = 28;
+    const BORDER_RADIUS: u32 = 32;
+    const BLUR_RADIUS: f32 = 7.5;
+    const PADDING: u32 = 8;
+
+    let small_font_size = FONT_SIZE * 0.5;
+    let big_font_size = FONT_SIZE * 1.5;
+
+    let roboto_font = &ROBOTO_FONT_FAMILY.fonts[0].font;
+
+    // load icon, fall back to a gradient if the clan has no icon or it can not be decoded
+    let icon = if clan_icon_bytes.is_empty() {
+        None
+    } else {
+        decode_image(clan_icon_bytes, None)
+    };
+
+    let bg = icon
+        .clone()
+        .unwrap_or_else(|| gradient_background(WIDTH, WIDTH));
+
+    let mut image = draw_card_background(
+        bg,
+        WIDTH,
+        HEIGHT,
+        BORDER_SIZE,
+        BORDER_RADIUS,
+        BLUR_RADIUS,
+        &Theme::default(),
+    );
+
+    // paste masked icon
+    let icon_pos_x = BORDER_SIZE / 2 + ICON_SIZE / 4;
+    let icon_pos_y = HEIGHT - BORDER_SIZE - BORDER_RADIUS / 6 - FONT_SIZE as u32 - ICON_SIZE;
+    if let Some(mut icon) = icon {
+        icon.resize(ICON_SIZE, ICON_SIZE, ResizeAlgorithm::Lanczos3);
+
+        let mut mask = Image::new(ICON_SIZE, ICON_SIZE, BitPixel::off());
+        mask.draw(
+            &Ellipse::from_bounding_box(0, 0, ICON_SIZE, ICON_SIZE).with_fill(BitPixel::on()),
+        );
+        image.paste_with_mask(icon_pos_x, icon_pos_y, &icon, &mask);
+    }
+
+    draw_text_segment(
+        &mut image,
+        &mut TextSegment::new(
+            roboto_font,
+            match rank {
+                Some(rank) => format!("#{} {}", rank, clan.tag),
+                None => clan.tag.clone(),
+            },
+            Rgba::white(),
+        )
+        .with_size(FONT_SIZE),
+        BORDER_SIZE / 2 + BORDER_RADIUS / 2,
+        BORDER_SIZE / 2 + BORDER_RADIUS / 4,
+        WIDTH - BORDER_SIZE - BORDER_RADIUS,
+        0,
+        0,
+    );
+
+    let text = clan.name.as_str();
+    let mut text_fonts = split_text_by_fonts(text, &ROBOTO_FONT_FAMILY);
+    if !could_be_drawn(&text_fonts) {
+        text_fonts = split_text_by_fonts(text, &NOTO_FONT_FAMILY);
+    }
+
+    draw_multilang_text(
+        &mut image,
+        text_fonts,
+        Rgba::white(),
+        FONT_SIZE,
+        BORDER_SIZE / 2 + BORDER_RADIUS / 2,
+        HEIGHT - BORDER_SIZE / 2 - BORDER_SIZE / 4 - BORDER_RADIUS / 4 - FONT_SIZE as u32,
+        WIDTH - BORDER_SIZE - BORDER_RADIUS,
+        icon_pos_x - ICON_SIZE / 4,
+        ICON_SIZE + ICON_SIZE / 2,
+    );
+
+    let stats_width =
+        WIDTH - icon_pos_x - ICON_SIZE - BORDER_SIZE / 2 - BORDER_RADIUS / 2 - PADDING * 2;
+    let stats_pos_x = icon_pos_x + ICON_SIZE + PADDING * 2;
+    let stats_pos_y = BORDER_SIZE / 2 + BORDER_RADIUS / 2 + (FONT_SIZE * 1.25) as u32;
+    draw_text_segment(
+        &mut image,
+        &mut TextSegment::new(
+            roboto_font,
+            format!("{}pp", format_decimal(clan.pp, decimal_places)),
+            Rgba::white(),
+        )
+        .with_size(big_font_size),
+        stats_pos_x,
+        stats_pos_y,
+        stats_width,
+        stats_pos_x,
+        stats_width,
+    );
+
+    let stats = [
+        format!(
+            "{} member{}",
+            clan.players_count,
+            if clan.players_count != 1 { "s" } else { "" }
+        ),
+        format!(
+            "{} maps captured • {}%",
+            clan.capture_leaderboards_count,
+            format_decimal(clan.ranked_pool_percent_captured * 100.0, decimal_places)
+        ),
+        format!(
+            "{}% avg acc • #{:.0} avg rank",
+            format_decimal(clan.average_accuracy * 100.0, decimal_places),
+            clan.average_rank
+        ),
+    ];
+
+    for (idx, stat) in stats.into_iter().enumerate() {
+        draw_text_segment(
+            &mut image,
+            &mut TextSegment::new(roboto_font, stat, Rgba::white()).with_size(small_font_size),
+            stats_pos_x,
+            stats_pos_y
+                + (big_font_size * 1.2) as u32
+                + PADDING
+                + (small_font_size as u32 + PADDING / 2) * idx as u32,
+            stats_width,
+            stats_pos_x,
+            stats_width,
+        );
+    }
+
+    let mut buffer = Vec::<u8>::with_capacity(200_000);
+    if image
+        .encode(ril::prelude::ImageFormat::Png, &mut buffer)
+        .is_ok()
+    {
+        return Some(buffer);
+    }
+
+    None
+}
+
+// draws the card frame: the background blurred into a rounded border tinted with the accent color
+// and the less blurred background under the tint inside it
+fn draw_card_background(
+    mut bg: Image<Rgba>,
+    width: u32,
+    height: u32,
+    border_size: u32,
+    border_radius: u32,
+    blur_radius: f32,
+    theme: &Theme,
+) -> Image<Rgba> {
+    const BLUR_RADIUS_BORDER: f32 = 25.0;
+
+    let all_corners = [
+        Corner::TopLeft,
+        Corner::TopRight,
+        Corner::BottomLeft,
+        Corner::BottomRight,
+    ];
+
+    // resize background to width x width and crop width x height from the center
+    bg.resize(width, width, ResizeAlgorithm::Lanczos3);
+    let bg_y = (width - height) / 2;
+    bg.crop(0, bg_y, width, bg_y + height);
+
+    // blur the background
+    let mut bg_border = bg.clone();
+    gaussian_blur(
+        &mut bg_border.data,
+        width as usize,
+        height as usize,
+        BLUR_RADIUS_BORDER,
+    );
+    gaussian_blur(&mut bg.data, width as usize, height as usize, blur_radius);
+
+    // create image
+    let mut image = Image::<Rgba>::new(
+        width,
+        height,
+        Rgba {
+            a: 1,
+            ..theme.background
+        },
+    )
+    .with_overlay_mode(OverlayMode::Merge);
+
+    // add rounded corners mask & paste background blurred with BLUR_RADIUS_BORDER
+    let mut bg_mask = Image::new(width, height, L::new(0));
+    draw_rounded_rectangle(
+        &mut bg_mask,
+        L::new(255),
+        width,
+        height,
+        border_radius,
+        &all_corners,
+    );
+    bg_border.mask_alpha(&bg_mask);
+    image.paste(0, 0, &bg_border);
+
+    // tint the border with the accent color, inner part is covered by the background below
+    if let Some(accent) = theme.accent {
+        let mut accent_border = Image::new(width, height, Rgba::transparent());
+        draw_rounded_rectangle(
+            &mut accent_border,
+            accent,
+            width,
+            height,
+            border_radius,
+            &all_corners,
+        );
+        image.paste(0, 0, &accent_border);
+    }
+
+    // add rounder corners inner mask & paste background blurred with blur_radius
+    let mut bg_mask = Image::new(width, height, L::new(0));
+    draw_rounded_rectangle(
+        &mut bg_mask,
+        L::new(255),
+        width - border_size,
+        height - border_size,
+        border_radius,
+        &all_corners,
+    );
+    // make the tint more opaque if the text would not be readable on the background
+    let tint = theme.tint_for(&bg);
+
+    bg.mask_alpha(&bg_mask);
+    image.paste(border_size / 2, border_size / 2, &bg);
+
+    let mut overlay = Image::new(
+        width - border_size,
+        height - border_size,
+        Rgba::transparent(),
+    );
+    draw_rounded_rectangle(
+        &mut overlay,
+        tint,
+        width - border_size,
+        height - border_size,
+        border_radius,
+        &all_corners,
+    );
+    image.paste(border_size / 2, border_size / 2, &overlay);
+
+    image
+}
+
+fn gradient_background(width: u32, height: u32) -> Image<Rgba> {
+    let gradient = colorgrad::CustomGradient::new()
+        .colors(&[
+            colorgrad::Color::from_rgba8(143, 72, 219, 255),
+            colorgrad::Color::from_rgba8(31, 111, 189, 255),
+        ])
+        .build()
+        .unwrap();
+
+    Image::from_fn(width, height, |x, y| {
+        let [r, g, b, a] = gradient
+            .at((x + y) as f64 / (width + height) as f64)
+            .to_rgba8();
+
+        Rgba::new(r, g, b, a)
+    })
+}
+
+fn decode_image(bytes: &[u8], content_type: Option<&str>) -> Option<Image<Rgba>> {
+    if let Ok(image) = Image::<Rgba>::from_bytes_inferred(bytes) {
+        return Some(image);
+    }
+
+    // format could not be inferred from the magic bytes (e.g. some WebP variants), try the one
+    // reported by the server
+    let mime_type = content_type?.split(';').next()?.trim();
+    match ImageFormat::from_mime_type(mime_type) {
+        ImageFormat::Unknown => None,
+        format => Image::<Rgba>::from_bytes(format, bytes).ok(),
+    }
+}
+
+fn draw_bar(width: u32, height: u32, fill: f64, color: Rgba, theme: &Theme) -> Image<Rgba> {
+    let mut bar = Image::new(width, height, Rgba::transparent());
+    draw_rounded_rectangle(
+        &mut bar,
+        Rgba {
+            a: 64,
+            ..theme.text
+        },
+        width,
+        height,
+        height,
+        &[
+            Corner::TopLeft,
+            Corner::TopRight,
+            Corner::BottomLeft,
+            Corner::BottomRight,
+        ],
+    );
+
+    // rounded corners need at least the bar height to be drawn
+    let fill_width = (width as f64 * fill.clamp(0.0, 1.0)).round() as u32;
+    if fill_width >= height {
+        let mut filled = Image::new(fill_width, height, Rgba::transparent());
+        draw_rounded_rectangle(
+            &mut filled,
+            color,
+            fill_width,
+            height,
+            height,
+            &[
+                Corner::TopLeft,
+                Corner::TopRight,
+                Corner::BottomLeft,
+                Corner::BottomRight,
+            ],
+        );
+        bar.paste(0, 0, &filled);
+    }
+
+    bar
+}
+
+// decoding some malformed images panics instead of returning an error
+fn decode_avatar(bytes: &[u8], size: u32) -> Option<Image<Rgba>> {
+    std::panic::catch_unwind(|| {
+        let mut avatar = Image::<Rgba>::from_bytes_inferred(bytes).ok()?;
+        avatar.resize(size, size, ResizeAlgorithm::Lanczos3);
+
+        Some(avatar)
+    })
+    .ok()
+    .flatten()
+}
+
 fn difficulty_color(name: &str) -> Rgba {
     match name {
         "Easy" => Rgba::new(60, 179, 113, 192),
@@ -812,3 +1083,48 @@ pub(crate) fn clamp<T: PartialOrd>(input: T, min: T, max: T) -> T {
         input
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use chrono_tz::Tz;
+    use ril::prelude::*;
+
+    use crate::discord::bot::beatleader::player::Player;
+    use crate::embed::map_triangle::get_star_tier_color;
+    use crate::embed::{embed_profile, Theme};
+
+    #[tokio::test]
+    async fn it_renders_profile_with_malformed_avatar() {
+        let mut cover = Vec::new();
+        Image::<Rgba>::new(64, 64, Rgba::new(10, 20, 30, 255))
+            .encode(ImageFormat::Png, &mut cover)
+            .unwrap();
+
+        let player = Player {
+            name: "Player".to_owned(),
+            ..Default::default()
+        };
+
+        let png = embed_profile(
+            &player,
+            b"not an image",
+            &cover,
+            &Theme::default(),
+            &Tz::UTC,
+        )
+        .await
+        .expect("profile card should be rendered");
+
+        assert!(png.starts_with(b"\x89PNG"));
+    }
+
+    #[test]
+    fn it_colors_map_triangle_by_star_tier() {
+        assert_eq!(get_star_tier_color(0.0), get_star_tier_color(5.99));
+        assert_ne!(get_star_tier_color(5.99), get_star_tier_color(6.0));
+        assert_eq!(get_star_tier_color(6.0), get_star_tier_color(8.99));
+        assert_ne!(get_star_tier_color(8.99), get_star_tier_color(9.0));
+        assert_ne!(get_star_tier_color(11.99), get_star_tier_color(12.0));
+        assert_eq!(get_star_tier_color(12.0), get_star_tier_color(16.0));
+    }
+}
@@ -0,0 +1,101 @@
+use ril::prelude::*;
+
+use crate::embed::map_triangle::MapTriangleColors;
+use crate::other::string_utils::DEFAULT_DECIMAL_PLACES;
+
+// the tint can not get more opaque than this, otherwise the background is no longer visible
+const MAX_TINT_ALPHA: u8 = 192;
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Theme {
+    pub background: Rgba,
+    pub tint: Rgba,
+    pub text: Rgba,
+    pub accent: Option<Rgba>,
+    pub triangle_colors: MapTriangleColors,
+    pub decimal_places: u8,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::dark()
+    }
+}
+
+impl Theme {
+    pub fn dark() -> Self {
+        Self {
+            background: Rgba::new(66, 66, 66, 255),
+            tint: Rgba::new(1, 1, 1, 64),
+            text: Rgba::white(),
+            accent: None,
+            triangle_colors: MapTriangleColors::default(),
+            decimal_places: DEFAULT_DECIMAL_PLACES,
+        }
+    }
+
+    pub fn light() -> Self {
+        Self {
+            background: Rgba::new(224, 224, 224, 255),
+            tint: Rgba::new(255, 255, 255, 96),
+            text: Rgba::new(33, 33, 33, 255),
+            accent: None,
+            triangle_colors: MapTriangleColors::default(),
+            decimal_places: DEFAULT_DECIMAL_PLACES,
+        }
+    }
+
+    pub fn with_accent(self, accent: Rgba) -> Self {
+        Self {
+            accent: Some(Rgba { a: 160, ..accent }),
+            ..self
+        }
+    }
+
+    pub fn with_triangle_colors(self, triangle_colors: MapTriangleColors) -> Self {
+        Self {
+            triangle_colors,
+            ..self
+        }
+    }
+
+    pub fn with_decimal_places(self, decimal_places: u8) -> Self {
+        Self {
+            decimal_places,
+            ..self
+        }
+    }
+
+    /// Returns the tint made more opaque when the background is close to the text color
+    pub fn tint_for(&self, background: &Image<Rgba>) -> Rgba {
+        if background.data.is_empty() {
+            return self.tint;
+        }
+
+        let background_luminance = background
+            .data
+            .iter()
+            .map(|pixel| pixel.luminance() as f32)
+            .sum::<f32>()
+            / background.data.len() as f32;
+
+        let text_luminance = self.text.luminance() as f32;
+
+        // 0.0 when the background is on the opposite side of the scale than the text,
+        // up to 1.0 when they are the same
+        let similarity = if text_luminance > 127.5 {
+            (background_luminance - 127.5) / 127.5
+        } else {
+            (127.5 - background_luminance) / 127.5
+        }
+        .clamp(0.0, 1.0);
+
+        let alpha = self.tint.a as f32
+            + (MAX_TINT_ALPHA.max(self.tint.a) - self.tint.a) as f32 * similarity;
+
+        Rgba {
+            a: alpha.round() as u8,
+            ..self.tint
+        }
+    }
+}
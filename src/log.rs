@@ -1,7 +1,8 @@
 use std::io::IsTerminal;
+use std::time::Duration;
 
 use axum::body::Body;
-use axum::http::Request;
+use axum::http::{Request, Response};
 use tower_governor::key_extractor::SmartIpKeyExtractor;
 use tracing::Span;
 use tracing_appender::non_blocking::WorkerGuard;
@@ -18,7 +19,7 @@ pub(crate) fn init(tracing_settings: TracingSettings) -> Option<WorkerGuard> {
 
     let stdout_default_level: tracing::Level = tracing_settings.stdout_default_level.clone().into();
     let stdout_level: tracing::Level = tracing_settings.stdout_level.clone().into();
-    let log_default_level: tracing::Level = tracing_settings.log_level.clone().into();
+    let log_default_level: tracing::Level = tracing_settings.log_default_level.clone().into();
     let log_level: tracing::Level = tracing_settings.log_level.clone().into();
 
     let (file_layer, guard) = match tracing_settings.log_enabled {
@@ -57,7 +58,7 @@ pub(crate) fn init(tracing_settings: TracingSettings) -> Option<WorkerGuard> {
                     match tracing_settings.log_format {
                         TracingFormat::Compact => file_layer.compact().boxed(),
                         TracingFormat::Pretty => file_layer.pretty().boxed(),
-                        TracingFormat::Json => file_layer.json().boxed(),
+                        TracingFormat::Json => file_layer.json().flatten_event(true).boxed(),
                     }
                     .with_filter(
                         filter::Targets::new()
@@ -82,7 +83,7 @@ pub(crate) fn init(tracing_settings: TracingSettings) -> Option<WorkerGuard> {
                 match tracing_settings.stdout_format {
                     TracingFormat::Compact => stdout_layer.compact().boxed(),
                     TracingFormat::Pretty => stdout_layer.pretty().boxed(),
-                    TracingFormat::Json => stdout_layer.json().boxed(),
+                    TracingFormat::Json => stdout_layer.json().flatten_event(true).boxed(),
                 }
                 .with_filter(
                     filter::Targets::new()
@@ -128,3 +129,11 @@ pub(crate) fn make_span_with(request: &Request<Body>) -> Span {
         user_id = tracing::field::Empty,
     )
 }
+
+// fills in the fields left empty by make_span_with, so they are available in both text and json logs
+pub(crate) fn on_response<B>(response: &Response<B>, latency: Duration, span: &Span) {
+    span.record("status", response.status().as_u16());
+    span.record("latency", latency.as_millis() as u64);
+
+    tracing::info!("finished processing request");
+}
@@ -8,10 +8,15 @@ use tracing::info;
 
 use other::ram_reporter::RamReporter;
 
-use crate::beatleader::Client;
+use crate::beatleader::{Client, DEFAULT_TIMEOUT};
 use crate::config::Settings;
 use crate::discord::DiscordClient;
+use crate::other::binary_file_cache::{
+    BinaryFileCache, BINARY_FILE_CACHE_MAX_BYTES, BINARY_FILE_CACHE_TTL,
+};
 use crate::other::commander_orders::CommanderOrdersCleanupWorker;
+use crate::other::metrics::Metrics;
+use crate::other::playlist_cleanup::PlaylistCleanupWorker;
 use crate::webserver::WebServer;
 
 mod beatleader;
@@ -25,14 +30,23 @@ mod storage;
 mod webserver;
 
 lazy_static! {
-    static ref BL_CLIENT: Client = Client::default();
+    static ref SETTINGS: Settings = Settings::new().unwrap();
+    static ref BL_CLIENT: Client = Client::new(
+        SETTINGS.bl_api_url.trim_end_matches('/').to_string(),
+        DEFAULT_TIMEOUT,
+        SETTINGS.bl_api_rate_limit,
+        SETTINGS.bl_api_max_attempts,
+    );
+    static ref METRICS: Metrics = Metrics::default();
+    static ref BINARY_FILE_CACHE: BinaryFileCache =
+        BinaryFileCache::new(BINARY_FILE_CACHE_MAX_BYTES, BINARY_FILE_CACHE_TTL);
 }
 
 pub(crate) type Error = Box<dyn std::error::Error + Send + Sync>;
 
 #[tokio::main]
 async fn main() -> Result<(), Error> {
-    let settings = Settings::new().unwrap();
+    let settings = SETTINGS.clone();
 
     let _tracing_guard = log::init(settings.tracing.clone());
 
@@ -47,11 +61,13 @@ async fn main() -> Result<(), Error> {
     let webserver = WebServer::new(common_data.clone(), tracker.clone(), token.clone());
     let discord = DiscordClient::new(common_data.clone(), tracker.clone(), token.clone()).await;
     let commander_orders = CommanderOrdersCleanupWorker::new(common_data.clone(), token.clone());
+    let playlist_cleanup = PlaylistCleanupWorker::new(common_data.clone(), token.clone());
 
     tracker.spawn(discord.start());
     tracker.spawn(ram_reporter.start());
     tracker.spawn(webserver.start());
     tracker.spawn(commander_orders.run());
+    tracker.spawn(playlist_cleanup.run());
 
     tracker.close();
 
@@ -0,0 +1,158 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use bytes::Bytes;
+
+pub(crate) const BINARY_FILE_CACHE_MAX_BYTES: usize = 32 * 1024 * 1024;
+pub(crate) const BINARY_FILE_CACHE_TTL: Duration = Duration::from_secs(10 * 60);
+
+#[derive(Debug, Clone)]
+struct CachedFile {
+    bytes: Bytes,
+    content_type: Option<String>,
+    fetched_at: Instant,
+    last_used: u64,
+}
+
+#[derive(Debug, Default)]
+struct CacheState {
+    files: HashMap<String, CachedFile>,
+    total_bytes: usize,
+    // incremented on every access, the file with the lowest value is evicted first
+    clock: u64,
+}
+
+// in-memory LRU cache of downloaded files (avatars, covers, etc.) bounded by the total size in bytes
+#[derive(Debug)]
+pub(crate) struct BinaryFileCache {
+    max_bytes: usize,
+    ttl: Duration,
+    state: Mutex<CacheState>,
+}
+
+impl BinaryFileCache {
+    pub fn new(max_bytes: usize, ttl: Duration) -> Self {
+        Self {
+            max_bytes,
+            ttl,
+            state: Mutex::new(CacheState::default()),
+        }
+    }
+
+    pub fn get(&self, url: &str) -> Option<(Bytes, Option<String>)> {
+        let mut state = self.state.lock().unwrap();
+
+        state.clock += 1;
+        let clock = state.clock;
+
+        match state.files.get_mut(url) {
+            Some(file) if file.fetched_at.elapsed() <= self.ttl => {
+                file.last_used = clock;
+
+                Some((file.bytes.clone(), file.content_type.clone()))
+            }
+            Some(_) => {
+                if let Some(expired) = state.files.remove(url) {
+                    state.total_bytes -= expired.bytes.len();
+                }
+
+                None
+            }
+            None => None,
+        }
+    }
+
+    pub fn insert(&self, url: &str, bytes: Bytes, content_type: Option<String>) {
+        if bytes.len() > self.max_bytes {
+            return;
+        }
+
+        let mut state = self.state.lock().unwrap();
+
+        if let Some(previous) = state.files.remove(url) {
+            state.total_bytes -= previous.bytes.len();
+        }
+
+        while state.total_bytes + bytes.len() > self.max_bytes {
+            let Some(lru_url) = state
+                .files
+                .iter()
+                .min_by_key(|(_, file)| file.last_used)
+                .map(|(url, _)| url.clone())
+            else {
+                break;
+            };
+
+            if let Some(evicted) = state.files.remove(&lru_url) {
+                state.total_bytes -= evicted.bytes.len();
+            }
+        }
+
+        state.clock += 1;
+        let clock = state.clock;
+
+        state.total_bytes += bytes.len();
+        state.files.insert(
+            url.to_owned(),
+            CachedFile {
+                bytes,
+                content_type,
+                fetched_at: Instant::now(),
+                last_used: clock,
+            },
+        );
+    }
+
+    pub fn size_in_bytes(&self) -> usize {
+        self.state.lock().unwrap().total_bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bytes::Bytes;
+
+    use crate::other::binary_file_cache::BinaryFileCache;
+
+    #[test]
+    fn it_evicts_least_recently_used_files() {
+        let cache = BinaryFileCache::new(10, Duration::from_secs(60));
+
+        cache.insert("a", Bytes::from_static(b"1234"), None);
+        cache.insert(
+            "b",
+            Bytes::from_static(b"1234"),
+            Some("image/png".to_owned()),
+        );
+        assert!(cache.get("a").is_some());
+
+        cache.insert("c", Bytes::from_static(b"1234"), None);
+
+        assert!(cache.get("a").is_some());
+        assert!(cache.get("b").is_none());
+        assert_eq!(
+            cache.get("c"),
+            Some((Bytes::from_static(b"1234"), None::<String>))
+        );
+        assert_eq!(cache.size_in_bytes(), 8);
+
+        // files larger than the whole cache are not stored
+        cache.insert("d", Bytes::from_static(b"12345678901"), None);
+        assert!(cache.get("d").is_none());
+        assert_eq!(cache.size_in_bytes(), 8);
+    }
+
+    #[test]
+    fn it_expires_files() {
+        let cache = BinaryFileCache::new(10, Duration::ZERO);
+
+        cache.insert("a", Bytes::from_static(b"1234"), None);
+        std::thread::sleep(Duration::from_millis(1));
+
+        assert!(cache.get("a").is_none());
+        assert_eq!(cache.size_in_bytes(), 0);
+    }
+}
@@ -0,0 +1,99 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::beatleader::clan::ClanTag;
+use crate::beatleader::player::PlayerId;
+
+// clan membership changes made on BL within this window may not be visible to the bot,
+// e.g. a player who has just joined the clan may still be told they are not a member
+pub(crate) const CLAN_MEMBERSHIP_CACHE_TTL: Duration = Duration::from_secs(5 * 60);
+
+#[derive(Debug, Clone)]
+struct CachedMembership {
+    // in the order set by the player, the first one is the primary clan
+    clans: Vec<ClanTag>,
+    fetched_at: Instant,
+}
+
+// short-lived cache of players' clans fetched from BL, so members running clan commands
+// back-to-back do not refetch their profiles
+#[derive(Debug)]
+pub(crate) struct ClanMembershipCache {
+    ttl: Duration,
+    players: Mutex<HashMap<PlayerId, CachedMembership>>,
+}
+
+impl ClanMembershipCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            players: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn get(&self, player_id: &PlayerId) -> Option<Vec<ClanTag>> {
+        let mut players = self.players.lock().unwrap();
+
+        match players.get(player_id) {
+            Some(membership) if membership.fetched_at.elapsed() <= self.ttl => {
+                Some(membership.clans.clone())
+            }
+            Some(_) => {
+                players.remove(player_id);
+
+                None
+            }
+            None => None,
+        }
+    }
+
+    pub fn insert(&self, player_id: &PlayerId, clans: Vec<ClanTag>) {
+        self.players.lock().unwrap().insert(
+            player_id.to_owned(),
+            CachedMembership {
+                clans,
+                fetched_at: Instant::now(),
+            },
+        );
+    }
+
+    pub fn clear(&self) {
+        self.players.lock().unwrap().clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::other::clan_membership_cache::ClanMembershipCache;
+
+    #[test]
+    fn it_caches_clan_membership() {
+        let cache = ClanMembershipCache::new(Duration::from_secs(60));
+        let player_id = "1".to_owned();
+
+        assert_eq!(cache.get(&player_id), None);
+
+        cache.insert(&player_id, vec!["ABC".to_owned(), "DEF".to_owned()]);
+        assert_eq!(
+            cache.get(&player_id),
+            Some(vec!["ABC".to_owned(), "DEF".to_owned()])
+        );
+
+        cache.clear();
+        assert_eq!(cache.get(&player_id), None);
+    }
+
+    #[test]
+    fn it_expires_clan_membership() {
+        let cache = ClanMembershipCache::new(Duration::ZERO);
+        let player_id = "1".to_owned();
+
+        cache.insert(&player_id, vec!["ABC".to_owned()]);
+        std::thread::sleep(Duration::from_millis(1));
+
+        assert_eq!(cache.get(&player_id), None);
+    }
+}
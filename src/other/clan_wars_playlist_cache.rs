@@ -0,0 +1,98 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::beatleader::clan::ClanTag;
+use crate::discord::bot::beatleader::clan::{ClanWarsSort, Playlist};
+
+// clan wars maps change only when maps are captured or lost, so a clan-wide playlist link
+// opened by many members at once does not need to hit BL every time
+pub(crate) const CLAN_WARS_PLAYLIST_CACHE_TTL: Duration = Duration::from_secs(2 * 60);
+
+#[derive(Debug, Clone)]
+struct CachedPlaylist {
+    playlist: Playlist,
+    generated_at: Instant,
+}
+
+#[derive(Debug)]
+pub(crate) struct ClanWarsPlaylistCache {
+    ttl: Duration,
+    playlists: Mutex<HashMap<(ClanTag, ClanWarsSort), CachedPlaylist>>,
+}
+
+impl ClanWarsPlaylistCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            playlists: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn get(&self, clan_tag: &ClanTag, sort: &ClanWarsSort) -> Option<Playlist> {
+        let mut playlists = self.playlists.lock().unwrap();
+        let key = (clan_tag.to_owned(), sort.clone());
+
+        match playlists.get(&key) {
+            Some(cached) if cached.generated_at.elapsed() <= self.ttl => {
+                Some(cached.playlist.clone())
+            }
+            Some(_) => {
+                playlists.remove(&key);
+
+                None
+            }
+            None => None,
+        }
+    }
+
+    pub fn insert(&self, clan_tag: &ClanTag, sort: &ClanWarsSort, playlist: Playlist) {
+        self.playlists.lock().unwrap().insert(
+            (clan_tag.to_owned(), sort.clone()),
+            CachedPlaylist {
+                playlist,
+                generated_at: Instant::now(),
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::discord::bot::beatleader::clan::{ClanWarsSort, Playlist};
+    use crate::other::clan_wars_playlist_cache::ClanWarsPlaylistCache;
+
+    #[test]
+    fn it_caches_clan_wars_playlists_per_sort() {
+        let cache = ClanWarsPlaylistCache::new(Duration::from_secs(60));
+        let clan_tag = "ABC".to_owned();
+        let playlist = Playlist::default();
+
+        assert!(cache.get(&clan_tag, &ClanWarsSort::ToConquer).is_none());
+
+        cache.insert(&clan_tag, &ClanWarsSort::ToConquer, playlist.clone());
+        assert_eq!(
+            cache
+                .get(&clan_tag, &ClanWarsSort::ToConquer)
+                .map(|playlist| playlist.get_id().clone()),
+            Some(playlist.get_id().clone())
+        );
+        assert!(cache.get(&clan_tag, &ClanWarsSort::ToHold).is_none());
+        assert!(cache
+            .get(&"DEF".to_owned(), &ClanWarsSort::ToConquer)
+            .is_none());
+    }
+
+    #[test]
+    fn it_expires_clan_wars_playlists() {
+        let cache = ClanWarsPlaylistCache::new(Duration::ZERO);
+        let clan_tag = "ABC".to_owned();
+
+        cache.insert(&clan_tag, &ClanWarsSort::ToConquer, Playlist::default());
+        std::thread::sleep(Duration::from_millis(1));
+
+        assert!(cache.get(&clan_tag, &ClanWarsSort::ToConquer).is_none());
+    }
+}
@@ -1,11 +1,13 @@
 use chrono::Utc;
 use std::sync::Arc;
+use std::time::Instant;
 
 use tokio_util::sync::CancellationToken;
 use tracing::{info, warn};
 
 use crate::persist::CommonData;
 use crate::storage::bsmaps::BsMapsRepository;
+use crate::METRICS;
 
 pub struct CommanderOrdersCleanupWorker {
     maps_repository: Arc<BsMapsRepository>,
@@ -33,6 +35,8 @@ impl CommanderOrdersCleanupWorker {
         );
 
         'outer: loop {
+            let started_at = Instant::now();
+
             let commander_orders = self
                 .maps_repository
                 .all_commander_orders()
@@ -64,6 +68,8 @@ impl CommanderOrdersCleanupWorker {
                 }
             }
 
+            METRICS.record_worker_run("commander_orders_cleanup", started_at.elapsed());
+
             tokio::select! {
                 _ = self.token.cancelled() => {
                     warn!("Commander's order cleanup task is shutting down...");
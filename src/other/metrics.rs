@@ -0,0 +1,278 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+#[derive(Debug, Default, Clone, Copy)]
+struct WorkerRuns {
+    count: u64,
+    total_seconds: f64,
+    last_seconds: f64,
+}
+
+// counters exported in the Prometheus text format by the /metrics route
+#[derive(Debug, Default)]
+pub(crate) struct Metrics {
+    bl_api_requests: AtomicU64,
+    bl_api_errors: Mutex<BTreeMap<&'static str, u64>>,
+    binary_file_cache_hits: AtomicU64,
+    binary_file_cache_misses: AtomicU64,
+    commands: Mutex<BTreeMap<String, u64>>,
+    worker_runs: Mutex<BTreeMap<&'static str, WorkerRuns>>,
+    // items processed by workers, e.g. players, by worker and result
+    worker_items: Mutex<BTreeMap<(&'static str, &'static str), u64>>,
+    // corrupted items skipped while loading the storage at startup
+    storage_skipped_items: Mutex<BTreeMap<String, u64>>,
+}
+
+impl Metrics {
+    pub fn record_bl_api_request(&self) {
+        self.bl_api_requests.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_bl_api_error(&self, kind: &'static str) {
+        *self.bl_api_errors.lock().unwrap().entry(kind).or_default() += 1;
+    }
+
+    pub fn record_binary_file_cache_hit(&self) {
+        self.binary_file_cache_hits.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_binary_file_cache_miss(&self) {
+        self.binary_file_cache_misses
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_command(&self, command: &str) {
+        *self
+            .commands
+            .lock()
+            .unwrap()
+            .entry(command.to_owned())
+            .or_default() += 1;
+    }
+
+    pub fn record_worker_run(&self, worker: &'static str, duration: Duration) {
+        let mut worker_runs = self.worker_runs.lock().unwrap();
+        let runs = worker_runs.entry(worker).or_default();
+
+        runs.count += 1;
+        runs.total_seconds += duration.as_secs_f64();
+        runs.last_seconds = duration.as_secs_f64();
+    }
+
+    pub fn record_worker_items(&self, worker: &'static str, succeeded: usize, failed: usize) {
+        let mut worker_items = self.worker_items.lock().unwrap();
+
+        *worker_items.entry((worker, "success")).or_default() += succeeded as u64;
+        *worker_items.entry((worker, "failure")).or_default() += failed as u64;
+    }
+
+    pub fn record_storage_skipped_items(&self, storage: &str, count: usize) {
+        self.storage_skipped_items
+            .lock()
+            .unwrap()
+            .insert(storage.to_owned(), count as u64);
+    }
+
+    pub fn render(&self, storage_sizes: &[(&str, usize)]) -> String {
+        let mut out = String::new();
+
+        Self::header(
+            &mut out,
+            "blbot_commands_executed_total",
+            "counter",
+            "Discord commands executed.",
+        );
+        for (command, count) in self.commands.lock().unwrap().iter() {
+            let _ = writeln!(
+                out,
+                "blbot_commands_executed_total{{command=\"{}\"}} {}",
+                command, count
+            );
+        }
+
+        Self::header(
+            &mut out,
+            "blbot_bl_api_requests_total",
+            "counter",
+            "Requests sent to the BL API.",
+        );
+        let _ = writeln!(
+            out,
+            "blbot_bl_api_requests_total {}",
+            self.bl_api_requests.load(Ordering::Relaxed)
+        );
+
+        Self::header(
+            &mut out,
+            "blbot_bl_api_errors_total",
+            "counter",
+            "Failed BL API requests by error kind.",
+        );
+        for (kind, count) in self.bl_api_errors.lock().unwrap().iter() {
+            let _ = writeln!(
+                out,
+                "blbot_bl_api_errors_total{{kind=\"{}\"}} {}",
+                kind, count
+            );
+        }
+
+        Self::header(
+            &mut out,
+            "blbot_binary_file_cache_requests_total",
+            "counter",
+            "Downloaded files (avatars, covers, etc.) requests by cache result.",
+        );
+        let _ = writeln!(
+            out,
+            "blbot_binary_file_cache_requests_total{{result=\"hit\"}} {}",
+            self.binary_file_cache_hits.load(Ordering::Relaxed)
+        );
+        let _ = writeln!(
+            out,
+            "blbot_binary_file_cache_requests_total{{result=\"miss\"}} {}",
+            self.binary_file_cache_misses.load(Ordering::Relaxed)
+        );
+
+        let worker_runs = self.worker_runs.lock().unwrap().clone();
+
+        Self::header(
+            &mut out,
+            "blbot_worker_runs_total",
+            "counter",
+            "Background worker runs.",
+        );
+        for (worker, runs) in worker_runs.iter() {
+            let _ = writeln!(
+                out,
+                "blbot_worker_runs_total{{worker=\"{}\"}} {}",
+                worker, runs.count
+            );
+        }
+
+        Self::header(
+            &mut out,
+            "blbot_worker_run_duration_seconds_total",
+            "counter",
+            "Total time spent in background worker runs.",
+        );
+        for (worker, runs) in worker_runs.iter() {
+            let _ = writeln!(
+                out,
+                "blbot_worker_run_duration_seconds_total{{worker=\"{}\"}} {:.3}",
+                worker, runs.total_seconds
+            );
+        }
+
+        Self::header(
+            &mut out,
+            "blbot_worker_last_run_duration_seconds",
+            "gauge",
+            "Duration of the last background worker run.",
+        );
+        for (worker, runs) in worker_runs.iter() {
+            let _ = writeln!(
+                out,
+                "blbot_worker_last_run_duration_seconds{{worker=\"{}\"}} {:.3}",
+                worker, runs.last_seconds
+            );
+        }
+
+        Self::header(
+            &mut out,
+            "blbot_worker_items_total",
+            "counter",
+            "Items processed by background workers by result.",
+        );
+        for ((worker, result), count) in self.worker_items.lock().unwrap().iter() {
+            let _ = writeln!(
+                out,
+                "blbot_worker_items_total{{worker=\"{}\",result=\"{}\"}} {}",
+                worker, result, count
+            );
+        }
+
+        Self::header(
+            &mut out,
+            "blbot_storage_items",
+            "gauge",
+            "Number of items kept in storage.",
+        );
+        for (storage, size) in storage_sizes {
+            let _ = writeln!(
+                out,
+                "blbot_storage_items{{storage=\"{}\"}} {}",
+                storage, size
+            );
+        }
+
+        Self::header(
+            &mut out,
+            "blbot_storage_skipped_items",
+            "gauge",
+            "Number of corrupted storage items skipped at startup.",
+        );
+        for (storage, count) in self.storage_skipped_items.lock().unwrap().iter() {
+            let _ = writeln!(
+                out,
+                "blbot_storage_skipped_items{{storage=\"{}\"}} {}",
+                storage, count
+            );
+        }
+
+        out
+    }
+
+    fn header(out: &mut String, name: &str, metric_type: &str, help: &str) {
+        let _ = writeln!(out, "# HELP {} {}", name, help);
+        let _ = writeln!(out, "# TYPE {} {}", name, metric_type);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::other::metrics::Metrics;
+
+    #[test]
+    fn it_renders_prometheus_metrics() {
+        let metrics = Metrics::default();
+
+        metrics.record_command("bl-profile");
+        metrics.record_command("bl-profile");
+        metrics.record_bl_api_request();
+        metrics.record_bl_api_error("server");
+        metrics.record_binary_file_cache_hit();
+        metrics.record_binary_file_cache_miss();
+        metrics.record_binary_file_cache_miss();
+        metrics.record_worker_run("player_stats", Duration::from_millis(1500));
+        metrics.record_worker_run("player_stats", Duration::from_millis(500));
+        metrics.record_worker_items("player_stats", 8, 2);
+        metrics.record_worker_items("player_stats", 1, 0);
+        metrics.record_storage_skipped_items("players", 1);
+
+        let out = metrics.render(&[("players", 10)]);
+
+        assert!(out.contains("# TYPE blbot_commands_executed_total counter\n"));
+        assert!(out.contains("blbot_commands_executed_total{command=\"bl-profile\"} 2\n"));
+        assert!(out.contains("blbot_bl_api_requests_total 1\n"));
+        assert!(out.contains("blbot_bl_api_errors_total{kind=\"server\"} 1\n"));
+        assert!(out.contains("blbot_binary_file_cache_requests_total{result=\"hit\"} 1\n"));
+        assert!(out.contains("blbot_binary_file_cache_requests_total{result=\"miss\"} 2\n"));
+        assert!(out.contains("blbot_worker_runs_total{worker=\"player_stats\"} 2\n"));
+        assert!(out
+            .contains("blbot_worker_run_duration_seconds_total{worker=\"player_stats\"} 2.000\n"));
+        assert!(
+            out.contains("blbot_worker_last_run_duration_seconds{worker=\"player_stats\"} 0.500\n")
+        );
+        assert!(out
+            .contains("blbot_worker_items_total{worker=\"player_stats\",result=\"success\"} 9\n"));
+        assert!(out
+            .contains("blbot_worker_items_total{worker=\"player_stats\",result=\"failure\"} 2\n"));
+        assert!(out.contains("blbot_storage_items{storage=\"players\"} 10\n"));
+        assert!(out.contains("blbot_storage_skipped_items{storage=\"players\"} 1\n"));
+    }
+}
@@ -1,3 +1,10 @@
+pub(crate) mod binary_file_cache;
+pub(crate) mod clan_membership_cache;
+pub(crate) mod clan_wars_playlist_cache;
 pub(crate) mod commander_orders;
+pub(crate) mod metrics;
+pub(crate) mod playlist_cleanup;
 pub(crate) mod ram_reporter;
+pub(crate) mod single_flight;
 pub mod string_utils;
+pub(crate) mod verification_reminders;
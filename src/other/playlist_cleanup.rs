@@ -0,0 +1,85 @@
+use chrono::Utc;
+use std::sync::Arc;
+use std::time::Instant;
+
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+use crate::persist::CommonData;
+use crate::storage::playlist::PlaylistRepository;
+use crate::METRICS;
+
+pub struct PlaylistCleanupWorker {
+    playlists_repository: Arc<PlaylistRepository>,
+    ttl_days: u64,
+    token: CancellationToken,
+}
+
+impl PlaylistCleanupWorker {
+    pub fn new(data: CommonData, token: CancellationToken) -> Self {
+        Self {
+            playlists_repository: data.playlists_repository,
+            ttl_days: data.settings.playlist_ttl,
+            token,
+        }
+    }
+
+    pub async fn run(self) {
+        if self.ttl_days == 0 {
+            info!("Playlist TTL is disabled, playlist cleanup task will not run");
+            return;
+        }
+
+        let interval = std::time::Duration::from_secs(60 * 60);
+        let ttl = chrono::Duration::days(self.ttl_days as i64);
+
+        info!(
+            "Run playlist cleanup task with TTL of {} day(s)",
+            self.ttl_days
+        );
+
+        'outer: loop {
+            let started_at = Instant::now();
+            let now = Utc::now();
+
+            let to_delete = self
+                .playlists_repository
+                .all()
+                .await
+                .into_iter()
+                .filter(|playlist| playlist.is_unused_for(ttl, now))
+                .collect::<Vec<_>>();
+
+            let mut deleted = 0;
+            for playlist in to_delete.iter() {
+                if self.token.is_cancelled() {
+                    break;
+                }
+
+                match self.playlists_repository.remove(playlist.get_id()).await {
+                    Ok(_) => deleted += 1,
+                    Err(e) => warn!("Failed to delete playlist {}: {}", playlist.get_id(), e),
+                }
+            }
+
+            info!(
+                "Deleted {} of {} playlist(s) unused for {} day(s).",
+                deleted,
+                to_delete.len(),
+                self.ttl_days
+            );
+
+            METRICS.record_worker_run("playlist_cleanup", started_at.elapsed());
+
+            tokio::select! {
+                _ = self.token.cancelled() => {
+                    warn!("Playlist cleanup task is shutting down...");
+                    break 'outer;
+                }
+                _ = tokio::time::sleep(interval) => {}
+            }
+        }
+
+        warn!("Playlist cleanup task shut down.");
+    }
+}
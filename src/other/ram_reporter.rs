@@ -3,7 +3,7 @@ use tokio_util::sync::CancellationToken;
 use tracing::{info, warn};
 
 #[global_allocator]
-static PEAK_ALLOC: PeakAlloc = PeakAlloc;
+pub(crate) static PEAK_ALLOC: PeakAlloc = PeakAlloc;
 
 pub struct RamReporter {
     token: CancellationToken,
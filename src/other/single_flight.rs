@@ -0,0 +1,207 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::hash::Hash;
+use std::sync::Mutex;
+
+use futures::channel::oneshot;
+use futures::future::Shared;
+use futures::FutureExt;
+
+// deduplicates concurrent calls with the same key, callers arriving while the call is in flight
+// await its result instead of running it again; only successful results are shared, if the
+// call fails or is cancelled one of the waiting callers runs it again and the rest await it
+#[derive(Debug)]
+pub(crate) struct SingleFlight<K, V> {
+    calls: Mutex<HashMap<K, Shared<oneshot::Receiver<V>>>>,
+}
+
+impl<K, V> Default for SingleFlight<K, V> {
+    fn default() -> Self {
+        Self {
+            calls: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+// removes the key even if the call future is dropped before completion
+struct InFlightGuard<'a, K: Eq + Hash, V> {
+    calls: &'a Mutex<HashMap<K, Shared<oneshot::Receiver<V>>>>,
+    key: K,
+}
+
+impl<K: Eq + Hash, V> Drop for InFlightGuard<'_, K, V> {
+    fn drop(&mut self) {
+        self.calls.lock().unwrap().remove(&self.key);
+    }
+}
+
+impl<K, V> SingleFlight<K, V>
+where
+    K: Eq + Hash + Clone,
+    V: Clone,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub async fn run<F, Fut, E>(&self, key: &K, call: F) -> Result<V, E>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<V, E>>,
+    {
+        loop {
+            let in_flight = {
+                let mut calls = self.calls.lock().unwrap();
+
+                match calls.get(key) {
+                    Some(receiver) => Err(receiver.clone()),
+                    None => {
+                        let (sender, receiver) = oneshot::channel();
+                        calls.insert(key.clone(), receiver.shared());
+
+                        Ok(sender)
+                    }
+                }
+            };
+
+            match in_flight {
+                Ok(sender) => {
+                    let guard = InFlightGuard {
+                        calls: &self.calls,
+                        key: key.clone(),
+                    };
+
+                    let result = call().await;
+
+                    drop(guard);
+
+                    if let Ok(value) = &result {
+                        let _ = sender.send(value.clone());
+                    }
+
+                    return result;
+                }
+                Err(receiver) => {
+                    if let Ok(value) = receiver.await {
+                        return Ok(value);
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn in_flight_count(&self) -> usize {
+        self.calls.lock().unwrap().len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    use crate::other::single_flight::SingleFlight;
+
+    #[tokio::test]
+    async fn it_runs_concurrent_calls_once() {
+        let single_flight = SingleFlight::<String, u32>::new();
+        let calls = AtomicUsize::new(0);
+
+        let call = || async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(50)).await;
+
+            Ok::<u32, String>(42)
+        };
+
+        let key = "player".to_owned();
+        let other_key = "other".to_owned();
+
+        let (first, second, third, other) = tokio::join!(
+            single_flight.run(&key, call),
+            single_flight.run(&key, call),
+            single_flight.run(&key, call),
+            single_flight.run(&other_key, call),
+        );
+
+        assert_eq!(first, Ok(42));
+        assert_eq!(second, Ok(42));
+        assert_eq!(third, Ok(42));
+        assert_eq!(other, Ok(42));
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        assert_eq!(single_flight.in_flight_count(), 0);
+
+        // calls made after the previous one completed are not deduplicated
+        assert_eq!(single_flight.run(&key, call).await, Ok(42));
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn it_retries_waiting_calls_if_the_call_failed() {
+        let single_flight = SingleFlight::<String, u32>::new();
+        let calls = AtomicUsize::new(0);
+
+        let call = || async {
+            let call_no = calls.fetch_add(1, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(50)).await;
+
+            if call_no == 0 {
+                Err("BL API error".to_owned())
+            } else {
+                Ok(42)
+            }
+        };
+
+        let key = "player".to_owned();
+
+        let (first, second) = tokio::join!(single_flight.run(&key, call), async {
+            // make sure the first call is in flight
+            tokio::time::sleep(Duration::from_millis(10)).await;
+
+            single_flight.run(&key, call).await
+        });
+
+        assert_eq!(first, Err("BL API error".to_owned()));
+        assert_eq!(second, Ok(42));
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn it_retries_failed_call_only_once_for_all_waiting_calls() {
+        let single_flight = SingleFlight::<String, u32>::new();
+        let calls = AtomicUsize::new(0);
+
+        let call = || async {
+            let call_no = calls.fetch_add(1, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(50)).await;
+
+            if call_no == 0 {
+                Err("BL API error".to_owned())
+            } else {
+                Ok(42)
+            }
+        };
+
+        let key = "player".to_owned();
+        let waiting_call = || async {
+            // make sure the first call is in flight
+            tokio::time::sleep(Duration::from_millis(10)).await;
+
+            single_flight.run(&key, call).await
+        };
+
+        let (first, second, third, fourth) = tokio::join!(
+            single_flight.run(&key, call),
+            waiting_call(),
+            waiting_call(),
+            waiting_call(),
+        );
+
+        assert_eq!(first, Err("BL API error".to_owned()));
+        assert_eq!(second, Ok(42));
+        assert_eq!(third, Ok(42));
+        assert_eq!(fourth, Ok(42));
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        assert_eq!(single_flight.in_flight_count(), 0);
+    }
+}
@@ -5,3 +5,15 @@ pub fn capitalize(s: &str) -> String {
         Some(f) => f.to_uppercase().collect::<String>() + c.as_str(),
     }
 }
+
+// decimal places of pp, acc and stars shown to users, set per guild
+pub const DEFAULT_DECIMAL_PLACES: u8 = 2;
+pub const MAX_DECIMAL_PLACES: u8 = 3;
+
+pub fn format_decimal(value: f64, decimal_places: u8) -> String {
+    format!(
+        "{:.*}",
+        decimal_places.min(MAX_DECIMAL_PLACES) as usize,
+        value
+    )
+}
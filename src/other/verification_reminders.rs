@@ -0,0 +1,39 @@
+use std::collections::HashSet;
+use std::sync::Mutex;
+
+use poise::serenity_prelude::{GuildId, UserId};
+
+// users already sent a DM with the profile verification steps, kept in memory only,
+// so a user may be reminded once more after the bot restart
+#[derive(Debug, Default)]
+pub(crate) struct VerificationReminders {
+    reminded: Mutex<HashSet<(GuildId, UserId)>>,
+}
+
+impl VerificationReminders {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // returns false if the user has already been reminded in the guild
+    pub fn mark_reminded(&self, guild_id: GuildId, user_id: UserId) -> bool {
+        self.reminded.lock().unwrap().insert((guild_id, user_id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use poise::serenity_prelude::{GuildId, UserId};
+
+    use crate::other::verification_reminders::VerificationReminders;
+
+    #[test]
+    fn it_reminds_user_once_per_guild() {
+        let reminders = VerificationReminders::new();
+
+        assert!(reminders.mark_reminded(GuildId::new(1), UserId::new(1)));
+        assert!(!reminders.mark_reminded(GuildId::new(1), UserId::new(1)));
+        assert!(reminders.mark_reminded(GuildId::new(2), UserId::new(1)));
+        assert!(reminders.mark_reminded(GuildId::new(1), UserId::new(2)));
+    }
+}
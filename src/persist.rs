@@ -2,26 +2,33 @@ use std::path::PathBuf;
 use std::sync::Arc;
 use tracing::info;
 
-use crate::beatleader::BlContext;
 use crate::config::Settings;
 use crate::storage::bsmaps::BsMapsRepository;
+use crate::storage::clan_map_capture::ClanMapCaptureRepository;
 use crate::storage::clan_peak::ClanPeakRepository;
+use crate::storage::clan_recap::ClanRecapRepository;
+use crate::storage::clan_wars_post::ClanWarsPostRepository;
 use crate::storage::guild::GuildSettingsRepository;
 use crate::storage::persist::PersistInstance;
 use crate::storage::player::PlayerRepository;
 use crate::storage::player_oauth_token::PlayerOAuthTokenRepository;
-use crate::storage::player_scores::PlayerScoresRepository;
+use crate::storage::player_scores::PlayerScoresRepositories;
 use crate::storage::playlist::PlaylistRepository;
+use crate::storage::role_change::RoleChangeRepository;
 
 #[derive(Clone)]
 pub struct CommonData {
     pub guild_settings_repository: Arc<GuildSettingsRepository>,
     pub players_repository: Arc<PlayerRepository>,
-    pub player_scores_repository: Arc<PlayerScoresRepository>,
+    pub player_scores_repositories: Arc<PlayerScoresRepositories>,
     pub player_oauth_token_repository: Arc<PlayerOAuthTokenRepository>,
     pub playlists_repository: Arc<PlaylistRepository>,
     pub maps_repository: Arc<BsMapsRepository>,
     pub clan_peak_repository: Arc<ClanPeakRepository>,
+    pub clan_map_capture_repository: Arc<ClanMapCaptureRepository>,
+    pub role_change_repository: Arc<RoleChangeRepository>,
+    pub clan_wars_post_repository: Arc<ClanWarsPostRepository>,
+    pub clan_recap_repository: Arc<ClanRecapRepository>,
     pub settings: Settings,
 }
 
@@ -57,13 +64,13 @@ pub async fn init(settings: Settings) -> CommonData {
         players_repository.len().await
     );
 
-    info!("Initializing players scores repository...");
-    let player_scores_repository = Arc::new(
-        PlayerScoresRepository::new(Arc::clone(&persist), BlContext::General)
+    info!("Initializing players scores repositories...");
+    let player_scores_repositories = Arc::new(
+        PlayerScoresRepositories::new(Arc::clone(&persist), &settings.score_contexts)
             .await
             .unwrap(),
     );
-    info!("Players scores repository initialized.");
+    info!("Players scores repositories initialized.");
 
     info!("Initializing playlists repository...");
     let playlists_repository =
@@ -79,14 +86,62 @@ pub async fn init(settings: Settings) -> CommonData {
         Arc::new(ClanPeakRepository::new(Arc::clone(&persist)).await.unwrap());
     info!("Clan peak repository initialized.");
 
+    info!("Initializing clan map captures repository...");
+    let clan_map_capture_repository = Arc::new(
+        ClanMapCaptureRepository::new(Arc::clone(&persist))
+            .await
+            .unwrap(),
+    );
+    info!(
+        "Clan map captures repository initialized, length: {}.",
+        clan_map_capture_repository.len().await
+    );
+
+    info!("Initializing role changes repository...");
+    let role_change_repository = Arc::new(
+        RoleChangeRepository::new(Arc::clone(&persist))
+            .await
+            .unwrap(),
+    );
+    info!(
+        "Role changes repository initialized, length: {}.",
+        role_change_repository.len().await
+    );
+
+    info!("Initializing clan wars posts repository...");
+    let clan_wars_post_repository = Arc::new(
+        ClanWarsPostRepository::new(Arc::clone(&persist))
+            .await
+            .unwrap(),
+    );
+    info!(
+        "Clan wars posts repository initialized, length: {}.",
+        clan_wars_post_repository.len().await
+    );
+
+    info!("Initializing clan recaps repository...");
+    let clan_recap_repository = Arc::new(
+        ClanRecapRepository::new(Arc::clone(&persist))
+            .await
+            .unwrap(),
+    );
+    info!(
+        "Clan recaps repository initialized, length: {}.",
+        clan_recap_repository.len().await
+    );
+
     CommonData {
         guild_settings_repository,
         players_repository,
         player_oauth_token_repository,
-        player_scores_repository,
+        player_scores_repositories,
         playlists_repository,
         maps_repository,
         clan_peak_repository,
+        clan_map_capture_repository,
+        role_change_repository,
+        clan_wars_post_repository,
+        clan_recap_repository,
         settings,
     }
 }
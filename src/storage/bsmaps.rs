@@ -8,7 +8,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::beatleader::player::{Leaderboard, LeaderboardId};
 use crate::storage::persist::PersistInstance;
-use crate::storage::{CachedStorage, Storage, StorageValue};
+use crate::storage::{CachedStorage, CompactionStats, Storage, StorageValue};
 
 use super::Result;
 
@@ -193,6 +193,10 @@ impl<'a> BsMapsRepository {
         self.storage.len().await
     }
 
+    pub(crate) async fn compact(&self) -> Result<CompactionStats> {
+        self.storage.compact().await
+    }
+
     pub(crate) async fn get(&self, map_id: &BsMapId) -> Option<BsMap> {
         self.storage.get(map_id).await
     }
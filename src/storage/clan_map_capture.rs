@@ -0,0 +1,150 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use std::sync::Arc;
+
+use crate::beatleader::clan::{ClanId, ClanTag};
+use crate::beatleader::player::LeaderboardId;
+use crate::storage::persist::PersistInstance;
+use crate::storage::{CachedStorage, CompactionStats, Storage, StorageKey, StorageValue};
+
+use super::Result;
+
+#[derive(Serialize, Deserialize, Default, Debug, Clone, Eq, PartialEq, Hash)]
+#[serde(rename_all = "camelCase")]
+pub struct ClanMapCaptureKey {
+    pub clan_id: ClanId,
+    pub leaderboard_id: LeaderboardId,
+}
+
+impl Display for ClanMapCaptureKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}-{}", self.clan_id, self.leaderboard_id)
+    }
+}
+
+impl StorageKey for ClanMapCaptureKey {}
+
+// leaderboard held by the clan as of the last check
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+#[serde(default)]
+pub struct ClanMapCapture {
+    pub key: ClanMapCaptureKey,
+    pub clan_tag: ClanTag,
+    pub song_name: String,
+    pub difficulty_name: String,
+    pub captured_at: DateTime<Utc>,
+}
+
+impl Display for ClanMapCapture {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        // clan maps list does not always include difficulty
+        let name = if self.difficulty_name.is_empty() {
+            self.song_name.clone()
+        } else {
+            format!("{} / {}", self.song_name, self.difficulty_name)
+        };
+
+        write!(
+            f,
+            "[{}](https://www.beatleader.com/leaderboard/clanranking/{}/1)",
+            name, self.key.leaderboard_id
+        )
+    }
+}
+
+impl StorageValue<ClanMapCaptureKey> for ClanMapCapture {
+    fn get_key(&self) -> ClanMapCaptureKey {
+        self.key.clone()
+    }
+}
+
+// marks a clan whose maps were checked at least once, a clan holding no maps stores no captures
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+#[serde(default)]
+pub struct ClanMapCaptureCheck {
+    pub clan_id: ClanId,
+    pub checked_at: DateTime<Utc>,
+}
+
+impl StorageValue<ClanId> for ClanMapCaptureCheck {
+    fn get_key(&self) -> ClanId {
+        self.clan_id
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct ClanMapCaptureRepository {
+    storage: CachedStorage<ClanMapCaptureKey, ClanMapCapture>,
+    checks: CachedStorage<ClanId, ClanMapCaptureCheck>,
+}
+
+impl ClanMapCaptureRepository {
+    pub(crate) async fn new(persist: Arc<PersistInstance>) -> Result<ClanMapCaptureRepository> {
+        Ok(Self {
+            storage: CachedStorage::new(Storage::new("clan-map-captures", persist.clone())).await?,
+            checks: CachedStorage::new(Storage::new("clan-map-capture-checks", persist)).await?,
+        })
+    }
+
+    pub(crate) async fn len(&self) -> usize {
+        self.storage.len().await
+    }
+
+    pub(crate) async fn compact(&self) -> Result<Vec<CompactionStats>> {
+        Ok(vec![
+            self.storage.compact().await?,
+            self.checks.compact().await?,
+        ])
+    }
+
+    pub(crate) async fn is_checked(&self, clan_id: ClanId) -> bool {
+        self.checks.contains_key(&clan_id).await
+    }
+
+    pub(crate) async fn set_checked(&self, clan_id: ClanId) -> Result<()> {
+        self.checks
+            .set(
+                &clan_id,
+                ClanMapCaptureCheck {
+                    clan_id,
+                    checked_at: Utc::now(),
+                },
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    pub(crate) async fn by_clan(&self, clan_id: ClanId) -> Vec<ClanMapCapture> {
+        self.storage
+            .filter_map_collect(|capture| (capture.key.clan_id == clan_id).then(|| capture.clone()))
+            .await
+    }
+
+    pub(crate) async fn set_all(&self, captures: Vec<ClanMapCapture>) -> Result<()> {
+        self.storage
+            .with_batch(|| async {
+                for capture in captures {
+                    self.storage.set(&capture.get_key(), capture).await?;
+                }
+
+                Ok(())
+            })
+            .await
+    }
+
+    pub(crate) async fn remove_all(&self, keys: &[ClanMapCaptureKey]) -> Result<()> {
+        self.storage
+            .with_batch(|| async {
+                for key in keys {
+                    self.storage.remove(key).await?;
+                }
+
+                Ok(())
+            })
+            .await
+    }
+}
@@ -4,7 +4,7 @@ use std::sync::Arc;
 
 use crate::beatleader::clan::{ClanId, ClanTag};
 use crate::storage::persist::PersistInstance;
-use crate::storage::{CachedStorage, Storage, StorageKey, StorageValue};
+use crate::storage::{CachedStorage, CompactionStats, Storage, StorageKey, StorageValue};
 use serde::{Deserialize, Serialize};
 
 use super::Result;
@@ -74,6 +74,10 @@ impl<'a> ClanPeakRepository {
         self.storage.len().await
     }
 
+    pub(crate) async fn compact(&self) -> Result<CompactionStats> {
+        self.storage.compact().await
+    }
+
     pub(crate) async fn get(&self, clan_id: &ClanId) -> Result<Option<ClanPeak>> {
         Ok(self.storage.get(clan_id).await)
     }
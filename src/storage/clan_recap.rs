@@ -0,0 +1,74 @@
+use chrono::{DateTime, Utc};
+use poise::serenity_prelude::GuildId;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::sync::Arc;
+
+use crate::beatleader::clan::ClanTag;
+use crate::beatleader::player::PlayerId;
+use crate::storage::clan_map_capture::ClanMapCapture;
+use crate::storage::persist::PersistInstance;
+use crate::storage::{CachedStorage, CompactionStats, Storage, StorageValue};
+
+use super::Result;
+
+// state of the clan at the time of the last weekly recap, the next recap is compared against it
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+#[serde(default)]
+pub struct ClanRecapSnapshot {
+    pub guild_id: GuildId,
+    pub clan_tag: ClanTag,
+    pub taken_at: DateTime<Utc>,
+    pub player_ranks: HashMap<PlayerId, u32>,
+    pub captures: Vec<ClanMapCapture>,
+}
+
+impl Display for ClanRecapSnapshot {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} recap snapshot taken at {}: {} player(s), {} map(s)",
+            self.clan_tag,
+            self.taken_at,
+            self.player_ranks.len(),
+            self.captures.len()
+        )
+    }
+}
+
+impl StorageValue<GuildId> for ClanRecapSnapshot {
+    fn get_key(&self) -> GuildId {
+        self.guild_id
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct ClanRecapRepository {
+    storage: CachedStorage<GuildId, ClanRecapSnapshot>,
+}
+
+impl ClanRecapRepository {
+    pub(crate) async fn new(persist: Arc<PersistInstance>) -> Result<ClanRecapRepository> {
+        Ok(Self {
+            storage: CachedStorage::new(Storage::new("clan-recap", persist)).await?,
+        })
+    }
+
+    pub(crate) async fn len(&self) -> usize {
+        self.storage.len().await
+    }
+
+    pub(crate) async fn compact(&self) -> Result<CompactionStats> {
+        self.storage.compact().await
+    }
+
+    pub(crate) async fn get(&self, guild_id: &GuildId) -> Option<ClanRecapSnapshot> {
+        self.storage.get(guild_id).await
+    }
+
+    pub(crate) async fn set(&self, snapshot: ClanRecapSnapshot) -> Result<ClanRecapSnapshot> {
+        self.storage.set(&snapshot.get_key(), snapshot).await
+    }
+}
@@ -0,0 +1,129 @@
+use chrono::{DateTime, Utc};
+use poise::serenity_prelude::GuildId;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt::{Display, Formatter};
+use std::sync::Arc;
+
+use crate::beatleader::player::LeaderboardId;
+use crate::storage::persist::PersistInstance;
+use crate::storage::{CachedStorage, CompactionStats, Storage, StorageKey, StorageValue};
+
+use super::Result;
+
+#[derive(Serialize, Deserialize, Default, Debug, Clone, Eq, PartialEq, Hash)]
+#[serde(rename_all = "camelCase")]
+pub struct ClanWarsPostKey {
+    pub guild_id: GuildId,
+    pub leaderboard_id: LeaderboardId,
+}
+
+impl Display for ClanWarsPostKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}-{}", self.guild_id, self.leaderboard_id)
+    }
+}
+
+impl StorageKey for ClanWarsPostKey {}
+
+// clan wars map already announced on the guild maps channel
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+#[serde(default)]
+pub struct ClanWarsPost {
+    pub key: ClanWarsPostKey,
+    pub posted_at: DateTime<Utc>,
+}
+
+impl StorageValue<ClanWarsPostKey> for ClanWarsPost {
+    fn get_key(&self) -> ClanWarsPostKey {
+        self.key.clone()
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct ClanWarsPostRepository {
+    storage: CachedStorage<ClanWarsPostKey, ClanWarsPost>,
+}
+
+impl ClanWarsPostRepository {
+    pub(crate) async fn new(persist: Arc<PersistInstance>) -> Result<ClanWarsPostRepository> {
+        Ok(Self {
+            storage: CachedStorage::new(Storage::new("clan-wars-posts", persist)).await?,
+        })
+    }
+
+    pub(crate) async fn len(&self) -> usize {
+        self.storage.len().await
+    }
+
+    pub(crate) async fn compact(&self) -> Result<CompactionStats> {
+        self.storage.compact().await
+    }
+
+    pub(crate) async fn posted_leaderboard_ids(
+        &self,
+        guild_id: &GuildId,
+    ) -> HashSet<LeaderboardId> {
+        self.storage
+            .filter_map_collect(|post| {
+                (&post.key.guild_id == guild_id).then(|| post.key.leaderboard_id.clone())
+            })
+            .await
+            .into_iter()
+            .collect()
+    }
+
+    pub(crate) async fn set_posted_at(
+        &self,
+        key: ClanWarsPostKey,
+        posted_at: DateTime<Utc>,
+    ) -> Result<ClanWarsPost> {
+        self.storage
+            .set(&key.clone(), ClanWarsPost { key, posted_at })
+            .await
+    }
+
+    // removes guild posts of maps that are no longer on the clan wars map list, returns the number of removed entries
+    pub(crate) async fn remove_unlisted(
+        &self,
+        guild_id: &GuildId,
+        listed: &[LeaderboardId],
+    ) -> Result<usize> {
+        let unlisted = self
+            .storage
+            .filter_map_collect(|post| {
+                (&post.key.guild_id == guild_id && !listed.contains(&post.key.leaderboard_id))
+                    .then(|| post.key.clone())
+            })
+            .await;
+
+        self.remove_all(&unlisted).await?;
+
+        Ok(unlisted.len())
+    }
+
+    // returns the number of removed entries
+    pub(crate) async fn remove_posted_before(&self, before: DateTime<Utc>) -> Result<usize> {
+        let expired = self
+            .storage
+            .filter_map_collect(|post| (post.posted_at <= before).then(|| post.key.clone()))
+            .await;
+
+        self.remove_all(&expired).await?;
+
+        Ok(expired.len())
+    }
+
+    async fn remove_all(&self, keys: &[ClanWarsPostKey]) -> Result<()> {
+        self.storage
+            .with_batch(|| async {
+                for key in keys.iter() {
+                    self.storage.remove(key).await?;
+                }
+
+                Ok(())
+            })
+            .await
+    }
+}
@@ -1,4 +1,5 @@
 use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
 use std::sync::Arc;
 
 use crate::storage::persist::PersistInstance;
@@ -6,13 +7,26 @@ use poise::serenity_prelude::{ChannelId, GuildId, RoleId, UserId};
 use tokio::sync::MutexGuard;
 use tracing::{debug, trace};
 
+use crate::discord::bot::beatleader::clan::ClanWarsSort;
 use crate::discord::bot::{
-    ClanSettings, Condition, GuildSettings, RequirementMetricValue, RoleGroup, RoleSettings,
+    ClanRecapSchedule, ClanSettings, Condition, EmbedTheme, GuildSettings, RequirementMetricValue,
+    RoleGroup, RoleMatchMode, RoleSettings,
 };
-use crate::storage::{CachedStorage, Storage, StorageError};
+use crate::embed::MapTriangleColors;
+use crate::storage::{CachedStorage, CompactionStats, Storage, StorageError};
 
 use super::Result;
 
+// conditions of an auto role added to a new or an existing role
+#[derive(Debug, Clone)]
+pub(crate) struct AutoRoleParams {
+    pub metric_and_value: RequirementMetricValue,
+    pub condition: Condition,
+    pub tolerance: Option<f64>,
+    pub match_mode: Option<RoleMatchMode>,
+    pub required_role: Option<RoleId>,
+}
+
 #[derive(Debug)]
 pub(crate) struct GuildSettingsRepository {
     storage: CachedStorage<GuildId, GuildSettings>,
@@ -33,6 +47,10 @@ impl<'a> GuildSettingsRepository {
         self.storage.len().await
     }
 
+    pub(crate) async fn compact(&self) -> Result<CompactionStats> {
+        self.storage.compact().await
+    }
+
     pub(crate) async fn get(&self, guild_id: &GuildId) -> Result<GuildSettings> {
         match self.storage.get(guild_id).await {
             Some(guild_settings) => Ok(guild_settings),
@@ -84,6 +102,68 @@ impl<'a> GuildSettingsRepository {
         }
     }
 
+    pub(crate) async fn set_role_changes_channel(
+        &self,
+        guild_id: &GuildId,
+        channel_id: Option<ChannelId>,
+    ) -> Result<GuildSettings> {
+        trace!("Setting role changes channel for guild {}...", guild_id);
+
+        if let Some(guild_settings) = self
+            .storage
+            .get_and_modify_or_insert(
+                guild_id,
+                |guild_settings| guild_settings.set_role_changes_channel(channel_id),
+                || {
+                    let mut guild_settings = GuildSettings::new(*guild_id);
+                    guild_settings.set_role_changes_channel(channel_id);
+
+                    Some(guild_settings)
+                },
+            )
+            .await?
+        {
+            debug!("Role changes channel for guild {} set.", guild_id);
+
+            Ok(guild_settings)
+        } else {
+            Err(StorageError::NotFound(
+                "guild is not registered".to_string(),
+            ))
+        }
+    }
+
+    pub(crate) async fn set_errors_channel(
+        &self,
+        guild_id: &GuildId,
+        channel_id: Option<ChannelId>,
+    ) -> Result<GuildSettings> {
+        trace!("Setting errors channel for guild {}...", guild_id);
+
+        if let Some(guild_settings) = self
+            .storage
+            .get_and_modify_or_insert(
+                guild_id,
+                |guild_settings| guild_settings.set_errors_channel(channel_id),
+                || {
+                    let mut guild_settings = GuildSettings::new(*guild_id);
+                    guild_settings.set_errors_channel(channel_id);
+
+                    Some(guild_settings)
+                },
+            )
+            .await?
+        {
+            debug!("Errors channel for guild {} set.", guild_id);
+
+            Ok(guild_settings)
+        } else {
+            Err(StorageError::NotFound(
+                "guild is not registered".to_string(),
+            ))
+        }
+    }
+
     pub(crate) async fn set_clan_wars_maps_channel(
         &self,
         guild_id: &GuildId,
@@ -115,6 +195,69 @@ impl<'a> GuildSettingsRepository {
         }
     }
 
+    pub(crate) async fn set_clan_wars_maps_count(
+        &self,
+        guild_id: &GuildId,
+        count: Option<u16>,
+    ) -> Result<GuildSettings> {
+        trace!("Setting clan wars maps count for guild {}...", guild_id);
+
+        if let Some(guild_settings) = self
+            .storage
+            .get_and_modify_or_insert(
+                guild_id,
+                |guild_settings| guild_settings.set_clan_wars_maps_count(count),
+                || {
+                    let mut guild_settings = GuildSettings::new(*guild_id);
+                    guild_settings.set_clan_wars_maps_count(count);
+
+                    Some(guild_settings)
+                },
+            )
+            .await?
+        {
+            debug!("Clan wars maps count for guild {} set.", guild_id);
+
+            Ok(guild_settings)
+        } else {
+            Err(StorageError::NotFound(
+                "guild is not registered".to_string(),
+            ))
+        }
+    }
+
+    pub(crate) async fn set_clan_wars_star_range(
+        &self,
+        guild_id: &GuildId,
+        min_stars: Option<f64>,
+        max_stars: Option<f64>,
+    ) -> Result<GuildSettings> {
+        trace!("Setting clan wars star range for guild {}...", guild_id);
+
+        if let Some(guild_settings) = self
+            .storage
+            .get_and_modify_or_insert(
+                guild_id,
+                |guild_settings| guild_settings.set_clan_wars_star_range(min_stars, max_stars),
+                || {
+                    let mut guild_settings = GuildSettings::new(*guild_id);
+                    guild_settings.set_clan_wars_star_range(min_stars, max_stars);
+
+                    Some(guild_settings)
+                },
+            )
+            .await?
+        {
+            debug!("Clan wars star range for guild {} set.", guild_id);
+
+            Ok(guild_settings)
+        } else {
+            Err(StorageError::NotFound(
+                "guild is not registered".to_string(),
+            ))
+        }
+    }
+
     pub(crate) async fn set_clan_commander_role(
         &self,
         guild_id: &GuildId,
@@ -282,6 +425,72 @@ impl<'a> GuildSettingsRepository {
         }
     }
 
+    pub(crate) async fn set_clan_recap_schedule(
+        &self,
+        guild_id: &GuildId,
+        schedule: Option<ClanRecapSchedule>,
+    ) -> Result<GuildSettings> {
+        trace!("Setting clan recap schedule for guild {}...", guild_id);
+
+        // posted time is reset, so the recap worker takes a fresh snapshot before the first post
+        if let Some(guild_settings) = self
+            .storage
+            .get_and_modify_or_insert(
+                guild_id,
+                |guild_settings| {
+                    guild_settings.set_clan_recap_schedule(schedule);
+                    guild_settings.set_clan_recap_posted_at(None);
+                },
+                || {
+                    let mut guild_settings = GuildSettings::new(*guild_id);
+                    guild_settings.set_clan_recap_schedule(schedule);
+
+                    Some(guild_settings)
+                },
+            )
+            .await?
+        {
+            debug!("Clan recap schedule for guild {} set.", guild_id);
+
+            Ok(guild_settings)
+        } else {
+            Err(StorageError::NotFound(
+                "guild is not registered".to_string(),
+            ))
+        }
+    }
+
+    pub(crate) async fn set_clan_recap_posted_at(
+        &self,
+        guild_id: &GuildId,
+        posted_at: DateTime<Utc>,
+    ) -> Result<GuildSettings> {
+        trace!("Setting clan recap posted time for guild {}...", guild_id);
+
+        if let Some(guild_settings) = self
+            .storage
+            .get_and_modify_or_insert(
+                guild_id,
+                |guild_settings| guild_settings.set_clan_recap_posted_at(Some(posted_at)),
+                || {
+                    let mut guild_settings = GuildSettings::new(*guild_id);
+                    guild_settings.set_clan_recap_posted_at(Some(posted_at));
+
+                    Some(guild_settings)
+                },
+            )
+            .await?
+        {
+            debug!("Clan recap posted time for guild {} set.", guild_id);
+
+            Ok(guild_settings)
+        } else {
+            Err(StorageError::NotFound(
+                "guild is not registered".to_string(),
+            ))
+        }
+    }
+
     pub(crate) async fn add_clan_wars_soldier(
         &self,
         guild_id: &GuildId,
@@ -381,13 +590,14 @@ impl<'a> GuildSettingsRepository {
         }
     }
 
-    pub(crate) async fn set_verified_profile_requirement(
+    pub(crate) async fn set_clan_wars_ping_role(
         &self,
         guild_id: &GuildId,
-        requires_verified_profile: bool,
+        role_id: Option<RoleId>,
     ) -> Result<GuildSettings> {
         trace!(
-            "Setting verified profile requirement for guild {}...",
+            "Setting new clan wars ping role {:?} for guild {}...",
+            role_id,
             guild_id
         );
 
@@ -395,19 +605,15 @@ impl<'a> GuildSettingsRepository {
             .storage
             .get_and_modify_or_insert(
                 guild_id,
-                |guild_settings| {
-                    guild_settings.set_verified_profile_requirement(requires_verified_profile)
-                },
-                || {
-                    let mut guild_settings = GuildSettings::new(*guild_id);
-                    guild_settings.set_verified_profile_requirement(requires_verified_profile);
-
-                    Some(guild_settings)
-                },
+                |guild_settings| guild_settings.set_clan_wars_ping_role(role_id),
+                || Some(GuildSettings::new(*guild_id)),
             )
             .await?
         {
-            debug!("Verified profile requirement for guild {} set.", guild_id);
+            debug!(
+                "Clan wars ping role {:?} for guild {} set.",
+                role_id, guild_id
+            );
 
             Ok(guild_settings)
         } else {
@@ -417,29 +623,28 @@ impl<'a> GuildSettingsRepository {
         }
     }
 
-    pub(crate) async fn set_clan_settings(
+    pub(crate) async fn set_verification_reminder(
         &self,
         guild_id: &GuildId,
-        clan_settings: Option<ClanSettings>,
+        verification_reminder: bool,
     ) -> Result<GuildSettings> {
-        trace!("Setting clan settings for guild {}...", guild_id);
+        trace!("Setting verification reminder for guild {}...", guild_id);
 
-        let clan_settings_clone = clan_settings.clone();
         if let Some(guild_settings) = self
             .storage
             .get_and_modify_or_insert(
                 guild_id,
-                |guild_settings| guild_settings.set_clan_settings(clan_settings),
+                |guild_settings| guild_settings.set_verification_reminder(verification_reminder),
                 || {
                     let mut guild_settings = GuildSettings::new(*guild_id);
-                    guild_settings.set_clan_settings(clan_settings_clone);
+                    guild_settings.set_verification_reminder(verification_reminder);
 
                     Some(guild_settings)
                 },
             )
             .await?
         {
-            debug!("Clan settings for guild {} set.", guild_id);
+            debug!("Verification reminder for guild {} set.", guild_id);
 
             Ok(guild_settings)
         } else {
@@ -449,29 +654,33 @@ impl<'a> GuildSettingsRepository {
         }
     }
 
-    pub(crate) async fn set_oauth_token<ModifyFunc>(
+    pub(crate) async fn set_verified_profile_requirement(
         &self,
         guild_id: &GuildId,
-        modify_func: ModifyFunc,
-        oauth_token: bool,
-    ) -> Result<GuildSettings>
-    where
-        ModifyFunc: FnOnce(&mut MutexGuard<GuildSettings>),
-    {
-        trace!("Setting OAuth token for guild {}...", guild_id);
+        requires_verified_profile: bool,
+    ) -> Result<GuildSettings> {
+        trace!(
+            "Setting verified profile requirement for guild {}...",
+            guild_id
+        );
 
         if let Some(guild_settings) = self
             .storage
-            .get_and_modify_or_insert(guild_id, modify_func, || {
-                let mut guild_settings = GuildSettings::new(*guild_id);
-
-                guild_settings.set_oauth_token(oauth_token);
+            .get_and_modify_or_insert(
+                guild_id,
+                |guild_settings| {
+                    guild_settings.set_verified_profile_requirement(requires_verified_profile)
+                },
+                || {
+                    let mut guild_settings = GuildSettings::new(*guild_id);
+                    guild_settings.set_verified_profile_requirement(requires_verified_profile);
 
-                Some(guild_settings)
-            })
+                    Some(guild_settings)
+                },
+            )
             .await?
         {
-            debug!("OAuth token for guild {} set.", guild_id);
+            debug!("Verified profile requirement for guild {} set.", guild_id);
 
             Ok(guild_settings)
         } else {
@@ -481,40 +690,28 @@ impl<'a> GuildSettingsRepository {
         }
     }
 
-    pub(crate) async fn add_auto_role(
+    pub(crate) async fn set_auto_roles_dry_run(
         &self,
-        guild_id: GuildId,
-        role_group: RoleGroup,
-        role_id: RoleId,
-        metric_and_value: RequirementMetricValue,
-        condition: Condition,
-        weight: u32,
+        guild_id: &GuildId,
+        auto_roles_dry_run: bool,
     ) -> Result<GuildSettings> {
-        trace!("Adding auto role for guild {}...", guild_id);
-
-        let mut rs = RoleSettings::new(role_id, weight);
-        rs.add_requirement(condition, metric_and_value);
-
-        let role_group_clone = role_group.clone();
-        let role_settings_clone = rs.clone();
+        trace!("Setting auto roles dry run for guild {}...", guild_id);
 
         if let Some(guild_settings) = self
             .storage
             .get_and_modify_or_insert(
-                &guild_id,
-                move |guild_settings| {
-                    guild_settings.merge(role_group, rs);
-                },
+                guild_id,
+                |guild_settings| guild_settings.set_auto_roles_dry_run(auto_roles_dry_run),
                 || {
-                    let mut guild_settings = GuildSettings::new(guild_id);
-                    guild_settings.merge(role_group_clone, role_settings_clone);
+                    let mut guild_settings = GuildSettings::new(*guild_id);
+                    guild_settings.set_auto_roles_dry_run(auto_roles_dry_run);
 
                     Some(guild_settings)
                 },
             )
             .await?
         {
-            debug!("Auto role for guild {} added.", guild_id);
+            debug!("Auto roles dry run for guild {} set.", guild_id);
 
             Ok(guild_settings)
         } else {
@@ -524,26 +721,645 @@ impl<'a> GuildSettingsRepository {
         }
     }
 
-    pub(crate) async fn remove_auto_role(
+    pub(crate) async fn set_auto_roles_cooldown(
         &self,
-        guild_id: GuildId,
-        role_group: RoleGroup,
-        role_id: RoleId,
+        guild_id: &GuildId,
+        hours: u32,
     ) -> Result<GuildSettings> {
-        trace!("Removing auto role for guild {}...", guild_id);
+        trace!("Setting auto roles cooldown for guild {}...", guild_id);
 
         if let Some(guild_settings) = self
             .storage
             .get_and_modify_or_insert(
-                &guild_id,
-                move |guild_settings| {
-                    guild_settings.remove(role_group, role_id);
+                guild_id,
+                |guild_settings| guild_settings.set_auto_roles_cooldown(hours),
+                || {
+                    let mut guild_settings = GuildSettings::new(*guild_id);
+                    guild_settings.set_auto_roles_cooldown(hours);
+
+                    Some(guild_settings)
                 },
-                || Some(GuildSettings::new(guild_id)),
             )
             .await?
         {
-            debug!("Auto role for guild {} removed.", guild_id);
+            debug!("Auto roles cooldown for guild {} set.", guild_id);
+
+            Ok(guild_settings)
+        } else {
+            Err(StorageError::NotFound(
+                "guild is not registered".to_string(),
+            ))
+        }
+    }
+
+    pub(crate) async fn set_command_cooldown(
+        &self,
+        guild_id: &GuildId,
+        command_name: &str,
+        secs: Option<u32>,
+    ) -> Result<GuildSettings> {
+        trace!(
+            "Setting {} command cooldown for guild {}...",
+            command_name,
+            guild_id
+        );
+
+        if let Some(guild_settings) = self
+            .storage
+            .get_and_modify_or_insert(
+                guild_id,
+                |guild_settings| guild_settings.set_command_cooldown(command_name, secs),
+                || {
+                    let mut guild_settings = GuildSettings::new(*guild_id);
+                    guild_settings.set_command_cooldown(command_name, secs);
+
+                    Some(guild_settings)
+                },
+            )
+            .await?
+        {
+            debug!(
+                "{} command cooldown for guild {} set.",
+                command_name, guild_id
+            );
+
+            Ok(guild_settings)
+        } else {
+            Err(StorageError::NotFound(
+                "guild is not registered".to_string(),
+            ))
+        }
+    }
+
+    pub(crate) async fn set_playlist_cover(
+        &self,
+        guild_id: &GuildId,
+        playlist_type: Option<ClanWarsSort>,
+        cover: Option<String>,
+    ) -> Result<GuildSettings> {
+        trace!("Setting playlist cover for guild {}...", guild_id);
+
+        if let Some(guild_settings) = self
+            .storage
+            .get_and_modify_or_insert(
+                guild_id,
+                |guild_settings| {
+                    guild_settings.set_playlist_cover(playlist_type.clone(), cover.clone())
+                },
+                || {
+                    let mut guild_settings = GuildSettings::new(*guild_id);
+                    guild_settings.set_playlist_cover(playlist_type.clone(), cover.clone());
+
+                    Some(guild_settings)
+                },
+            )
+            .await?
+        {
+            debug!("Playlist cover for guild {} set.", guild_id);
+
+            Ok(guild_settings)
+        } else {
+            Err(StorageError::NotFound(
+                "guild is not registered".to_string(),
+            ))
+        }
+    }
+
+    pub(crate) async fn set_playlist_skip_fcs(
+        &self,
+        guild_id: &GuildId,
+        playlist_skip_fcs: bool,
+    ) -> Result<GuildSettings> {
+        trace!("Setting playlist skip FCs for guild {}...", guild_id);
+
+        if let Some(guild_settings) = self
+            .storage
+            .get_and_modify_or_insert(
+                guild_id,
+                |guild_settings| guild_settings.set_playlist_skip_fcs(playlist_skip_fcs),
+                || {
+                    let mut guild_settings = GuildSettings::new(*guild_id);
+                    guild_settings.set_playlist_skip_fcs(playlist_skip_fcs);
+
+                    Some(guild_settings)
+                },
+            )
+            .await?
+        {
+            debug!("Playlist skip FCs for guild {} set.", guild_id);
+
+            Ok(guild_settings)
+        } else {
+            Err(StorageError::NotFound(
+                "guild is not registered".to_string(),
+            ))
+        }
+    }
+
+    pub(crate) async fn set_commander_order_min_stars(
+        &self,
+        guild_id: &GuildId,
+        min_stars: Option<f64>,
+    ) -> Result<GuildSettings> {
+        trace!(
+            "Setting commander's orders min. stars for guild {}...",
+            guild_id
+        );
+
+        if let Some(guild_settings) = self
+            .storage
+            .get_and_modify_or_insert(
+                guild_id,
+                |guild_settings| guild_settings.set_commander_order_min_stars(min_stars),
+                || {
+                    let mut guild_settings = GuildSettings::new(*guild_id);
+                    guild_settings.set_commander_order_min_stars(min_stars);
+
+                    Some(guild_settings)
+                },
+            )
+            .await?
+        {
+            debug!("Commander's orders min. stars for guild {} set.", guild_id);
+
+            Ok(guild_settings)
+        } else {
+            Err(StorageError::NotFound(
+                "guild is not registered".to_string(),
+            ))
+        }
+    }
+
+    pub(crate) async fn set_profile_private(
+        &self,
+        guild_id: &GuildId,
+        profile_private: bool,
+    ) -> Result<GuildSettings> {
+        trace!("Setting profile visibility for guild {}...", guild_id);
+
+        if let Some(guild_settings) = self
+            .storage
+            .get_and_modify_or_insert(
+                guild_id,
+                |guild_settings| guild_settings.set_profile_private(profile_private),
+                || {
+                    let mut guild_settings = GuildSettings::new(*guild_id);
+                    guild_settings.set_profile_private(profile_private);
+
+                    Some(guild_settings)
+                },
+            )
+            .await?
+        {
+            debug!("Profile visibility for guild {} set.", guild_id);
+
+            Ok(guild_settings)
+        } else {
+            Err(StorageError::NotFound(
+                "guild is not registered".to_string(),
+            ))
+        }
+    }
+
+    pub(crate) async fn set_welcome_roles(
+        &self,
+        guild_id: &GuildId,
+        welcome_roles: Vec<RoleId>,
+    ) -> Result<GuildSettings> {
+        trace!("Setting welcome roles for guild {}...", guild_id);
+
+        let welcome_roles_clone = welcome_roles.clone();
+
+        if let Some(guild_settings) = self
+            .storage
+            .get_and_modify_or_insert(
+                guild_id,
+                |guild_settings| guild_settings.set_welcome_roles(welcome_roles),
+                || {
+                    let mut guild_settings = GuildSettings::new(*guild_id);
+                    guild_settings.set_welcome_roles(welcome_roles_clone);
+
+                    Some(guild_settings)
+                },
+            )
+            .await?
+        {
+            debug!("Welcome roles for guild {} set.", guild_id);
+
+            Ok(guild_settings)
+        } else {
+            Err(StorageError::NotFound(
+                "guild is not registered".to_string(),
+            ))
+        }
+    }
+
+    pub(crate) async fn set_map_triangle_colors(
+        &self,
+        guild_id: &GuildId,
+        map_triangle_colors: MapTriangleColors,
+    ) -> Result<GuildSettings> {
+        trace!("Setting map triangle colors for guild {}...", guild_id);
+
+        if let Some(guild_settings) = self
+            .storage
+            .get_and_modify_or_insert(
+                guild_id,
+                |guild_settings| guild_settings.set_map_triangle_colors(map_triangle_colors),
+                || {
+                    let mut guild_settings = GuildSettings::new(*guild_id);
+                    guild_settings.set_map_triangle_colors(map_triangle_colors);
+
+                    Some(guild_settings)
+                },
+            )
+            .await?
+        {
+            debug!("Map triangle colors for guild {} set.", guild_id);
+
+            Ok(guild_settings)
+        } else {
+            Err(StorageError::NotFound(
+                "guild is not registered".to_string(),
+            ))
+        }
+    }
+
+    pub(crate) async fn set_decimal_places(
+        &self,
+        guild_id: &GuildId,
+        decimal_places: Option<u8>,
+    ) -> Result<GuildSettings> {
+        trace!("Setting decimal places for guild {}...", guild_id);
+
+        if let Some(guild_settings) = self
+            .storage
+            .get_and_modify_or_insert(
+                guild_id,
+                |guild_settings| guild_settings.set_decimal_places(decimal_places),
+                || {
+                    let mut guild_settings = GuildSettings::new(*guild_id);
+                    guild_settings.set_decimal_places(decimal_places);
+
+                    Some(guild_settings)
+                },
+            )
+            .await?
+        {
+            debug!("Decimal places for guild {} set.", guild_id);
+
+            Ok(guild_settings)
+        } else {
+            Err(StorageError::NotFound(
+                "guild is not registered".to_string(),
+            ))
+        }
+    }
+
+    pub(crate) async fn set_min_account_age(
+        &self,
+        guild_id: &GuildId,
+        days: u32,
+    ) -> Result<GuildSettings> {
+        trace!("Setting min account age for guild {}...", guild_id);
+
+        if let Some(guild_settings) = self
+            .storage
+            .get_and_modify_or_insert(
+                guild_id,
+                |guild_settings| guild_settings.set_min_account_age_days(days),
+                || {
+                    let mut guild_settings = GuildSettings::new(*guild_id);
+                    guild_settings.set_min_account_age_days(days);
+
+                    Some(guild_settings)
+                },
+            )
+            .await?
+        {
+            debug!("Min account age for guild {} set.", guild_id);
+
+            Ok(guild_settings)
+        } else {
+            Err(StorageError::NotFound(
+                "guild is not registered".to_string(),
+            ))
+        }
+    }
+
+    pub(crate) async fn set_embed_theme(
+        &self,
+        guild_id: &GuildId,
+        embed_theme: EmbedTheme,
+    ) -> Result<GuildSettings> {
+        trace!("Setting embed theme for guild {}...", guild_id);
+
+        let embed_theme_clone = embed_theme.clone();
+        if let Some(guild_settings) = self
+            .storage
+            .get_and_modify_or_insert(
+                guild_id,
+                |guild_settings| guild_settings.set_embed_theme(embed_theme),
+                || {
+                    let mut guild_settings = GuildSettings::new(*guild_id);
+                    guild_settings.set_embed_theme(embed_theme_clone);
+
+                    Some(guild_settings)
+                },
+            )
+            .await?
+        {
+            debug!("Embed theme for guild {} set.", guild_id);
+
+            Ok(guild_settings)
+        } else {
+            Err(StorageError::NotFound(
+                "guild is not registered".to_string(),
+            ))
+        }
+    }
+
+    pub(crate) async fn set_timezone(
+        &self,
+        guild_id: &GuildId,
+        timezone: Option<Tz>,
+    ) -> Result<GuildSettings> {
+        trace!("Setting timezone for guild {}...", guild_id);
+
+        if let Some(guild_settings) = self
+            .storage
+            .get_and_modify_or_insert(
+                guild_id,
+                |guild_settings| guild_settings.set_timezone(timezone),
+                || {
+                    let mut guild_settings = GuildSettings::new(*guild_id);
+                    guild_settings.set_timezone(timezone);
+
+                    Some(guild_settings)
+                },
+            )
+            .await?
+        {
+            debug!("Timezone for guild {} set.", guild_id);
+
+            Ok(guild_settings)
+        } else {
+            Err(StorageError::NotFound(
+                "guild is not registered".to_string(),
+            ))
+        }
+    }
+
+    pub(crate) async fn set_clan_settings(
+        &self,
+        guild_id: &GuildId,
+        clan_settings: Option<ClanSettings>,
+    ) -> Result<GuildSettings> {
+        trace!("Setting clan settings for guild {}...", guild_id);
+
+        let clan_settings_clone = clan_settings.clone();
+        if let Some(guild_settings) = self
+            .storage
+            .get_and_modify_or_insert(
+                guild_id,
+                |guild_settings| guild_settings.set_clan_settings(clan_settings),
+                || {
+                    let mut guild_settings = GuildSettings::new(*guild_id);
+                    guild_settings.set_clan_settings(clan_settings_clone);
+
+                    Some(guild_settings)
+                },
+            )
+            .await?
+        {
+            debug!("Clan settings for guild {} set.", guild_id);
+
+            Ok(guild_settings)
+        } else {
+            Err(StorageError::NotFound(
+                "guild is not registered".to_string(),
+            ))
+        }
+    }
+
+    pub(crate) async fn set_oauth_token<ModifyFunc>(
+        &self,
+        guild_id: &GuildId,
+        modify_func: ModifyFunc,
+        oauth_token: bool,
+    ) -> Result<GuildSettings>
+    where
+        ModifyFunc: FnOnce(&mut MutexGuard<GuildSettings>),
+    {
+        trace!("Setting OAuth token for guild {}...", guild_id);
+
+        if let Some(guild_settings) = self
+            .storage
+            .get_and_modify_or_insert(guild_id, modify_func, || {
+                let mut guild_settings = GuildSettings::new(*guild_id);
+
+                guild_settings.set_oauth_token(oauth_token);
+
+                Some(guild_settings)
+            })
+            .await?
+        {
+            debug!("OAuth token for guild {} set.", guild_id);
+
+            Ok(guild_settings)
+        } else {
+            Err(StorageError::NotFound(
+                "guild is not registered".to_string(),
+            ))
+        }
+    }
+
+    pub(crate) async fn add_auto_role(
+        &self,
+        guild_id: GuildId,
+        role_group: RoleGroup,
+        role_id: RoleId,
+        weight: u32,
+        params: AutoRoleParams,
+    ) -> Result<GuildSettings> {
+        trace!("Adding auto role for guild {}...", guild_id);
+
+        let AutoRoleParams {
+            metric_and_value,
+            condition,
+            tolerance,
+            match_mode,
+            required_role,
+        } = params;
+
+        let mut rs = RoleSettings::new(role_id, weight);
+        rs.add_requirement_with_tolerance(condition, metric_and_value, tolerance);
+        if let Some(match_mode) = match_mode {
+            rs.set_match_mode(match_mode);
+        }
+        rs.set_required_role(required_role);
+
+        let role_group_clone = role_group.clone();
+        let role_settings_clone = rs.clone();
+
+        if let Some(guild_settings) = self
+            .storage
+            .get_and_modify_or_insert(
+                &guild_id,
+                move |guild_settings| {
+                    guild_settings.merge(role_group.clone(), rs);
+
+                    // match mode of an existing role is changed only if it is given explicitly
+                    if let Some(match_mode) = match_mode {
+                        guild_settings.set_role_match_mode(&role_group, role_id, match_mode);
+                    }
+
+                    // so is the prerequisite role
+                    if required_role.is_some() {
+                        guild_settings.set_role_required_role(&role_group, role_id, required_role);
+                    }
+                },
+                || {
+                    let mut guild_settings = GuildSettings::new(guild_id);
+                    guild_settings.merge(role_group_clone, role_settings_clone);
+
+                    Some(guild_settings)
+                },
+            )
+            .await?
+        {
+            debug!("Auto role for guild {} added.", guild_id);
+
+            Ok(guild_settings)
+        } else {
+            Err(StorageError::NotFound(
+                "guild is not registered".to_string(),
+            ))
+        }
+    }
+
+    pub(crate) async fn merge_auto_roles(
+        &self,
+        guild_id: GuildId,
+        auto_roles: Vec<(RoleGroup, RoleSettings)>,
+    ) -> Result<GuildSettings> {
+        trace!("Merging auto roles for guild {}...", guild_id);
+
+        let auto_roles_clone = auto_roles.clone();
+
+        if let Some(guild_settings) = self
+            .storage
+            .get_and_modify_or_insert(
+                &guild_id,
+                move |guild_settings| {
+                    for (role_group, role_settings) in auto_roles {
+                        guild_settings.merge(role_group, role_settings);
+                    }
+                },
+                || {
+                    let mut guild_settings = GuildSettings::new(guild_id);
+                    for (role_group, role_settings) in auto_roles_clone {
+                        guild_settings.merge(role_group, role_settings);
+                    }
+
+                    Some(guild_settings)
+                },
+            )
+            .await?
+        {
+            debug!("Auto roles for guild {} merged.", guild_id);
+
+            Ok(guild_settings)
+        } else {
+            Err(StorageError::NotFound(
+                "guild is not registered".to_string(),
+            ))
+        }
+    }
+
+    pub(crate) async fn remove_auto_role(
+        &self,
+        guild_id: GuildId,
+        role_group: RoleGroup,
+        role_id: RoleId,
+    ) -> Result<GuildSettings> {
+        trace!("Removing auto role for guild {}...", guild_id);
+
+        if let Some(guild_settings) = self
+            .storage
+            .get_and_modify_or_insert(
+                &guild_id,
+                move |guild_settings| {
+                    guild_settings.remove(role_group, role_id);
+                },
+                || Some(GuildSettings::new(guild_id)),
+            )
+            .await?
+        {
+            debug!("Auto role for guild {} removed.", guild_id);
+
+            Ok(guild_settings)
+        } else {
+            Err(StorageError::NotFound(
+                "guild is not registered".to_string(),
+            ))
+        }
+    }
+
+    pub(crate) async fn set_auto_role_required_role(
+        &self,
+        guild_id: GuildId,
+        role_group: RoleGroup,
+        role_id: RoleId,
+        required_role: Option<RoleId>,
+    ) -> Result<GuildSettings> {
+        trace!("Setting auto role prerequisite for guild {}...", guild_id);
+
+        if let Some(guild_settings) = self
+            .storage
+            .get_and_modify_or_insert(
+                &guild_id,
+                move |guild_settings| {
+                    guild_settings.set_role_required_role(&role_group, role_id, required_role);
+                },
+                || Some(GuildSettings::new(guild_id)),
+            )
+            .await?
+        {
+            debug!("Auto role prerequisite for guild {} set.", guild_id);
+
+            Ok(guild_settings)
+        } else {
+            Err(StorageError::NotFound(
+                "guild is not registered".to_string(),
+            ))
+        }
+    }
+
+    pub(crate) async fn set_role_group_order(
+        &self,
+        guild_id: GuildId,
+        group_order: Vec<RoleGroup>,
+    ) -> Result<GuildSettings> {
+        trace!("Setting role group order for guild {}...", guild_id);
+
+        let group_order_clone = group_order.clone();
+
+        if let Some(guild_settings) = self
+            .storage
+            .get_and_modify_or_insert(
+                &guild_id,
+                move |guild_settings| guild_settings.set_group_order(group_order),
+                || {
+                    let mut guild_settings = GuildSettings::new(guild_id);
+                    guild_settings.set_group_order(group_order_clone);
+
+                    Some(guild_settings)
+                },
+            )
+            .await?
+        {
+            debug!("Role group order for guild {} set.", guild_id);
 
             Ok(guild_settings)
         } else {
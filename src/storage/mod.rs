@@ -1,7 +1,9 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fmt::Display;
+use std::future::Future;
 use std::hash::Hash;
 use std::marker::PhantomData;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::{error, fmt};
 
@@ -9,16 +11,22 @@ use serde::{Deserialize, Serialize};
 use tokio::sync::{Mutex, MutexGuard, RwLock, RwLockWriteGuard};
 use tracing::{debug, error, trace, warn};
 
+use crate::discord::bot::beatleader::player::PROFILE_VERIFICATION_STEPS;
 use crate::storage::persist::{PersistError, PersistInstance};
+use crate::METRICS;
 
 pub(crate) mod bsmaps;
+pub(crate) mod clan_map_capture;
 pub(crate) mod clan_peak;
+pub(crate) mod clan_recap;
+pub(crate) mod clan_wars_post;
 pub(crate) mod guild;
 pub(crate) mod persist;
 pub(crate) mod player;
 pub(crate) mod player_oauth_token;
 pub(crate) mod player_scores;
 pub(crate) mod playlist;
+pub(crate) mod role_change;
 
 type Result<T> = std::result::Result<T, StorageError>;
 
@@ -59,11 +67,31 @@ impl Display for StorageError {
             StorageError::NotFound(e) => write!(f, "{}", e),
             StorageError::Unknown => write!(f, "unknown error"),
             StorageError::Cancelled => write!(f, "cancelled"),
-            StorageError::ProfileNotVerified => write!(f, "profile is not verified. Log in to the Beat Leader website and link your Discord account under Settings/Account."),
+            StorageError::ProfileNotVerified => {
+                write!(f, "profile is not verified. {}", PROFILE_VERIFICATION_STEPS)
+            }
         }
     }
 }
 
+#[derive(Debug, Default, Clone)]
+pub(crate) struct CompactionStats {
+    pub storage: String,
+    pub items: usize,
+    pub removed: usize,
+    pub restored: usize,
+}
+
+impl Display for CompactionStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} item(s), {} orphaned file(s) removed, {} missing file(s) restored",
+            self.storage, self.items, self.removed, self.restored
+        )
+    }
+}
+
 pub(crate) trait StorageKey:
     Serialize + for<'b> Deserialize<'b> + Hash + Eq + Send + Sync + Clone + Display
 {
@@ -72,6 +100,11 @@ pub(crate) trait StorageValue<K: StorageKey>:
     Serialize + for<'b> Deserialize<'b> + Send + Sync + Clone
 {
     fn get_key(&self) -> K;
+
+    // upgrades a value stored with an older schema, returns true if the value has changed
+    fn migrate(&mut self) -> bool {
+        false
+    }
 }
 
 #[derive(Debug)]
@@ -82,6 +115,9 @@ where
 {
     state: RwLock<HashMap<K, Mutex<V>>>,
     storage: Storage<K, V>,
+    // index writes are deferred while any batch is running
+    batches: AtomicUsize,
+    index_dirty: AtomicBool,
 }
 
 impl<'a, K, V> CachedStorage<K, V>
@@ -108,19 +144,62 @@ where
         trace!("{} storage loaded.", storage_name);
 
         trace!("Loading {} storage data...", storage_name);
+        let mut skipped = 0;
         for key in keys.into_iter() {
             trace!("Loading {} storage data for key {}", storage_name, key);
-            let value = storage.load(&key).await?;
+            // corrupted items are moved aside, so the bot boots with partial data; any other
+            // error (e.g. a schema regression) still fails the startup, so no data is dropped
+            let mut value = match storage.load(&key).await {
+                Ok(value) => value,
+                Err(StorageError::Storage(PersistError::Checksum)) => {
+                    warn!(
+                        "{} storage item {} is corrupted ({}), moving it aside.",
+                        storage_name,
+                        key,
+                        PersistError::Checksum
+                    );
+                    storage.quarantine(&key)?;
+                    skipped += 1;
+
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
             trace!("{} storage data for key {} loaded.", storage_name, key);
+
+            if value.migrate() {
+                trace!(
+                    "Saving migrated {} storage data for key {}",
+                    storage_name,
+                    key
+                );
+                value = storage.save(key.clone(), value).await?;
+                debug!("{} storage data for key {} migrated.", storage_name, key);
+            }
             hm.insert(key, Mutex::new(value));
         }
         trace!("{} storage data loaded.", storage_name);
 
+        if skipped > 0 {
+            warn!(
+                "{} corrupted {} storage item(s) skipped.",
+                skipped, storage_name
+            );
+
+            // the index must not list the items moved aside, the next boot would fail otherwise
+            storage
+                .save_index(hm.keys().cloned().collect::<Vec<K>>())
+                .await?;
+        }
+        METRICS.record_storage_skipped_items(&storage_name, skipped);
+
         debug!("{} storage initialized.", storage_name);
 
         Ok(Self {
             state: RwLock::new(hm),
             storage,
+            batches: AtomicUsize::new(0),
+            index_dirty: AtomicBool::new(false),
         })
     }
 
@@ -178,8 +257,36 @@ where
         ret
     }
 
+    // applies the function to each value under its lock, only the returned results are collected,
+    // so values filtered out are never cloned
+    pub(super) async fn filter_map_collect<R>(&self, func: impl Fn(&V) -> Option<R>) -> Vec<R> {
+        let storage_name = self.storage.get_name();
+
+        trace!("Filtering {} storage data...", storage_name);
+
+        let read_lock = self.state.read().await;
+
+        let mut ret = Vec::new();
+
+        for value in read_lock.values() {
+            if let Some(result) = func(&*value.lock().await) {
+                ret.push(result);
+            }
+        }
+
+        trace!(
+            "{} storage data filtered, {} result(s) returned.",
+            storage_name,
+            ret.len()
+        );
+
+        ret
+    }
+
     async fn filtered(&self, func: impl Fn(&V) -> bool) -> Result<Vec<V>> {
-        Ok(self.values().await.into_iter().filter(func).collect())
+        Ok(self
+            .filter_map_collect(|value| func(value).then(|| value.clone()))
+            .await)
     }
 
     pub(super) async fn contains_key(&self, key: &K) -> bool {
@@ -333,9 +440,29 @@ where
         self.storage.save(key, value).await
     }
 
+    // unlike remove, it also deletes the stored item file
+    pub(super) async fn delete(&self, key: &K) -> Result<bool> {
+        let removed = self.remove(key).await?;
+
+        if removed {
+            self.storage
+                .remove_file(&self.storage.get_storage_item_name(key))?;
+        }
+
+        Ok(removed)
+    }
+
     pub(super) async fn update_index(&self) -> Result<()> {
         let storage_name = self.storage.get_name();
 
+        if self.batches.load(Ordering::SeqCst) > 0 {
+            trace!("{} storage index update deferred.", storage_name);
+
+            self.index_dirty.store(true, Ordering::SeqCst);
+
+            return Ok(());
+        }
+
         trace!("Updating {} storage index...", storage_name);
 
         let read_lock = self.state.read().await;
@@ -352,6 +479,94 @@ where
         result
     }
 
+    // runs a sequence of mutations writing the index once at the end instead of after each added
+    // or removed key; the batch is shared by the whole storage, so index writes of mutations made
+    // concurrently outside of it are deferred too (they are still written at the end of the batch)
+    pub(super) async fn with_batch<F, Fut, R>(&self, func: F) -> Result<R>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<R>>,
+    {
+        self.batches.fetch_add(1, Ordering::SeqCst);
+
+        let result = func().await;
+
+        // the last finished batch writes the index, even if the mutations failed halfway
+        if self.batches.fetch_sub(1, Ordering::SeqCst) == 1
+            && self.index_dirty.swap(false, Ordering::SeqCst)
+        {
+            self.update_index().await?;
+        }
+
+        result
+    }
+
+    // removes files of keys that are no longer in the cache, saves missing ones and rebuilds the index
+    pub(super) async fn compact(&self) -> Result<CompactionStats> {
+        let storage_name = self.storage.get_name();
+
+        trace!("Compacting {} storage...", storage_name);
+
+        // keep the write lock for the whole reconciliation so keys can not be added or removed meanwhile
+        let write_lock = self.state.write().await;
+
+        let item_names = write_lock
+            .keys()
+            .map(|key| self.storage.get_storage_item_name(key))
+            .collect::<HashSet<String>>();
+        let index_name = self.storage.get_storage_index_name();
+
+        let file_names = self.storage.list_file_names()?;
+
+        let mut stats = CompactionStats {
+            storage: storage_name.clone(),
+            items: write_lock.len(),
+            ..Default::default()
+        };
+
+        for file_name in file_names.iter() {
+            if file_name == &index_name || item_names.contains(file_name) {
+                continue;
+            }
+
+            trace!(
+                "Removing orphaned {} storage file {}",
+                storage_name,
+                file_name
+            );
+            self.storage.remove_file(file_name)?;
+
+            stats.removed += 1;
+        }
+
+        for (key, value) in write_lock.iter() {
+            if file_names.contains(&self.storage.get_storage_item_name(key)) {
+                continue;
+            }
+
+            trace!(
+                "Restoring missing {} storage file for key {}",
+                storage_name,
+                key
+            );
+            self.storage
+                .save(key.clone(), (*value.lock().await).clone())
+                .await?;
+
+            stats.restored += 1;
+        }
+
+        self.storage
+            .save_index(write_lock.keys().cloned().collect::<Vec<K>>())
+            .await?;
+
+        drop(write_lock);
+
+        debug!("{} storage compacted.", storage_name);
+
+        Ok(stats)
+    }
+
     pub(super) async fn restore(&self, values: Vec<V>) -> Result<()> {
         let storage_name = self.storage.get_name();
 
@@ -365,7 +580,8 @@ where
 
         // save all values to the storage first
         let mut saved_values = Vec::with_capacity(values.len());
-        for value in values {
+        for mut value in values {
+            value.migrate();
             saved_values.push(self.storage.save(value.get_key(), value).await?);
         }
 
@@ -508,6 +724,12 @@ where
         }
     }
 
+    pub(super) fn quarantine(&self, key: &K) -> Result<()> {
+        self.persist
+            .quarantine(self.get_storage_item_name(key).as_str())
+            .map_err(StorageError::Storage)
+    }
+
     pub(super) async fn save(&self, key: K, value: V) -> Result<V> {
         let storage_name = self.get_storage_item_name(&key);
 
@@ -542,6 +764,54 @@ where
         }
     }
 
+    // storage names must not be prefixes of each other, otherwise files of other storages would be listed
+    pub(super) fn list_file_names(&self) -> Result<Vec<String>> {
+        let prefix = format!("{}-", self.name);
+
+        Ok(self
+            .persist
+            .list()
+            .map_err(StorageError::Storage)?
+            .into_iter()
+            .filter(|file_name| file_name.starts_with(&prefix))
+            .collect())
+    }
+
+    // returns false if there was nothing to remove
+    pub(super) async fn remove(&self, key: &K) -> Result<bool> {
+        let storage_name = self.get_storage_item_name(key);
+
+        trace!(
+            "Removing item {} from {} storage with name {}...",
+            key.to_string(),
+            self.name,
+            storage_name
+        );
+
+        match self.persist.remove(storage_name.as_str()) {
+            Ok(_) => Ok(true),
+            Err(PersistError::RemoveFile(e)) if e.kind() == std::io::ErrorKind::NotFound => {
+                Ok(false)
+            }
+            Err(e) => {
+                error!(
+                    "Can not remove {} from {} storage: {}",
+                    key.to_string(),
+                    self.name,
+                    e
+                );
+
+                Err(StorageError::Storage(e))
+            }
+        }
+    }
+
+    pub(super) fn remove_file(&self, file_name: &str) -> Result<()> {
+        self.persist
+            .remove(file_name)
+            .map_err(StorageError::Storage)
+    }
+
     fn get_storage_index_name(&self) -> String {
         format!("{}-index", self.name)
     }
@@ -550,3 +820,404 @@ where
         format!("{}-{}", self.name, key)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use chrono::Utc;
+    use poise::serenity_prelude::{GuildId, RoleId, UserId};
+
+    use super::*;
+    use crate::storage::clan_map_capture::ClanMapCaptureRepository;
+    use crate::storage::clan_wars_post::{ClanWarsPostKey, ClanWarsPostRepository};
+    use crate::storage::role_change::{RoleChange, RoleChangeKey, RoleChangeRepository};
+
+    fn test_output_path(s: &str) -> PathBuf {
+        PathBuf::from(format!(".test_output/{s}"))
+    }
+
+    fn setup(s: &str) -> Arc<PersistInstance> {
+        let path = test_output_path(s);
+        let _ = std::fs::remove_dir_all(&path);
+
+        Arc::new(PersistInstance::new(path).unwrap())
+    }
+
+    #[tokio::test]
+    async fn it_compacts_storage() {
+        let persist = setup("it_compacts_storage");
+
+        let storage: CachedStorage<RoleChangeKey, RoleChange> =
+            CachedStorage::new(Storage::new("role-changes", persist.clone()))
+                .await
+                .unwrap();
+
+        let keys = (1..=3)
+            .map(|i| RoleChangeKey {
+                guild_id: GuildId::new(1),
+                user_id: UserId::new(i),
+                role_id: RoleId::new(1),
+            })
+            .collect::<Vec<_>>();
+        for key in keys.iter() {
+            storage
+                .set(
+                    key,
+                    RoleChange {
+                        key: *key,
+                        changed_at: Utc::now(),
+                    },
+                )
+                .await
+                .unwrap();
+        }
+
+        // removing a key leaves its file on disk
+        storage.remove(&keys[0]).await.unwrap();
+        // file of an existing key is lost
+        persist
+            .remove(format!("role-changes-{}", keys[1]).as_str())
+            .unwrap();
+        // files of other storages are left intact
+        persist.save("players-index", Vec::<String>::new()).unwrap();
+
+        let stats = storage.compact().await.unwrap();
+
+        assert_eq!(stats.items, 2);
+        assert_eq!(stats.removed, 1);
+        assert_eq!(stats.restored, 1);
+
+        let mut files = persist.list().unwrap();
+        files.sort();
+        let mut expected = vec![
+            "players-index".to_owned(),
+            "role-changes-index".to_owned(),
+            format!("role-changes-{}", keys[1]),
+            format!("role-changes-{}", keys[2]),
+        ];
+        expected.sort();
+        assert_eq!(files, expected);
+
+        let stats = storage.compact().await.unwrap();
+        assert_eq!(stats.removed, 0);
+        assert_eq!(stats.restored, 0);
+    }
+
+    #[tokio::test]
+    async fn it_expires_clan_wars_posts() {
+        let persist = setup("it_expires_clan_wars_posts");
+
+        let repository = ClanWarsPostRepository::new(persist).await.unwrap();
+
+        let post_key = |guild_id: u64, leaderboard_id: &str| ClanWarsPostKey {
+            guild_id: GuildId::new(guild_id),
+            leaderboard_id: leaderboard_id.to_owned(),
+        };
+        let now = Utc::now();
+
+        for (key, hours) in [
+            (post_key(1, "old"), 24 * 40),
+            (post_key(1, "listed"), 7),
+            (post_key(1, "captured"), 1),
+            (post_key(2, "captured"), 1),
+        ] {
+            repository
+                .set_posted_at(key, now - chrono::Duration::hours(hours))
+                .await
+                .unwrap();
+        }
+
+        // posts are kept as long as their maps stay on the list, regardless of age
+        let removed = repository
+            .remove_unlisted(&GuildId::new(1), &["old".to_owned(), "listed".to_owned()])
+            .await
+            .unwrap();
+        assert_eq!(removed, 1);
+        assert_eq!(
+            repository.posted_leaderboard_ids(&GuildId::new(1)).await,
+            HashSet::from(["old".to_owned(), "listed".to_owned()])
+        );
+        assert_eq!(
+            repository.posted_leaderboard_ids(&GuildId::new(2)).await,
+            HashSet::from(["captured".to_owned()])
+        );
+
+        let removed = repository
+            .remove_posted_before(now - chrono::Duration::days(30))
+            .await
+            .unwrap();
+        assert_eq!(removed, 1);
+        assert_eq!(repository.len().await, 2);
+        assert_eq!(
+            repository.posted_leaderboard_ids(&GuildId::new(1)).await,
+            HashSet::from(["listed".to_owned()])
+        );
+    }
+
+    #[tokio::test]
+    async fn it_remembers_clan_map_captures_check_without_maps() {
+        let persist = setup("it_remembers_clan_map_captures_check_without_maps");
+
+        let repository = ClanMapCaptureRepository::new(Arc::clone(&persist))
+            .await
+            .unwrap();
+        assert!(!repository.is_checked(1).await);
+
+        repository.set_checked(1).await.unwrap();
+        drop(repository);
+
+        // the marker survives a restart although the clan holds no maps
+        let repository = ClanMapCaptureRepository::new(persist).await.unwrap();
+        assert!(repository.by_clan(1).await.is_empty());
+        assert!(repository.is_checked(1).await);
+        assert!(!repository.is_checked(2).await);
+    }
+
+    #[tokio::test]
+    async fn it_removes_role_changes_after_guild_cooldown() {
+        let persist = setup("it_removes_role_changes_after_guild_cooldown");
+
+        let repository = RoleChangeRepository::new(persist).await.unwrap();
+
+        let key = |guild_id: u64, role_id: u64| RoleChangeKey {
+            guild_id: GuildId::new(guild_id),
+            user_id: UserId::new(1),
+            role_id: RoleId::new(role_id),
+        };
+        let now = Utc::now();
+
+        for (key, hours) in [
+            (key(1, 1), 7),
+            (key(1, 2), 1),
+            (key(2, 1), 1),
+            (key(3, 1), 1),
+        ] {
+            repository
+                .set_changed_at(key, now - chrono::Duration::hours(hours))
+                .await
+                .unwrap();
+        }
+
+        // guild 3 is not registered anymore
+        let cooldowns = HashMap::from([
+            (GuildId::new(1), chrono::Duration::hours(6)),
+            (GuildId::new(2), chrono::Duration::zero()),
+        ]);
+
+        let removed = repository.remove_expired(&cooldowns, now).await.unwrap();
+        assert_eq!(removed, 3);
+        assert_eq!(repository.len().await, 1);
+        assert!(repository.get_changed_at(&key(1, 2)).await.is_some());
+    }
+
+    #[tokio::test]
+    async fn it_removes_storage_item() {
+        let persist = setup("it_removes_storage_item");
+
+        let storage: Storage<RoleChangeKey, RoleChange> = Storage::new("role-changes", persist);
+
+        let key = RoleChangeKey {
+            guild_id: GuildId::new(1),
+            user_id: UserId::new(1),
+            role_id: RoleId::new(1),
+        };
+        storage
+            .save(
+                key,
+                RoleChange {
+                    key,
+                    changed_at: Utc::now(),
+                },
+            )
+            .await
+            .unwrap();
+
+        assert!(storage.remove(&key).await.unwrap());
+        assert!(storage.load(&key).await.is_err());
+        assert!(!storage.remove(&key).await.unwrap());
+    }
+
+    thread_local! {
+        static CLONES: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+    }
+
+    #[derive(Serialize, Deserialize, Debug)]
+    struct CountedValue {
+        key: u32,
+    }
+
+    impl Clone for CountedValue {
+        fn clone(&self) -> Self {
+            CLONES.with(|clones| clones.set(clones.get() + 1));
+
+            Self { key: self.key }
+        }
+    }
+
+    impl StorageValue<u32> for CountedValue {
+        fn get_key(&self) -> u32 {
+            self.key
+        }
+    }
+
+    #[tokio::test]
+    async fn it_filters_storage_without_cloning_all_values() {
+        let persist = setup("it_filters_storage_without_cloning_all_values");
+
+        let storage: CachedStorage<u32, CountedValue> =
+            CachedStorage::new(Storage::new("counted", persist))
+                .await
+                .unwrap();
+
+        // bypass the persistence, only the cached values are filtered
+        {
+            let mut write_lock = storage.write_lock().await;
+            for key in 0..1000 {
+                write_lock.insert(key, Mutex::new(CountedValue { key }));
+            }
+        }
+
+        CLONES.with(|clones| clones.set(0));
+        let keys = storage
+            .filter_map_collect(|value| (value.key % 100 == 0).then_some(value.key))
+            .await;
+        assert_eq!(keys.len(), 10);
+        assert_eq!(CLONES.with(|clones| clones.get()), 0);
+
+        let values = storage.filtered(|value| value.key < 5).await.unwrap();
+        assert_eq!(values.len(), 5);
+        assert_eq!(CLONES.with(|clones| clones.get()), 5);
+
+        let _ = storage.values().await;
+        assert_eq!(CLONES.with(|clones| clones.get()), 1005);
+    }
+
+    #[tokio::test]
+    async fn it_writes_index_once_per_batch() {
+        let persist = setup("it_writes_index_once_per_batch");
+
+        let storage: CachedStorage<RoleChangeKey, RoleChange> =
+            CachedStorage::new(Storage::new("role-changes", persist))
+                .await
+                .unwrap();
+
+        let keys = (1..=10)
+            .map(|i| RoleChangeKey {
+                guild_id: GuildId::new(1),
+                user_id: UserId::new(i),
+                role_id: RoleId::new(1),
+            })
+            .collect::<Vec<_>>();
+
+        storage
+            .with_batch(|| async {
+                for key in keys.iter() {
+                    storage
+                        .set(
+                            key,
+                            RoleChange {
+                                key: *key,
+                                changed_at: Utc::now(),
+                            },
+                        )
+                        .await?;
+
+                    // the index is not written until the batch ends
+                    assert!(storage.storage.load_index().await.is_err());
+                }
+
+                Ok(())
+            })
+            .await
+            .unwrap();
+
+        let mut index = storage.storage.load_index().await.unwrap();
+        index.sort_unstable_by_key(|key| key.user_id);
+        assert_eq!(index, keys);
+
+        // outside of the batch every added key is written to the index right away
+        let key = RoleChangeKey {
+            guild_id: GuildId::new(1),
+            user_id: UserId::new(11),
+            role_id: RoleId::new(1),
+        };
+        storage
+            .set(
+                &key,
+                RoleChange {
+                    key,
+                    changed_at: Utc::now(),
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(storage.storage.load_index().await.unwrap().len(), 11);
+    }
+
+    #[tokio::test]
+    async fn it_skips_corrupted_items_on_load() {
+        let persist = setup("it_skips_corrupted_items_on_load");
+        let path = test_output_path("it_skips_corrupted_items_on_load");
+
+        let storage: CachedStorage<RoleChangeKey, RoleChange> =
+            CachedStorage::new(Storage::new("role-changes", persist.clone()))
+                .await
+                .unwrap();
+
+        let keys = (1..=3)
+            .map(|i| RoleChangeKey {
+                guild_id: GuildId::new(1),
+                user_id: UserId::new(i),
+                role_id: RoleId::new(1),
+            })
+            .collect::<Vec<_>>();
+        for key in keys.iter() {
+            storage
+                .set(
+                    key,
+                    RoleChange {
+                        key: *key,
+                        changed_at: Utc::now(),
+                    },
+                )
+                .await
+                .unwrap();
+        }
+
+        // valid JSON with a stale checksum
+        std::fs::write(
+            path.join(format!("role-changes-{}.json", keys[0])),
+            serde_json::to_string(&RoleChange {
+                key: keys[0],
+                changed_at: Utc::now(),
+            })
+            .unwrap(),
+        )
+        .unwrap();
+
+        let storage: CachedStorage<RoleChangeKey, RoleChange> =
+            CachedStorage::new(Storage::new("role-changes", persist.clone()))
+                .await
+                .unwrap();
+
+        let mut loaded_keys = storage.keys().await;
+        loaded_keys.sort_unstable_by_key(|key| key.to_string());
+        assert_eq!(loaded_keys, vec![keys[1], keys[2]]);
+        // the corrupted file is moved aside, so the compaction does not remove it
+        assert!(path
+            .join(format!("role-changes-{}.json.corrupt", keys[0]))
+            .exists());
+        assert!(!path.join(format!("role-changes-{}.json", keys[0])).exists());
+
+        // a file that can not be deserialized fails the startup instead of being dropped
+        std::fs::write(path.join(format!("role-changes-{}.json", keys[1])), "{").unwrap();
+        std::fs::remove_file(path.join(format!("role-changes-{}.crc32", keys[1]))).unwrap();
+
+        assert!(
+            CachedStorage::<RoleChangeKey, RoleChange>::new(Storage::new("role-changes", persist))
+                .await
+                .is_err()
+        );
+    }
+}
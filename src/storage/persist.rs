@@ -1,7 +1,7 @@
 use std::{
     fs::{self, File},
     io::{BufReader, Read, Write},
-    path::PathBuf,
+    path::{Path, PathBuf},
 };
 
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
@@ -31,6 +31,8 @@ pub enum PersistError {
     Load(std::io::Error),
     #[error("failed to save data: {0}")]
     Save(std::io::Error),
+    #[error("checksum mismatch, the file is corrupted")]
+    Checksum,
 }
 
 #[derive(Serialize)]
@@ -49,19 +51,66 @@ impl PersistInstance {
         Ok(Self { dir })
     }
 
-    /// Save a key-value pair to disk
+    /// Save a key-value pair to disk, along with the CRC32 checksum sidecar file
+    ///
+    /// The stale checksum is removed first and both files are written through a temporary file,
+    /// so a crash in between leaves either the old data or new data without a checksum
     pub fn save<T: Serialize>(&self, key: &str, data: T) -> Result<(), PersistError> {
         let json = serde_json::to_string::<T>(&data).map_err(PersistError::Serialize)?;
 
-        let file_path = self.get_storage_file(key)?;
-        let mut file = File::create(file_path).map_err(PersistError::Open)?;
+        let checksum_path = self.get_checksum_file(key)?;
+        match fs::remove_file(&checksum_path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                return Err(PersistError::RemoveFile(e))
+            }
+            _ => {}
+        }
+
+        Self::write_atomically(&self.get_storage_file(key)?, json.as_bytes())?;
+        Self::write_atomically(
+            &checksum_path,
+            crc32fast::hash(json.as_bytes()).to_string().as_bytes(),
+        )
+    }
+
+    fn write_atomically(path: &Path, contents: &[u8]) -> Result<(), PersistError> {
+        let mut tmp_path = path.as_os_str().to_owned();
+        tmp_path.push(".tmp");
+
+        let mut file = File::create(&tmp_path).map_err(PersistError::Open)?;
+        file.write_all(contents).map_err(PersistError::Save)?;
+        file.sync_all().map_err(PersistError::Save)?;
 
-        file.write_all(json.as_bytes()).map_err(PersistError::Save)
+        fs::rename(&tmp_path, path).map_err(PersistError::Save)
+    }
+
+    /// Moves the key's files aside with the ``.corrupt`` suffix, so they are neither loaded
+    /// nor removed by the compaction, but can be inspected and restored manually
+    pub fn quarantine(&self, key: &str) -> Result<(), PersistError> {
+        for path in [self.get_storage_file(key)?, self.get_checksum_file(key)?] {
+            let mut corrupt_path = path.as_os_str().to_owned();
+            corrupt_path.push(".corrupt");
+
+            match fs::rename(&path, corrupt_path) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                    return Err(PersistError::Save(e))
+                }
+                _ => {}
+            }
+        }
+
+        Ok(())
     }
 
-    /// List contents of folder
-    fn entries(&self) -> Result<std::fs::ReadDir, PersistError> {
-        fs::read_dir(&self.dir).map_err(PersistError::ListFolder)
+    /// List data files of folder, checksum sidecar files are skipped
+    fn entries(&self) -> Result<impl Iterator<Item = std::io::Result<fs::DirEntry>>, PersistError> {
+        Ok(fs::read_dir(&self.dir)
+            .map_err(PersistError::ListFolder)?
+            .filter(|entry| {
+                entry.as_ref().map_or(true, |entry| {
+                    entry.path().extension().and_then(|ext| ext.to_str()) == Some("json")
+                })
+            }))
     }
 
     /// Returns the number of keys in this instance
@@ -100,10 +149,14 @@ impl PersistInstance {
         let file_path = self.get_storage_file(key)?;
         fs::remove_file(file_path).map_err(PersistError::RemoveFile)?;
 
-        Ok(())
+        match fs::remove_file(self.get_checksum_file(key)?) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(PersistError::RemoveFile(e)),
+            _ => Ok(()),
+        }
     }
 
-    /// Loads a value from disk
+    /// Loads a value from disk, verifying its checksum if the sidecar file exists
+    /// (files saved before checksums were introduced do not have one)
     pub fn load<T>(&self, key: &str) -> Result<T, PersistError>
     where
         T: DeserializeOwned,
@@ -117,11 +170,29 @@ impl PersistInstance {
             .read_to_string(&mut contents)
             .map_err(PersistError::Load)?;
 
+        match fs::read_to_string(self.get_checksum_file(key)?) {
+            Ok(checksum) => {
+                if checksum.trim() != crc32fast::hash(contents.as_bytes()).to_string() {
+                    return Err(PersistError::Checksum);
+                }
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(PersistError::Load(e)),
+        }
+
         serde_json::from_str::<T>(contents.as_str()).map_err(PersistError::Deserialize)
     }
 
     fn get_storage_file(&self, key: &str) -> Result<PathBuf, PersistError> {
-        let p = self.dir.join(format!("{key}.json"));
+        self.get_file(format!("{key}.json"))
+    }
+
+    fn get_checksum_file(&self, key: &str) -> Result<PathBuf, PersistError> {
+        self.get_file(format!("{key}.crc32"))
+    }
+
+    fn get_file(&self, file_name: String) -> Result<PathBuf, PersistError> {
+        let p = self.dir.join(file_name);
         if p.parent().unwrap() != self.dir {
             Err(PersistError::InvalidKey)
         } else {
@@ -221,4 +292,54 @@ mod tests {
         assert!(persist.save("/test", "test").is_err());
         assert!(persist.save("~/test", "test").is_err());
     }
+
+    #[test]
+    fn test_checksum() {
+        let persist = setup("test_checksum");
+
+        persist.save("test", "test").unwrap();
+        // checksum sidecar files are not listed
+        assert_eq!(persist.size().unwrap(), 1);
+
+        fs::write(persist.get_storage_file("test").unwrap(), "\"tset\"").unwrap();
+        assert!(matches!(
+            persist.load::<String>("test"),
+            Err(PersistError::Checksum)
+        ));
+
+        // files saved without the checksum are loaded as they are
+        persist.save("legacy", "legacy").unwrap();
+        fs::remove_file(persist.get_checksum_file("legacy").unwrap()).unwrap();
+        assert_eq!(persist.load::<String>("legacy").unwrap(), "legacy");
+
+        persist.remove("test").unwrap();
+        assert!(!persist.get_checksum_file("test").unwrap().exists());
+
+        // no temporary files are left after saving
+        persist.save("test", "test").unwrap();
+        assert_eq!(
+            fs::read_dir(&persist.dir)
+                .unwrap()
+                .filter(|entry| entry
+                    .as_ref()
+                    .unwrap()
+                    .path()
+                    .to_string_lossy()
+                    .ends_with(".tmp"))
+                .count(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_quarantine() {
+        let persist = setup("test_quarantine");
+
+        persist.save("test", "test").unwrap();
+        persist.quarantine("test").unwrap();
+
+        assert_eq!(persist.size().unwrap(), 0);
+        assert!(persist.dir.join("test.json.corrupt").exists());
+        assert!(persist.dir.join("test.crc32.corrupt").exists());
+    }
 }
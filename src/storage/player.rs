@@ -3,21 +3,33 @@
 use std::fmt::{Display, Formatter};
 use std::sync::Arc;
 
+use futures::{Stream, StreamExt};
 use poise::serenity_prelude::{GuildId, UserId};
 use serde::{Deserialize, Serialize};
 use tokio_util::sync::CancellationToken;
-use tracing::{debug, trace};
+use tracing::{debug, trace, warn};
 
 use crate::beatleader::player::{Player as BlPlayer, PlayerId};
 use crate::discord::bot::beatleader::player::Player as BotPlayer;
 use crate::discord::bot::beatleader::player::{fetch_player_from_bl, Player};
 use crate::discord::bot::beatleader::score::fetch_ranked_scores_stats;
 use crate::storage::persist::PersistInstance;
-use crate::storage::player_scores::PlayerScoresRepository;
-use crate::storage::{CachedStorage, Storage, StorageError, StorageKey, StorageValue};
+use crate::storage::player_scores::{PlayerScoresRepositories, PlayerScoresRepository};
+use crate::storage::{
+    CachedStorage, CompactionStats, Storage, StorageError, StorageKey, StorageValue,
+};
 
 use super::Result;
 
+// players updated at once, enough to overlap BL requests without bursting the API
+const PLAYERS_STATS_CONCURRENCY: usize = 4;
+
+#[derive(Debug, Default)]
+pub(crate) struct PlayersStatsUpdate {
+    pub players: Vec<BotPlayer>,
+    pub failed: usize,
+}
+
 #[derive(Debug)]
 pub(crate) struct PlayerRepository {
     storage: CachedStorage<UserId, BotPlayer>,
@@ -67,6 +79,13 @@ impl<'a> PlayerRepository {
         self.storage.len().await
     }
 
+    pub(crate) async fn compact(&self) -> Result<Vec<CompactionStats>> {
+        Ok(vec![
+            self.storage.compact().await?,
+            self.user_player_idx_repository.compact().await?,
+        ])
+    }
+
     pub(crate) async fn get(&self, user_id: &UserId) -> Option<BotPlayer> {
         match self.storage.get(user_id).await {
             None => None,
@@ -88,6 +107,22 @@ impl<'a> PlayerRepository {
         }
     }
 
+    // case-insensitive substring of the BL name, sorted by name
+    pub(crate) async fn search_by_name(&self, guild_id: &GuildId, query: &str) -> Vec<BotPlayer> {
+        let query = query.to_lowercase();
+
+        let mut players = self
+            .storage
+            .filter_map_collect(|player| {
+                (player.is_linked_to_guild(guild_id) && player.name.to_lowercase().contains(&query))
+                    .then(|| player.clone())
+            })
+            .await;
+        players.sort_unstable_by_key(|player| player.name.to_lowercase());
+
+        players
+    }
+
     pub(crate) async fn link(
         &self,
         guild_id: GuildId,
@@ -142,7 +177,7 @@ impl<'a> PlayerRepository {
         }
     }
 
-    pub(crate) async fn unlink(&self, guild_id: &GuildId, user_id: &UserId) -> Result<()> {
+    pub(crate) async fn unlink(&self, guild_id: &GuildId, user_id: &UserId) -> Result<BotPlayer> {
         trace!("Unlinking user {} from guild {}...", user_id, guild_id);
 
         let mut existed = false;
@@ -163,11 +198,11 @@ impl<'a> PlayerRepository {
             )
             .await?
         {
-            Some(_) => {
+            Some(player) => {
                 if existed {
                     debug!("User {} unlinked from guild {}.", user_id, guild_id);
 
-                    Ok(())
+                    Ok(player)
                 } else {
                     debug!("User {} is not linked to guild {}.", user_id, guild_id);
 
@@ -250,47 +285,132 @@ impl<'a> PlayerRepository {
         }
     }
 
+    // players are updated concurrently, a failed player is logged and left out of the result,
+    // so the rest of the batch is still returned
     pub(crate) async fn update_all_players_stats(
         &self,
-        player_scores_repository: &Arc<PlayerScoresRepository>,
+        player_scores_repositories: &Arc<PlayerScoresRepositories>,
         force_scores_download: bool,
         token: Option<CancellationToken>,
-    ) -> Result<Vec<BotPlayer>> {
+    ) -> Result<PlayersStatsUpdate> {
         trace!("Updating all users stats...");
 
-        let mut ret = Vec::with_capacity(self.storage.len().await);
+        let players = self
+            .storage
+            .filter_map_collect(|player| {
+                if !player.is_linked_to_any_guild() {
+                    trace!(
+                        "User {} / BL player {} is not linked to any guild, skipped.",
+                        player.user_id,
+                        player.id
+                    );
 
-        for player in self.storage.values().await {
-            if !player.is_linked_to_any_guild() {
-                trace!(
-                    "User {} / BL player {} is not linked to any guild, skipped.",
-                    player.user_id,
-                    player.id
-                );
+                    return None;
+                }
 
-                continue;
-            }
+                Some(player.clone())
+            })
+            .await;
+
+        let mut update = PlayersStatsUpdate {
+            players: Vec::with_capacity(players.len()),
+            failed: 0,
+        };
+
+        let mut results =
+            self.update_players_stats(player_scores_repositories, players, force_scores_download);
+
+        while let Some((player, result)) = results.next().await {
+            match result {
+                Ok(player) => update.players.push(player),
+                Err(err) => {
+                    warn!(
+                        "Can not update user {} / BL player {} stats: {}",
+                        player.user_id, player.name, err
+                    );
 
-            if let Ok(player) = self
-                .update_player_stats(player_scores_repository, &player, force_scores_download)
-                .await
-            {
-                ret.push(player);
+                    update.failed += 1;
+                }
             }
 
-            if token.is_some() && token.as_ref().unwrap().is_cancelled() {
+            if token.as_ref().is_some_and(|token| token.is_cancelled()) {
                 return Err(StorageError::Cancelled);
             }
         }
 
-        debug!("All users stats updated.");
+        debug!(
+            "All users stats updated, succeeded: {}, failed: {}.",
+            update.players.len(),
+            update.failed
+        );
+
+        Ok(update)
+    }
+
+    // players are updated concurrently, results are yielded in completion order together with
+    // the player they belong to;
+    // BL requests go through the shared client, so the rate limiter still applies
+    pub(crate) fn update_players_stats<'b>(
+        &'b self,
+        player_scores_repositories: &'b Arc<PlayerScoresRepositories>,
+        players: Vec<BotPlayer>,
+        force_scores_download: bool,
+    ) -> impl Stream<Item = (BotPlayer, Result<BotPlayer>)> + 'b {
+        futures::stream::iter(players)
+            .map(move |player| async move {
+                let result = self
+                    .update_player_stats(player_scores_repositories, &player, force_scores_download)
+                    .await;
 
-        Ok(ret)
+                (player, result)
+            })
+            .buffer_unordered(PLAYERS_STATS_CONCURRENCY)
+    }
+
+    // refreshes only the scores cache of a single context, players stats are not updated
+    pub(crate) async fn update_all_players_scores(
+        &self,
+        player_scores_repository: &PlayerScoresRepository,
+        force_scores_download: bool,
+    ) -> usize {
+        trace!(
+            "Updating all users {} scores...",
+            player_scores_repository.bl_context
+        );
+
+        let mut updated = 0;
+
+        let players = self
+            .storage
+            .filter_map_collect(|player| player.is_linked_to_any_guild().then(|| player.clone()))
+            .await;
+
+        for player in players {
+            match player_scores_repository
+                .update_player_scores(&player, force_scores_download)
+                .await
+            {
+                Ok(_) => updated += 1,
+                Err(err) => {
+                    warn!(
+                        "Can not update user {} / BL player {} {} scores: {}",
+                        player.user_id, player.name, player_scores_repository.bl_context, err
+                    );
+                }
+            }
+        }
+
+        debug!(
+            "All users {} scores updated.",
+            player_scores_repository.bl_context
+        );
+
+        updated
     }
 
     pub(crate) async fn update_player_stats(
         &self,
-        player_scores_repository: &Arc<PlayerScoresRepository>,
+        player_scores_repositories: &Arc<PlayerScoresRepositories>,
         player: &BotPlayer,
         force_scores_download: bool,
     ) -> Result<BotPlayer> {
@@ -313,9 +433,25 @@ impl<'a> PlayerRepository {
             bl_player.name
         );
 
-        let scores_stats =
-            fetch_ranked_scores_stats(player_scores_repository, player, force_scores_download)
-                .await?;
+        let scores_stats = fetch_ranked_scores_stats(
+            player_scores_repositories.general(),
+            player,
+            force_scores_download,
+        )
+        .await?;
+
+        // scores of other contexts are only stored for playlists, stats are based on general context
+        for player_scores_repository in player_scores_repositories.others() {
+            if let Err(err) = player_scores_repository
+                .update_player_scores(player, force_scores_download)
+                .await
+            {
+                warn!(
+                    "Can not update user {} / BL player {} {} scores: {}",
+                    player.user_id, player.name, player_scores_repository.bl_context, err
+                );
+            }
+        }
 
         match self
             .storage
@@ -326,7 +462,9 @@ impl<'a> PlayerRepository {
                         player.last_scores_fetch = Some(score_stats.last_scores_fetch);
                         player.plus_1pp = score_stats.plus_1pp;
                         player.last_ranked_paused_at = score_stats.last_ranked_paused_at;
+                        player.total_ranked_pauses = score_stats.total_ranked_pauses;
                         player.top_stars = score_stats.top_stars;
+                        player.acc_consistency = score_stats.acc_consistency;
                     }
 
                     **player = BotPlayer::from_user_id_and_bl_player(
@@ -356,6 +494,81 @@ impl<'a> PlayerRepository {
         }
     }
 
+    pub(crate) async fn set_replay_watch_subscription(
+        &self,
+        user_id: &UserId,
+        subscribed: bool,
+    ) -> Result<BotPlayer> {
+        trace!(
+            "Setting user {} replay watch subscription to {}...",
+            user_id,
+            subscribed
+        );
+
+        match self
+            .storage
+            .get_and_modify_or_insert(
+                user_id,
+                move |player| {
+                    player.replay_watch_subscribed = subscribed;
+                    // watches made before subscribing are not notified
+                    player.replay_watch_notified_count = if subscribed {
+                        Some(player.total_replay_watched)
+                    } else {
+                        None
+                    };
+                },
+                || None,
+            )
+            .await?
+        {
+            Some(player) => {
+                debug!(
+                    "User {} replay watch subscription set to {}.",
+                    user_id, subscribed
+                );
+
+                Ok(player)
+            }
+            None => {
+                debug!("User {} does not exists.", user_id);
+
+                Err(StorageError::NotFound("user does not exists".to_owned()))
+            }
+        }
+    }
+
+    pub(crate) async fn set_replay_watch_notified_count(
+        &self,
+        user_id: &UserId,
+        notified_count: u32,
+    ) -> Result<()> {
+        trace!(
+            "Setting user {} replay watch notified count to {}...",
+            user_id,
+            notified_count
+        );
+
+        match self
+            .storage
+            .get_and_modify_or_insert(
+                user_id,
+                move |player| {
+                    player.replay_watch_notified_count = Some(notified_count);
+                },
+                || None,
+            )
+            .await?
+        {
+            Some(_) => Ok(()),
+            None => {
+                debug!("User {} does not exists.", user_id);
+
+                Err(StorageError::NotFound("user does not exists".to_owned()))
+            }
+        }
+    }
+
     pub(crate) async fn restore(&self, values: Vec<BotPlayer>) -> Result<()> {
         self.storage.restore(values).await
     }
@@ -488,4 +701,8 @@ impl<'a> PlayerUserIdxRepository {
     pub(crate) async fn len(&self) -> usize {
         self.storage.len().await
     }
+
+    pub(crate) async fn compact(&self) -> Result<CompactionStats> {
+        self.storage.compact().await
+    }
 }
@@ -8,7 +8,7 @@ use crate::storage::persist::PersistInstance;
 
 use crate::beatleader::oauth::OAuthToken;
 use crate::beatleader::player::PlayerId;
-use crate::storage::{CachedStorage, Storage};
+use crate::storage::{CachedStorage, CompactionStats, Storage};
 use crate::storage::{StorageKey, StorageValue};
 
 use super::Result;
@@ -64,6 +64,10 @@ impl<'a> PlayerOAuthTokenRepository {
         self.storage.len().await
     }
 
+    pub(crate) async fn compact(&self) -> Result<CompactionStats> {
+        self.storage.compact().await
+    }
+
     pub(crate) async fn get(&self, player_id: &PlayerId) -> Option<PlayerOAuthToken> {
         self.storage.get(player_id).await
     }
@@ -116,6 +120,10 @@ impl<'a> PlayerOAuthTokenRepository {
         Ok(value)
     }
 
+    pub(crate) async fn remove(&self, player_id: &PlayerId) -> Result<bool> {
+        self.storage.delete(player_id).await
+    }
+
     pub(crate) async fn restore(&self, values: Vec<PlayerOAuthToken>) -> Result<()> {
         self.storage.restore(values).await
     }
@@ -1,14 +1,16 @@
+use std::collections::HashMap;
 use std::sync::Arc;
 
 use poise::serenity_prelude::UserId;
 use serde::{Deserialize, Serialize};
-use tracing::{debug, trace, warn};
+use tracing::{debug, info, trace, warn};
 
 use crate::beatleader::player::PlayerId;
 use crate::beatleader::BlContext;
 use crate::discord::bot::beatleader::player::fetch_all_player_scores;
 use crate::discord::bot::beatleader::player::Player;
 use crate::discord::bot::beatleader::score::Score;
+use crate::other::single_flight::SingleFlight;
 use crate::storage::persist::PersistInstance;
 use crate::storage::Storage;
 
@@ -33,6 +35,8 @@ impl StorageValue<PlayerId> for PlayerScores {
 pub(crate) struct PlayerScoresRepository {
     storage: Storage<PlayerId, PlayerScores>,
     pub bl_context: BlContext,
+    // concurrent refreshes of the same player share a single fetch, so they do not clobber each other
+    updates_in_flight: SingleFlight<PlayerId, Option<PlayerScores>>,
 }
 
 impl<'a> PlayerScoresRepository {
@@ -43,6 +47,7 @@ impl<'a> PlayerScoresRepository {
         Ok(Self {
             storage: Storage::new(format!("player-scores-{}", bl_context).as_str(), persist),
             bl_context,
+            updates_in_flight: SingleFlight::new(),
         })
     }
 
@@ -50,10 +55,51 @@ impl<'a> PlayerScoresRepository {
         self.storage.load(player_id).await.ok()
     }
 
+    // scores are not cached nor indexed, so the files are counted
+    pub(crate) async fn len(&self) -> Result<usize> {
+        Ok(self
+            .storage
+            .list_file_names()?
+            .into_iter()
+            .filter(|file_name| !file_name.ends_with("-index"))
+            .count())
+    }
+
+    pub(crate) async fn remove(&self, player_id: &PlayerId) -> Result<bool> {
+        trace!(
+            "Removing BL player {} {} scores...",
+            player_id,
+            self.bl_context
+        );
+
+        let removed = self.storage.remove(player_id).await?;
+
+        debug!(
+            "BL player {} {} scores {}.",
+            player_id,
+            self.bl_context,
+            if removed { "removed" } else { "not found" }
+        );
+
+        Ok(removed)
+    }
+
     pub(crate) async fn update_player_scores(
         &self,
         player: &Player,
         force_scores_download: bool,
+    ) -> Result<Option<PlayerScores>> {
+        self.updates_in_flight
+            .run(&player.id, || {
+                self.fetch_and_save_player_scores(player, force_scores_download)
+            })
+            .await
+    }
+
+    async fn fetch_and_save_player_scores(
+        &self,
+        player: &Player,
+        force_scores_download: bool,
     ) -> Result<Option<PlayerScores>> {
         trace!(
             "Updating user {} / BL player {} scores...",
@@ -112,3 +158,58 @@ impl<'a> PlayerScoresRepository {
         }
     }
 }
+
+#[derive(Debug)]
+pub(crate) struct PlayerScoresRepositories {
+    repositories: HashMap<BlContext, Arc<PlayerScoresRepository>>,
+}
+
+impl PlayerScoresRepositories {
+    pub(crate) async fn new(
+        persist: Arc<PersistInstance>,
+        bl_contexts: &[BlContext],
+    ) -> Result<PlayerScoresRepositories> {
+        let mut repositories = HashMap::new();
+
+        // general context is always available, it is used to calculate players stats
+        for bl_context in std::iter::once(&BlContext::General).chain(bl_contexts.iter()) {
+            if repositories.contains_key(bl_context) {
+                continue;
+            }
+
+            info!("Initializing {} players scores repository...", bl_context);
+
+            repositories.insert(
+                bl_context.clone(),
+                Arc::new(
+                    PlayerScoresRepository::new(Arc::clone(&persist), bl_context.clone()).await?,
+                ),
+            );
+        }
+
+        Ok(Self { repositories })
+    }
+
+    pub(crate) fn get(&self, bl_context: &BlContext) -> Option<&Arc<PlayerScoresRepository>> {
+        self.repositories.get(bl_context)
+    }
+
+    pub(crate) fn general(&self) -> &Arc<PlayerScoresRepository> {
+        self.repositories.get(&BlContext::General).unwrap()
+    }
+
+    pub(crate) fn contains(&self, bl_context: &BlContext) -> bool {
+        self.repositories.contains_key(bl_context)
+    }
+
+    pub(crate) fn all(&self) -> impl Iterator<Item = &Arc<PlayerScoresRepository>> {
+        self.repositories.values()
+    }
+
+    pub(crate) fn others(&self) -> impl Iterator<Item = &Arc<PlayerScoresRepository>> {
+        self.repositories
+            .iter()
+            .filter(|(bl_context, _)| **bl_context != BlContext::General)
+            .map(|(_, repository)| repository)
+    }
+}
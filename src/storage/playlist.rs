@@ -1,8 +1,10 @@
+use crate::beatleader::player::PlayerId;
 use crate::discord::bot::beatleader::clan::{Playlist, PlaylistId};
+use chrono::Utc;
 use std::sync::Arc;
 
 use crate::storage::persist::PersistInstance;
-use crate::storage::{CachedStorage, Storage, StorageValue};
+use crate::storage::{CachedStorage, CompactionStats, Storage, StorageValue};
 
 use super::Result;
 
@@ -10,6 +12,17 @@ impl StorageValue<PlaylistId> for Playlist {
     fn get_key(&self) -> PlaylistId {
         self.get_id().clone()
     }
+
+    // playlists stored before access tracking are kept for the whole TTL from now on
+    fn migrate(&mut self) -> bool {
+        if self.last_accessed.is_some() {
+            return false;
+        }
+
+        self.last_accessed = Some(Utc::now());
+
+        true
+    }
 }
 
 #[derive(Debug)]
@@ -32,12 +45,40 @@ impl<'a> PlaylistRepository {
         self.storage.len().await
     }
 
+    pub(crate) async fn compact(&self) -> Result<CompactionStats> {
+        self.storage.compact().await
+    }
+
+    // only clan wars playlists are stored with the owner, the most recently created first
+    pub(crate) async fn by_player(&self, player_id: &PlayerId) -> Vec<Playlist> {
+        let mut playlists = self
+            .storage
+            .filter_map_collect(|playlist| {
+                playlist.is_owned_by(player_id).then(|| playlist.clone())
+            })
+            .await;
+
+        playlists.sort_unstable_by_key(|playlist| std::cmp::Reverse(playlist.created_at));
+
+        playlists
+    }
+
     pub(crate) async fn get(&self, playlist_id: &PlaylistId) -> Option<Playlist> {
         self.storage.get(playlist_id).await
     }
 
+    // saving the playlist counts as an access, the synced playlist is saved again
     pub(crate) async fn save(&self, mut playlist: Playlist) -> Result<Playlist> {
+        let now = Utc::now();
+
         playlist.set_image("".to_owned());
+        playlist.created_at = self
+            .storage
+            .get(playlist.get_id())
+            .await
+            .and_then(|stored| stored.created_at)
+            .or(Some(now));
+        playlist.last_accessed = Some(now);
 
         let playlist = self
             .storage
@@ -48,6 +89,23 @@ impl<'a> PlaylistRepository {
 
         Ok(playlist)
     }
+
+    pub(crate) async fn mark_accessed(&self, playlist_id: &PlaylistId) -> Result<()> {
+        self.storage
+            .get_and_modify_or_insert(
+                playlist_id,
+                |playlist| playlist.last_accessed = Some(Utc::now()),
+                || None,
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    pub(crate) async fn remove(&self, playlist_id: &PlaylistId) -> Result<bool> {
+        self.storage.remove(playlist_id).await
+    }
+
     pub(crate) async fn restore(&self, values: Vec<Playlist>) -> Result<()> {
         self.storage.restore(values).await
     }
@@ -0,0 +1,107 @@
+use chrono::{DateTime, Duration, Utc};
+use poise::serenity_prelude::{GuildId, RoleId, UserId};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::sync::Arc;
+
+use crate::storage::persist::PersistInstance;
+use crate::storage::{CachedStorage, CompactionStats, Storage, StorageKey, StorageValue};
+
+use super::Result;
+
+#[derive(Serialize, Deserialize, Default, Debug, Clone, Copy, Eq, PartialEq, Hash)]
+#[serde(rename_all = "camelCase")]
+pub struct RoleChangeKey {
+    pub guild_id: GuildId,
+    pub user_id: UserId,
+    pub role_id: RoleId,
+}
+
+impl Display for RoleChangeKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}-{}-{}", self.guild_id, self.user_id, self.role_id)
+    }
+}
+
+impl StorageKey for RoleChangeKey {}
+
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+#[serde(default)]
+pub struct RoleChange {
+    pub key: RoleChangeKey,
+    pub changed_at: DateTime<Utc>,
+}
+
+impl StorageValue<RoleChangeKey> for RoleChange {
+    fn get_key(&self) -> RoleChangeKey {
+        self.key
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct RoleChangeRepository {
+    storage: CachedStorage<RoleChangeKey, RoleChange>,
+}
+
+impl RoleChangeRepository {
+    pub(crate) async fn new(persist: Arc<PersistInstance>) -> Result<RoleChangeRepository> {
+        Ok(Self {
+            storage: CachedStorage::new(Storage::new("role-changes", persist)).await?,
+        })
+    }
+
+    pub(crate) async fn len(&self) -> usize {
+        self.storage.len().await
+    }
+
+    pub(crate) async fn compact(&self) -> Result<CompactionStats> {
+        self.storage.compact().await
+    }
+
+    pub(crate) async fn get_changed_at(&self, key: &RoleChangeKey) -> Option<DateTime<Utc>> {
+        self.storage
+            .get(key)
+            .await
+            .map(|role_change| role_change.changed_at)
+    }
+
+    pub(crate) async fn set_changed_at(
+        &self,
+        key: RoleChangeKey,
+        changed_at: DateTime<Utc>,
+    ) -> Result<RoleChange> {
+        self.storage.set(&key, RoleChange { key, changed_at }).await
+    }
+
+    // changes are only needed while their guild's cooldown lasts, so older ones and the ones of
+    // guilds without a cooldown are removed; returns the number of removed entries
+    pub(crate) async fn remove_expired(
+        &self,
+        cooldowns: &HashMap<GuildId, Duration>,
+        now: DateTime<Utc>,
+    ) -> Result<usize> {
+        let expired = self
+            .storage
+            .filter_map_collect(|role_change| {
+                cooldowns
+                    .get(&role_change.key.guild_id)
+                    .is_none_or(|cooldown| role_change.changed_at + *cooldown <= now)
+                    .then_some(role_change.key)
+            })
+            .await;
+
+        self.storage
+            .with_batch(|| async {
+                for key in expired.iter() {
+                    self.storage.remove(key).await?;
+                }
+
+                Ok(())
+            })
+            .await?;
+
+        Ok(expired.len())
+    }
+}
@@ -4,27 +4,27 @@ use std::sync::Arc;
 use tokio_util::sync::CancellationToken;
 use tokio_util::task::TaskTracker;
 use tower_http::timeout::TimeoutLayer;
-use tower_http::trace::{self, TraceLayer};
-use tower_http::LatencyUnit;
+use tower_http::trace::TraceLayer;
 use tracing::{info, warn};
 
 use crate::config::Settings;
+use crate::other::clan_wars_playlist_cache::{ClanWarsPlaylistCache, CLAN_WARS_PLAYLIST_CACHE_TTL};
 use crate::persist::CommonData;
 use crate::storage::bsmaps::BsMapsRepository;
 use crate::storage::guild::GuildSettingsRepository;
 use crate::storage::player::PlayerRepository;
 use crate::storage::player_oauth_token::PlayerOAuthTokenRepository;
-use crate::storage::player_scores::PlayerScoresRepository;
+use crate::storage::player_scores::PlayerScoresRepositories;
 use crate::storage::playlist::PlaylistRepository;
-use crate::webserver::routes::app_router;
+use crate::webserver::routes::{app_router, BlHealthCheck, BL_HEALTH_CHECK_TTL};
 
-mod routes;
+pub(crate) mod routes;
 
 pub struct WebServer {
     pub guild_settings_repository: Arc<GuildSettingsRepository>,
     pub player_oauth_token_repository: Arc<PlayerOAuthTokenRepository>,
     pub players_repository: Arc<PlayerRepository>,
-    pub player_scores_repository: Arc<PlayerScoresRepository>,
+    pub player_scores_repositories: Arc<PlayerScoresRepositories>,
     pub playlists_repository: Arc<PlaylistRepository>,
     pub maps_repository: Arc<BsMapsRepository>,
     pub settings: Settings,
@@ -37,9 +37,11 @@ pub(crate) struct AppState {
     pub guild_settings_repository: Arc<GuildSettingsRepository>,
     pub player_oauth_token_repository: Arc<PlayerOAuthTokenRepository>,
     pub players_repository: Arc<PlayerRepository>,
-    pub player_scores_repository: Arc<PlayerScoresRepository>,
+    pub player_scores_repositories: Arc<PlayerScoresRepositories>,
     pub playlists_repository: Arc<PlaylistRepository>,
     pub maps_repository: Arc<BsMapsRepository>,
+    pub clan_wars_playlist_cache: Arc<ClanWarsPlaylistCache>,
+    pub bl_health_check: Arc<BlHealthCheck>,
     pub settings: Settings,
 }
 
@@ -49,7 +51,7 @@ impl WebServer {
             guild_settings_repository: data.guild_settings_repository,
             player_oauth_token_repository: data.player_oauth_token_repository,
             players_repository: data.players_repository,
-            player_scores_repository: data.player_scores_repository,
+            player_scores_repositories: data.player_scores_repositories,
             playlists_repository: data.playlists_repository,
             maps_repository: data.maps_repository,
             settings: data.settings,
@@ -72,20 +74,20 @@ impl WebServer {
             guild_settings_repository: self.guild_settings_repository,
             player_oauth_token_repository: self.player_oauth_token_repository,
             players_repository: self.players_repository,
-            player_scores_repository: self.player_scores_repository,
+            player_scores_repositories: self.player_scores_repositories,
             playlists_repository: self.playlists_repository,
             maps_repository: self.maps_repository,
+            clan_wars_playlist_cache: Arc::new(ClanWarsPlaylistCache::new(
+                CLAN_WARS_PLAYLIST_CACHE_TTL,
+            )),
+            bl_health_check: Arc::new(BlHealthCheck::new(BL_HEALTH_CHECK_TTL)),
             settings: self.settings,
         };
 
         let app_router = app_router(self.tracker.clone(), self.token.clone(), state).layer((
             TraceLayer::new_for_http()
                 .make_span_with(crate::log::make_span_with)
-                .on_response(
-                    trace::DefaultOnResponse::new()
-                        .latency_unit(LatencyUnit::Millis)
-                        .level(tracing::Level::INFO),
-                ),
+                .on_response(crate::log::on_response),
             // Graceful shutdown will wait for outstanding requests to complete. Add a timeout so
             // requests don't hang forever.
             TimeoutLayer::new(std::time::Duration::from_secs(timeout as u64)),
@@ -1,7 +1,225 @@
-use axum::Router;
+use std::sync::Arc;
+use std::time::Duration;
 
+use axum::extract::{Path, Query, Request, State};
+use axum::http::{header, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, MethodRouter};
+use axum::{Json, Router};
+use poise::serenity_prelude::GuildId;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tower_governor::governor::GovernorConfigBuilder;
+use tower_governor::key_extractor::SmartIpKeyExtractor;
+use tower_governor::GovernorLayer;
+
+use crate::discord::bot::beatleader::clan::{ClanWarsSort, Playlist};
+use crate::discord::bot::{RoleGroup, RoleSettings};
+use crate::other::clan_wars_playlist_cache::{ClanWarsPlaylistCache, CLAN_WARS_PLAYLIST_CACHE_TTL};
+use crate::webserver::routes::{playlist_governor_config, rate_limit_error_response};
 use crate::webserver::AppState;
 
-pub(crate) fn router() -> Router<AppState> {
+const CLAN_WARS_PLAYLIST_MAPS_COUNT: u32 = 100;
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct PublicRoleGroup {
+    name: RoleGroup,
+    roles: Vec<RoleSettings>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct PublicGuildRoles {
+    guild_id: GuildId,
+    role_groups: Vec<PublicRoleGroup>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ClanWarsPlaylistParams {
+    #[serde(default)]
+    sort: ClanWarsSort,
+}
+
+pub(crate) fn router(clan_wars_playlist_cache: Arc<ClanWarsPlaylistCache>) -> Router<AppState> {
+    let api_governor_conf = Arc::new(
+        GovernorConfigBuilder::default()
+            .key_extractor(SmartIpKeyExtractor)
+            .period(Duration::from_secs(10))
+            .burst_size(5)
+            .use_headers()
+            .error_handler(rate_limit_error_response)
+            .finish()
+            .unwrap(),
+    );
+
     Router::new()
+        .route("/guild/:id/roles", get(guild_roles))
+        .layer(GovernorLayer {
+            config: api_governor_conf,
+        })
+        .merge(clan_wars_playlist_router(
+            get(clan_wars_playlist),
+            clan_wars_playlist_cache,
+        ))
+}
+
+// cached playlists are served before the rate limiter, so only requests generating a playlist
+// count; they are limited per client, so cycling clan tags does not bypass the limit
+pub(super) fn clan_wars_playlist_router<S: Clone + Send + Sync + 'static>(
+    handler: MethodRouter<S>,
+    clan_wars_playlist_cache: Arc<ClanWarsPlaylistCache>,
+) -> Router<S> {
+    let clan_wars_playlist_governor_conf = playlist_governor_config(SmartIpKeyExtractor);
+
+    Router::new()
+        .route("/clan/:tag/wars.json", handler)
+        .route_layer(GovernorLayer {
+            config: clan_wars_playlist_governor_conf,
+        })
+        .route_layer(middleware::from_fn_with_state(
+            clan_wars_playlist_cache,
+            serve_cached_clan_wars_playlist,
+        ))
+}
+
+async fn serve_cached_clan_wars_playlist(
+    State(clan_wars_playlist_cache): State<Arc<ClanWarsPlaylistCache>>,
+    Path(clan_tag): Path<String>,
+    Query(params): Query<ClanWarsPlaylistParams>,
+    request: Request,
+    next: Next,
+) -> Response {
+    match clan_wars_playlist_cache.get(&clan_tag.to_uppercase(), &params.sort) {
+        Some(playlist) => clan_wars_playlist_response(&playlist),
+        None => next.run(request).await,
+    }
+}
+
+#[tracing::instrument(skip(app_state), level=tracing::Level::INFO, name="webserver:api:guild-roles")]
+async fn guild_roles(
+    State(app_state): State<AppState>,
+    Path(guild_id): Path<String>,
+) -> (StatusCode, impl IntoResponse) {
+    let Ok(guild_id) = guild_id.parse::<GuildId>() else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({"error": {"code": "invalid_guild_id", "message": "Invalid guild ID"}}))
+                .into_response(),
+        );
+    };
+
+    let Ok(guild_settings) = app_state.guild_settings_repository.get(&guild_id).await else {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(json!({"error": {"code": "not_found", "message": "Guild not found"}}))
+                .into_response(),
+        );
+    };
+
+    // only role groups and their requirements are exposed, channels and clan settings stay private
+    let mut role_groups: Vec<PublicRoleGroup> = vec![];
+    for (role_group, role_settings) in guild_settings.get_auto_roles() {
+        match role_groups.last_mut() {
+            Some(public_role_group) if public_role_group.name == role_group => {
+                public_role_group.roles.push(role_settings)
+            }
+            _ => role_groups.push(PublicRoleGroup {
+                name: role_group,
+                roles: vec![role_settings],
+            }),
+        }
+    }
+
+    (
+        StatusCode::OK,
+        Json(PublicGuildRoles {
+            guild_id,
+            role_groups,
+        })
+        .into_response(),
+    )
+}
+
+#[tracing::instrument(skip(app_state), level=tracing::Level::INFO, name="webserver:api:clan-wars-playlist")]
+async fn clan_wars_playlist(
+    State(app_state): State<AppState>,
+    Path(clan_tag): Path<String>,
+    Query(params): Query<ClanWarsPlaylistParams>,
+) -> (StatusCode, impl IntoResponse) {
+    let clan_tag = clan_tag.to_uppercase();
+
+    let playlist = match app_state
+        .clan_wars_playlist_cache
+        .get(&clan_tag, &params.sort)
+    {
+        Some(playlist) => playlist,
+        None => {
+            // the image is not known for clans without a guild, the default one is used then
+            let cover = app_state
+                .guild_settings_repository
+                .all()
+                .await
+                .into_iter()
+                .find(|guild_settings| {
+                    guild_settings
+                        .get_clan_settings()
+                        .is_some_and(|clan_settings| clan_settings.get_clan() == clan_tag)
+                })
+                .and_then(|guild_settings| guild_settings.get_playlist_cover(&params.sort));
+
+            match Playlist::for_clan_wars(
+                clan_tag.clone(),
+                params.sort.clone(),
+                CLAN_WARS_PLAYLIST_MAPS_COUNT,
+                cover,
+            )
+            .await
+            {
+                Ok(playlist) => {
+                    app_state
+                        .clan_wars_playlist_cache
+                        .insert(&clan_tag, &params.sort, playlist.clone());
+
+                    playlist
+                }
+                Err(err) => {
+                    return (
+                        StatusCode::BAD_GATEWAY,
+                        Json(
+                            json!({"error": {"code": "bl_error", "message": format!("Playlist generating error: {}", err)}}),
+                        )
+                        .into_response(),
+                    )
+                }
+            }
+        }
+    };
+
+    (StatusCode::OK, clan_wars_playlist_response(&playlist))
+}
+
+fn clan_wars_playlist_response(playlist: &Playlist) -> Response {
+    let mut response = Json(json!(playlist)).into_response();
+
+    response.headers_mut().insert(
+        header::CONTENT_DISPOSITION,
+        format!(
+            "attachment; filename=\"{}.json\"",
+            playlist
+                .get_title()
+                .replace([' ', '-', '/', '\\', ':'], "_")
+        )
+        .parse()
+        .unwrap(),
+    );
+    response.headers_mut().insert(
+        header::CACHE_CONTROL,
+        format!("public, max-age={}", CLAN_WARS_PLAYLIST_CACHE_TTL.as_secs())
+            .parse()
+            .unwrap(),
+    );
+
+    response
 }
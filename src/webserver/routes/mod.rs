@@ -1,23 +1,28 @@
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use std::{fmt, str::FromStr};
 
+use axum::body::Body;
 use axum::extract::Path;
 use axum::http::{Request, Response, StatusCode};
 use axum::response::IntoResponse;
 use axum::{extract::Query, extract::State, http::header, routing::get, Json, Router};
+use governor::middleware::StateInformationMiddleware;
 use serde::{de, Deserialize, Deserializer, Serialize};
 use serde_json::json;
 use tokio_util::sync::CancellationToken;
 use tokio_util::task::TaskTracker;
+use tower_governor::governor::{GovernorConfig, GovernorConfigBuilder};
 use tower_governor::key_extractor::KeyExtractor;
-use tower_governor::{governor::GovernorConfigBuilder, GovernorError, GovernorLayer};
+use tower_governor::{GovernorError, GovernorLayer};
 
+use crate::beatleader::error::Error as BlError;
 use crate::beatleader::oauth::OAuthAppCredentials;
+use crate::config::OAuthSettings;
 use crate::discord::bot::beatleader::clan::Playlist;
-use crate::discord::bot::GuildOAuthTokenRepository;
+use crate::discord::bot::{GuildOAuthTokenRepository, GuildSettings};
 use crate::webserver::AppState;
-use crate::BL_CLIENT;
+use crate::{BL_CLIENT, METRICS};
 use magic_crypt::{new_magic_crypt, MagicCryptTrait};
 use poise::serenity_prelude::GuildId;
 
@@ -49,38 +54,58 @@ impl KeyExtractor for PlaylistUserExtractor {
     }
 }
 
-pub(crate) fn app_router(
-    tracker: TaskTracker,
-    token: CancellationToken,
-    state: AppState,
-) -> Router {
-    let playlist_governor_conf = Arc::new(
+pub(super) fn rate_limit_error_response(err: GovernorError) -> Response<Body> {
+    match err {
+        GovernorError::TooManyRequests { wait_time, headers } => {
+            let (mut parts, body) = Json(json!({"error": {"code": "rate_limit", "message": format!("Too Many Requests! Wait for {}s", wait_time), "retry_after": wait_time}}))
+                .into_response()
+                .into_parts();
+
+            parts.status = StatusCode::TOO_MANY_REQUESTS;
+            if let Some(headers) = headers {
+                headers.into_iter().for_each(|(name_option, value)| {
+                    if let Some(name) = name_option {
+                        parts.headers.insert(name, value);
+                    }
+                });
+            }
+
+            Response::from_parts(parts, body)
+        }
+        _ => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(
+                json!({"error": {"code": "internal_rate_limit_error", "message": "Unknown error"}}),
+            )
+            .into_response(),
+        )
+            .into_response(),
+    }
+}
+
+// rate limit headers are sent with every response, so playlist clients can throttle themselves
+// before they get 429
+fn playlist_governor_config<K: KeyExtractor>(
+    key_extractor: K,
+) -> Arc<GovernorConfig<K, StateInformationMiddleware>> {
+    Arc::new(
         GovernorConfigBuilder::default()
-            .key_extractor(PlaylistUserExtractor)
+            .key_extractor(key_extractor)
             .period(Duration::from_secs(180))
             .burst_size(3)
             .use_headers()
-            .error_handler(|err| match err {
-                GovernorError::TooManyRequests { wait_time, headers } => {
-                    let (mut parts, body) = Json(json!({"error": {"code": "rate_limit", "message": format!("Too Many Requests! Wait for {}s", wait_time), "retry_after": wait_time}}))
-                        .into_response()
-                        .into_parts();
-
-                    parts.status = StatusCode::TOO_MANY_REQUESTS;
-                    if let Some(headers) = headers {
-                        headers.into_iter().for_each(|(name_option, value)| if let Some(name) = name_option {parts.headers.insert(name, value);});
-                    }
-
-                    Response::from_parts(parts, body)
-                }
-                _ => (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(json!({"error": {"code": "internal_rate_limit_error", "message": "Unknown error"}})).into_response(),
-                ).into_response(),
-            })
+            .error_handler(rate_limit_error_response)
             .finish()
-            .unwrap()
-    );
+            .unwrap(),
+    )
+}
+
+pub(crate) fn app_router(
+    tracker: TaskTracker,
+    token: CancellationToken,
+    state: AppState,
+) -> Router {
+    let playlist_governor_conf = playlist_governor_config(PlaylistUserExtractor);
 
     let playlist_governor_limiter = playlist_governor_conf.limiter().clone();
 
@@ -107,15 +132,26 @@ pub(crate) fn app_router(
         tracing::warn!("Playlist rate limiting shut down.");
     });
 
+    // mounted after the playlist governor layer, so scrapes are not rate limited
+    let metrics_router = if state.settings.metrics_enabled {
+        Router::new().route("/metrics", get(metrics))
+    } else {
+        Router::new()
+    };
+
+    let api_router = api::router(Arc::clone(&state.clan_wars_playlist_cache));
+
     Router::new()
         .route("/playlist/:user/:id", get(playlist))
         .layer(GovernorLayer {
             config: playlist_governor_conf,
         })
+        .merge(metrics_router)
         .route("/health_check", get(health_check))
+        .route("/health_check/deep", get(deep_health_check))
         .route("/bl-oauth/", get(bl_oauth))
         .route("/bl-oauth", get(bl_oauth))
-        .nest("/api", api::router())
+        .nest("/api", api_router)
         .nest("/", web::router())
         .with_state(state)
 }
@@ -161,21 +197,47 @@ async fn playlist(
                         ),
                     };
 
+                // the image and the commander's orders floor are not stored with the playlist,
+                // so they are taken from the clan's guild
+                let clan_guild_settings = app_state
+                    .guild_settings_repository
+                    .all()
+                    .await
+                    .into_iter()
+                    .find(|guild_settings| {
+                        guild_settings
+                            .get_clan_settings()
+                            .is_some_and(|clan_settings| clan_settings.get_clan() == custom_data.clan_tag)
+                    });
+                let cover = clan_guild_settings
+                    .as_ref()
+                    .and_then(|guild_settings| guild_settings.get_playlist_cover(&custom_data.playlist_type));
+                let commander_order_min_stars = clan_guild_settings
+                    .as_ref()
+                    .and_then(|guild_settings| guild_settings.get_commander_order_min_stars());
+
                 match Playlist::for_clan_player(
-                    &app_state.player_scores_repository,
+                    &app_state.player_scores_repositories,
                     &app_state.maps_repository,
                     app_state.settings.server.url.as_str(),
                     custom_data.clan_tag.clone(),
                     player,
+                    custom_data.bl_context.clone(),
                     custom_data.playlist_type.clone(),
                     custom_data.last_played.clone(),
                     custom_data.count,
                     custom_data.max_stars,
                     custom_data.max_clan_pp_diff,
-                    custom_data.fc_status,
+                    custom_data.min_clan_pp_diff,
+                    custom_data.map_status.clone(),
+                    // the stored status is already resolved against the guild default
+                    Some(custom_data.fc_status.into()),
+                    false,
                     custom_data.skip_commander_orders,
+                    commander_order_min_stars,
                     Some(repository_playlist.get_title().clone()),
-                    true
+                    true,
+                    cover,
                 )
                 .await
                 {
@@ -187,10 +249,7 @@ async fn playlist(
                             .save(refreshed_playlist.clone())
                             .await;
 
-                        let mut response = Json(json!(
-                            refreshed_playlist.set_image(Playlist::default_image())
-                        ))
-                        .into_response();
+                        let mut response = Json(json!(refreshed_playlist)).into_response();
 
                         response.headers_mut().insert(
                             header::CONTENT_DISPOSITION,
@@ -202,23 +261,137 @@ async fn playlist(
 
                         (StatusCode::OK, response)
                     }
-                    Err(err) => (
-                        StatusCode::BAD_GATEWAY,
-                        Json(
-                            json!({"error": {"code": "bl_error", "message": format!("Playlist generating error: {}", err)}}),
-                        ).into_response(),
-                    ),
+                    Err(err) => {
+                        // the player is still using the playlist, do not let it expire because of BL errors
+                        let _ = app_state
+                            .playlists_repository
+                            .mark_accessed(repository_playlist.get_id())
+                            .await;
+
+                        (
+                            StatusCode::BAD_GATEWAY,
+                            Json(
+                                json!({"error": {"code": "bl_error", "message": format!("Playlist generating error: {}", err)}}),
+                            ).into_response(),
+                        )
+                    }
                 }
             }
         },
     }
 }
 
+#[tracing::instrument(skip(app_state), level=tracing::Level::INFO, name="webserver:metrics")]
+async fn metrics(State(app_state): State<AppState>) -> impl IntoResponse {
+    let storage_sizes = [
+        ("guilds", app_state.guild_settings_repository.len().await),
+        ("players", app_state.players_repository.len().await),
+        (
+            "player_oauth_tokens",
+            app_state.player_oauth_token_repository.len().await,
+        ),
+        ("playlists", app_state.playlists_repository.len().await),
+        ("maps", app_state.maps_repository.len().await),
+    ];
+
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        METRICS.render(&storage_sizes),
+    )
+}
+
 #[tracing::instrument(level=tracing::Level::INFO, name="webserver:health_check")]
 async fn health_check() -> StatusCode {
     StatusCode::OK
 }
 
+const DEEP_HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+pub(crate) const BL_HEALTH_CHECK_TTL: Duration = Duration::from_secs(30);
+
+// the deep health check is public, so BL is probed at most once per ttl no matter how often it is hit;
+// concurrent checks wait for the one in progress
+#[derive(Debug)]
+pub(crate) struct BlHealthCheck {
+    ttl: Duration,
+    last_check: tokio::sync::Mutex<Option<(Instant, Result<(), String>)>>,
+}
+
+impl BlHealthCheck {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            last_check: tokio::sync::Mutex::new(None),
+        }
+    }
+
+    async fn get_or_check<F, Fut>(&self, check: F) -> Result<(), String>
+    where
+        F: FnOnce() -> Fut,
+        Fut: std::future::Future<Output = Result<(), String>>,
+    {
+        let mut last_check = self.last_check.lock().await;
+
+        if let Some((checked_at, result)) = last_check.as_ref() {
+            if checked_at.elapsed() < self.ttl {
+                return result.clone();
+            }
+        }
+
+        let result = check().await;
+        *last_check = Some((Instant::now(), result.clone()));
+
+        result
+    }
+}
+
+#[tracing::instrument(skip(app_state), level=tracing::Level::INFO, name="webserver:deep_health_check")]
+async fn deep_health_check(State(app_state): State<AppState>) -> impl IntoResponse {
+    // a storage lock that is never released makes even the in-memory read hang
+    let storage = match tokio::time::timeout(
+        DEEP_HEALTH_CHECK_TIMEOUT,
+        app_state.guild_settings_repository.len(),
+    )
+    .await
+    {
+        Ok(_) => Ok(()),
+        Err(_) => Err("guild settings storage read timed out".to_owned()),
+    };
+
+    let beatleader = app_state
+        .bl_health_check
+        .get_or_check(|| async {
+            match tokio::time::timeout(DEEP_HEALTH_CHECK_TIMEOUT, BL_CLIENT.head("/")).await {
+                Ok(Ok(_)) => Ok(()),
+                // any non-retryable error (e.g. 404 or 405) still means the API responded
+                Ok(Err(err)) if !err.is_retryable() && !matches!(err, BlError::Request(_)) => {
+                    Ok(())
+                }
+                Ok(Err(err)) => Err(err.to_string()),
+                Err(_) => Err("BL API request timed out".to_owned()),
+            }
+        })
+        .await;
+
+    let status = if storage.is_ok() && beatleader.is_ok() {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+
+    let check_result = |result: Result<(), String>| match result {
+        Ok(_) => json!({"status": "ok"}),
+        Err(message) => json!({"status": "error", "message": message}),
+    };
+
+    (
+        status,
+        Json(json!({
+            "storage": check_result(storage),
+            "beatleader": check_result(beatleader),
+        })),
+    )
+}
+
 #[derive(Debug, Deserialize)]
 #[allow(dead_code)]
 struct Params {
@@ -228,6 +401,8 @@ struct Params {
     iss: Option<String>,
     #[serde(default, deserialize_with = "empty_string_as_none")]
     state: Option<String>,
+    #[serde(default)]
+    format: OAuthResponseFormat,
 }
 
 /// Serde deserialization decorator to map empty Strings to None,
@@ -244,126 +419,387 @@ where
     }
 }
 
-#[tracing::instrument(skip(app_state), level=tracing::Level::INFO, name="webserver:bl-oauth")]
-async fn bl_oauth(
-    Query(params): Query<Params>,
-    State(app_state): State<AppState>,
-) -> (StatusCode, String) {
-    let oauth_settings = match app_state.settings.oauth {
-        None => return (
-            StatusCode::SERVICE_UNAVAILABLE,
-            "The bot is not properly configured to send invitations to the clan. Contact the bot owner to have it configured."
-                .to_string(),
-        ),
-        Some(oauth_settings) => oauth_settings.clone()
+#[derive(Debug)]
+pub(crate) enum OAuthStateError {
+    Decode(String),
+    InvalidGuildId(String),
+}
+
+// the OAuth state carries the id of the guild that requested the authorization, encrypted with
+// the client secret so it can not be forged
+pub(crate) fn encode_oauth_state(client_secret: &str, guild_id: GuildId) -> String {
+    new_magic_crypt!(client_secret, 256).encrypt_str_to_base64(guild_id.to_string())
+}
+
+pub(crate) fn decode_oauth_state(
+    client_secret: &str,
+    state: &str,
+) -> Result<GuildId, OAuthStateError> {
+    new_magic_crypt!(client_secret, 256)
+        .decrypt_base64_to_string(state)
+        .map_err(|err| OAuthStateError::Decode(err.to_string()))?
+        .parse::<GuildId>()
+        .map_err(|err| OAuthStateError::InvalidGuildId(err.to_string()))
+}
+
+// errors are sent as plain text for browsers redirected by BL, or as JSON for programmatic
+// integrations requesting it with the format=json query param
+#[derive(Debug, Default, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum OAuthResponseFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+#[derive(Debug, Clone)]
+struct OAuthCallbackError {
+    status: StatusCode,
+    code: &'static str,
+    message: String,
+}
+
+impl OAuthCallbackError {
+    fn new(status: StatusCode, code: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            code,
+            message: message.into(),
+        }
+    }
+
+    fn into_response(self, format: OAuthResponseFormat) -> Response<Body> {
+        match format {
+            OAuthResponseFormat::Text => (self.status, self.message).into_response(),
+            OAuthResponseFormat::Json => (
+                self.status,
+                Json(json!({"error": {"code": self.code, "message": self.message}})),
+            )
+                .into_response(),
+        }
+    }
+}
+
+fn oauth_success_response(message: String, format: OAuthResponseFormat) -> Response<Body> {
+    match format {
+        OAuthResponseFormat::Text => (StatusCode::OK, message).into_response(),
+        OAuthResponseFormat::Json => {
+            (StatusCode::OK, Json(json!({"message": message}))).into_response()
+        }
+    }
+}
+
+fn oauth_code_and_state(params: Params) -> Result<(String, String), OAuthCallbackError> {
+    match (params.code, params.state) {
+        (Some(code), Some(state)) if !code.is_empty() => Ok((code, state)),
+        _ => Err(OAuthCallbackError::new(
+            StatusCode::BAD_GATEWAY,
+            "missing_code_or_state",
+            "Something went wrong.\n\nNo authorization code or oauth state in response, can not continue.",
+        )),
+    }
+}
+
+async fn oauth_guild_settings(
+    app_state: &AppState,
+    client_secret: &str,
+    state: &str,
+) -> Result<GuildSettings, OAuthCallbackError> {
+    let guild_id = match decode_oauth_state(client_secret, state) {
+        Ok(guild_id) => guild_id,
+        Err(OAuthStateError::Decode(err)) => {
+            let err_string = format!("Can not decode oauth state: {}", err);
+            tracing::error!("{}", err_string.as_str());
+
+            return Err(OAuthCallbackError::new(
+                StatusCode::BAD_REQUEST,
+                "invalid_state",
+                err_string,
+            ));
+        }
+        Err(OAuthStateError::InvalidGuildId(err)) => {
+            return Err(OAuthCallbackError::new(
+                StatusCode::BAD_REQUEST,
+                "invalid_state",
+                format!("Invalid oauth state: {}", err),
+            ))
+        }
+    };
+
+    app_state
+        .guild_settings_repository
+        .get(&guild_id)
+        .await
+        .map_err(|_| {
+            OAuthCallbackError::new(
+                StatusCode::BAD_REQUEST,
+                "guild_not_found",
+                "Invalid guild ID",
+            )
+        })
+}
+
+// exchanges the authorization code for the clan owner's token, returns the message for the user
+async fn store_oauth_token(
+    app_state: &AppState,
+    oauth_settings: &OAuthSettings,
+    guild_settings: &GuildSettings,
+    auth_code: &str,
+) -> Result<String, OAuthCallbackError> {
+    let Some(mut clan_settings) = guild_settings.get_clan_settings() else {
+        return Err(OAuthCallbackError::new(
+            StatusCode::BAD_REQUEST,
+            "clan_not_set_up",
+            "Clan settings not found, use ``/bl-set-clan-invitation`` command first",
+        ));
     };
 
-    let error_response = (
-        StatusCode::BAD_GATEWAY,
-        "Something went wrong.\n\nNo authorization code or oauth state in response, can not continue."
-            .to_string(),
+    let oauth_client = BL_CLIENT.with_oauth(
+        OAuthAppCredentials {
+            client_id: oauth_settings.client_id.clone(),
+            client_secret: oauth_settings.client_secret.clone(),
+            redirect_uri: oauth_settings.redirect_uri.clone(),
+        },
+        GuildOAuthTokenRepository::new(
+            clan_settings.get_owner().clone(),
+            Arc::clone(&app_state.player_oauth_token_repository),
+        ),
     );
 
-    let auth_code = match params.code {
-        None => return error_response.clone(),
-        Some(code) => {
-            if code.is_empty() {
-                return error_response.clone();
-            }
+    match oauth_client.oauth().access_token_and_store(auth_code).await {
+        Err(BlError::OAuthStorage) => {
+            return Err(OAuthCallbackError::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "oauth_storage_error",
+                "An error occurred while saving the authorization, please contact the bot owner.",
+            ))
+        }
+        Err(err) => {
+            return Err(OAuthCallbackError::new(
+                StatusCode::BAD_GATEWAY,
+                "bl_api_error",
+                format!(
+                    "An error has occurred: {}\n\nUse the /bl-set-clan-invitation command again.",
+                    err
+                ),
+            ))
+        }
+        Ok(_) => {}
+    }
+
+    clan_settings.set_oauth_token(true);
+
+    let self_invite = clan_settings.supports_self_invitation();
+
+    if app_state
+        .guild_settings_repository
+        .set_clan_settings(&guild_settings.get_key(), Some(clan_settings))
+        .await
+        .is_err()
+    {
+        return Err(OAuthCallbackError::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "clan_settings_storage_error",
+            "An error occurred while saving clan settings",
+        ));
+    }
 
-            code
+    Ok(format!(
+        "Clan invitation service has been set up.\n\n{}",
+        if self_invite {
+            "Players can use the ``/bl-clan-invitation`` command to send themselves an invitation to join the clan."
+        } else {
+            "You can use the ``/bl-invite-player`` command to send a player an invitation to join the clan."
         }
+    ))
+}
+
+async fn authorize_clan_invitations(
+    app_state: &AppState,
+    params: Params,
+) -> Result<String, OAuthCallbackError> {
+    let Some(oauth_settings) = app_state.settings.oauth.as_ref() else {
+        return Err(OAuthCallbackError::new(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "oauth_not_configured",
+            "The bot is not properly configured to send invitations to the clan. Contact the bot owner to have it configured.",
+        ));
     };
 
-    match params.state {
-        Some(state) => {
-            let mc = new_magic_crypt!(oauth_settings.client_secret.as_str(), 256);
+    let (auth_code, state) = oauth_code_and_state(params)?;
 
-            match mc.decrypt_base64_to_string(state.as_str()) {
-                Err(err) => {
-                    let err_string = format!("Can not decode oauth state: {}", err);
-                    tracing::error!("{}", err_string.as_str());
+    let guild_settings =
+        oauth_guild_settings(app_state, oauth_settings.client_secret.as_str(), &state).await?;
 
-                    return (StatusCode::BAD_REQUEST, err_string);
-                }
-                Ok(decoded_state) => match decoded_state.parse::<GuildId>() {
-                    Err(err) => {
-                        return (
-                            StatusCode::BAD_REQUEST,
-                            format!("Invalid oauth state: {}", err),
-                        )
-                    }
-                    Ok(guild_id) => {
-                        match app_state.guild_settings_repository.get(&guild_id).await {
-                            Err(_) => {
-                                return (StatusCode::BAD_REQUEST, "Invalid guild ID".to_string())
-                            }
-                            Ok(guild_settings) => {
-                                let Some(mut clan_settings) = guild_settings.get_clan_settings()
-                                else {
-                                    return (StatusCode::BAD_REQUEST, "Clan settings not found, use ``/bl-set-clan-invitation`` command first".to_string());
-                                };
-
-                                let oauth_client = BL_CLIENT.with_oauth(
-                                    OAuthAppCredentials {
-                                        client_id: oauth_settings.client_id.clone(),
-                                        client_secret: oauth_settings.client_secret.clone(),
-                                        redirect_uri: oauth_settings.redirect_uri.clone(),
-                                    },
-                                    GuildOAuthTokenRepository::new(
-                                        clan_settings.get_owner().clone(),
-                                        Arc::clone(&app_state.player_oauth_token_repository),
-                                    ),
-                                );
-
-                                match oauth_client
-                                    .oauth()
-                                    .access_token_and_store(auth_code.as_str())
-                                    .await {
-                                    Err(err) => {
-                                        return (StatusCode::BAD_GATEWAY, format!(
-                                            "An error has occurred: {}\n\nUse the /bl-set-clan-invitation command again.", err
-                                        ))
-                                    },
-                                    Ok(_) => {
-                                        clan_settings.set_oauth_token(true);
-
-                                        let self_invite = clan_settings.supports_self_invitation();
-
-                                        if app_state
-                                            .guild_settings_repository
-                                            .set_clan_settings(
-                                                &guild_settings.get_key(),
-                                                Some(clan_settings),
-                                            )
-                                            .await
-                                            .is_err()
-                                        {
-                                            return (
-                                                StatusCode::INTERNAL_SERVER_ERROR,
-                                                "An error occurred while saving clan settings".to_string(),
-                                            );
-                                        }
-
-                                        (
-                                            StatusCode::OK,
-                                            format!(
-                                                "Clan invitation service has been set up.\n\n{}",
-                                                if self_invite {
-                                                    "Players can use the ``/bl-clan-invitation`` command to send themselves an invitation to join the clan."
-                                                } else {
-                                                    "You can use the ``/bl-invite-player`` command to send a player an invitation to join the clan."
-                                                }
-                                            ),
-                                        )
-                                    }
-                                }
-                            }
-                        }
-                    }
-                },
-            }
+    store_oauth_token(app_state, oauth_settings, &guild_settings, &auth_code).await
+}
+
+#[tracing::instrument(skip(app_state), level=tracing::Level::INFO, name="webserver:bl-oauth")]
+async fn bl_oauth(
+    Query(params): Query<Params>,
+    State(app_state): State<AppState>,
+) -> Response<Body> {
+    let format = params.format;
+
+    match authorize_clan_invitations(&app_state, params).await {
+        Ok(message) => oauth_success_response(message, format),
+        Err(err) => err.into_response(format),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::extract::Query;
+    use axum::http::StatusCode;
+    use axum::routing::get;
+    use axum::Router;
+    use tower_governor::GovernorLayer;
+
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use crate::discord::bot::beatleader::clan::{ClanWarsSort, Playlist};
+    use crate::other::clan_wars_playlist_cache::ClanWarsPlaylistCache;
+    use crate::webserver::routes::{
+        api, oauth_code_and_state, playlist_governor_config, BlHealthCheck, OAuthCallbackError,
+        OAuthResponseFormat, Params, PlaylistUserExtractor,
+    };
+
+    #[tokio::test]
+    async fn it_sends_rate_limit_headers_with_successful_playlist_response() {
+        let router = Router::new()
+            .route("/playlist/:user/:id", get(|| async { "{}" }))
+            .layer(GovernorLayer {
+                config: playlist_governor_config(PlaylistUserExtractor),
+            });
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, router).await });
+
+        let response = reqwest::get(format!("http://{}/playlist/1/1", addr))
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response
+                .headers()
+                .get("x-ratelimit-limit")
+                .and_then(|value| value.to_str().ok()),
+            Some("3")
+        );
+        assert_eq!(
+            response
+                .headers()
+                .get("x-ratelimit-remaining")
+                .and_then(|value| value.to_str().ok()),
+            Some("2")
+        );
+    }
+
+    #[tokio::test]
+    async fn it_rate_limits_only_uncached_clan_wars_playlists_per_client() {
+        let cache = Arc::new(ClanWarsPlaylistCache::new(Duration::from_secs(60)));
+        cache.insert(
+            &"ABC".to_owned(),
+            &ClanWarsSort::ToConquer,
+            Playlist::default(),
+        );
+
+        let router = Router::new().nest(
+            "/api",
+            api::clan_wars_playlist_router(get(|| async { "{}" }), cache),
+        );
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, router).await });
+
+        let client = reqwest::Client::new();
+
+        for (client_ip, tag, expected) in [
+            ("10.0.0.1", "abc", None),
+            ("10.0.0.1", "ABC", None),
+            ("10.0.0.1", "def", Some("2")),
+            ("10.0.0.1", "DEF", Some("1")),
+            ("10.0.0.1", "ghi", Some("0")),
+            ("10.0.0.2", "ghi", Some("2")),
+        ] {
+            let response = client
+                .get(format!(
+                    "http://{}/api/clan/{}/wars.json?sort=toConquer",
+                    addr, tag
+                ))
+                .header("x-forwarded-for", client_ip)
+                .send()
+                .await
+                .unwrap();
+
+            assert_eq!(response.status(), StatusCode::OK);
+            assert_eq!(
+                response
+                    .headers()
+                    .get("x-ratelimit-remaining")
+                    .and_then(|value| value.to_str().ok()),
+                expected
+            );
         }
-        None => error_response,
+    }
+
+    #[tokio::test]
+    async fn it_reuses_bl_health_check_result_within_ttl() {
+        let checks = AtomicUsize::new(0);
+        let check = || async {
+            checks.fetch_add(1, Ordering::SeqCst);
+
+            Err("BL API request timed out".to_owned())
+        };
+
+        let health_check = BlHealthCheck::new(Duration::from_secs(60));
+        assert!(health_check.get_or_check(check).await.is_err());
+        assert!(health_check.get_or_check(check).await.is_err());
+        assert_eq!(checks.load(Ordering::SeqCst), 1);
+
+        let health_check = BlHealthCheck::new(Duration::ZERO);
+        assert!(health_check.get_or_check(check).await.is_err());
+        assert!(health_check.get_or_check(check).await.is_err());
+        assert_eq!(checks.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn it_sends_oauth_errors_in_requested_format() {
+        let params =
+            Query::<Params>::try_from_uri(&"/bl-oauth?code=abc&format=json".parse().unwrap())
+                .unwrap()
+                .0;
+        assert_eq!(params.format, OAuthResponseFormat::Json);
+
+        let err = oauth_code_and_state(params).unwrap_err();
+        assert_eq!(err.code, "missing_code_or_state");
+
+        let response = err.clone().into_response(OAuthResponseFormat::Json);
+        assert_eq!(response.status(), StatusCode::BAD_GATEWAY);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json = serde_json::from_slice::<serde_json::Value>(&body).unwrap();
+        assert_eq!(json["error"]["code"], "missing_code_or_state");
+        assert_eq!(json["error"]["message"], err.message.as_str());
+
+        // browsers redirected by BL get plain text by default
+        let params = Query::<Params>::try_from_uri(&"/bl-oauth?state=abc".parse().unwrap())
+            .unwrap()
+            .0;
+        assert_eq!(params.format, OAuthResponseFormat::Text);
+
+        let response = OAuthCallbackError::new(StatusCode::BAD_REQUEST, "invalid_state", "Invalid")
+            .into_response(params.format);
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(body.as_ref(), b"Invalid");
     }
 }